@@ -0,0 +1,176 @@
+use anyhow::{Context, Result};
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::decode;
+
+/// How samples are stored in a written WAV file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    #[default]
+    Pcm16,
+    Pcm24,
+    Float32,
+}
+
+impl Encoding {
+    /// The closest encoding to a file's own, so rewriting it doesn't lose
+    /// depth. 8-bit files become 16-bit and 32-bit integer ones 24-bit.
+    pub fn of(spec: &WavSpec) -> Self {
+        match (spec.sample_format, spec.bits_per_sample) {
+            (SampleFormat::Float, _) => Encoding::Float32,
+            (SampleFormat::Int, bits) if bits > 16 => Encoding::Pcm24,
+            (SampleFormat::Int, _) => Encoding::Pcm16,
+        }
+    }
+}
+
+/// Decoded audio as interleaved `f32` samples in `-1.0..=1.0`, for
+/// processing that works on any WAV the app can read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioBuffer {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub samples: Vec<f32>,
+}
+
+impl AudioBuffer {
+    /// Decodes a PCM or float WAV file of any bit depth and channel count.
+    pub fn read(path: &Path) -> Result<Self> {
+        decode::decode(path)
+    }
+
+    /// Writes 16-bit PCM, clipping anything outside `-1.0..=1.0`.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        self.write_as(path, Encoding::Pcm16)
+    }
+
+    /// Writes with the given encoding. PCM clips anything outside
+    /// `-1.0..=1.0`; float keeps it.
+    pub fn write_as(&self, path: &Path, encoding: Encoding) -> Result<()> {
+        let (bits_per_sample, sample_format) = match encoding {
+            Encoding::Pcm16 => (16, SampleFormat::Int),
+            Encoding::Pcm24 => (24, SampleFormat::Int),
+            Encoding::Float32 => (32, SampleFormat::Float),
+        };
+        let spec = WavSpec {
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            bits_per_sample,
+            sample_format,
+        };
+        let mut writer = WavWriter::create(path, spec)
+            .with_context(|| format!("failed to create {}", path.display()))?;
+        for &sample in &self.samples {
+            match encoding {
+                Encoding::Pcm16 => {
+                    writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?
+                }
+                Encoding::Pcm24 => {
+                    writer.write_sample((sample.clamp(-1.0, 1.0) * 8_388_607.0) as i32)?
+                }
+                Encoding::Float32 => writer.write_sample(sample)?,
+            }
+        }
+        writer.finalize()?;
+        Ok(())
+    }
+
+    /// Writes next to `path` in the encoding it already has and then
+    /// renames over it, so a failure never leaves a half-written recording
+    /// behind.
+    pub fn replace(&self, path: &Path) -> Result<()> {
+        let spec = WavReader::open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?
+            .spec();
+        let temp = path.with_extension("processing.wav");
+        self.write_as(&temp, Encoding::of(&spec))?;
+        std::fs::rename(&temp, path)
+            .with_context(|| format!("failed to replace {}", path.display()))
+    }
+
+    /// Samples per channel.
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels.max(1) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    #[test]
+    fn write_round_trips_16_bit() {
+        let original = AudioBuffer::read(&fixture("stereo_16k.wav")).unwrap();
+        let path = std::env::temp_dir().join(format!("app-core-buffer-{}.wav", std::process::id()));
+        original.write(&path).unwrap();
+        let copy = AudioBuffer::read(&path).unwrap();
+        assert_eq!(copy.channels, 2);
+        assert_eq!(copy.samples.len(), original.samples.len());
+        assert!(original
+            .samples
+            .iter()
+            .zip(&copy.samples)
+            .all(|(a, b)| (a - b).abs() < 1e-3));
+    }
+
+    #[test]
+    fn writes_wider_encodings() {
+        let original = AudioBuffer::read(&fixture("mono_16k.wav")).unwrap();
+        for (encoding, bits) in [(Encoding::Pcm24, 24), (Encoding::Float32, 32)] {
+            let path = std::env::temp_dir().join(format!(
+                "app-core-buffer-{:?}-{}.wav",
+                encoding,
+                std::process::id()
+            ));
+            original.write_as(&path, encoding).unwrap();
+            assert_eq!(
+                hound::WavReader::open(&path)
+                    .unwrap()
+                    .spec()
+                    .bits_per_sample,
+                bits
+            );
+            let copy = AudioBuffer::read(&path).unwrap();
+            assert!(original
+                .samples
+                .iter()
+                .zip(&copy.samples)
+                .all(|(a, b)| (a - b).abs() < 1e-4));
+        }
+    }
+
+    #[test]
+    fn replace_keeps_the_source_encoding() {
+        let dir = std::env::temp_dir();
+        let pid = std::process::id();
+        let deep = dir.join(format!("app-core-buffer-replace-24-{}.wav", pid));
+        std::fs::copy(fixture("mono_24bit.wav"), &deep).unwrap();
+        let float = dir.join(format!("app-core-buffer-replace-float-{}.wav", pid));
+        AudioBuffer::read(&fixture("mono_16k.wav"))
+            .unwrap()
+            .write_as(&float, Encoding::Float32)
+            .unwrap();
+        for (path, bits, format) in [
+            (deep, 24, SampleFormat::Int),
+            (float, 32, SampleFormat::Float),
+        ] {
+            let mut buffer = AudioBuffer::read(&path).unwrap();
+            for sample in &mut buffer.samples {
+                *sample *= 0.5;
+            }
+            buffer.replace(&path).unwrap();
+            let spec = WavReader::open(&path).unwrap().spec();
+            assert_eq!((spec.bits_per_sample, spec.sample_format), (bits, format));
+            let copy = AudioBuffer::read(&path).unwrap();
+            assert!(buffer
+                .samples
+                .iter()
+                .zip(&copy.samples)
+                .all(|(a, b)| (a - b).abs() < 1e-4));
+        }
+    }
+}
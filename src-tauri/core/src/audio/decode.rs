@@ -0,0 +1,201 @@
+use anyhow::{Context, Result};
+use hound::{SampleFormat, WavReader, WavSpec};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use super::buffer::AudioBuffer;
+use super::resample::{ResampleQuality, StreamResampler};
+
+/// Frames read from disk at a time when streaming a file.
+const READ_FRAMES: usize = 16 * 1024;
+
+/// Opens a PCM or float WAV file of any bit depth and channel count,
+/// returning its format and its interleaved samples as `f32` in
+/// `-1.0..=1.0`, decoded lazily.
+pub fn samples(path: &Path) -> Result<(WavSpec, Box<dyn Iterator<Item = Result<f32>>>)> {
+    let reader = WavReader::open(path).context("not a readable WAV file")?;
+    let spec = reader.spec();
+    let samples: Box<dyn Iterator<Item = Result<f32>>> = match spec.sample_format {
+        SampleFormat::Float => Box::new(reader.into_samples::<f32>().map(read)),
+        SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            Box::new(
+                reader
+                    .into_samples::<i32>()
+                    .map(move |sample| read(sample).map(|s| s as f32 / scale)),
+            )
+        }
+    };
+    Ok((spec, samples))
+}
+
+fn read<T>(sample: hound::Result<T>) -> Result<T> {
+    sample.context("failed to read sample")
+}
+
+/// Decodes a whole file into memory.
+pub fn decode(path: &Path) -> Result<AudioBuffer> {
+    let (spec, samples) = samples(path)?;
+    Ok(AudioBuffer {
+        sample_rate: spec.sample_rate,
+        channels: spec.channels,
+        samples: samples.collect::<Result<_>>()?,
+    })
+}
+
+/// Decodes a file mixed down to mono and resampled to `sample_rate`, e.g.
+/// for whisper. Samples are read, mixed and resampled a chunk at a time, so
+/// only the output is ever held in memory.
+pub fn decode_mono(path: &Path, sample_rate: u32, quality: ResampleQuality) -> Result<AudioBuffer> {
+    let mut stream = MonoStream::open(path, sample_rate, quality)?;
+    Ok(AudioBuffer {
+        sample_rate,
+        channels: 1,
+        samples: stream.read(usize::MAX)?,
+    })
+}
+
+/// Reads a file mixed down to mono and resampled, a window at a time, so
+/// even the output never has to be in memory all at once.
+pub struct MonoStream {
+    samples: Box<dyn Iterator<Item = Result<f32>>>,
+    channels: usize,
+    resampler: Option<StreamResampler>,
+    /// Output decoded but not yet read.
+    pending: Vec<f32>,
+    done: bool,
+    resample_time: Duration,
+}
+
+impl MonoStream {
+    pub fn open(path: &Path, sample_rate: u32, quality: ResampleQuality) -> Result<Self> {
+        let (spec, samples) = samples(path)?;
+        Ok(MonoStream {
+            samples,
+            channels: spec.channels.max(1) as usize,
+            resampler: (spec.sample_rate != sample_rate)
+                .then(|| StreamResampler::new(spec.sample_rate, sample_rate as f64, quality))
+                .transpose()?,
+            pending: Vec::new(),
+            done: false,
+            resample_time: Duration::ZERO,
+        })
+    }
+
+    /// The next `len` samples, or fewer at the end of the file; empty once
+    /// it's all been read.
+    pub fn read(&mut self, len: usize) -> Result<Vec<f32>> {
+        let mut chunk = Vec::with_capacity(READ_FRAMES);
+        while self.pending.len() < len && !self.done {
+            chunk.clear();
+            while chunk.len() < READ_FRAMES {
+                let mut frame = 0.0;
+                let mut read = 0;
+                for sample in self.samples.by_ref().take(self.channels) {
+                    frame += sample?;
+                    read += 1;
+                }
+                if read == 0 {
+                    break;
+                }
+                chunk.push(frame / read as f32);
+            }
+            let started = Instant::now();
+            if chunk.is_empty() {
+                self.done = true;
+                if let Some(resampler) = self.resampler.take() {
+                    self.pending.extend(resampler.finish()?);
+                }
+                self.resample_time += started.elapsed();
+                break;
+            }
+            match &mut self.resampler {
+                Some(resampler) => {
+                    resampler.push(&chunk)?;
+                    self.pending.extend(resampler.drain());
+                }
+                None => self.pending.extend_from_slice(&chunk),
+            }
+            self.resample_time += started.elapsed();
+        }
+        let rest = self.pending.split_off(len.min(self.pending.len()));
+        Ok(std::mem::replace(&mut self.pending, rest))
+    }
+
+    /// Time spent resampling so far, out of the time spent in [`Self::read`].
+    pub fn resample_time(&self) -> Duration {
+        self.resample_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    #[test]
+    fn decodes_any_bit_depth() {
+        let wide = decode(&fixture("mono_16k.wav")).unwrap();
+        assert_eq!(wide.frames(), 4000);
+        let narrow = decode(&fixture("mono_8bit.wav")).unwrap();
+        assert!(narrow.samples.iter().all(|s| (-1.0..=1.0).contains(s)));
+        let stereo = decode(&fixture("stereo_16k.wav")).unwrap();
+        assert_eq!(stereo.channels, 2);
+        assert_eq!(stereo.frames(), 4000);
+    }
+
+    #[test]
+    fn skips_resampling_at_target_rate() {
+        let direct = decode(&fixture("mono_16k.wav")).unwrap();
+        let mono =
+            decode_mono(&fixture("mono_16k.wav"), 16000, ResampleQuality::default()).unwrap();
+        assert_eq!(mono, direct);
+    }
+
+    #[test]
+    fn upsamples_to_target_rate() {
+        let mono = decode_mono(&fixture("mono_8k.wav"), 16000, ResampleQuality::default()).unwrap();
+        // 0.25s at 16 kHz.
+        assert_eq!(mono.sample_rate, 16000);
+        assert_eq!(mono.frames(), 4000);
+    }
+
+    #[test]
+    fn mixes_stereo_down_to_mono() {
+        let stereo = decode(&fixture("stereo_16k.wav")).unwrap();
+        let mono = decode_mono(
+            &fixture("stereo_16k.wav"),
+            16000,
+            ResampleQuality::default(),
+        )
+        .unwrap();
+        assert_eq!(mono.channels, 1);
+        assert_eq!(mono.frames(), 4000);
+        assert!((mono.samples[10] - (stereo.samples[20] + stereo.samples[21]) / 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn streams_in_windows() {
+        let whole = decode_mono(&fixture("mono_8k.wav"), 16000, ResampleQuality::Fast).unwrap();
+        let mut stream =
+            MonoStream::open(&fixture("mono_8k.wav"), 16000, ResampleQuality::Fast).unwrap();
+        let mut windows = Vec::new();
+        loop {
+            let window = stream.read(1500).unwrap();
+            if window.is_empty() {
+                break;
+            }
+            windows.push(window);
+        }
+        assert_eq!(
+            windows.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![1500, 1500, 1000]
+        );
+        assert_eq!(windows.concat(), whole.samples);
+    }
+
+    #[test]
+    fn missing_file_is_an_error() {
+        assert!(decode(&fixture("does_not_exist.wav")).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+use anyhow::{Context, Result};
+use cpal::traits::{DeviceTrait, HostTrait};
+use cpal::{Device, Host, SampleRate, SupportedStreamConfig};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AudioDevice {
+    pub name: String,
+    /// The system's current default for its direction.
+    pub is_default: bool,
+}
+
+/// Capture settings remembered for one input device and applied whenever a
+/// take is recorded from it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceProfile {
+    /// Applied to every captured sample, in dB.
+    pub gain_db: f32,
+    /// Zero-based device channels to keep, in order. Empty keeps them all,
+    /// e.g. `[0]` records only input 1 of an interface.
+    pub channels: Vec<u16>,
+    /// Capture at this rate if the device supports it, instead of its default.
+    pub sample_rate: Option<u32>,
+    /// Turn down background noise between phrases.
+    pub denoise: bool,
+}
+
+fn describe(devices: impl Iterator<Item = Device>, default: Option<Device>) -> Vec<AudioDevice> {
+    let default = default.and_then(|d| d.name().ok());
+    devices
+        .filter_map(|device| device.name().ok())
+        .map(|name| AudioDevice {
+            is_default: default.as_deref() == Some(name.as_str()),
+            name,
+        })
+        .collect()
+}
+
+fn find(mut devices: impl Iterator<Item = Device>, name: &str) -> Option<Device> {
+    devices.find(|device| device.name().is_ok_and(|n| n == name))
+}
+
+/// Every output device the system offers, e.g. built-in speakers and
+/// connected headphones.
+pub fn list_output_devices() -> Result<Vec<AudioDevice>> {
+    let host = cpal::default_host();
+    let devices = host
+        .output_devices()
+        .context("failed to list output devices")?;
+    Ok(describe(devices, host.default_output_device()))
+}
+
+/// Every microphone and other input the system offers.
+pub fn list_input_devices() -> Result<Vec<AudioDevice>> {
+    let host = cpal::default_host();
+    let devices = host
+        .input_devices()
+        .context("failed to list input devices")?;
+    Ok(describe(devices, host.default_input_device()))
+}
+
+/// The output device called `name`, or the system default when it's `None`
+/// or no longer connected.
+pub fn output_device(name: Option<&str>) -> Result<Device> {
+    let host = cpal::default_host();
+    if let Some(name) = name {
+        match find(host.output_devices()?, name) {
+            Some(device) => return Ok(device),
+            None => eprintln!("Output device {:?} not found, using the default", name),
+        }
+    }
+    host.default_output_device().context("no output device")
+}
+
+/// Input devices to try recording from, in order: each of `preferred`
+/// that's plugged in, then the system default. The flag is set on the
+/// default when it's standing in for preferred devices.
+pub fn input_candidates(host: &Host, preferred: &[String]) -> Vec<(Device, bool)> {
+    let mut candidates: Vec<(Device, bool)> = preferred
+        .iter()
+        .filter_map(|name| find(host.input_devices().ok()?, name))
+        .map(|device| (device, false))
+        .collect();
+    if let Some(default) = host.default_input_device() {
+        let name = default.name().ok();
+        let listed = candidates
+            .iter()
+            .any(|(device, _)| device.name().ok() == name);
+        if !listed {
+            candidates.push((default, !preferred.is_empty()));
+        }
+    }
+    candidates
+}
+
+/// The input device called `name`, or the system default when it's `None`.
+pub fn input_device(host: &Host, name: Option<&str>) -> Result<Device> {
+    match name {
+        Some(name) => find(host.input_devices()?, name)
+            .with_context(|| format!("no input device called {:?}", name)),
+        None => host.default_input_device().context("no input device"),
+    }
+}
+
+/// The device's default input format, at `sample_rate` instead when the
+/// device supports it.
+pub fn input_config(device: &Device, sample_rate: Option<u32>) -> Result<SupportedStreamConfig> {
+    let default = device.default_input_config()?;
+    let Some(rate) = sample_rate else {
+        return Ok(default);
+    };
+    let found = device.supported_input_configs()?.find(|range| {
+        range.channels() == default.channels()
+            && range.sample_format() == default.sample_format()
+            && (range.min_sample_rate().0..=range.max_sample_rate().0).contains(&rate)
+    });
+    match found {
+        Some(range) => Ok(range.with_sample_rate(SampleRate(rate))),
+        None => {
+            eprintln!(
+                "Input device doesn't support {} Hz, using its default",
+                rate
+            );
+            Ok(default)
+        }
+    }
+}
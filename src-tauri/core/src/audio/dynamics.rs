@@ -0,0 +1,292 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::path::Path;
+
+use super::buffer::AudioBuffer;
+
+/// -1 dBFS: room for the inter-sample peaks that lossy codecs and resampling
+/// bring out.
+pub const DEFAULT_CEILING: f32 = 0.891;
+const RELEASE_MS: f32 = 50.0;
+
+/// Peak limiter with instant attack and smooth release, linked across
+/// channels so the stereo image doesn't shift. Output never exceeds the
+/// ceiling, unlike the hard clipping a plain gain change ends in.
+pub struct Limiter {
+    ceiling: f32,
+    release: f32,
+    envelope: f32,
+    channels: usize,
+}
+
+impl Limiter {
+    pub fn new(ceiling: f32, sample_rate: u32, channels: u16) -> Self {
+        let release_frames = RELEASE_MS / 1000.0 * sample_rate as f32;
+        Limiter {
+            ceiling,
+            release: (-1.0 / release_frames.max(1.0)).exp(),
+            envelope: 0.0,
+            channels: channels.max(1) as usize,
+        }
+    }
+
+    /// Limits one interleaved frame in place.
+    pub fn process_frame(&mut self, frame: &mut [f32]) {
+        let peak = frame.iter().fold(0f32, |peak, s| peak.max(s.abs()));
+        self.envelope = peak.max(self.envelope * self.release);
+        if self.envelope > self.ceiling {
+            let gain = self.ceiling / self.envelope;
+            for sample in frame {
+                *sample *= gain;
+            }
+        }
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for frame in samples.chunks_mut(self.channels) {
+            self.process_frame(frame);
+        }
+    }
+}
+
+/// Frames quieter than this count as background noise for [`NoiseGate`].
+const GATE_THRESHOLD: f32 = 0.003; // About -50 dBFS
+/// How far the gate turns noise down; fully muting it sounds choppy.
+const GATE_FLOOR: f32 = 0.1;
+const GATE_OPEN_MS: f32 = 5.0;
+const GATE_CLOSE_MS: f32 = 150.0;
+
+/// Turns steady background noise down between phrases, opening quickly when
+/// someone speaks and closing slowly so word endings aren't cut.
+pub struct NoiseGate {
+    release: f32,
+    open: f32,
+    close: f32,
+    envelope: f32,
+    gain: f32,
+}
+
+impl NoiseGate {
+    pub fn new(sample_rate: u32) -> Self {
+        let coefficient = |ms: f32| (-1.0 / (ms / 1000.0 * sample_rate as f32).max(1.0)).exp();
+        NoiseGate {
+            release: coefficient(RELEASE_MS),
+            open: coefficient(GATE_OPEN_MS),
+            close: coefficient(GATE_CLOSE_MS),
+            envelope: 0.0,
+            gain: 1.0,
+        }
+    }
+
+    /// Gates one interleaved frame in place.
+    pub fn process_frame(&mut self, frame: &mut [f32]) {
+        let peak = frame.iter().fold(0f32, |peak, s| peak.max(s.abs()));
+        self.envelope = peak.max(self.envelope * self.release);
+        let (target, coefficient) = match self.envelope >= GATE_THRESHOLD {
+            true => (1.0, self.open),
+            false => (GATE_FLOOR, self.close),
+        };
+        self.gain = target + (self.gain - target) * coefficient;
+        for sample in frame {
+            *sample *= self.gain;
+        }
+    }
+}
+
+/// Limits a whole buffer to `ceiling`.
+pub fn limit(buffer: &mut AudioBuffer, ceiling: f32) {
+    Limiter::new(ceiling, buffer.sample_rate, buffer.channels).process(&mut buffer.samples);
+}
+
+/// What [`declip`] found and changed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Declip {
+    /// Runs of consecutive clipped samples that were rebuilt.
+    pub clipped_runs: usize,
+    pub clipped_samples: usize,
+    /// Attenuation applied so the rebuilt peaks fit, in dB (zero or negative).
+    pub gain_db: f32,
+}
+
+/// Samples this close to full scale count as clipped.
+const CLIP_LEVEL: f32 = 0.999;
+
+/// Rebuilds the tops of clipped waveforms by fitting a cubic through the two
+/// good samples on either side of each clipped run, then lowers the level
+/// so the restored peaks fit without clipping again.
+pub fn declip(buffer: &mut AudioBuffer) -> Declip {
+    let channels = buffer.channels.max(1) as usize;
+    let frames = buffer.frames();
+    let mut clipped_runs = 0;
+    let mut clipped_samples = 0;
+    for channel in 0..channels {
+        let at = |frame: usize| frame * channels + channel;
+        let mut frame = 0;
+        while frame < frames {
+            if buffer.samples[at(frame)].abs() < CLIP_LEVEL {
+                frame += 1;
+                continue;
+            }
+            let start = frame;
+            while frame < frames && buffer.samples[at(frame)].abs() >= CLIP_LEVEL {
+                frame += 1;
+            }
+            let end = frame;
+            // Needs two clean samples either side to fit through.
+            if start < 2 || end + 2 > frames {
+                continue;
+            }
+            let known =
+                [start - 2, start - 1, end, end + 1].map(|f| (f as f32, buffer.samples[at(f)]));
+            let sign = buffer.samples[at(start)].signum();
+            for f in start..end {
+                let rebuilt = lagrange(&known, f as f32);
+                // Never make a clipped peak smaller than the clip level.
+                buffer.samples[at(f)] = if rebuilt * sign > CLIP_LEVEL {
+                    rebuilt
+                } else {
+                    sign * CLIP_LEVEL
+                };
+            }
+            clipped_runs += 1;
+            clipped_samples += end - start;
+        }
+    }
+
+    let peak = buffer
+        .samples
+        .iter()
+        .fold(0f32, |peak, s| peak.max(s.abs()));
+    let gain = if peak > DEFAULT_CEILING {
+        DEFAULT_CEILING / peak
+    } else {
+        1.0
+    };
+    if clipped_runs > 0 && gain < 1.0 {
+        for sample in &mut buffer.samples {
+            *sample *= gain;
+        }
+    }
+    Declip {
+        clipped_runs,
+        clipped_samples,
+        gain_db: if clipped_runs > 0 {
+            20.0 * gain.log10()
+        } else {
+            0.0
+        },
+    }
+}
+
+/// Evaluates the cubic through `points` at `x`.
+fn lagrange(points: &[(f32, f32); 4], x: f32) -> f32 {
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, &(xi, yi))| {
+            points
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .fold(yi, |term, (_, &(xj, _))| term * (x - xj) / (xi - xj))
+        })
+        .sum()
+}
+
+/// Declips the WAV file at `path` in place.
+pub fn declip_file(path: &Path) -> Result<Declip> {
+    let mut buffer = AudioBuffer::read(path)?;
+    let declip = declip(&mut buffer);
+    if declip.clipped_runs > 0 {
+        buffer.replace(path)?;
+    }
+    Ok(declip)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(amplitude: f32, channels: u16) -> AudioBuffer {
+        let sample_rate = 16_000;
+        AudioBuffer {
+            sample_rate,
+            channels,
+            samples: (0..sample_rate as usize)
+                .flat_map(|i| {
+                    let s = amplitude
+                        * (2.0 * std::f32::consts::PI * 200.0 * i as f32 / sample_rate as f32)
+                            .sin();
+                    std::iter::repeat(s).take(channels as usize)
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn gate_turns_down_noise_but_not_speech() {
+        let mut gate = NoiseGate::new(16_000);
+        let mut noise = sine(0.001, 1);
+        for frame in noise.samples.chunks_mut(1) {
+            gate.process_frame(frame);
+        }
+        let tail = &noise.samples[12_000..];
+        assert!(tail.iter().all(|s| s.abs() < 0.001 * GATE_FLOOR * 1.2));
+
+        let mut speech = sine(0.3, 1);
+        let original = speech.clone();
+        for frame in speech.samples.chunks_mut(1) {
+            gate.process_frame(frame);
+        }
+        // Open again within a few milliseconds of the level coming up.
+        assert!(speech.samples[800..]
+            .iter()
+            .zip(&original.samples[800..])
+            .all(|(a, b)| (a - b).abs() < 1e-3));
+    }
+
+    #[test]
+    fn limiter_holds_the_ceiling() {
+        let mut buffer = sine(2.0, 2);
+        limit(&mut buffer, DEFAULT_CEILING);
+        assert!(buffer
+            .samples
+            .iter()
+            .all(|s| s.abs() <= DEFAULT_CEILING + 1e-6));
+        // Linked: both channels get the same gain.
+        assert!(buffer.samples.chunks(2).all(|frame| frame[0] == frame[1]));
+    }
+
+    #[test]
+    fn limiter_leaves_quiet_audio_alone() {
+        let mut buffer = sine(0.5, 1);
+        let original = buffer.clone();
+        limit(&mut buffer, DEFAULT_CEILING);
+        assert_eq!(buffer, original);
+    }
+
+    #[test]
+    fn declip_rebuilds_flattened_peaks() {
+        let mut buffer = sine(1.2, 1);
+        for sample in &mut buffer.samples {
+            *sample = sample.clamp(-1.0, 1.0);
+        }
+        let declip = declip(&mut buffer);
+        assert!(declip.clipped_runs > 0);
+        assert!(declip.gain_db < 0.0);
+        let peak = buffer
+            .samples
+            .iter()
+            .fold(0f32, |peak, s| peak.max(s.abs()));
+        assert!(peak <= DEFAULT_CEILING + 1e-6);
+        // The rebuilt peaks stand above the old flat tops once scaled back.
+        let restored = peak / 10f32.powf(declip.gain_db / 20.0);
+        assert!(restored > 1.1, "{}", restored);
+    }
+
+    #[test]
+    fn clean_audio_is_not_declipped() {
+        let mut buffer = sine(0.5, 1);
+        assert_eq!(declip(&mut buffer).clipped_runs, 0);
+    }
+}
@@ -0,0 +1,371 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use super::buffer::{AudioBuffer, Encoding};
+use super::resample::{resample_buffer, ResampleQuality};
+use crate::library::unique_path;
+
+/// A span of a recording, in milliseconds from its start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrimMode {
+    /// Only the given ranges are kept, in order.
+    Keep,
+    /// The given ranges are cut out and the rest joined up.
+    Remove,
+}
+
+/// Sorted, non-overlapping frame ranges for `ranges`, clipped to the buffer.
+fn frame_ranges(buffer: &AudioBuffer, ranges: &[TimeRange]) -> Result<Vec<Range<usize>>> {
+    let frames = buffer.frames();
+    let to_frame =
+        |ms: u64| ((ms as u128 * buffer.sample_rate as u128 / 1000) as usize).min(frames);
+    let mut spans = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        if range.start_ms >= range.end_ms {
+            bail!(
+                "range {}–{} ms doesn't end after it starts",
+                range.start_ms,
+                range.end_ms
+            );
+        }
+        spans.push(to_frame(range.start_ms)..to_frame(range.end_ms));
+    }
+    spans.sort_by_key(|span| span.start);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(spans.len());
+    for span in spans {
+        match merged.last_mut() {
+            Some(last) if span.start <= last.end => last.end = last.end.max(span.end),
+            _ => merged.push(span),
+        }
+    }
+    merged.retain(|span| !span.is_empty());
+    Ok(merged)
+}
+
+/// Keeps or removes `ranges` from `buffer`. Overlapping ranges are merged and
+/// anything past the end is ignored.
+pub fn trim(buffer: &AudioBuffer, ranges: &[TimeRange], mode: TrimMode) -> Result<AudioBuffer> {
+    let spans = frame_ranges(buffer, ranges)?;
+    let kept = match mode {
+        TrimMode::Keep => spans,
+        TrimMode::Remove => {
+            let mut gaps = Vec::with_capacity(spans.len() + 1);
+            let mut start = 0;
+            for span in spans {
+                gaps.push(start..span.start);
+                start = span.end;
+            }
+            gaps.push(start..buffer.frames());
+            gaps.retain(|gap| !gap.is_empty());
+            gaps
+        }
+    };
+    if kept.is_empty() {
+        bail!("trimming would leave no audio");
+    }
+
+    let channels = buffer.channels.max(1) as usize;
+    let mut samples = Vec::with_capacity(kept.iter().map(|span| span.len() * channels).sum());
+    for span in kept {
+        samples.extend_from_slice(&buffer.samples[span.start * channels..span.end * channels]);
+    }
+    Ok(AudioBuffer {
+        sample_rate: buffer.sample_rate,
+        channels: buffer.channels,
+        samples,
+    })
+}
+
+/// Writes a trimmed copy of the WAV file at `path` to `output`.
+pub fn trim_audio(path: &Path, ranges: &[TimeRange], mode: TrimMode, output: &Path) -> Result<()> {
+    trim(&AudioBuffer::read(path)?, ranges, mode)?.write(output)
+}
+
+/// Converts to `channels`: mono is copied to every channel, anything mixes
+/// down to mono by averaging, and other layouts wrap source channels around.
+pub fn remix(buffer: &AudioBuffer, channels: u16) -> AudioBuffer {
+    let from = buffer.channels.max(1) as usize;
+    let to = channels.max(1) as usize;
+    if from == to {
+        return buffer.clone();
+    }
+    let samples = buffer
+        .samples
+        .chunks(from)
+        .flat_map(|frame| {
+            let mono = frame.iter().sum::<f32>() / from as f32;
+            (0..to).map(move |channel| match (from, to) {
+                (_, 1) => mono,
+                (1, _) => frame[0],
+                _ => frame[channel % from],
+            })
+        })
+        .collect();
+    AudioBuffer {
+        sample_rate: buffer.sample_rate,
+        channels: to as u16,
+        samples,
+    }
+}
+
+/// Joins buffers end to end at the first one's sample rate, with as many
+/// channels as the widest of them.
+pub fn concat(buffers: &[AudioBuffer], quality: ResampleQuality) -> Result<AudioBuffer> {
+    let Some(first) = buffers.first() else {
+        bail!("nothing to join");
+    };
+    let channels = buffers.iter().map(|buffer| buffer.channels).max().unwrap();
+    let mut joined = AudioBuffer {
+        sample_rate: first.sample_rate,
+        channels,
+        samples: Vec::with_capacity(
+            buffers
+                .iter()
+                .map(|buffer| buffer.frames() * channels as usize)
+                .sum(),
+        ),
+    };
+    for buffer in buffers {
+        let buffer = remix(
+            &resample_buffer(buffer, first.sample_rate, quality)?,
+            channels,
+        );
+        joined.samples.extend_from_slice(&buffer.samples);
+    }
+    Ok(joined)
+}
+
+/// Joins WAV files of any format into one 16-bit file at `output`.
+pub fn concat_audio(paths: &[PathBuf], output: &Path, quality: ResampleQuality) -> Result<()> {
+    let buffers = paths
+        .iter()
+        .map(|path| {
+            AudioBuffer::read(path).with_context(|| format!("failed to read {}", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    concat(&buffers, quality)?.write(output)
+}
+
+/// Target format for [`convert_audio`]; unset fields keep the input's.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConvertOptions {
+    pub encoding: Encoding,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+}
+
+/// Reads any WAV the app understands and writes it to `output` in the
+/// requested format.
+pub fn convert_audio(
+    input: &Path,
+    output: &Path,
+    options: ConvertOptions,
+    quality: ResampleQuality,
+) -> Result<()> {
+    let buffer = AudioBuffer::read(input)?;
+    let buffer = match options.sample_rate {
+        Some(0) => bail!("sample rate must be positive"),
+        Some(rate) => resample_buffer(&buffer, rate, quality)?,
+        None => buffer,
+    };
+    let buffer = match options.channels {
+        Some(0) => bail!("channel count must be positive"),
+        Some(channels) => remix(&buffer, channels),
+        None => buffer,
+    };
+    buffer.write_as(output, options.encoding)
+}
+
+/// One mono buffer per channel, in channel order.
+pub fn split_channels(buffer: &AudioBuffer) -> Vec<AudioBuffer> {
+    let channels = buffer.channels.max(1) as usize;
+    (0..channels)
+        .map(|channel| AudioBuffer {
+            sample_rate: buffer.sample_rate,
+            channels: 1,
+            samples: buffer
+                .samples
+                .iter()
+                .skip(channel)
+                .step_by(channels)
+                .copied()
+                .collect(),
+        })
+        .collect()
+}
+
+/// "left" and "right" for stereo, "channel N" otherwise.
+fn channel_name(channel: usize, channels: usize) -> String {
+    match (channels, channel) {
+        (2, 0) => "left".to_string(),
+        (2, 1) => "right".to_string(),
+        _ => format!("channel {}", channel + 1),
+    }
+}
+
+/// Writes each channel of a multichannel WAV to its own mono file next to
+/// it, e.g. `call (left).wav` and `call (right).wav`, returning their paths.
+pub fn split_channels_file(path: &Path) -> Result<Vec<PathBuf>> {
+    let buffer = AudioBuffer::read(path)?;
+    if buffer.channels < 2 {
+        bail!("{} only has one channel", path.display());
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let parts = split_channels(&buffer);
+    let count = parts.len();
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(channel, part)| {
+            let name = format!("{} ({}).wav", stem, channel_name(channel, count));
+            let output = unique_path(&path.with_file_name(name));
+            part.write(&output)?;
+            Ok(output)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    /// One second at 1 kHz where each sample holds its own index.
+    fn ramp(channels: u16) -> AudioBuffer {
+        AudioBuffer {
+            sample_rate: 1000,
+            channels,
+            samples: (0..1000)
+                .flat_map(|i| std::iter::repeat(i as f32).take(channels as usize))
+                .collect(),
+        }
+    }
+
+    fn range(start_ms: u64, end_ms: u64) -> TimeRange {
+        TimeRange { start_ms, end_ms }
+    }
+
+    #[test]
+    fn keeps_ranges_in_order() {
+        let trimmed = trim(
+            &ramp(1),
+            &[range(500, 600), range(100, 200)],
+            TrimMode::Keep,
+        )
+        .unwrap();
+        assert_eq!(trimmed.frames(), 200);
+        assert_eq!(trimmed.samples[0], 100.0);
+        assert_eq!(trimmed.samples[100], 500.0);
+    }
+
+    #[test]
+    fn removes_ranges_and_merges_overlaps() {
+        let trimmed = trim(
+            &ramp(2),
+            &[range(0, 300), range(200, 400), range(900, 5000)],
+            TrimMode::Remove,
+        )
+        .unwrap();
+        assert_eq!(trimmed.channels, 2);
+        assert_eq!(trimmed.frames(), 500);
+        assert_eq!(&trimmed.samples[..2], &[400.0, 400.0]);
+        assert_eq!(trimmed.samples.last(), Some(&899.0));
+    }
+
+    #[test]
+    fn rejects_empty_results_and_backwards_ranges() {
+        assert!(trim(&ramp(1), &[range(0, 1000)], TrimMode::Remove).is_err());
+        assert!(trim(&ramp(1), &[range(2000, 3000)], TrimMode::Keep).is_err());
+        assert!(trim(&ramp(1), &[range(300, 200)], TrimMode::Keep).is_err());
+    }
+
+    #[test]
+    fn remixes_between_mono_and_stereo() {
+        let stereo = AudioBuffer {
+            sample_rate: 1000,
+            channels: 2,
+            samples: vec![0.2, 0.4, -0.2, 0.0],
+        };
+        let mono = remix(&stereo, 1);
+        assert_eq!(mono.samples.len(), 2);
+        assert!((mono.samples[0] - 0.3).abs() < 1e-6);
+        assert_eq!(remix(&mono, 2).samples.len(), 4);
+        assert_eq!(remix(&mono, 2).samples[1], mono.samples[0]);
+    }
+
+    #[test]
+    fn concatenates_mixed_formats() {
+        let joined = concat(
+            &[
+                AudioBuffer::read(&fixture("mono_16k.wav")).unwrap(),
+                AudioBuffer::read(&fixture("stereo_16k.wav")).unwrap(),
+                AudioBuffer::read(&fixture("mono_8k.wav")).unwrap(),
+            ],
+            ResampleQuality::Fast,
+        )
+        .unwrap();
+        assert_eq!(joined.sample_rate, 16000);
+        assert_eq!(joined.channels, 2);
+        assert_eq!(joined.frames(), 4000 * 3);
+    }
+
+    #[test]
+    fn converts_rate_channels_and_encoding() {
+        let output =
+            std::env::temp_dir().join(format!("app-core-convert-{}.wav", std::process::id()));
+        let options = ConvertOptions {
+            encoding: Encoding::Float32,
+            sample_rate: Some(8000),
+            channels: Some(1),
+        };
+        convert_audio(
+            &fixture("stereo_16k.wav"),
+            &output,
+            options,
+            ResampleQuality::Fast,
+        )
+        .unwrap();
+        let converted = AudioBuffer::read(&output).unwrap();
+        assert_eq!(converted.sample_rate, 8000);
+        assert_eq!(converted.channels, 1);
+        assert_eq!(converted.frames(), 2000);
+    }
+
+    #[test]
+    fn splits_stereo_into_named_mono_files() {
+        let dir = std::env::temp_dir().join(format!("app-core-split-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("call.wav");
+        std::fs::copy(fixture("stereo_16k.wav"), &path).unwrap();
+        let stereo = AudioBuffer::read(&path).unwrap();
+
+        let parts = split_channels_file(&path).unwrap();
+        assert_eq!(
+            parts,
+            vec![dir.join("call (left).wav"), dir.join("call (right).wav")]
+        );
+        for (channel, part) in parts.iter().enumerate() {
+            let mono = AudioBuffer::read(part).unwrap();
+            assert_eq!(mono.channels, 1);
+            assert_eq!(mono.frames(), stereo.frames());
+            assert!((mono.samples[100] - stereo.samples[200 + channel]).abs() < 1e-3);
+        }
+        assert!(split_channels_file(&fixture("mono_16k.wav")).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn concatenating_nothing_fails() {
+        assert!(concat(&[], ResampleQuality::Fast).is_err());
+    }
+}
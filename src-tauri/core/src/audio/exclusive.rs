@@ -0,0 +1,216 @@
+//! WASAPI exclusive-mode capture, for interfaces that misbehave behind the
+//! Windows mixer. cpal only opens streams in shared mode, so this talks to
+//! WASAPI directly.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use wasapi::{AudioClient, Direction, SampleType, ShareMode, WaveFormat};
+
+/// What an exclusive stream was opened with.
+#[derive(Debug, Clone, Copy)]
+pub struct Format {
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Frames per period, when one was asked for.
+    pub buffer_frames: Option<u32>,
+}
+
+/// Called from the capture thread with interleaved samples.
+pub type OnData = Box<dyn FnMut(&[f32]) + Send>;
+
+/// A device held exclusively by a capture thread of its own. Nothing is
+/// captured until [`ExclusiveStream::start`]; dropping it stops capture and
+/// releases the device.
+pub struct ExclusiveStream {
+    stopping: Arc<AtomicBool>,
+    start: Option<Sender<OnData>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ExclusiveStream {
+    /// Starts feeding samples to `on_data`.
+    pub fn start(&mut self, on_data: OnData) -> Result<()> {
+        self.start
+            .take()
+            .context("exclusive stream already started")?
+            .send(on_data)
+            .map_err(|_| anyhow!("exclusive capture thread exited"))
+    }
+}
+
+impl Drop for ExclusiveStream {
+    fn drop(&mut self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        self.start = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// wasapi's errors aren't `Send`, so they're flattened to text.
+fn check<T>(result: Result<T, Box<dyn std::error::Error>>) -> Result<T> {
+    result.map_err(|err| anyhow!("{}", err))
+}
+
+/// Sample formats to try, best first. Exclusive mode takes only what the
+/// hardware does natively, so there's no conversion to fall back on.
+fn formats(sample_rate: usize, channels: usize) -> [WaveFormat; 4] {
+    [
+        WaveFormat::new(32, 32, &SampleType::Float, sample_rate, channels, None),
+        WaveFormat::new(32, 24, &SampleType::Int, sample_rate, channels, None),
+        WaveFormat::new(24, 24, &SampleType::Int, sample_rate, channels, None),
+        WaveFormat::new(16, 16, &SampleType::Int, sample_rate, channels, None),
+    ]
+}
+
+/// Opens the input device called `name` in exclusive mode, at
+/// `sample_rate` if given or the rate Windows mixes at otherwise. Fails, for
+/// instance, when another app holds the device or exclusive mode is turned
+/// off for it in the Sound control panel.
+pub fn open(
+    name: &str,
+    sample_rate: Option<u32>,
+    buffer_frames: Option<u32>,
+) -> Result<(ExclusiveStream, Format)> {
+    let (opened, result) = mpsc::channel();
+    let (start, started) = mpsc::channel::<OnData>();
+    let stopping = Arc::new(AtomicBool::new(false));
+    let thread = {
+        let name = name.to_string();
+        let stopping = stopping.clone();
+        std::thread::spawn(move || {
+            // COM is set up per thread, so the device is opened here too.
+            let client = wasapi::initialize_mta()
+                .ok()
+                .map_err(|err| anyhow!("{}", err))
+                .and_then(|_| initialize(&name, sample_rate, buffer_frames));
+            let (client, format, wave) = match client {
+                Ok(client) => client,
+                Err(err) => {
+                    let _ = opened.send(Err(err));
+                    return;
+                }
+            };
+            let _ = opened.send(Ok(format));
+            // Dropped before it was started.
+            let Ok(on_data) = started.recv() else {
+                return;
+            };
+            if let Err(err) = capture(client, &wave, on_data, &stopping) {
+                eprintln!("Exclusive capture from {:?} failed: {:?}", name, err);
+            }
+        })
+    };
+    let format = result
+        .recv()
+        .map_err(|_| anyhow!("exclusive capture thread exited"))??;
+    Ok((
+        ExclusiveStream {
+            stopping,
+            start: Some(start),
+            thread: Some(thread),
+        },
+        format,
+    ))
+}
+
+fn initialize(
+    name: &str,
+    sample_rate: Option<u32>,
+    buffer_frames: Option<u32>,
+) -> Result<(AudioClient, Format, WaveFormat)> {
+    let devices = check(wasapi::DeviceCollection::new(&Direction::Capture))?;
+    let device = check(devices.get_device_with_name(name))?;
+    let mut client = check(device.get_iaudioclient())?;
+    let mix = check(client.get_mixformat())?;
+    let rate = sample_rate.unwrap_or(mix.get_samplespersec()) as usize;
+    let channels = mix.get_nchannels() as usize;
+    let Some(wave) = formats(rate, channels)
+        .into_iter()
+        .find(|wave| matches!(client.is_supported(wave, &ShareMode::Exclusive), Ok(None)))
+    else {
+        bail!("{:?} has no exclusive-mode format at {} Hz", name, rate);
+    };
+    let (default_period, min_period) = check(client.get_periods())?;
+    // Periods are in 100 ns units.
+    let period = match buffer_frames {
+        Some(frames) => (frames as i64 * 10_000_000 / rate as i64).max(min_period),
+        None => default_period,
+    };
+    check(client.initialize_client(
+        &wave,
+        period,
+        &Direction::Capture,
+        &ShareMode::Exclusive,
+        false,
+    ))?;
+    let format = Format {
+        sample_rate: rate as u32,
+        channels: channels as u16,
+        buffer_frames: buffer_frames.map(|_| (period * rate as i64 / 10_000_000) as u32),
+    };
+    Ok((client, format, wave))
+}
+
+/// Reads from the device until `stopping` is set.
+fn capture(
+    client: AudioClient,
+    wave: &WaveFormat,
+    mut on_data: OnData,
+    stopping: &AtomicBool,
+) -> Result<()> {
+    let event = check(client.set_get_eventhandle())?;
+    let capture = check(client.get_audiocaptureclient())?;
+    let bytes = wave.get_blockalign() as usize / wave.get_nchannels().max(1) as usize;
+    let float = matches!(check(wave.get_subformat())?, SampleType::Float);
+    let mut raw = VecDeque::new();
+    let mut samples = Vec::new();
+    check(client.start_stream())?;
+    while !stopping.load(Ordering::SeqCst) {
+        if event.wait_for_event(200).is_err() {
+            continue;
+        }
+        check(capture.read_from_device_to_deque(&mut raw))?;
+        let whole = raw.len() - raw.len() % bytes;
+        let read: Vec<u8> = raw.drain(..whole).collect();
+        samples.clear();
+        samples.extend(read.chunks_exact(bytes).map(|sample| decode(sample, float)));
+        if !samples.is_empty() {
+            on_data(&samples);
+        }
+    }
+    check(client.stop_stream())
+}
+
+/// One little-endian sample as a float in [-1, 1].
+fn decode(sample: &[u8], float: bool) -> f32 {
+    match (sample.len(), float) {
+        (4, true) => f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]),
+        (4, false) => {
+            i32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]) as f32
+                / i32::MAX as f32
+        }
+        (3, _) => i32::from_le_bytes([0, sample[0], sample[1], sample[2]]) as f32 / i32::MAX as f32,
+        (2, _) => i16::from_le_bytes([sample[0], sample[1]]) as f32 / i16::MAX as f32,
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_each_sample_width() {
+        assert_eq!(decode(&0.5f32.to_le_bytes(), true), 0.5);
+        assert_eq!(decode(&i16::MAX.to_le_bytes(), false), 1.0);
+        assert_eq!(decode(&[0, 0, 0x80], false), -1.0);
+        let half = (i32::MAX / 2).to_le_bytes();
+        assert!((decode(&half, false) - 0.5).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,157 @@
+use anyhow::{bail, Result};
+use std::path::Path;
+
+use super::buffer::AudioBuffer;
+
+/// A second-order IIR filter in transposed direct form II.
+#[derive(Debug, Clone, Copy)]
+pub struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    /// Coefficients normalized so `a0` is 1.
+    pub fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Biquad { b, a, z: [0.0; 2] }
+    }
+
+    pub fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// Second-order Butterworth high-pass (RBJ cookbook) at `cutoff_hz`.
+pub fn high_pass(cutoff_hz: f32, sample_rate: u32) -> Biquad {
+    let w0 = 2.0 * std::f64::consts::PI * cutoff_hz as f64 / sample_rate as f64;
+    let alpha = w0.sin() / (2.0 * std::f64::consts::FRAC_1_SQRT_2);
+    let cos = w0.cos();
+    let a0 = 1.0 + alpha;
+    Biquad::new(
+        [
+            (1.0 + cos) / 2.0 / a0,
+            -(1.0 + cos) / a0,
+            (1.0 + cos) / 2.0 / a0,
+        ],
+        [-2.0 * cos / a0, (1.0 - alpha) / a0],
+    )
+}
+
+/// One filter per channel, for running over interleaved samples.
+pub struct ChannelFilters {
+    filters: Vec<Biquad>,
+    next: usize,
+}
+
+impl ChannelFilters {
+    pub fn new(filter: Biquad, channels: u16) -> Self {
+        ChannelFilters {
+            filters: vec![filter; channels.max(1) as usize],
+            next: 0,
+        }
+    }
+
+    /// Filters the next interleaved sample.
+    pub fn process(&mut self, sample: f32) -> f32 {
+        let y = self.filters[self.next].process(sample as f64) as f32;
+        self.next = (self.next + 1) % self.filters.len();
+        y
+    }
+}
+
+/// Removes rumble below `cutoff_hz` (desk thumps, HVAC) from every channel.
+pub fn high_pass_filter(buffer: &mut AudioBuffer, cutoff_hz: f32) -> Result<()> {
+    let nyquist = buffer.sample_rate as f32 / 2.0;
+    if !(cutoff_hz > 0.0 && cutoff_hz < nyquist) {
+        bail!("cutoff must be between 0 and {} Hz", nyquist);
+    }
+    let mut filters =
+        ChannelFilters::new(high_pass(cutoff_hz, buffer.sample_rate), buffer.channels);
+    for sample in &mut buffer.samples {
+        *sample = filters.process(*sample);
+    }
+    Ok(())
+}
+
+/// High-pass filters the WAV file at `path` in place.
+pub fn apply_high_pass(path: &Path, cutoff_hz: f32) -> Result<()> {
+    let mut buffer = AudioBuffer::read(path)?;
+    high_pass_filter(&mut buffer, cutoff_hz)?;
+    buffer.replace(path)
+}
+
+/// The two-stage "K" pre-filter of ITU-R BS.1770: a high shelf modelling the
+/// head followed by a high-pass, as computed by libebur128 for any rate.
+pub fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let fs = sample_rate as f64;
+
+    let f0 = 1681.974450955533;
+    let gain_db = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad::new(
+        [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad::new(
+        [1.0, -2.0, 1.0],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    [shelf, high_pass]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rms_of_sine(frequency: f32, cutoff_hz: f32) -> f32 {
+        let sample_rate = 16_000;
+        let mut buffer = AudioBuffer {
+            sample_rate,
+            channels: 1,
+            samples: (0..sample_rate)
+                .map(|i| {
+                    (2.0 * std::f32::consts::PI * frequency * i as f32 / sample_rate as f32).sin()
+                })
+                .collect(),
+        };
+        high_pass_filter(&mut buffer, cutoff_hz).unwrap();
+        // Skip the filter's settling time.
+        let tail = &buffer.samples[4000..];
+        (tail.iter().map(|s| s * s).sum::<f32>() / tail.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn high_pass_cuts_rumble_and_keeps_speech() {
+        let full = std::f32::consts::FRAC_1_SQRT_2;
+        assert!(rms_of_sine(20.0, 100.0) < full * 0.1);
+        assert!((rms_of_sine(1000.0, 100.0) - full).abs() < 0.02);
+    }
+
+    #[test]
+    fn high_pass_rejects_cutoff_above_nyquist() {
+        let mut buffer = AudioBuffer {
+            sample_rate: 8000,
+            channels: 1,
+            samples: vec![0.0; 10],
+        };
+        assert!(high_pass_filter(&mut buffer, 5000.0).is_err());
+    }
+}
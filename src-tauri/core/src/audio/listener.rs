@@ -0,0 +1,168 @@
+use anyhow::{anyhow, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use rtrb::RingBuffer;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::resample::{ResampleQuality, StreamResampler};
+use super::vad::Vad;
+use crate::i18n::{t, Msg};
+
+/// Audio kept from before speech was detected, so the first syllable isn't
+/// clipped.
+const PRE_ROLL_MS: usize = 300;
+
+/// How long the listening thread sleeps when it has caught up.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Cuts a mono stream into utterances: stretches of speech with a little
+/// lead-in. Speech running longer than the maximum is conversation, not a
+/// command, and is dropped.
+pub struct Utterances {
+    vad: Vad,
+    frame_len: usize,
+    pre_roll: usize,
+    max_len: usize,
+    buffer: Vec<f32>,
+    /// Where the utterance being spoken starts in `buffer`.
+    start: Option<usize>,
+}
+
+impl Utterances {
+    pub fn new(sample_rate: u32, max_ms: usize) -> Self {
+        let per_ms = sample_rate as usize / 1000;
+        Utterances {
+            vad: Vad::new(sample_rate, 1),
+            frame_len: per_ms * 20,
+            pre_roll: per_ms * PRE_ROLL_MS,
+            max_len: per_ms * max_ms,
+            buffer: Vec::new(),
+            start: None,
+        }
+    }
+
+    /// Feeds samples through, returning the utterances that ended in them.
+    pub fn push(&mut self, samples: &[f32]) -> Vec<Vec<f32>> {
+        let mut finished = Vec::new();
+        for chunk in samples.chunks(self.frame_len) {
+            let was_speaking = self.vad.speaking();
+            self.vad.push(chunk);
+            self.buffer.extend_from_slice(chunk);
+            match (was_speaking, self.vad.speaking()) {
+                (false, true) => {
+                    // The detector needs a few frames to decide, so reach back
+                    // past them as well as the pre-roll.
+                    self.start = Some(self.buffer.len().saturating_sub(self.pre_roll));
+                }
+                (true, false) => {
+                    if let Some(start) = self.start.take() {
+                        finished.push(self.buffer[start..].to_vec());
+                    }
+                }
+                _ => {}
+            }
+            match self.start {
+                Some(start) if self.buffer.len() - start > self.max_len => self.start = None,
+                Some(_) => continue,
+                None => {}
+            }
+            let excess = self.buffer.len().saturating_sub(self.pre_roll);
+            self.buffer.drain(..excess);
+        }
+        finished
+    }
+}
+
+/// Listens on the default input device until `stop` is set, handing each
+/// utterance of at most `max_ms` to `on_utterance` as mono samples at
+/// `sample_rate`. Blocks the calling thread, which owns the stream.
+pub fn listen(
+    sample_rate: u32,
+    max_ms: usize,
+    stop: Arc<AtomicBool>,
+    mut on_utterance: impl FnMut(Vec<f32>),
+) -> Result<()> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or_else(|| anyhow!(t(Msg::NoInputDevice)))?;
+    let config = device.default_input_config()?;
+    let channels = config.channels().max(1) as usize;
+    let device_rate = config.sample_rate().0;
+    let (mut producer, mut consumer) = RingBuffer::<f32>::new(device_rate as usize);
+    let stream = device.build_input_stream(
+        &config.into(),
+        move |data: &[f32], _: &cpal::InputCallbackInfo| {
+            // Mixed down here so the ring only ever holds whole frames.
+            for frame in data.chunks(channels) {
+                let _ = producer.push(frame.iter().sum::<f32>() / frame.len() as f32);
+            }
+        },
+        |err| eprintln!("Error: {:?}", err),
+        Some(Duration::from_secs(30)),
+    )?;
+    stream.play()?;
+
+    let mut resampler = (device_rate != sample_rate)
+        .then(|| StreamResampler::new(device_rate, sample_rate as f64, ResampleQuality::Fast))
+        .transpose()?;
+    let mut utterances = Utterances::new(sample_rate, max_ms);
+    let mut batch = Vec::new();
+    while !stop.load(Ordering::SeqCst) {
+        let available = consumer.slots();
+        if available == 0 {
+            std::thread::sleep(POLL_INTERVAL);
+            continue;
+        }
+        let chunk = consumer.read_chunk(available)?;
+        let (first, second) = chunk.as_slices();
+        batch.clear();
+        batch.extend_from_slice(first);
+        batch.extend_from_slice(second);
+        chunk.commit_all();
+        let samples = match &mut resampler {
+            Some(resampler) => {
+                resampler.push(&batch)?;
+                resampler.drain()
+            }
+            None => batch.clone(),
+        };
+        for utterance in utterances.push(&samples) {
+            on_utterance(utterance);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(amplitude: f32, ms: usize) -> Vec<f32> {
+        (0..ms * 16)
+            .map(|i| amplitude * (i as f32 * 0.1).sin())
+            .collect()
+    }
+
+    #[test]
+    fn cuts_out_short_utterances() {
+        let mut utterances = Utterances::new(16_000, 3000);
+        assert!(utterances.push(&tone(0.001, 1000)).is_empty());
+        assert!(utterances.push(&tone(0.2, 1000)).is_empty());
+        let found = utterances.push(&tone(0.001, 1000));
+        assert_eq!(found.len(), 1);
+        // The speech, the lead-in and the detector's hangover.
+        let ms = found[0].len() / 16;
+        assert!((1300..=1700).contains(&ms), "{}", ms);
+    }
+
+    #[test]
+    fn long_speech_is_dropped() {
+        let mut utterances = Utterances::new(16_000, 3000);
+        utterances.push(&tone(0.001, 1000));
+        utterances.push(&tone(0.2, 5000));
+        assert!(utterances.push(&tone(0.001, 1000)).is_empty());
+        // Memory doesn't grow while it goes on.
+        assert!(utterances.buffer.len() <= utterances.pre_roll);
+    }
+}
@@ -0,0 +1,157 @@
+use anyhow::{bail, Result};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::path::Path;
+
+use super::buffer::AudioBuffer;
+use super::dynamics;
+use super::filters::k_weighting;
+
+const BLOCK_SECS: f64 = 0.4;
+const STEP_SECS: f64 = 0.1;
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+/// What [`normalize_loudness`] measured and did.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Normalization {
+    pub measured_lufs: f64,
+    pub gain_db: f64,
+}
+
+fn lufs(mean_square: f64) -> f64 {
+    -0.691 + 10.0 * mean_square.log10()
+}
+
+/// Integrated loudness per EBU R128 / ITU-R BS.1770: K-weighted, in 400 ms
+/// blocks overlapping by 75 %, with the absolute and relative gates. Every
+/// channel is weighted equally, which is exact for mono and stereo. `None`
+/// when the audio is shorter than a block or silent.
+pub fn integrated_loudness(buffer: &AudioBuffer) -> Option<f64> {
+    let channels = buffer.channels.max(1) as usize;
+    let block = (BLOCK_SECS * buffer.sample_rate as f64) as usize;
+    let step = (STEP_SECS * buffer.sample_rate as f64) as usize;
+    let frames = buffer.frames();
+    if block == 0 || frames < block {
+        return None;
+    }
+
+    // Squared K-weighted samples summed over channels, per frame. The
+    // filters are recursive, so channels rather than chunks run in parallel.
+    let power = (0..channels)
+        .into_par_iter()
+        .map(|channel| {
+            let mut filters = k_weighting(buffer.sample_rate);
+            (0..frames)
+                .map(|frame| {
+                    let x = buffer.samples[frame * channels + channel] as f64;
+                    let y = filters.iter_mut().fold(x, |x, filter| filter.process(x));
+                    y * y
+                })
+                .collect::<Vec<f64>>()
+        })
+        .reduce_with(|mut total, channel| {
+            total.iter_mut().zip(channel).for_each(|(t, p)| *t += p);
+            total
+        })
+        .unwrap_or_default();
+
+    let mut prefix = Vec::with_capacity(frames + 1);
+    prefix.push(0f64);
+    for p in &power {
+        prefix.push(prefix.last().unwrap() + p);
+    }
+    let blocks: Vec<f64> = (0..=(frames - block) / step)
+        .map(|i| (prefix[i * step + block] - prefix[i * step]) / block as f64)
+        .filter(|&z| lufs(z) > ABSOLUTE_GATE_LUFS)
+        .collect();
+    if blocks.is_empty() {
+        return None;
+    }
+    let relative_gate = lufs(blocks.iter().sum::<f64>() / blocks.len() as f64) + RELATIVE_GATE_LU;
+    let gated: Vec<f64> = blocks
+        .into_iter()
+        .filter(|&z| lufs(z) > relative_gate)
+        .collect();
+    Some(lufs(gated.iter().sum::<f64>() / gated.len() as f64))
+}
+
+/// Scales `buffer` so its integrated loudness becomes `target_lufs`. Peaks
+/// the gain pushes past -1 dBFS are limited rather than clipped.
+pub fn normalize(buffer: &mut AudioBuffer, target_lufs: f64) -> Result<Normalization> {
+    let Some(measured_lufs) = integrated_loudness(buffer) else {
+        bail!("the audio is too short or quiet to measure its loudness");
+    };
+    let gain_db = target_lufs - measured_lufs;
+    let gain = 10f64.powf(gain_db / 20.0) as f32;
+    buffer
+        .samples
+        .par_iter_mut()
+        .for_each(|sample| *sample *= gain);
+    if gain > 1.0 {
+        dynamics::limit(buffer, dynamics::DEFAULT_CEILING);
+    }
+    Ok(Normalization {
+        measured_lufs,
+        gain_db,
+    })
+}
+
+/// Normalizes the WAV file at `path` in place to `target_lufs`, e.g. -16 for
+/// spoken word or -23 for broadcast.
+pub fn normalize_loudness(path: &Path, target_lufs: f64) -> Result<Normalization> {
+    let mut buffer = AudioBuffer::read(path)?;
+    let normalization = normalize(&mut buffer, target_lufs)?;
+    buffer.replace(path)?;
+    Ok(normalization)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(amplitude: f32, frequency: f32, secs: f32, channels: u16) -> AudioBuffer {
+        let sample_rate = 48_000;
+        let frames = (secs * sample_rate as f32) as usize;
+        AudioBuffer {
+            sample_rate,
+            channels,
+            samples: (0..frames)
+                .flat_map(|i| {
+                    let t = i as f32 / sample_rate as f32;
+                    let s = amplitude * (2.0 * std::f32::consts::PI * frequency * t).sin();
+                    std::iter::repeat(s).take(channels as usize)
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn full_scale_sine_is_minus_three_lufs() {
+        // The BS.1770 reference: a 997 Hz sine at 0 dBFS in one channel.
+        let loudness = integrated_loudness(&sine(1.0, 997.0, 2.0, 1)).unwrap();
+        assert!((loudness + 3.01).abs() < 0.05, "{}", loudness);
+    }
+
+    #[test]
+    fn stereo_sums_channels() {
+        let loudness = integrated_loudness(&sine(0.1, 997.0, 2.0, 2)).unwrap();
+        assert!((loudness + 20.0).abs() < 0.05, "{}", loudness);
+    }
+
+    #[test]
+    fn normalizes_to_target() {
+        let mut buffer = sine(0.05, 997.0, 2.0, 1);
+        let normalization = normalize(&mut buffer, -16.0).unwrap();
+        assert!(normalization.gain_db > 0.0);
+        let loudness = integrated_loudness(&buffer).unwrap();
+        assert!((loudness + 16.0).abs() < 0.05, "{}", loudness);
+    }
+
+    #[test]
+    fn silence_cannot_be_normalized() {
+        let mut buffer = sine(0.0, 997.0, 1.0, 1);
+        assert!(normalize(&mut buffer, -16.0).is_err());
+        assert_eq!(integrated_loudness(&sine(1.0, 997.0, 0.2, 1)), None);
+    }
+}
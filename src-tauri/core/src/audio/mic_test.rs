@@ -0,0 +1,121 @@
+use anyhow::{anyhow, Result};
+use cpal::traits::{DeviceTrait, StreamTrait};
+use rtrb::RingBuffer;
+use serde::Serialize;
+use std::time::Duration;
+
+use super::buffer::AudioBuffer;
+use super::devices;
+
+/// Levels below this are reported as this rather than -inf.
+const FLOOR_DB: f32 = -120.0;
+
+/// What a short test recording picked up.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MicTest {
+    pub device: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub peak_db: f32,
+    pub rms_db: f32,
+    /// Samples at or above full scale; the input gain is too high.
+    pub clipped_samples: usize,
+    /// The recording itself, e.g. to play back.
+    #[serde(skip)]
+    pub audio: AudioBuffer,
+}
+
+fn to_db(level: f32) -> f32 {
+    (20.0 * level.log10()).max(FLOOR_DB)
+}
+
+/// Peak and RMS level of a buffer in dBFS, and how many samples clipped.
+pub fn levels(buffer: &AudioBuffer) -> (f32, f32, usize) {
+    let peak = buffer
+        .samples
+        .iter()
+        .fold(0f32, |peak, s| peak.max(s.abs()));
+    let rms = (buffer.samples.iter().map(|s| s * s).sum::<f32>()
+        / buffer.samples.len().max(1) as f32)
+        .sqrt();
+    let clipped = buffer.samples.iter().filter(|s| s.abs() >= 1.0).count();
+    (to_db(peak), to_db(rms), clipped)
+}
+
+/// Records `duration` from the input device called `device` (the default if
+/// `None`) into memory, blocking the calling thread, and measures it.
+pub fn test_microphone(device: Option<&str>, duration: Duration) -> Result<MicTest> {
+    let host = cpal::default_host();
+    let device = devices::input_device(&host, device)?;
+    let name = device.name().unwrap_or_default();
+    let config = device.default_input_config()?;
+    let sample_rate = config.sample_rate().0;
+    let channels = config.channels();
+    let capacity = (duration.as_secs_f64() * sample_rate as f64) as usize * channels as usize;
+    let (mut producer, mut consumer) = RingBuffer::<f32>::new(capacity.max(1));
+    let stream = device.build_input_stream(
+        &config.into(),
+        move |data: &[f32], _: &cpal::InputCallbackInfo| {
+            let n = data.len().min(producer.slots());
+            if let Ok(chunk) = producer.write_chunk_uninit(n) {
+                chunk.fill_from_iter(data.iter().copied());
+            }
+        },
+        |err| eprintln!("Error: {:?}", err),
+        Some(Duration::from_secs(5)),
+    )?;
+    stream.play()?;
+    std::thread::sleep(duration);
+    drop(stream);
+
+    let chunk = consumer.read_chunk(consumer.slots())?;
+    let (first, second) = chunk.as_slices();
+    let samples = [first, second].concat();
+    chunk.commit_all();
+    if samples.is_empty() {
+        return Err(anyhow!("{} didn't deliver any audio", name));
+    }
+    let audio = AudioBuffer {
+        sample_rate,
+        channels,
+        samples,
+    };
+    let (peak_db, rms_db, clipped_samples) = levels(&audio);
+    Ok(MicTest {
+        device: name,
+        sample_rate,
+        channels,
+        peak_db,
+        rms_db,
+        clipped_samples,
+        audio,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_levels() {
+        let buffer = AudioBuffer {
+            sample_rate: 1000,
+            channels: 1,
+            samples: vec![0.5, -0.5, 0.5, -1.0],
+        };
+        let (peak_db, rms_db, clipped) = levels(&buffer);
+        assert_eq!(peak_db, 0.0);
+        assert!((rms_db - -3.59).abs() < 0.01, "{}", rms_db);
+        assert_eq!(clipped, 1);
+    }
+
+    #[test]
+    fn silence_is_floored() {
+        let buffer = AudioBuffer {
+            sample_rate: 1000,
+            channels: 1,
+            samples: vec![0.0; 10],
+        };
+        assert_eq!(levels(&buffer).0, FLOOR_DB);
+    }
+}
@@ -0,0 +1,27 @@
+pub mod buffer;
+pub mod decode;
+pub mod devices;
+pub mod dynamics;
+pub mod edit;
+#[cfg(windows)]
+pub mod exclusive;
+pub mod filters;
+pub mod listener;
+pub mod loudness;
+pub mod mic_test;
+pub mod playback;
+pub mod recorder;
+pub mod repair;
+pub mod resample;
+pub mod silence;
+pub mod spectrogram;
+pub mod vad;
+pub mod wav;
+pub mod waveform;
+
+pub use buffer::{AudioBuffer, Encoding};
+pub use loudness::normalize_loudness;
+pub use playback::{PlaybackStatus, Player};
+pub use recorder::{AudioController, CaptureOptions, Recorder, StreamInfo};
+pub use repair::repair_wav;
+pub use resample::{resample_mono, ResampleQuality};
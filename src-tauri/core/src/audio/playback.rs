@@ -0,0 +1,184 @@
+use anyhow::{anyhow, Context, Result};
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+use serde::Serialize;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::time::Duration;
+
+use super::devices;
+use crate::jobs::Worker;
+
+/// Where playback is at, as reported to the frontend.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PlaybackStatus {
+    /// The file loaded for playback, if any.
+    pub path: Option<PathBuf>,
+    pub position_ms: u64,
+    /// Unknown for files whose header doesn't say.
+    pub duration_ms: Option<u64>,
+    pub playing: bool,
+    /// The file played through to its end.
+    pub finished: bool,
+}
+
+/// The output stream and what's playing on it. Lives on the player thread,
+/// since rodio's `OutputStream` isn't `Send`.
+#[derive(Default)]
+struct Playback {
+    output: Option<(OutputStream, OutputStreamHandle)>,
+    /// The device `output` was opened on; `None` is the default.
+    device: Option<String>,
+    sink: Option<Sink>,
+    path: Option<PathBuf>,
+    duration_ms: Option<u64>,
+}
+
+impl Playback {
+    fn play(&mut self, path: PathBuf, start_ms: u64, device: Option<String>) -> Result<()> {
+        self.stop();
+        let source = Decoder::new(BufReader::new(
+            File::open(&path).with_context(|| format!("failed to open {}", path.display()))?,
+        ))
+        .context("not a playable audio file")?;
+        if self.output.is_none() || self.device != device {
+            self.output = None;
+            let output = devices::output_device(device.as_deref())?;
+            self.output = Some(
+                OutputStream::try_from_device(&output).context("failed to open output device")?,
+            );
+            self.device = device;
+        }
+        let (_, handle) = self.output.as_ref().unwrap();
+        let sink = Sink::try_new(handle)?;
+        self.duration_ms = source.total_duration().map(|d| d.as_millis() as u64);
+        sink.append(source);
+        if start_ms > 0 {
+            sink.try_seek(Duration::from_millis(start_ms))
+                .map_err(|err| anyhow!("failed to seek: {}", err))?;
+        }
+        self.sink = Some(sink);
+        self.path = Some(path);
+        Ok(())
+    }
+
+    fn sink(&self) -> Result<&Sink> {
+        self.sink.as_ref().context("nothing is playing")
+    }
+
+    fn stop(&mut self) {
+        if let Some(sink) = self.sink.take() {
+            sink.stop();
+        }
+        self.path = None;
+        self.duration_ms = None;
+    }
+
+    fn status(&self) -> PlaybackStatus {
+        let Some(sink) = &self.sink else {
+            return PlaybackStatus::default();
+        };
+        let finished = sink.empty();
+        PlaybackStatus {
+            path: self.path.clone(),
+            position_ms: match (finished, self.duration_ms) {
+                (true, Some(duration_ms)) => duration_ms,
+                _ => sink.get_pos().as_millis() as u64,
+            },
+            duration_ms: self.duration_ms,
+            playing: !finished && !sink.is_paused(),
+            finished,
+        }
+    }
+}
+
+enum PlayerCommand {
+    Play(PathBuf, u64, Option<String>, Sender<Result<()>>),
+    Pause(Sender<Result<()>>),
+    Resume(Sender<Result<()>>),
+    Seek(u64, Sender<Result<()>>),
+    Stop(Sender<Result<()>>),
+    Status(Sender<PlaybackStatus>),
+}
+
+/// Plays one audio file at a time on its own thread.
+pub struct Player {
+    worker: Worker<PlayerCommand>,
+}
+
+impl Player {
+    pub fn new() -> Self {
+        let worker = Worker::spawn(Playback::default, |playback, command| match command {
+            PlayerCommand::Play(path, start_ms, device, reply) => {
+                let _ = reply.send(playback.play(path, start_ms, device));
+            }
+            PlayerCommand::Pause(reply) => {
+                let _ = reply.send(playback.sink().map(Sink::pause));
+            }
+            PlayerCommand::Resume(reply) => {
+                let _ = reply.send(playback.sink().map(Sink::play));
+            }
+            PlayerCommand::Seek(position_ms, reply) => {
+                let _ = reply.send(playback.sink().and_then(|sink| {
+                    sink.try_seek(Duration::from_millis(position_ms))
+                        .map_err(|err| anyhow!("failed to seek: {}", err))
+                }));
+            }
+            PlayerCommand::Stop(reply) => {
+                playback.stop();
+                let _ = reply.send(Ok(()));
+            }
+            PlayerCommand::Status(reply) => {
+                let _ = reply.send(playback.status());
+            }
+        });
+        Player { worker }
+    }
+
+    /// Starts playing `path` from `start_ms` on the output device called
+    /// `device` (the default if `None`), replacing whatever was playing.
+    pub fn play(&self, path: PathBuf, start_ms: u64, device: Option<String>) -> Result<()> {
+        self.request(|reply| PlayerCommand::Play(path, start_ms, device, reply))
+    }
+
+    pub fn pause(&self) -> Result<()> {
+        self.request(PlayerCommand::Pause)
+    }
+
+    pub fn resume(&self) -> Result<()> {
+        self.request(PlayerCommand::Resume)
+    }
+
+    /// Jumps to `position_ms` in the current file, keeping it paused or
+    /// playing.
+    pub fn seek(&self, position_ms: u64) -> Result<()> {
+        self.request(|reply| PlayerCommand::Seek(position_ms, reply))
+    }
+
+    pub fn stop(&self) -> Result<()> {
+        self.request(PlayerCommand::Stop)
+    }
+
+    pub fn status(&self) -> Result<PlaybackStatus> {
+        let (reply, response) = mpsc::channel();
+        self.worker.send(PlayerCommand::Status(reply))?;
+        response
+            .recv()
+            .map_err(|_| anyhow!("player thread has stopped"))
+    }
+
+    fn request(&self, command: impl FnOnce(Sender<Result<()>>) -> PlayerCommand) -> Result<()> {
+        let (reply, response) = mpsc::channel();
+        self.worker.send(command(reply))?;
+        response
+            .recv()
+            .map_err(|_| anyhow!("player thread has stopped"))?
+    }
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self::new()
+    }
+}
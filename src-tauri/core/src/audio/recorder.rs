@@ -0,0 +1,774 @@
+use anyhow::{anyhow, bail, Result};
+use cpal::traits::{DeviceTrait, StreamTrait};
+use cpal::{BufferSize, Device, Stream, SupportedBufferSize};
+use hound::{WavSpec, WavWriter};
+use rtrb::{Consumer, Producer, RingBuffer};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use super::buffer::AudioBuffer;
+use super::devices::{self, DeviceProfile};
+use super::dynamics::NoiseGate;
+#[cfg(windows)]
+use super::exclusive::{self, ExclusiveStream};
+use super::filters::{self, ChannelFilters};
+use super::resample::{ResampleQuality, StreamResampler};
+use super::vad::Vad;
+use crate::i18n::{t, tf, Msg};
+use crate::jobs::Worker;
+
+/// How often the WAV header is brought up to date while recording, bounding
+/// what a crash can lose.
+const FLUSH_INTERVAL_SECS: u64 = 2;
+
+/// Processing applied to samples as they're captured.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CaptureOptions {
+    /// Input devices to record from, most preferred first. The first one
+    /// that's plugged in is used, falling back to the system default.
+    pub preferred_devices: Vec<String>,
+    /// Cut rumble below this frequency; sensible values are 80–120 Hz.
+    pub high_pass_hz: Option<f32>,
+    /// Also keep a mono `f32` copy of the take at this rate in memory, handed
+    /// back by [`Recorder::stop`]. Lets a take be transcribed without going
+    /// through 16-bit samples on disk.
+    pub tap_sample_rate: Option<u32>,
+    /// Keep the take only in the tap, without writing a WAV file. Meant for
+    /// short notes: nothing survives a crash.
+    pub memory_only: bool,
+    /// Frames per device buffer. Larger buffers add latency but ride out
+    /// hiccups on flaky USB interfaces. `None` leaves it to the driver;
+    /// requests outside what the device supports are clamped.
+    pub buffer_frames: Option<u32>,
+    /// Saved settings for each input device by name, applied to whichever
+    /// one is recorded from.
+    pub profiles: BTreeMap<String, DeviceProfile>,
+    /// Windows only: hold the device in WASAPI exclusive mode, bypassing the
+    /// system mixer. Devices that refuse it are recorded in shared mode.
+    pub exclusive: bool,
+}
+
+/// Turns the device's frames into the frames written to the take, applying
+/// its profile and the high-pass filter.
+struct Processor {
+    device_channels: usize,
+    /// Device channels kept, in the order they're written.
+    selected: Vec<usize>,
+    gain: f32,
+    high_pass: Option<ChannelFilters>,
+    gate: Option<NoiseGate>,
+}
+
+impl Processor {
+    fn new(
+        profile: &DeviceProfile,
+        high_pass_hz: Option<f32>,
+        sample_rate: u32,
+        device_channels: u16,
+    ) -> Self {
+        let device_channels = device_channels.max(1) as usize;
+        let mut selected: Vec<usize> = profile
+            .channels
+            .iter()
+            .map(|&channel| channel as usize)
+            .filter(|&channel| channel < device_channels)
+            .collect();
+        if selected.is_empty() {
+            selected = (0..device_channels).collect();
+        }
+        Processor {
+            device_channels,
+            gain: 10f32.powf(profile.gain_db / 20.0),
+            high_pass: high_pass_hz.map(|hz| {
+                ChannelFilters::new(filters::high_pass(hz, sample_rate), selected.len() as u16)
+            }),
+            gate: profile.denoise.then(|| NoiseGate::new(sample_rate)),
+            selected,
+        }
+    }
+
+    /// Appends the processed frames of `input` to `output`.
+    fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        for frame in input.chunks_exact(self.device_channels) {
+            let start = output.len();
+            output.extend(
+                self.selected
+                    .iter()
+                    .map(|&channel| frame[channel] * self.gain),
+            );
+            let frame = &mut output[start..];
+            if let Some(filters) = &mut self.high_pass {
+                for sample in frame.iter_mut() {
+                    *sample = filters.process(*sample);
+                }
+            }
+            if let Some(gate) = &mut self.gate {
+                gate.process_frame(frame);
+            }
+        }
+    }
+}
+
+/// The buffer size to ask the device for.
+fn buffer_size(requested: Option<u32>, supported: &SupportedBufferSize) -> BufferSize {
+    match (requested, supported) {
+        (Some(frames), SupportedBufferSize::Range { min, max }) => {
+            BufferSize::Fixed(frames.clamp(*min, *max))
+        }
+        (Some(_), SupportedBufferSize::Unknown) => {
+            eprintln!("Input device doesn't report buffer sizes, using its default");
+            BufferSize::Default
+        }
+        (None, _) => BufferSize::Default,
+    }
+}
+
+/// Rates at or below this are what Bluetooth hands-free profiles offer.
+pub const NARROWBAND_MAX_RATE: u32 = 16_000;
+
+/// What the capture stream actually ended up with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StreamInfo {
+    pub device: String,
+    /// None of the preferred devices were available, so the system default
+    /// was used instead.
+    pub fallback: bool,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// The device only offers a telephone-quality rate, as Bluetooth headsets
+    /// do while their microphone is in use (HFP at 8 or 16 kHz). The take is
+    /// recorded at that rate, but transcripts will be less accurate.
+    pub narrowband: bool,
+    /// The buffer size asked for, after clamping; `None` for the default.
+    pub requested_buffer_frames: Option<u32>,
+    /// The device is held in exclusive mode rather than shared through the
+    /// system mixer.
+    pub exclusive: bool,
+    /// How long the input has been near-silent, e.g. because the microphone
+    /// is muted. Zero while there's signal.
+    pub silent_ms: u64,
+    /// Someone is talking right now, by voice activity detection.
+    pub speaking: bool,
+    /// How much of the take so far is speech, in ms.
+    pub speech_ms: u64,
+    /// Frames the device delivered in its latest callback, which is what the
+    /// driver really negotiated. `None` until the first callback.
+    pub buffer_frames: Option<u32>,
+}
+
+/// The in-memory copy of a take, mixed down and resampled as it arrives.
+struct Tap {
+    sample_rate: u32,
+    /// `None` when the device already runs at `sample_rate`.
+    resampler: Option<StreamResampler>,
+    samples: Vec<f32>,
+    frame: Vec<f32>,
+}
+
+impl Tap {
+    fn new(device_rate: u32, sample_rate: u32) -> Result<Self> {
+        Ok(Tap {
+            sample_rate,
+            resampler: (device_rate != sample_rate)
+                .then(|| {
+                    StreamResampler::new(device_rate, sample_rate as f64, ResampleQuality::Fast)
+                })
+                .transpose()?,
+            samples: Vec::new(),
+            frame: Vec::new(),
+        })
+    }
+
+    fn push(&mut self, data: &[f32], channels: usize) {
+        self.frame.clear();
+        self.frame.extend(
+            data.chunks(channels)
+                .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32),
+        );
+        match &mut self.resampler {
+            Some(resampler) => {
+                if let Err(err) = resampler.push(&self.frame) {
+                    eprintln!("Failed to resample captured audio: {:?}", err);
+                }
+            }
+            None => self.samples.extend_from_slice(&self.frame),
+        }
+    }
+
+    fn finish(self) -> Result<AudioBuffer> {
+        let samples = match self.resampler {
+            Some(resampler) => resampler.finish()?,
+            None => self.samples,
+        };
+        Ok(AudioBuffer {
+            sample_rate: self.sample_rate,
+            channels: 1,
+            samples,
+        })
+    }
+}
+
+/// Seconds of audio the capture callback can get ahead of the writer thread
+/// before samples are dropped.
+const RING_SECS: usize = 4;
+
+/// How long the writer thread sleeps when it has caught up.
+const WRITE_INTERVAL: Duration = Duration::from_millis(20);
+
+/// The capture stream and the thread writing what it captures to disk. The
+/// audio callback only filters samples and pushes them into a lock-free ring
+/// buffer, so slow disks can't cause dropouts.
+pub struct Recorder {
+    stream: Option<Source>,
+    writer: Option<JoinHandle<Result<Option<AudioBuffer>>>>,
+    stopping: Arc<AtomicBool>,
+    dropped: Arc<AtomicUsize>,
+    info: Option<StreamInfo>,
+    /// Frames per callback, written by the audio callback.
+    callback_frames: Arc<AtomicU32>,
+    activity: Arc<Activity>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self {
+            stream: None,
+            writer: None,
+            stopping: Arc::new(AtomicBool::new(false)),
+            dropped: Arc::new(AtomicUsize::new(0)),
+            info: None,
+            callback_frames: Arc::new(AtomicU32::new(0)),
+            activity: Arc::default(),
+        }
+    }
+
+    /// Starts capturing from the preferred input device into a new WAV file
+    /// at `output_path`, unless [`CaptureOptions::memory_only`] is set. A
+    /// device that's busy, say held in exclusive mode by another app, is
+    /// skipped in favour of the next one.
+    pub fn start(&mut self, output_path: &Path, options: CaptureOptions) -> Result<()> {
+        let candidates =
+            devices::input_candidates(&cpal::default_host(), &options.preferred_devices);
+        let mut failures = Vec::new();
+        let mut opened = None;
+        for (device, fallback) in candidates {
+            let name = device.name().unwrap_or_default();
+            match open(&device, name.clone(), fallback, &options) {
+                Ok(capture) => {
+                    opened = Some(capture);
+                    break;
+                }
+                Err(err) => {
+                    eprintln!("Failed to open {:?}: {:?}", name, err);
+                    failures.push(name);
+                }
+            }
+        }
+        let Some(capture) = opened else {
+            return Err(match failures.is_empty() {
+                true => anyhow!(t(Msg::NoInputDevice)),
+                false => anyhow!(tf(
+                    Msg::InputDeviceBusy,
+                    &[("device", &failures.join(", "))]
+                )),
+            });
+        };
+
+        if options.memory_only && options.tap_sample_rate.is_none() {
+            bail!("a memory-only take needs a tap to keep it in");
+        }
+        let writer = match options.memory_only {
+            true => None,
+            false => Some(WavWriter::create(output_path, capture.spec)?),
+        };
+        let tap = options
+            .tap_sample_rate
+            .map(|rate| Tap::new(capture.spec.sample_rate, rate))
+            .transpose()?;
+        let channels = capture.spec.channels.max(1) as usize;
+        self.stopping = Arc::new(AtomicBool::new(false));
+        self.dropped = capture.dropped;
+        self.callback_frames = capture.callback_frames;
+        self.activity = Arc::default();
+        let monitor = SignalMonitor::new(capture.spec.sample_rate, channels, self.activity.clone());
+        let stopping = self.stopping.clone();
+        let consumer = capture.consumer;
+        self.writer = Some(std::thread::spawn(move || {
+            write_samples(consumer, writer, tap, monitor, stopping)
+        }));
+        self.stream = Some(capture.stream);
+        self.info = Some(capture.info);
+        Ok(())
+    }
+
+    /// The stream being recorded from, if any.
+    pub fn info(&self) -> Option<StreamInfo> {
+        let frames = self.callback_frames.load(Ordering::Relaxed);
+        self.info.clone().map(|info| StreamInfo {
+            buffer_frames: (frames > 0).then_some(frames),
+            silent_ms: self.activity.silent_ms.load(Ordering::Relaxed),
+            speaking: self.activity.speaking.load(Ordering::Relaxed),
+            speech_ms: self.activity.speech_ms.load(Ordering::Relaxed),
+            ..info
+        })
+    }
+
+    /// Stops capturing and finalizes the WAV file, returning the in-memory
+    /// copy if [`CaptureOptions::tap_sample_rate`] asked for one.
+    pub fn stop(&mut self) -> Result<Option<AudioBuffer>> {
+        self.info = None;
+        if let Some(source) = self.stream.take() {
+            match &source {
+                Source::Shared(stream) => stream.pause()?,
+                #[cfg(windows)]
+                Source::Exclusive(_) => {}
+            }
+            drop(source);
+        }
+        let Some(writer) = self.writer.take() else {
+            return Ok(None);
+        };
+        self.stopping.store(true, Ordering::SeqCst);
+        let tap = writer
+            .join()
+            .map_err(|_| anyhow!("recording writer thread panicked"))?;
+        let dropped = self.dropped.load(Ordering::Relaxed);
+        if dropped > 0 {
+            eprintln!("Dropped {} samples the disk couldn't keep up with", dropped);
+        }
+        tap
+    }
+
+    /// Records from the default input device for a fixed duration, blocking
+    /// the calling thread.
+    pub fn record_for(&mut self, output_path: &Path, duration: Duration) -> Result<()> {
+        self.start(output_path, CaptureOptions::default())?;
+        std::thread::sleep(duration);
+        self.stop().map(|_| ())
+    }
+}
+
+/// Where a take's audio comes from.
+enum Source {
+    /// A cpal stream, shared with other apps through the system mixer.
+    Shared(Stream),
+    #[cfg(windows)]
+    Exclusive(ExclusiveStream),
+}
+
+/// A capture stream that's playing into a ring buffer nobody reads yet.
+struct Capture {
+    stream: Source,
+    consumer: Consumer<f32>,
+    spec: WavSpec,
+    info: StreamInfo,
+    dropped: Arc<AtomicUsize>,
+    callback_frames: Arc<AtomicU32>,
+}
+
+/// The audio callback's half of a capture: processes what the device
+/// delivers and pushes it into the ring buffer.
+struct Sink {
+    processor: Processor,
+    processed: Vec<f32>,
+    producer: Producer<f32>,
+    /// Channels written per frame.
+    channels: usize,
+    dropped: Arc<AtomicUsize>,
+    callback_frames: Arc<AtomicU32>,
+}
+
+impl Sink {
+    fn push(&mut self, data: &[f32]) {
+        let frames = data.len() / self.processor.device_channels;
+        self.callback_frames.store(frames as u32, Ordering::Relaxed);
+        self.processed.clear();
+        self.processor.process(data, &mut self.processed);
+        // Whole frames only, so the writer never sees half of one.
+        let free = self.producer.slots();
+        let n = self.processed.len().min(free - free % self.channels);
+        if let Ok(chunk) = self.producer.write_chunk_uninit(n) {
+            chunk.fill_from_iter(self.processed[..n].iter().copied());
+        }
+        if n < self.processed.len() {
+            self.dropped
+                .fetch_add(self.processed.len() - n, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Sets up the processing and ring buffer for a device running at
+/// `sample_rate` with `device_channels`.
+fn sink(
+    profile: &DeviceProfile,
+    high_pass_hz: Option<f32>,
+    sample_rate: u32,
+    device_channels: u16,
+) -> (Sink, Consumer<f32>, WavSpec) {
+    let processor = Processor::new(profile, high_pass_hz, sample_rate, device_channels);
+    let spec = WavSpec {
+        channels: processor.selected.len() as u16,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let channels = spec.channels.max(1) as usize;
+    let (producer, consumer) =
+        RingBuffer::<f32>::new(spec.sample_rate as usize * channels * RING_SECS);
+    let sink = Sink {
+        processor,
+        processed: Vec::with_capacity(spec.sample_rate as usize * channels),
+        producer,
+        channels,
+        dropped: Arc::new(AtomicUsize::new(0)),
+        callback_frames: Arc::new(AtomicU32::new(0)),
+    };
+    (sink, consumer, spec)
+}
+
+impl StreamInfo {
+    fn opened(device: String, fallback: bool, spec: WavSpec) -> Self {
+        StreamInfo {
+            device,
+            fallback,
+            sample_rate: spec.sample_rate,
+            channels: spec.channels,
+            narrowband: spec.sample_rate <= NARROWBAND_MAX_RATE,
+            requested_buffer_frames: None,
+            exclusive: false,
+            buffer_frames: None,
+            silent_ms: 0,
+            speaking: false,
+            speech_ms: 0,
+        }
+    }
+}
+
+/// Opens and starts `device` with its profile from `options`, in exclusive
+/// mode first if that was asked for.
+fn open(
+    device: &Device,
+    name: String,
+    fallback: bool,
+    options: &CaptureOptions,
+) -> Result<Capture> {
+    let profile = options.profiles.get(&name).cloned().unwrap_or_default();
+    #[cfg(windows)]
+    if options.exclusive {
+        match open_exclusive(&name, fallback, &profile, options) {
+            Ok(capture) => return Ok(capture),
+            Err(err) => eprintln!(
+                "Couldn't open {:?} in exclusive mode, sharing it instead: {:?}",
+                name, err
+            ),
+        }
+    }
+    let config = devices::input_config(device, profile.sample_rate)?;
+    let mut stream_config = config.config();
+    stream_config.buffer_size = buffer_size(options.buffer_frames, config.buffer_size());
+
+    let (mut sink, consumer, spec) = sink(
+        &profile,
+        options.high_pass_hz,
+        config.sample_rate().0,
+        config.channels(),
+    );
+    let dropped = sink.dropped.clone();
+    let callback_frames = sink.callback_frames.clone();
+    let stream = device.build_input_stream(
+        &stream_config,
+        move |data: &[f32], _: &cpal::InputCallbackInfo| sink.push(data),
+        |err| eprintln!("Error: {:?}", err),
+        Some(Duration::from_secs(30)),
+    )?;
+    // Busy devices often only fail here, so it's part of opening.
+    stream.play()?;
+    Ok(Capture {
+        stream: Source::Shared(stream),
+        consumer,
+        spec,
+        info: StreamInfo {
+            requested_buffer_frames: match stream_config.buffer_size {
+                BufferSize::Fixed(frames) => Some(frames),
+                BufferSize::Default => None,
+            },
+            ..StreamInfo::opened(name, fallback, spec)
+        },
+        dropped,
+        callback_frames,
+    })
+}
+
+#[cfg(windows)]
+fn open_exclusive(
+    name: &str,
+    fallback: bool,
+    profile: &DeviceProfile,
+    options: &CaptureOptions,
+) -> Result<Capture> {
+    let (mut stream, format) = exclusive::open(name, profile.sample_rate, options.buffer_frames)?;
+    let (mut sink, consumer, spec) = sink(
+        profile,
+        options.high_pass_hz,
+        format.sample_rate,
+        format.channels,
+    );
+    let dropped = sink.dropped.clone();
+    let callback_frames = sink.callback_frames.clone();
+    stream.start(Box::new(move |data: &[f32]| sink.push(data)))?;
+    Ok(Capture {
+        stream: Source::Exclusive(stream),
+        consumer,
+        spec,
+        info: StreamInfo {
+            requested_buffer_frames: format.buffer_frames,
+            exclusive: true,
+            ..StreamInfo::opened(name.to_string(), fallback, spec)
+        },
+        dropped,
+        callback_frames,
+    })
+}
+
+impl Drop for Recorder {
+    /// Finalizes the WAV header if the recorder goes away mid-take, so the
+    /// file on disk is never left with zero-length RIFF fields.
+    fn drop(&mut self) {
+        if let Err(err) = self.stop() {
+            eprintln!("Failed to finalize recording: {:?}", err);
+        }
+    }
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Peaks below this (about -70 dBFS) are what a muted or dead microphone
+/// produces; even a quiet room is louder.
+const NO_SIGNAL_LEVEL: f32 = 0.0003;
+
+/// What the writer thread has heard so far, read by [`Recorder::info`].
+#[derive(Default)]
+struct Activity {
+    silent_ms: AtomicU64,
+    speaking: AtomicBool,
+    speech_ms: AtomicU64,
+}
+
+/// Tracks how long the input has been near-silent and whether someone is
+/// speaking.
+struct SignalMonitor {
+    sample_rate: u32,
+    channels: usize,
+    silent_frames: u64,
+    vad: Vad,
+    activity: Arc<Activity>,
+}
+
+impl SignalMonitor {
+    fn new(sample_rate: u32, channels: usize, activity: Arc<Activity>) -> Self {
+        SignalMonitor {
+            sample_rate,
+            channels,
+            silent_frames: 0,
+            vad: Vad::new(sample_rate, channels),
+            activity,
+        }
+    }
+
+    fn push(&mut self, samples: &[f32]) {
+        for frame in samples.chunks(self.channels) {
+            match frame.iter().all(|sample| sample.abs() < NO_SIGNAL_LEVEL) {
+                true => self.silent_frames += 1,
+                false => self.silent_frames = 0,
+            }
+        }
+        let ms = self.silent_frames * 1000 / self.sample_rate.max(1) as u64;
+        self.activity.silent_ms.store(ms, Ordering::Relaxed);
+        self.vad.push(samples);
+        let activity = &self.activity;
+        activity
+            .speaking
+            .store(self.vad.speaking(), Ordering::Relaxed);
+        activity
+            .speech_ms
+            .store(self.vad.speech_ms(), Ordering::Relaxed);
+    }
+}
+
+/// Drains captured samples into the WAV file (and the tap) in batches until
+/// `stopping` is set and nothing is left, then finalizes the file.
+fn write_samples(
+    mut consumer: Consumer<f32>,
+    mut writer: Option<WavWriter<BufWriter<File>>>,
+    mut tap: Option<Tap>,
+    mut monitor: SignalMonitor,
+    stopping: Arc<AtomicBool>,
+) -> Result<Option<AudioBuffer>> {
+    let channels = monitor.channels;
+    let flush_every = writer.as_ref().map_or(u64::MAX, |writer| {
+        writer.spec().sample_rate as u64 * channels as u64 * FLUSH_INTERVAL_SECS
+    });
+    let mut unflushed = 0u64;
+    let mut batch = Vec::new();
+    loop {
+        // Read the flag first: once it's set the stream is gone, so whatever
+        // is in the ring now is everything that's left.
+        let done = stopping.load(Ordering::SeqCst);
+        let available = consumer.slots();
+        if available == 0 {
+            if done {
+                break;
+            }
+            std::thread::sleep(WRITE_INTERVAL);
+            continue;
+        }
+        let chunk = consumer.read_chunk(available)?;
+        let (first, second) = chunk.as_slices();
+        batch.clear();
+        batch.extend_from_slice(first);
+        batch.extend_from_slice(second);
+        chunk.commit_all();
+
+        if let Some(writer) = &mut writer {
+            for &sample in &batch {
+                writer.write_sample((sample * i16::MAX as f32) as i16)?;
+            }
+        }
+        if let Some(tap) = &mut tap {
+            tap.push(&batch, channels);
+        }
+        monitor.push(&batch);
+        unflushed += batch.len() as u64;
+        if unflushed >= flush_every {
+            // Rewrites the RIFF/data lengths so the file is playable up to
+            // here if we never get to finalize.
+            if let Some(Err(err)) = writer.as_mut().map(WavWriter::flush) {
+                eprintln!("Failed to flush recording: {:?}", err);
+            }
+            unflushed = 0;
+        }
+    }
+    if let Some(writer) = writer {
+        writer.finalize()?;
+    }
+    tap.map(Tap::finish).transpose()
+}
+
+enum AudioCommand {
+    Start(PathBuf, CaptureOptions, Sender<Result<()>>),
+    Stop(Sender<Result<Option<AudioBuffer>>>),
+    Info(Sender<Result<Option<StreamInfo>>>),
+}
+
+/// Drives a [`Recorder`] on its own thread, since cpal streams aren't `Send`.
+pub struct AudioController {
+    worker: Worker<AudioCommand>,
+}
+
+impl AudioController {
+    pub fn new() -> Self {
+        let worker = Worker::spawn(Recorder::new, |recorder, command| match command {
+            AudioCommand::Start(path, options, reply) => {
+                let _ = reply.send(recorder.start(&path, options));
+            }
+            AudioCommand::Stop(reply) => {
+                let _ = reply.send(recorder.stop());
+            }
+            AudioCommand::Info(reply) => {
+                let _ = reply.send(Ok(recorder.info()));
+            }
+        });
+        AudioController { worker }
+    }
+
+    /// Starts recording to `path`, waiting for the stream to open.
+    pub fn start(&self, path: PathBuf, options: CaptureOptions) -> Result<()> {
+        self.request(|reply| AudioCommand::Start(path, options, reply))
+    }
+
+    /// Stops recording, returning once the WAV file has been finalized, with
+    /// the in-memory copy of the take if one was asked for.
+    pub fn stop(&self) -> Result<Option<AudioBuffer>> {
+        self.request(AudioCommand::Stop)
+    }
+
+    /// The device, format and negotiated buffer size of the current take.
+    pub fn info(&self) -> Result<Option<StreamInfo>> {
+        self.request(AudioCommand::Info)
+    }
+
+    fn request<T>(&self, command: impl FnOnce(Sender<Result<T>>) -> AudioCommand) -> Result<T> {
+        let (reply, response) = mpsc::channel();
+        self.worker.send(command(reply))?;
+        response
+            .recv()
+            .map_err(|_| anyhow!("recorder thread has stopped"))?
+    }
+}
+
+impl Default for AudioController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_selects_channels_and_applies_gain() {
+        let profile = DeviceProfile {
+            gain_db: 6.0,
+            channels: vec![1, 7],
+            ..DeviceProfile::default()
+        };
+        let mut processor = Processor::new(&profile, None, 16_000, 2);
+        // Channel 7 doesn't exist, so only the right channel is kept.
+        assert_eq!(processor.selected, vec![1]);
+        let mut output = Vec::new();
+        processor.process(&[0.1, 0.2, 0.3, 0.4], &mut output);
+        assert_eq!(output.len(), 2);
+        assert!((output[0] - 0.399).abs() < 1e-3 && (output[1] - 0.798).abs() < 1e-3);
+    }
+
+    #[test]
+    fn monitor_measures_trailing_silence() {
+        let activity = Arc::new(Activity::default());
+        let mut monitor = SignalMonitor::new(1000, 2, activity.clone());
+        monitor.push(&[0.0; 3000]);
+        assert_eq!(activity.silent_ms.load(Ordering::Relaxed), 1500);
+        // One channel with signal is enough to reset it.
+        monitor.push(&[0.0, 0.2]);
+        monitor.push(&[0.0001; 200]);
+        assert_eq!(activity.silent_ms.load(Ordering::Relaxed), 100);
+    }
+
+    #[test]
+    fn buffer_size_is_clamped_to_what_the_device_supports() {
+        let supported = SupportedBufferSize::Range { min: 64, max: 4096 };
+        assert_eq!(buffer_size(None, &supported), BufferSize::Default);
+        assert_eq!(buffer_size(Some(512), &supported), BufferSize::Fixed(512));
+        assert_eq!(buffer_size(Some(16), &supported), BufferSize::Fixed(64));
+        assert_eq!(
+            buffer_size(Some(1 << 16), &supported),
+            BufferSize::Fixed(4096)
+        );
+        assert_eq!(
+            buffer_size(Some(512), &SupportedBufferSize::Unknown),
+            BufferSize::Default
+        );
+    }
+}
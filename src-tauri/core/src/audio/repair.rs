@@ -0,0 +1,133 @@
+use anyhow::{bail, Context, Result};
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// What [`repair_wav`] changed.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Repair {
+    /// Whether any header field had to be rewritten.
+    pub repaired: bool,
+    /// Size of the audio data now declared in the header, in bytes.
+    pub data_bytes: u32,
+}
+
+/// Fixes the RIFF and `data` chunk lengths of a WAV file whose writer never
+/// finalized it (crash, power loss, force quit), so the audio that did reach
+/// disk becomes readable again. Trailing partial frames are excluded.
+pub fn repair_wav(path: &Path) -> Result<Repair> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .context("failed to open file")?;
+    let file_len = file.metadata()?.len();
+
+    let mut header = [0u8; 12];
+    file.read_exact(&mut header).context("file is too short")?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        bail!("not a RIFF/WAVE file");
+    }
+
+    let mut block_align = None;
+    let mut offset = 12u64;
+    loop {
+        let mut chunk = [0u8; 8];
+        file.seek(SeekFrom::Start(offset))?;
+        if file.read_exact(&mut chunk).is_err() {
+            bail!("no data chunk found");
+        }
+        let id = &chunk[0..4];
+        let size = u32::from_le_bytes(chunk[4..8].try_into().unwrap()) as u64;
+        let body = offset + 8;
+
+        if id == b"fmt " {
+            let mut fmt = [0u8; 16];
+            file.read_exact(&mut fmt).context("truncated fmt chunk")?;
+            block_align = Some(u16::from_le_bytes([fmt[12], fmt[13]]).max(1) as u64);
+        } else if id == b"data" {
+            let block_align = block_align.context("data chunk before fmt chunk")?;
+            let available = file_len.saturating_sub(body);
+            let data_len = (available - available % block_align).min(u32::MAX as u64) as u32;
+            let riff_len = (body + data_len as u64 - 8).min(u32::MAX as u64) as u32;
+
+            let declared_riff = u32::from_le_bytes(header[4..8].try_into().unwrap());
+            let repaired = declared_riff != riff_len || size != data_len as u64;
+            if repaired {
+                file.seek(SeekFrom::Start(4))?;
+                file.write_all(&riff_len.to_le_bytes())?;
+                file.seek(SeekFrom::Start(offset + 4))?;
+                file.write_all(&data_len.to_le_bytes())?;
+                file.flush()?;
+            }
+            return Ok(Repair {
+                repaired,
+                data_bytes: data_len,
+            });
+        }
+        // Chunks are padded to an even length.
+        offset = body + size + size % 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+    use hound::WavReader;
+    use std::fs;
+
+    fn broken_copy(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("repair-{}-{}", std::process::id(), name));
+        fs::copy(fixture("mono_16k.wav"), &path).unwrap();
+        // Zero the lengths, as hound leaves them until finalize().
+        let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.seek(SeekFrom::Start(4)).unwrap();
+        file.write_all(&0u32.to_le_bytes()).unwrap();
+        file.seek(SeekFrom::Start(40)).unwrap();
+        file.write_all(&0u32.to_le_bytes()).unwrap();
+        path
+    }
+
+    #[test]
+    fn restores_lengths_of_unfinalized_file() {
+        let path = broken_copy("unfinalized.wav");
+        assert_eq!(WavReader::open(&path).unwrap().len(), 0);
+
+        let repair = repair_wav(&path).unwrap();
+        assert!(repair.repaired);
+        assert_eq!(repair.data_bytes, 8000);
+        assert_eq!(WavReader::open(&path).unwrap().len(), 4000);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn drops_trailing_partial_frame() {
+        let path = broken_copy("partial.wav");
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(&[1])
+            .unwrap();
+
+        assert_eq!(repair_wav(&path).unwrap().data_bytes, 8000);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn healthy_file_is_left_alone() {
+        let path = std::env::temp_dir().join(format!("repair-{}-ok.wav", std::process::id()));
+        fs::copy(fixture("mono_16k.wav"), &path).unwrap();
+        assert!(!repair_wav(&path).unwrap().repaired);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rejects_non_wav() {
+        let path = std::env::temp_dir().join(format!("repair-{}-bad.wav", std::process::id()));
+        fs::write(&path, b"definitely not audio").unwrap();
+        assert!(repair_wav(&path).is_err());
+        fs::remove_file(path).unwrap();
+    }
+}
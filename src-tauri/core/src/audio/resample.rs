@@ -0,0 +1,324 @@
+use anyhow::Result;
+use rayon::prelude::*;
+use rubato::{
+    Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction,
+};
+use serde::{Deserialize, Serialize};
+
+use super::buffer::AudioBuffer;
+
+/// Trades resampling accuracy for speed. Speech recognition barely notices
+/// the difference, so `Medium` is plenty for transcription; `High` is the
+/// original 256-tap setup and can take minutes on hour-long files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResampleQuality {
+    Fast,
+    #[default]
+    Medium,
+    High,
+}
+
+impl ResampleQuality {
+    pub fn parameters(self) -> SincInterpolationParameters {
+        let (sinc_len, oversampling_factor, interpolation, window) = match self {
+            ResampleQuality::Fast => (32, 32, SincInterpolationType::Linear, WindowFunction::Hann2),
+            ResampleQuality::Medium => (
+                128,
+                128,
+                SincInterpolationType::Linear,
+                WindowFunction::Blackman2,
+            ),
+            ResampleQuality::High => (
+                256,
+                256,
+                SincInterpolationType::Cubic,
+                WindowFunction::BlackmanHarris2,
+            ),
+        };
+        SincInterpolationParameters {
+            sinc_len,
+            f_cutoff: 0.90,
+            interpolation,
+            oversampling_factor,
+            window,
+        }
+    }
+}
+
+/// Input frames fed to the resampler at a time, so memory stays flat no
+/// matter how long the recording is.
+const CHUNK_FRAMES: usize = 4096;
+
+/// Resamples every channel of a buffer to `target_rate`.
+pub fn resample_buffer(
+    buffer: &AudioBuffer,
+    target_rate: u32,
+    quality: ResampleQuality,
+) -> Result<AudioBuffer> {
+    if buffer.sample_rate == target_rate {
+        return Ok(buffer.clone());
+    }
+    let channels = buffer.channels.max(1) as usize;
+    let resampled = (0..channels)
+        .into_par_iter()
+        .map(|channel| {
+            let samples: Vec<f32> = buffer
+                .samples
+                .iter()
+                .skip(channel)
+                .step_by(channels)
+                .copied()
+                .collect();
+            resample_mono(&samples, buffer.sample_rate, target_rate as f64, quality)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let frames = resampled[0].len();
+    Ok(AudioBuffer {
+        sample_rate: target_rate,
+        channels: buffer.channels,
+        samples: (0..frames)
+            .flat_map(|frame| resampled.iter().map(move |channel| channel[frame]))
+            .collect(),
+    })
+}
+
+/// Inputs longer than this are split into segments resampled in parallel.
+const PARALLEL_SEGMENT_FRAMES: usize = 1 << 18;
+
+/// Input resampled on either side of a segment and then dropped, so the
+/// filter sees the same neighbours it would in a single pass.
+const SEGMENT_OVERLAP: usize = 1024;
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Resamples mono audio chunk by chunk. The resampler's delay is trimmed
+/// and its tail flushed, so the output lines up with the input and has
+/// exactly `len * target_rate / original_rate` samples. Long inputs are
+/// resampled as overlapping segments across all cores.
+pub fn resample_mono(
+    samples: &[f32],
+    original_rate: u32,
+    target_rate: f64,
+    quality: ResampleQuality,
+) -> Result<Vec<f32>> {
+    // Segments must start on input samples that map to whole output samples,
+    // or the joins would be out of phase.
+    let step = match target_rate.fract() == 0.0 && original_rate > 0 {
+        true => (original_rate as u64 / gcd(original_rate as u64, target_rate as u64)) as usize,
+        false => 0,
+    };
+    if step == 0 || step > SEGMENT_OVERLAP || samples.len() < 2 * PARALLEL_SEGMENT_FRAMES {
+        let mut resampler = StreamResampler::new(original_rate, target_rate, quality)?;
+        resampler.push(samples)?;
+        return resampler.finish();
+    }
+
+    let ratio = target_rate / original_rate as f64;
+    let segment = PARALLEL_SEGMENT_FRAMES.next_multiple_of(step);
+    let overlap = SEGMENT_OVERLAP.next_multiple_of(step);
+    let starts: Vec<usize> = (0..samples.len()).step_by(segment).collect();
+    let segments = starts
+        .into_par_iter()
+        .map(|start| {
+            let from = start.saturating_sub(overlap);
+            let end = (start + segment).min(samples.len());
+            let to = (end + overlap).min(samples.len());
+            let mut resampler = StreamResampler::new(original_rate, target_rate, quality)?;
+            resampler.push(&samples[from..to])?;
+            let mut output = resampler.finish()?;
+            let skip = ((start - from) as f64 * ratio).round() as usize;
+            let keep = ((end - start) as f64 * ratio).round() as usize;
+            output.truncate(skip + keep);
+            output.drain(..skip);
+            Ok(output)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(segments.concat())
+}
+
+/// Resamples mono audio fed to it piece by piece, so a long file can be
+/// read and resampled without holding its original samples in memory.
+pub struct StreamResampler {
+    resampler: SincFixedIn<f32>,
+    ratio: f64,
+    /// Output samples still to drop to make up for the resampler's delay.
+    skip: usize,
+    /// Input not yet making up a whole chunk.
+    pending: Vec<f32>,
+    input_len: usize,
+    scratch: Vec<Vec<f32>>,
+    output: Vec<f32>,
+    /// Output already handed out by [`StreamResampler::drain`].
+    drained: usize,
+}
+
+impl StreamResampler {
+    pub fn new(original_rate: u32, target_rate: f64, quality: ResampleQuality) -> Result<Self> {
+        let ratio = target_rate / original_rate as f64;
+        let resampler = SincFixedIn::<f32>::new(
+            ratio,
+            2.0,
+            quality.parameters(),
+            CHUNK_FRAMES,
+            1, // Channels
+        )?;
+        Ok(StreamResampler {
+            skip: resampler.output_delay(),
+            scratch: resampler.output_buffer_allocate(true),
+            resampler,
+            ratio,
+            pending: Vec::with_capacity(CHUNK_FRAMES),
+            input_len: 0,
+            output: Vec::new(),
+            drained: 0,
+        })
+    }
+
+    pub fn push(&mut self, mut samples: &[f32]) -> Result<()> {
+        self.input_len += samples.len();
+        while !samples.is_empty() {
+            let take = (CHUNK_FRAMES - self.pending.len()).min(samples.len());
+            self.pending.extend_from_slice(&samples[..take]);
+            samples = &samples[take..];
+            if self.pending.len() == CHUNK_FRAMES {
+                let (_, written) = self.resampler.process_into_buffer(
+                    &[&self.pending],
+                    &mut self.scratch,
+                    None,
+                )?;
+                self.emit(written);
+                self.pending.clear();
+            }
+        }
+        Ok(())
+    }
+
+    /// Takes the output produced so far, so a caller streaming a long input
+    /// doesn't have to hold all of it.
+    pub fn drain(&mut self) -> Vec<f32> {
+        self.drained += self.output.len();
+        std::mem::take(&mut self.output)
+    }
+
+    /// Flushes the resampler's tail, returning whatever hasn't been drained.
+    /// All the output together lines up with the input and has exactly
+    /// `len * target_rate / original_rate` samples.
+    pub fn finish(mut self) -> Result<Vec<f32>> {
+        let expected =
+            ((self.input_len as f64 * self.ratio).round() as usize).saturating_sub(self.drained);
+        let mut last = Some(vec![std::mem::take(&mut self.pending)]);
+        while self.output.len() < expected {
+            let (_, written) = self.resampler.process_partial_into_buffer(
+                last.take().as_deref(),
+                &mut self.scratch,
+                None,
+            )?;
+            self.emit(written);
+        }
+        self.output.truncate(expected);
+        Ok(self.output)
+    }
+
+    fn emit(&mut self, written: usize) {
+        let skipped = self.skip.min(written);
+        self.skip -= skipped;
+        self.output
+            .extend_from_slice(&self.scratch[0][skipped..written]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn halves_length_when_downsampling() {
+        let samples = vec![0.0; 3200];
+        for quality in [
+            ResampleQuality::Fast,
+            ResampleQuality::Medium,
+            ResampleQuality::High,
+        ] {
+            let out = resample_mono(&samples, 32000, 16000.0, quality).unwrap();
+            assert_eq!(out.len(), 1600);
+        }
+    }
+
+    #[test]
+    fn silence_stays_silent() {
+        let out = resample_mono(&[0.0; 800], 8000, 16000.0, ResampleQuality::Fast).unwrap();
+        assert!(out.iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn long_input_is_streamed_and_stays_aligned() {
+        // A 10 s, 100 Hz square wave: several chunks plus a partial one.
+        let samples: Vec<f32> = (0..80_000 + 123)
+            .map(|i| if (i / 40) % 2 == 0 { 0.3 } else { -0.3 })
+            .collect();
+        let out = resample_mono(&samples, 8000, 16000.0, ResampleQuality::Fast).unwrap();
+        assert_eq!(out.len(), 160_246);
+        // The delay is trimmed, so the wave starts right away instead of
+        // after a stretch of near-silence.
+        assert!(out[20] > 0.15, "{}", out[20]);
+        assert!(out[120] < -0.15, "{}", out[120]);
+    }
+
+    #[test]
+    fn parallel_segments_join_seamlessly() {
+        let samples: Vec<f32> = (0..3 * PARALLEL_SEGMENT_FRAMES + 999)
+            .map(|i| (i as f32 * 0.01).sin() * 0.5)
+            .collect();
+        let parallel = resample_mono(&samples, 48000, 16000.0, ResampleQuality::Fast).unwrap();
+        let mut single = StreamResampler::new(48000, 16000.0, ResampleQuality::Fast).unwrap();
+        single.push(&samples).unwrap();
+        let single = single.finish().unwrap();
+        assert_eq!(parallel.len(), single.len());
+        assert!(parallel
+            .iter()
+            .zip(&single)
+            .all(|(a, b)| (a - b).abs() < 1e-3));
+    }
+
+    #[test]
+    fn streaming_in_pieces_matches_one_shot() {
+        let samples: Vec<f32> = (0..10_000).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+        let whole = resample_mono(&samples, 8000, 16000.0, ResampleQuality::Fast).unwrap();
+        let mut streamed = StreamResampler::new(8000, 16000.0, ResampleQuality::Fast).unwrap();
+        for piece in samples.chunks(777) {
+            streamed.push(piece).unwrap();
+        }
+        assert_eq!(streamed.finish().unwrap(), whole);
+
+        let mut drained = StreamResampler::new(8000, 16000.0, ResampleQuality::Fast).unwrap();
+        let mut output = Vec::new();
+        for piece in samples.chunks(5000) {
+            drained.push(piece).unwrap();
+            output.extend(drained.drain());
+        }
+        output.extend(drained.finish().unwrap());
+        assert_eq!(output, whole);
+    }
+
+    #[test]
+    fn resamples_each_channel_of_a_buffer() {
+        // Left is silent and right is full scale, so any channel mixing shows.
+        let buffer = AudioBuffer {
+            sample_rate: 8000,
+            channels: 2,
+            samples: (0..8000).flat_map(|_| [0.0, 0.5]).collect(),
+        };
+        let out = resample_buffer(&buffer, 16000, ResampleQuality::Fast).unwrap();
+        assert_eq!(out.sample_rate, 16000);
+        assert_eq!(out.frames(), 16000);
+        let middle = &out.samples[16000..16002];
+        assert!(middle[0].abs() < 0.01 && (middle[1] - 0.5).abs() < 0.01);
+    }
+}
@@ -0,0 +1,199 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::buffer::AudioBuffer;
+use super::edit::TimeRange;
+use crate::library::unique_path;
+
+/// Loudness is measured over windows this long.
+const WINDOW_MS: u64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SilenceOptions {
+    /// Windows quieter than this RMS level count as silence.
+    pub threshold_db: f32,
+    /// Shorter pauses are part of speech, not a gap between takes.
+    pub min_silence_ms: u64,
+}
+
+impl Default for SilenceOptions {
+    fn default() -> Self {
+        SilenceOptions {
+            threshold_db: -40.0,
+            min_silence_ms: 2000,
+        }
+    }
+}
+
+/// Stretches of at least `min_silence_ms` below `threshold_db`, in order.
+pub fn find_silences(buffer: &AudioBuffer, options: &SilenceOptions) -> Vec<TimeRange> {
+    let channels = buffer.channels.max(1) as usize;
+    let window = (buffer.sample_rate as u64 * WINDOW_MS / 1000).max(1) as usize * channels;
+    let threshold = 10f32.powf(options.threshold_db / 20.0);
+    let mut silences = Vec::new();
+    let mut start = None;
+    let mut end_ms = 0;
+    for (i, chunk) in buffer.samples.chunks(window).enumerate() {
+        let rms = (chunk.iter().map(|s| s * s).sum::<f32>() / chunk.len() as f32).sqrt();
+        let start_ms = i as u64 * WINDOW_MS;
+        end_ms =
+            start_ms + chunk.len() as u64 * 1000 / (buffer.sample_rate as u64 * channels as u64);
+        match (rms < threshold, start) {
+            (true, None) => start = Some(start_ms),
+            (false, Some(silence_start)) => {
+                silences.push(TimeRange {
+                    start_ms: silence_start,
+                    end_ms: start_ms,
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start_ms) = start {
+        silences.push(TimeRange { start_ms, end_ms });
+    }
+    silences.retain(|silence| silence.end_ms - silence.start_ms >= options.min_silence_ms);
+    silences
+}
+
+/// Where to cut between takes: the middle of each gap that has audio on
+/// both sides. Leading and trailing silence stays with its take.
+pub fn cut_points(buffer: &AudioBuffer, options: &SilenceOptions) -> Vec<u64> {
+    let duration_ms = buffer.frames() as u64 * 1000 / buffer.sample_rate.max(1) as u64;
+    find_silences(buffer, options)
+        .into_iter()
+        .filter(|silence| silence.start_ms > 0 && silence.end_ms < duration_ms)
+        .map(|silence| (silence.start_ms + silence.end_ms) / 2)
+        .collect()
+}
+
+/// Splits at each of `cut_points_ms`, which must be in order.
+pub fn split_at(buffer: &AudioBuffer, cut_points_ms: &[u64]) -> Vec<AudioBuffer> {
+    let channels = buffer.channels.max(1) as usize;
+    let mut bounds: Vec<usize> = cut_points_ms
+        .iter()
+        .map(|&ms| {
+            ((ms as u128 * buffer.sample_rate as u128 / 1000) as usize).min(buffer.frames())
+                * channels
+        })
+        .collect();
+    bounds.insert(0, 0);
+    bounds.push(buffer.samples.len());
+    bounds
+        .windows(2)
+        .map(|bound| AudioBuffer {
+            sample_rate: buffer.sample_rate,
+            channels: buffer.channels,
+            samples: buffer.samples[bound[0]..bound[1]].to_vec(),
+        })
+        .collect()
+}
+
+/// The result of [`split_on_silence`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SilenceSplit {
+    /// Offsets into the original recording where one take ends and the next
+    /// begins.
+    pub cut_points_ms: Vec<u64>,
+    /// The takes in order, written next to the original.
+    pub takes: Vec<PathBuf>,
+}
+
+/// Splits a long WAV file into numbered takes, `<name> (take 1).wav` and so
+/// on, wherever it's silent for long enough. A recording without any such
+/// gap yields no cut points and no files.
+pub fn split_on_silence(path: &Path, options: &SilenceOptions) -> Result<SilenceSplit> {
+    let buffer = AudioBuffer::read(path)?;
+    let cut_points_ms = cut_points(&buffer, options);
+    if cut_points_ms.is_empty() {
+        return Ok(SilenceSplit {
+            cut_points_ms,
+            takes: Vec::new(),
+        });
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let takes = split_at(&buffer, &cut_points_ms)
+        .into_iter()
+        .enumerate()
+        .map(|(i, take)| {
+            let output =
+                unique_path(&path.with_file_name(format!("{} (take {}).wav", stem, i + 1)));
+            take.write(&output)?;
+            Ok(output)
+        })
+        .collect::<Result<_>>()?;
+    Ok(SilenceSplit {
+        cut_points_ms,
+        takes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1 kHz mono audio, loud where `pattern` is `true`, a second per entry.
+    fn pattern(pattern: &[bool]) -> AudioBuffer {
+        AudioBuffer {
+            sample_rate: 1000,
+            channels: 1,
+            samples: pattern
+                .iter()
+                .flat_map(|&loud| {
+                    (0..1000).map(move |i| {
+                        if loud && i % 2 == 0 {
+                            0.5
+                        } else if loud {
+                            -0.5
+                        } else {
+                            0.0
+                        }
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn cuts_in_the_middle_of_long_gaps_only() {
+        // A one-second pause is too short; the three-second one isn't.
+        let buffer = pattern(&[true, false, true, false, false, false, true]);
+        assert_eq!(cut_points(&buffer, &SilenceOptions::default()), vec![4500]);
+    }
+
+    #[test]
+    fn leading_and_trailing_silence_is_not_a_cut() {
+        let buffer = pattern(&[false, false, false, true, false, false, false]);
+        assert_eq!(find_silences(&buffer, &SilenceOptions::default()).len(), 2);
+        assert!(cut_points(&buffer, &SilenceOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn splits_at_cut_points() {
+        let takes = split_at(&pattern(&[true, true, true]), &[500, 2000]);
+        let frames: Vec<usize> = takes.iter().map(AudioBuffer::frames).collect();
+        assert_eq!(frames, vec![500, 1500, 1000]);
+    }
+
+    #[test]
+    fn writes_numbered_takes() {
+        let dir = std::env::temp_dir().join(format!("app-core-takes-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("standup.wav");
+        pattern(&[true, false, false, true]).write(&path).unwrap();
+
+        let split = split_on_silence(&path, &SilenceOptions::default()).unwrap();
+        assert_eq!(split.cut_points_ms, vec![2000]);
+        assert_eq!(
+            split.takes,
+            vec![
+                dir.join("standup (take 1).wav"),
+                dir.join("standup (take 2).wav")
+            ]
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,235 @@
+use anyhow::{bail, Result};
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::buffer::AudioBuffer;
+
+/// Values below this are shown as this, so silence doesn't dominate the range.
+const FLOOR_DB: f32 = -100.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrequencyScale {
+    Linear,
+    /// Triangular mel filter bank, closer to how we hear and to what whisper
+    /// sees.
+    Mel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpectrogramParams {
+    /// Samples per FFT window; a power of two.
+    pub fft_size: usize,
+    /// Samples between windows. Raised automatically to respect `max_frames`.
+    pub hop: usize,
+    pub scale: FrequencyScale,
+    /// Number of bands for the mel scale.
+    pub mel_bands: usize,
+    /// Longer recordings are summarized with a bigger hop so the UI gets at
+    /// most this many columns.
+    pub max_frames: usize,
+}
+
+impl Default for SpectrogramParams {
+    fn default() -> Self {
+        SpectrogramParams {
+            fft_size: 512,
+            hop: 160,
+            scale: FrequencyScale::Mel,
+            mel_bands: 80,
+            max_frames: 2000,
+        }
+    }
+}
+
+/// Power in dB per frame and frequency bin, frame-major: `db[frame * bins + bin]`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Spectrogram {
+    pub frames: usize,
+    pub bins: usize,
+    /// Center frequency of each bin in Hz.
+    pub frequencies: Vec<f32>,
+    /// Milliseconds between frames.
+    pub frame_ms: f32,
+    pub db: Vec<f32>,
+}
+
+/// Computes the spectrogram of a buffer, mixed down to mono.
+pub fn spectrogram(buffer: &AudioBuffer, params: &SpectrogramParams) -> Result<Spectrogram> {
+    let fft_size = params.fft_size;
+    if !fft_size.is_power_of_two() || fft_size < 16 {
+        bail!("fft_size must be a power of two of at least 16");
+    }
+    let channels = buffer.channels.max(1) as usize;
+    let mono: Vec<f32> = buffer
+        .samples
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect();
+    let windows = mono.len().saturating_sub(fft_size) + 1;
+    let hop = params
+        .hop
+        .max(1)
+        .max(windows.div_ceil(params.max_frames.max(1)));
+    let frames = if mono.len() < fft_size {
+        0
+    } else {
+        (mono.len() - fft_size) / hop + 1
+    };
+
+    let window: Vec<f32> = (0..fft_size)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / fft_size as f32).cos())
+        .collect();
+    let fft = FftPlanner::<f32>::new().plan_fft_forward(fft_size);
+    let linear_bins = fft_size / 2 + 1;
+    let bin_hz = buffer.sample_rate as f32 / fft_size as f32;
+    let (bins, frequencies, bank) = match params.scale {
+        FrequencyScale::Linear => (
+            linear_bins,
+            (0..linear_bins).map(|bin| bin as f32 * bin_hz).collect(),
+            None,
+        ),
+        FrequencyScale::Mel => {
+            let (centers, bank) = mel_bank(params.mel_bands.max(1), linear_bins, bin_hz);
+            (centers.len(), centers, Some(bank))
+        }
+    };
+
+    let mut db = Vec::with_capacity(frames * bins);
+    let mut spectrum = vec![Complex::default(); fft_size];
+    let mut power = vec![0f32; linear_bins];
+    for frame in 0..frames {
+        let start = frame * hop;
+        for (i, value) in spectrum.iter_mut().enumerate() {
+            *value = Complex::new(mono[start + i] * window[i], 0.0);
+        }
+        fft.process(&mut spectrum);
+        for (bin, power) in power.iter_mut().enumerate() {
+            *power = spectrum[bin].norm_sqr() / fft_size as f32;
+        }
+        let to_db = |p: f32| (10.0 * p.max(1e-12).log10()).max(FLOOR_DB);
+        match &bank {
+            None => db.extend(power.iter().map(|&p| to_db(p))),
+            Some(bank) => db.extend(
+                bank.iter()
+                    .map(|weights| to_db(weights.iter().map(|&(bin, w)| power[bin] * w).sum())),
+            ),
+        }
+    }
+
+    Ok(Spectrogram {
+        frames,
+        bins,
+        frequencies,
+        frame_ms: hop as f32 * 1000.0 / buffer.sample_rate as f32,
+        db,
+    })
+}
+
+fn hz_to_mel(hz: f32) -> f32 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+fn mel_to_hz(mel: f32) -> f32 {
+    700.0 * (10f32.powf(mel / 2595.0) - 1.0)
+}
+
+/// Triangular filters spaced evenly in mel from 0 Hz to Nyquist, as sparse
+/// `(linear bin, weight)` lists, with each filter's center frequency.
+fn mel_bank(bands: usize, linear_bins: usize, bin_hz: f32) -> (Vec<f32>, Vec<Vec<(usize, f32)>>) {
+    let max_mel = hz_to_mel(bin_hz * (linear_bins - 1) as f32);
+    let edges: Vec<f32> = (0..bands + 2)
+        .map(|i| mel_to_hz(max_mel * i as f32 / (bands + 1) as f32))
+        .collect();
+    let bank = edges
+        .windows(3)
+        .map(|edge| {
+            let (low, center, high) = (edge[0], edge[1], edge[2]);
+            (0..linear_bins)
+                .filter_map(|bin| {
+                    let hz = bin as f32 * bin_hz;
+                    let weight = if hz <= low || hz >= high {
+                        0.0
+                    } else if hz <= center {
+                        (hz - low) / (center - low)
+                    } else {
+                        (high - hz) / (high - center)
+                    };
+                    (weight > 0.0).then_some((bin, weight))
+                })
+                .collect()
+        })
+        .collect();
+    (edges[1..=bands].to_vec(), bank)
+}
+
+/// Reads a WAV file and computes its spectrogram.
+pub fn generate_spectrogram(path: &Path, params: &SpectrogramParams) -> Result<Spectrogram> {
+    spectrogram(&AudioBuffer::read(path)?, params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(hz: f32, secs: f32) -> AudioBuffer {
+        let sample_rate = 16_000;
+        AudioBuffer {
+            sample_rate,
+            channels: 1,
+            samples: (0..(secs * sample_rate as f32) as usize)
+                .map(|i| (2.0 * std::f32::consts::PI * hz * i as f32 / sample_rate as f32).sin())
+                .collect(),
+        }
+    }
+
+    fn loudest_frequency(spectrogram: &Spectrogram, frame: usize) -> f32 {
+        let row = &spectrogram.db[frame * spectrogram.bins..(frame + 1) * spectrogram.bins];
+        let loudest = (0..row.len())
+            .max_by(|&a, &b| row[a].total_cmp(&row[b]))
+            .unwrap();
+        spectrogram.frequencies[loudest]
+    }
+
+    #[test]
+    fn linear_peak_is_at_the_tone() {
+        let params = SpectrogramParams {
+            scale: FrequencyScale::Linear,
+            ..SpectrogramParams::default()
+        };
+        let spectrogram = spectrogram(&tone(1000.0, 1.0), &params).unwrap();
+        assert_eq!(spectrogram.bins, 257);
+        assert_eq!(spectrogram.frames, (16_000 - 512) / 160 + 1);
+        assert!((loudest_frequency(&spectrogram, 10) - 1000.0).abs() <= 31.25);
+    }
+
+    #[test]
+    fn mel_peak_is_near_the_tone() {
+        let spectrogram = spectrogram(&tone(1000.0, 1.0), &SpectrogramParams::default()).unwrap();
+        assert_eq!(spectrogram.bins, 80);
+        assert!((loudest_frequency(&spectrogram, 10) - 1000.0).abs() < 100.0);
+    }
+
+    #[test]
+    fn long_audio_is_capped_to_max_frames() {
+        let params = SpectrogramParams {
+            max_frames: 50,
+            ..SpectrogramParams::default()
+        };
+        let spectrogram = spectrogram(&tone(440.0, 3.0), &params).unwrap();
+        assert!(spectrogram.frames <= 50);
+        assert!(spectrogram.frame_ms > 10.0);
+    }
+
+    #[test]
+    fn fft_size_must_be_a_power_of_two() {
+        let params = SpectrogramParams {
+            fft_size: 500,
+            ..SpectrogramParams::default()
+        };
+        assert!(spectrogram(&tone(440.0, 0.1), &params).is_err());
+    }
+}
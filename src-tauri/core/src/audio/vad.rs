@@ -0,0 +1,142 @@
+/// Analysis frame length.
+const FRAME_MS: u32 = 20;
+/// Frames must be this much louder than the noise floor to count as speech.
+const SNR: f32 = 3.0; // About 10 dB
+/// Quieter frames never count as speech, however quiet the room.
+const MIN_SPEECH_LEVEL: f32 = 0.005; // About -46 dBFS
+/// Consecutive speech frames needed to start speaking, so clicks don't.
+const ONSET_FRAMES: u32 = 3;
+/// Non-speech frames before speaking ends, so pauses between words don't.
+const HANGOVER_FRAMES: u32 = 15;
+/// How quickly the noise floor follows the level between speech.
+const FLOOR_ADAPT: f32 = 0.05;
+
+/// Energy-based voice activity detection against an adaptive noise floor:
+/// cheap enough to run on everything captured, good enough for a "talking"
+/// indicator and speech-density stats.
+pub struct Vad {
+    frame_len: usize,
+    channels: usize,
+    energy: f32,
+    samples: usize,
+    noise_floor: f32,
+    speaking: bool,
+    /// Speech frames in a row while silent, or non-speech frames in a row
+    /// while speaking.
+    run: u32,
+    frames: u64,
+    speech_frames: u64,
+}
+
+impl Vad {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        let channels = channels.max(1);
+        Vad {
+            frame_len: (sample_rate * FRAME_MS / 1000).max(1) as usize * channels,
+            channels,
+            energy: 0.0,
+            samples: 0,
+            noise_floor: MIN_SPEECH_LEVEL / SNR,
+            speaking: false,
+            run: 0,
+            frames: 0,
+            speech_frames: 0,
+        }
+    }
+
+    /// Feeds interleaved samples through.
+    pub fn push(&mut self, samples: &[f32]) {
+        for frame in samples.chunks(self.channels) {
+            let mixed = frame.iter().sum::<f32>() / frame.len() as f32;
+            self.energy += mixed * mixed;
+            self.samples += self.channels;
+            if self.samples >= self.frame_len {
+                let rms = (self.energy / (self.frame_len / self.channels) as f32).sqrt();
+                self.frame(rms);
+                self.energy = 0.0;
+                self.samples = 0;
+            }
+        }
+    }
+
+    fn frame(&mut self, rms: f32) {
+        let voiced = rms > (self.noise_floor * SNR).max(MIN_SPEECH_LEVEL);
+        if !voiced {
+            self.noise_floor = match rms < self.noise_floor {
+                true => rms,
+                false => self.noise_floor + (rms - self.noise_floor) * FLOOR_ADAPT,
+            };
+        }
+        if voiced != self.speaking {
+            self.run += 1;
+            let needed = match self.speaking {
+                true => HANGOVER_FRAMES,
+                false => ONSET_FRAMES,
+            };
+            if self.run >= needed {
+                self.speaking = voiced;
+                self.run = 0;
+            }
+        } else {
+            self.run = 0;
+        }
+        self.frames += 1;
+        if self.speaking {
+            self.speech_frames += 1;
+        }
+    }
+
+    pub fn speaking(&self) -> bool {
+        self.speaking
+    }
+
+    /// Time spent speaking so far, in ms.
+    pub fn speech_ms(&self) -> u64 {
+        self.speech_frames * FRAME_MS as u64
+    }
+
+    /// Time analysed so far, in ms.
+    pub fn elapsed_ms(&self) -> u64 {
+        self.frames * FRAME_MS as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(amplitude: f32, ms: usize) -> Vec<f32> {
+        (0..ms * 16)
+            .map(|i| amplitude * (i as f32 * 0.1).sin())
+            .collect()
+    }
+
+    #[test]
+    fn detects_speech_between_silence() {
+        let mut vad = Vad::new(16_000, 1);
+        vad.push(&tone(0.001, 1000));
+        assert!(!vad.speaking());
+        vad.push(&tone(0.2, 1000));
+        assert!(vad.speaking());
+        // A short pause doesn't end it...
+        vad.push(&tone(0.001, 200));
+        assert!(vad.speaking());
+        // ...but a long one does.
+        vad.push(&tone(0.001, 800));
+        assert!(!vad.speaking());
+        assert_eq!(vad.elapsed_ms(), 3000);
+        let speech = vad.speech_ms();
+        assert!((1200..=1300).contains(&speech), "{}", speech);
+    }
+
+    #[test]
+    fn clicks_are_not_speech() {
+        let mut vad = Vad::new(16_000, 2);
+        let mut samples = vec![0.0; 32_000];
+        samples[1000] = 0.9;
+        samples[1001] = 0.9;
+        vad.push(&samples);
+        assert!(!vad.speaking());
+        assert_eq!(vad.speech_ms(), 0);
+    }
+}
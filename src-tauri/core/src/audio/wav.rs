@@ -0,0 +1,79 @@
+use anyhow::{bail, Context, Result};
+use hound::{WavReader, WavSpec};
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct AudioInfo {
+    pub duration_ms: u64,
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// Reads duration and format from the header of any WAV file.
+pub fn info(path: &Path) -> Result<AudioInfo> {
+    let reader = WavReader::open(path).context("not a readable WAV file")?;
+    let spec = reader.spec();
+    Ok(AudioInfo {
+        duration_ms: reader.duration() as u64 * 1000 / spec.sample_rate.max(1) as u64,
+        sample_rate: spec.sample_rate,
+        channels: spec.channels,
+    })
+}
+
+/// Checks that `path` is a WAV file the transcription pipeline can read,
+/// without decoding the samples.
+pub fn validate(path: &Path) -> Result<WavSpec> {
+    let reader = WavReader::open(path).context("not a readable WAV file")?;
+    let spec = reader.spec();
+    if spec.channels == 0 || spec.sample_rate == 0 {
+        bail!("WAV file has no audio");
+    }
+    Ok(spec)
+}
+
+/// Bytes needed to tell a WAV file: `RIFF`, its size and `WAVE`.
+pub const WAV_HEADER_LEN: usize = 12;
+
+/// Whether `header`, the start of a file, is a WAV file's, for checking
+/// uploads and downloads before they're saved.
+pub fn is_wav(header: &[u8]) -> bool {
+    header.len() >= WAV_HEADER_LEN && &header[..4] == b"RIFF" && &header[8..12] == b"WAVE"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    #[test]
+    fn info_reads_stereo_header() {
+        let info = info(&fixture("stereo_16k.wav")).unwrap();
+        assert_eq!(
+            info,
+            AudioInfo {
+                duration_ms: 250,
+                sample_rate: 16000,
+                channels: 2
+            }
+        );
+    }
+
+    #[test]
+    fn validate_reports_spec_without_decoding() {
+        let spec = validate(&fixture("mono_8k.wav")).unwrap();
+        assert_eq!(spec.sample_rate, 8000);
+        // Anything the decoder reads is fine; it mixes and converts.
+        assert!(validate(&fixture("stereo_16k.wav")).is_ok());
+        assert!(validate(&fixture("mono_8bit.wav")).is_ok());
+        assert!(validate(&fixture("does_not_exist.wav")).is_err());
+    }
+
+    #[test]
+    fn only_riff_wave_headers_count_as_wav() {
+        assert!(is_wav(b"RIFF\x24\x08\x00\x00WAVEfmt "));
+        assert!(!is_wav(b"ID3\x04\x00\x00\x00\x00\x00\x00\x00\x00"));
+        assert!(!is_wav(b"RIFF\x24\x08\x00\x00AVI "));
+        assert!(!is_wav(b"RIFF"));
+    }
+}
@@ -0,0 +1,71 @@
+use anyhow::{bail, Result};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::path::Path;
+
+use super::buffer::AudioBuffer;
+
+/// The quietest and loudest sample in one column of a waveform overview.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Peak {
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Summarizes `buffer`, all channels together, as `columns` evenly sized
+/// min/max pairs for drawing a waveform. Columns are computed in parallel.
+pub fn peaks(buffer: &AudioBuffer, columns: usize) -> Result<Vec<Peak>> {
+    if columns == 0 {
+        bail!("a waveform needs at least one column");
+    }
+    let channels = buffer.channels.max(1) as usize;
+    let frames_per_column = buffer.frames().div_ceil(columns).max(1);
+    Ok(buffer
+        .samples
+        .par_chunks(frames_per_column * channels)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .fold(Peak { min: 0.0, max: 0.0 }, |peak, &s| Peak {
+                    min: peak.min.min(s),
+                    max: peak.max.max(s),
+                })
+        })
+        .collect())
+}
+
+/// Reads a WAV file and computes its waveform overview.
+pub fn waveform_peaks(path: &Path, columns: usize) -> Result<Vec<Peak>> {
+    peaks(&AudioBuffer::read(path)?, columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_peak_per_column() {
+        let buffer = AudioBuffer {
+            sample_rate: 1000,
+            channels: 2,
+            samples: (0..1000)
+                .flat_map(|i| [i as f32 / 1000.0, -(i as f32) / 1000.0])
+                .collect(),
+        };
+        let peaks = peaks(&buffer, 10).unwrap();
+        assert_eq!(peaks.len(), 10);
+        assert_eq!(peaks[0].max, 0.099);
+        assert_eq!(peaks[9].min, -0.999);
+    }
+
+    #[test]
+    fn short_audio_yields_fewer_columns() {
+        let buffer = AudioBuffer {
+            sample_rate: 1000,
+            channels: 1,
+            samples: vec![0.5; 3],
+        };
+        assert_eq!(peaks(&buffer, 10).unwrap().len(), 3);
+        assert!(peaks(&buffer, 0).is_err());
+    }
+}
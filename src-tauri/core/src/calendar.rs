@@ -0,0 +1,243 @@
+//! Finding the meeting that's on right now, to title recordings after it.
+
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::Serialize;
+use std::time::Duration;
+
+/// A recording started this long before a meeting still counts as that meeting.
+const EARLY_START_MS: i64 = 5 * 60 * 1000;
+/// Titles name at most this many attendees.
+const MAX_NAMED_ATTENDEES: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Event {
+    pub title: String,
+    /// Unix time in milliseconds.
+    pub start: i64,
+    pub end: i64,
+    pub attendees: Vec<String>,
+}
+
+impl Event {
+    /// `"Weekly sync with Ana, Bo and 2 others"`.
+    pub fn recording_title(&self) -> String {
+        let title = self.title.trim();
+        let named: Vec<&str> = self
+            .attendees
+            .iter()
+            .take(MAX_NAMED_ATTENDEES)
+            .map(String::as_str)
+            .collect();
+        let others = self.attendees.len().saturating_sub(MAX_NAMED_ATTENDEES);
+        match (named.is_empty(), others) {
+            (true, _) => title.to_string(),
+            (false, 0) => format!("{} with {}", title, named.join(", ")),
+            (false, 1) => format!("{} with {} and 1 other", title, named.join(", ")),
+            (false, n) => format!("{} with {} and {} others", title, named.join(", "), n),
+        }
+    }
+}
+
+/// The meeting happening at `now`, or about to start. When meetings overlap
+/// the one that started last wins, since that's usually the one being joined.
+pub fn current_event(events: &[Event], now: i64) -> Option<&Event> {
+    events
+        .iter()
+        .filter(|event| event.start - EARLY_START_MS <= now && now < event.end)
+        .max_by_key(|event| event.start)
+}
+
+/// Reads an `.ics` file, or downloads it when `source` is an `http(s)://` or
+/// `webcal://` URL.
+pub fn load_ics(source: &str) -> Result<Vec<Event>> {
+    let text = if let Some(rest) = source.strip_prefix("webcal://") {
+        download(&format!("https://{}", rest))?
+    } else if source.starts_with("http://") || source.starts_with("https://") {
+        download(source)?
+    } else {
+        std::fs::read_to_string(source).with_context(|| format!("failed to read {}", source))?
+    };
+    Ok(parse_ics(&text))
+}
+
+fn download(url: &str) -> Result<String> {
+    // Recording waits on this, so a slow calendar server mustn't hold it up.
+    ureq::get(url)
+        .timeout(Duration::from_secs(5))
+        .call()
+        .with_context(|| format!("failed to download {}", url))?
+        .into_string()
+        .context("calendar is not text")
+}
+
+/// Timed events from an iCalendar feed. All-day events are skipped since
+/// they aren't meetings, and recurring events only match their first
+/// occurrence. Times with a `TZID` are read as local time.
+pub fn parse_ics(text: &str) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut current: Option<Draft> = None;
+    for line in unfold(text) {
+        let Some((name_and_params, value)) = line.split_once(':') else {
+            continue;
+        };
+        let mut params = name_and_params.split(';');
+        let name = params.next().unwrap_or_default().to_ascii_uppercase();
+        if name == "BEGIN" && value.eq_ignore_ascii_case("VEVENT") {
+            current = Some(Draft::default());
+            continue;
+        }
+        if name == "END" && value.eq_ignore_ascii_case("VEVENT") {
+            if let Some(Draft {
+                title: Some(title),
+                start: Some(start),
+                end: Some(end),
+                attendees,
+            }) = current.take()
+            {
+                events.push(Event {
+                    title,
+                    start,
+                    end,
+                    attendees,
+                });
+            }
+            continue;
+        }
+        let Some(event) = current.as_mut() else {
+            continue;
+        };
+        match name.as_str() {
+            "SUMMARY" => event.title = Some(unescape(value)),
+            "DTSTART" => event.start = parse_time(value),
+            "DTEND" => event.end = parse_time(value),
+            "ATTENDEE" => {
+                let common_name = params
+                    .filter_map(|param| param.split_once('='))
+                    .find(|(key, _)| key.eq_ignore_ascii_case("CN"))
+                    .map(|(_, name)| name.trim_matches('"').to_string());
+                let email = value
+                    .strip_prefix("mailto:")
+                    .or_else(|| value.strip_prefix("MAILTO:"))
+                    .unwrap_or(value);
+                event
+                    .attendees
+                    .push(common_name.unwrap_or_else(|| email.to_string()));
+            }
+            _ => {}
+        }
+    }
+    events
+}
+
+/// A `VEVENT` being read; kept only if it has a title and both times.
+#[derive(Default)]
+struct Draft {
+    title: Option<String>,
+    start: Option<i64>,
+    end: Option<i64>,
+    attendees: Vec<String>,
+}
+
+/// Joins folded lines: a line starting with a space or tab continues the
+/// previous one.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+/// `20240102T150000Z` is UTC and `20240102T150000` local time. Dates
+/// without a time are all-day and yield `None`.
+fn parse_time(value: &str) -> Option<i64> {
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(Utc.from_utc_datetime(&time).timestamp_millis());
+    }
+    if NaiveDate::parse_from_str(value, "%Y%m%d").is_ok() {
+        return None;
+    }
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Local
+        .from_local_datetime(&time)
+        .earliest()
+        .map(|time| time.timestamp_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ICS: &str = "BEGIN:VCALENDAR\r\n\
+        BEGIN:VEVENT\r\n\
+        SUMMARY:Weekly sync\\, team\r\n\
+        DTSTART:20240102T150000Z\r\n\
+        DTEND:20240102T153000Z\r\n\
+        ATTENDEE;CN=\"Ana Lima\";ROLE=REQ-PARTICIPANT:mailto:ana@example.com\r\n\
+        ATTENDEE:mailto:bo@exam\r\n ple.com\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        SUMMARY:Holiday\r\n\
+        DTSTART;VALUE=DATE:20240102\r\n\
+        DTEND;VALUE=DATE:20240103\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    fn ms(hour: u32, minute: u32) -> i64 {
+        Utc.with_ymd_and_hms(2024, 1, 2, hour, minute, 0)
+            .unwrap()
+            .timestamp_millis()
+    }
+
+    #[test]
+    fn parses_timed_events_with_attendees() {
+        assert_eq!(
+            parse_ics(ICS),
+            vec![Event {
+                title: "Weekly sync, team".to_string(),
+                start: ms(15, 0),
+                end: ms(15, 30),
+                attendees: vec!["Ana Lima".to_string(), "bo@example.com".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn current_event_allows_an_early_start() {
+        let events = parse_ics(ICS);
+        assert!(current_event(&events, ms(14, 50)).is_none());
+        assert!(current_event(&events, ms(14, 56)).is_some());
+        assert!(current_event(&events, ms(15, 29)).is_some());
+        assert!(current_event(&events, ms(15, 30)).is_none());
+    }
+
+    #[test]
+    fn titles_name_a_few_attendees() {
+        let mut event = Event {
+            title: "Sync".to_string(),
+            start: 0,
+            end: 1,
+            attendees: vec![],
+        };
+        assert_eq!(event.recording_title(), "Sync");
+        event.attendees = ["Ana", "Bo", "Cy", "Di", "Ed"].map(String::from).to_vec();
+        assert_eq!(
+            event.recording_title(),
+            "Sync with Ana, Bo, Cy and 2 others"
+        );
+    }
+}
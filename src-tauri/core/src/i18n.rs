@@ -0,0 +1,264 @@
+//! Translations for user-facing strings produced on the Rust side: errors,
+//! notifications, tray and menu labels.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    Es,
+    De,
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::En, Language::Es, Language::De];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    StartRecording,
+    StopRecording,
+    OpenLastTranscript,
+    Quit,
+    File,
+    Recording,
+    Record,
+    Stop,
+    ImportAudio,
+    ExportTranscript,
+    Preferences,
+    TranscriptionFinished,
+    ClickToOpenTranscript,
+    TranscriptionFailed,
+    TranscriptionsFinished,
+    /// `{count}`
+    TranscriptionsDone,
+    /// `{remediation}`
+    MicBlocked,
+    MicRemediationMacos,
+    MicRemediationWindows,
+    MicRemediationOther,
+    NoInputDevice,
+    /// `{device}`
+    InputDeviceBusy,
+    NarrowbandInput,
+    /// `{device}`, `{rate}`
+    NarrowbandInputDetail,
+    RecordingInterrupted,
+    RecordingInterruptedDetail,
+    AudioFileMissing,
+    ModelFileMissing,
+    ModelFileCorrupt,
+    AppClosedBeforeJobFinished,
+}
+
+impl Msg {
+    pub const ALL: &[Msg] = &[
+        Msg::StartRecording,
+        Msg::StopRecording,
+        Msg::OpenLastTranscript,
+        Msg::Quit,
+        Msg::File,
+        Msg::Recording,
+        Msg::Record,
+        Msg::Stop,
+        Msg::ImportAudio,
+        Msg::ExportTranscript,
+        Msg::Preferences,
+        Msg::TranscriptionFinished,
+        Msg::ClickToOpenTranscript,
+        Msg::TranscriptionFailed,
+        Msg::TranscriptionsFinished,
+        Msg::TranscriptionsDone,
+        Msg::MicBlocked,
+        Msg::MicRemediationMacos,
+        Msg::MicRemediationWindows,
+        Msg::MicRemediationOther,
+        Msg::NoInputDevice,
+        Msg::InputDeviceBusy,
+        Msg::NarrowbandInput,
+        Msg::NarrowbandInputDetail,
+        Msg::RecordingInterrupted,
+        Msg::RecordingInterruptedDetail,
+        Msg::AudioFileMissing,
+        Msg::ModelFileMissing,
+        Msg::ModelFileCorrupt,
+        Msg::AppClosedBeforeJobFinished,
+    ];
+}
+
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+pub fn language() -> Language {
+    match LANGUAGE.load(Ordering::Relaxed) {
+        1 => Language::Es,
+        2 => Language::De,
+        _ => Language::En,
+    }
+}
+
+/// Translates `msg` into the current language.
+pub fn t(msg: Msg) -> &'static str {
+    text(language(), msg)
+}
+
+/// Translates `msg` and fills in its `{name}` placeholders.
+pub fn tf(msg: Msg, args: &[(&str, &str)]) -> String {
+    args.iter().fold(t(msg).to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), value)
+    })
+}
+
+pub fn text(language: Language, msg: Msg) -> &'static str {
+    match language {
+        Language::En => en(msg),
+        Language::Es => es(msg),
+        Language::De => de(msg),
+    }
+}
+
+fn en(msg: Msg) -> &'static str {
+    match msg {
+        Msg::StartRecording => "Start Recording",
+        Msg::StopRecording => "Stop Recording",
+        Msg::OpenLastTranscript => "Open Last Transcript",
+        Msg::Quit => "Quit",
+        Msg::File => "File",
+        Msg::Recording => "Recording",
+        Msg::Record => "Record",
+        Msg::Stop => "Stop",
+        Msg::ImportAudio => "Import Audio…",
+        Msg::ExportTranscript => "Export Transcript…",
+        Msg::Preferences => "Preferences…",
+        Msg::TranscriptionFinished => "Transcription finished",
+        Msg::ClickToOpenTranscript => "Click to open the transcript.",
+        Msg::TranscriptionFailed => "Transcription failed",
+        Msg::TranscriptionsFinished => "Transcriptions finished",
+        Msg::TranscriptionsDone => "{count} transcriptions are done.",
+        Msg::MicBlocked => "Microphone access is blocked. {remediation}",
+        Msg::MicRemediationMacos => "Allow it in System Settings > Privacy & Security > Microphone, then restart the app.",
+        Msg::MicRemediationWindows => "Turn on \"Microphone access\" and \"Let desktop apps access your microphone\" in Settings > Privacy & security > Microphone. If the first switch is greyed out, your administrator has disabled it.",
+        Msg::MicRemediationOther => "Check your system's microphone privacy settings.",
+        Msg::NoInputDevice => "No input device available",
+        Msg::InputDeviceBusy => "Couldn't open {device}. Another app may be using it exclusively; close it or pick a different microphone.",
+        Msg::NarrowbandInput => "Low-quality microphone",
+        Msg::NarrowbandInputDetail => "Recording from {device} at {rate} kHz. Bluetooth headsets switch to phone quality while their microphone is in use, so transcripts may be less accurate.",
+        Msg::RecordingInterrupted => "Recording stopped",
+        Msg::RecordingInterruptedDetail => "Your computer went to sleep, so the recording was saved and stopped. Start a new one to keep going.",
+        Msg::AudioFileMissing => "audio file doesn't exist",
+        Msg::ModelFileMissing => "whisper file doesn't exist",
+        Msg::ModelFileCorrupt => "whisper model file is damaged; download it again",
+        Msg::AppClosedBeforeJobFinished => "The app was closed before this job finished.",
+    }
+}
+
+fn es(msg: Msg) -> &'static str {
+    match msg {
+        Msg::StartRecording => "Iniciar grabación",
+        Msg::StopRecording => "Detener grabación",
+        Msg::OpenLastTranscript => "Abrir última transcripción",
+        Msg::Quit => "Salir",
+        Msg::File => "Archivo",
+        Msg::Recording => "Grabación",
+        Msg::Record => "Grabar",
+        Msg::Stop => "Detener",
+        Msg::ImportAudio => "Importar audio…",
+        Msg::ExportTranscript => "Exportar transcripción…",
+        Msg::Preferences => "Preferencias…",
+        Msg::TranscriptionFinished => "Transcripción terminada",
+        Msg::ClickToOpenTranscript => "Haz clic para abrir la transcripción.",
+        Msg::TranscriptionFailed => "La transcripción falló",
+        Msg::TranscriptionsFinished => "Transcripciones terminadas",
+        Msg::TranscriptionsDone => "{count} transcripciones están listas.",
+        Msg::MicBlocked => "El acceso al micrófono está bloqueado. {remediation}",
+        Msg::MicRemediationMacos => "Permítelo en Ajustes del Sistema > Privacidad y seguridad > Micrófono y reinicia la aplicación.",
+        Msg::MicRemediationWindows => "Activa \"Acceso al micrófono\" y \"Permitir que las aplicaciones de escritorio accedan al micrófono\" en Configuración > Privacidad y seguridad > Micrófono. Si el primer interruptor está atenuado, tu administrador lo ha desactivado.",
+        Msg::MicRemediationOther => "Revisa la configuración de privacidad del micrófono de tu sistema.",
+        Msg::NoInputDevice => "No hay ningún dispositivo de entrada disponible",
+        Msg::InputDeviceBusy => "No se pudo abrir {device}. Puede que otra aplicación lo esté usando en exclusiva; ciérrala o elige otro micrófono.",
+        Msg::NarrowbandInput => "Micrófono de baja calidad",
+        Msg::NarrowbandInputDetail => "Grabando desde {device} a {rate} kHz. Los auriculares Bluetooth pasan a calidad telefónica mientras usan el micrófono, así que las transcripciones pueden ser menos precisas.",
+        Msg::RecordingInterrupted => "Grabación detenida",
+        Msg::RecordingInterruptedDetail => "El ordenador entró en suspensión, así que la grabación se guardó y se detuvo. Inicia una nueva para continuar.",
+        Msg::AudioFileMissing => "el archivo de audio no existe",
+        Msg::ModelFileMissing => "el archivo del modelo whisper no existe",
+        Msg::ModelFileCorrupt => "el archivo del modelo whisper está dañado; descárgalo de nuevo",
+        Msg::AppClosedBeforeJobFinished => "La aplicación se cerró antes de que terminara esta tarea.",
+    }
+}
+
+fn de(msg: Msg) -> &'static str {
+    match msg {
+        Msg::StartRecording => "Aufnahme starten",
+        Msg::StopRecording => "Aufnahme beenden",
+        Msg::OpenLastTranscript => "Letztes Transkript öffnen",
+        Msg::Quit => "Beenden",
+        Msg::File => "Ablage",
+        Msg::Recording => "Aufnahme",
+        Msg::Record => "Aufnehmen",
+        Msg::Stop => "Stopp",
+        Msg::ImportAudio => "Audio importieren…",
+        Msg::ExportTranscript => "Transkript exportieren…",
+        Msg::Preferences => "Einstellungen…",
+        Msg::TranscriptionFinished => "Transkription abgeschlossen",
+        Msg::ClickToOpenTranscript => "Klicken, um das Transkript zu öffnen.",
+        Msg::TranscriptionFailed => "Transkription fehlgeschlagen",
+        Msg::TranscriptionsFinished => "Transkriptionen abgeschlossen",
+        Msg::TranscriptionsDone => "{count} Transkriptionen sind fertig.",
+        Msg::MicBlocked => "Der Mikrofonzugriff ist blockiert. {remediation}",
+        Msg::MicRemediationMacos => "Erlaube ihn unter Systemeinstellungen > Datenschutz & Sicherheit > Mikrofon und starte die App neu.",
+        Msg::MicRemediationWindows => "Aktiviere \"Mikrofonzugriff\" und \"Desktop-Apps den Zugriff auf das Mikrofon erlauben\" unter Einstellungen > Datenschutz und Sicherheit > Mikrofon. Ist der erste Schalter ausgegraut, hat dein Administrator ihn deaktiviert.",
+        Msg::MicRemediationOther => "Prüfe die Datenschutzeinstellungen für das Mikrofon.",
+        Msg::NoInputDevice => "Kein Eingabegerät verfügbar",
+        Msg::InputDeviceBusy => "{device} konnte nicht geöffnet werden. Möglicherweise nutzt eine andere App das Gerät exklusiv; schließe sie oder wähle ein anderes Mikrofon.",
+        Msg::NarrowbandInput => "Mikrofon mit geringer Qualität",
+        Msg::NarrowbandInputDetail => "Aufnahme von {device} mit {rate} kHz. Bluetooth-Headsets wechseln in Telefonqualität, solange ihr Mikrofon verwendet wird, daher können Transkripte ungenauer sein.",
+        Msg::RecordingInterrupted => "Aufnahme beendet",
+        Msg::RecordingInterruptedDetail => "Dein Computer ist in den Ruhezustand gewechselt, daher wurde die Aufnahme gespeichert und beendet. Starte eine neue, um weiterzumachen.",
+        Msg::AudioFileMissing => "Audiodatei existiert nicht",
+        Msg::ModelFileMissing => "Whisper-Modelldatei existiert nicht",
+        Msg::ModelFileCorrupt => "Whisper-Modelldatei ist beschädigt; bitte erneut herunterladen",
+        Msg::AppClosedBeforeJobFinished => "Die App wurde geschlossen, bevor dieser Auftrag fertig war.",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_language_keeps_placeholders() {
+        for &msg in Msg::ALL {
+            let english: Vec<&str> = text(Language::En, msg).matches('{').collect();
+            for language in Language::ALL {
+                let translated: Vec<&str> = text(language, msg).matches('{').collect();
+                assert_eq!(english, translated, "{:?} in {:?}", msg, language);
+            }
+        }
+    }
+
+    #[test]
+    fn tf_fills_placeholders() {
+        assert_eq!(
+            text(Language::En, Msg::TranscriptionsDone).replace("{count}", "3"),
+            tf(Msg::TranscriptionsDone, &[("count", "3")])
+        );
+    }
+
+    #[test]
+    fn language_round_trips_through_settings_json() {
+        let json = serde_json::to_string(&Language::De).unwrap();
+        assert_eq!(json, "\"de\"");
+        assert_eq!(
+            serde_json::from_str::<Language>(&json).unwrap(),
+            Language::De
+        );
+    }
+}
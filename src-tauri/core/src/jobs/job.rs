@@ -0,0 +1,602 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::pool::{Pool, Priority};
+use super::retry::{self, RetryPolicy};
+
+pub const PROGRESS_EVENT: &str = "job://progress";
+pub const DONE_EVENT: &str = "job://done";
+pub const FAILED_EVENT: &str = "job://failed";
+pub const CANCELLED_EVENT: &str = "job://cancelled";
+
+pub type JobId = u64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Recording,
+    Transcription,
+    Download,
+    Export,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn is_finished(self) -> bool {
+        matches!(
+            self,
+            JobState::Done | JobState::Failed | JobState::Cancelled
+        )
+    }
+}
+
+/// Caps on what a job may use. The job manager hands them to the job's task
+/// through [`JobHandle::limits`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    /// Most threads it may run on; `None` leaves it to the task.
+    pub threads: Option<usize>,
+    /// Keep it off the GPU. Whisper keeps a CPU-only copy of the model for
+    /// such jobs next to the one on the GPU, so this costs a second model's
+    /// worth of memory while both kinds run.
+    pub cpu_only: bool,
+}
+
+/// How to run a queued job. A bare [`Priority`] converts into options with
+/// the default limits for that priority.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JobOptions {
+    pub priority: Priority,
+    /// `None` applies the limits set for background jobs to background jobs
+    /// and none to the rest.
+    pub limits: Option<Limits>,
+}
+
+impl From<Priority> for JobOptions {
+    fn from(priority: Priority) -> Self {
+        JobOptions {
+            priority,
+            limits: None,
+        }
+    }
+}
+
+/// Snapshot of a long-running operation, as sent to the frontend.
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: JobId,
+    pub kind: JobKind,
+    pub priority: Priority,
+    pub limits: Limits,
+    /// Percentage in `0.0..=100.0`.
+    pub progress: f32,
+    /// Roughly how long until it's done, from how fast it's gone so far.
+    /// `None` until there's enough progress to tell.
+    pub eta_ms: Option<u64>,
+    pub state: JobState,
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+    /// Errors of earlier attempts that were retried, oldest first.
+    pub failures: Vec<String>,
+}
+
+/// Receives job events. The app forwards these to the webview; tests collect them.
+pub trait JobEvents: Send + Sync {
+    fn emit(&self, event: &str, job: &Job);
+}
+
+struct Inner {
+    next_id: AtomicU64,
+    jobs: Mutex<HashMap<JobId, Job>>,
+    /// When each unfinished job started running.
+    started: Mutex<HashMap<JobId, Instant>>,
+    background_limits: Mutex<Limits>,
+    retry_policy: Mutex<RetryPolicy>,
+    events: Box<dyn JobEvents>,
+    pool: Pool,
+}
+
+/// Registry of every job the app has started, shared across commands.
+#[derive(Clone)]
+pub struct Jobs {
+    inner: Arc<Inner>,
+}
+
+impl Jobs {
+    pub fn new(events: impl JobEvents + 'static, max_parallel: usize) -> Self {
+        Jobs {
+            inner: Arc::new(Inner {
+                next_id: AtomicU64::new(1),
+                jobs: Mutex::new(HashMap::new()),
+                started: Mutex::new(HashMap::new()),
+                background_limits: Mutex::new(Limits::default()),
+                retry_policy: Mutex::new(RetryPolicy::default()),
+                events: Box::new(events),
+                pool: Pool::new(max_parallel),
+            }),
+        }
+    }
+
+    /// Registers a job that is already running on the caller's side, like a
+    /// recording driven by the audio thread.
+    pub fn start(&self, kind: JobKind) -> JobHandle {
+        self.insert(kind, Priority::Live, Limits::default(), JobState::Running)
+    }
+
+    /// Queues `task` on the shared worker pool. The job reports `Queued` until
+    /// a slot frees up, which comes sooner the higher its priority.
+    pub fn enqueue(
+        &self,
+        kind: JobKind,
+        options: impl Into<JobOptions>,
+        task: impl FnOnce(JobHandle) + Send + 'static,
+    ) -> JobHandle {
+        let JobOptions { priority, limits } = options.into();
+        let limits = limits.unwrap_or_else(|| match priority {
+            Priority::Background => *self.inner.background_limits.lock().unwrap(),
+            _ => Limits::default(),
+        });
+        let handle = self.insert(kind, priority, limits, JobState::Queued);
+        let worker_handle = handle.clone();
+        self.inner.pool.submit(priority, move || {
+            worker_handle.mark_started();
+            worker_handle
+                .jobs
+                .update(worker_handle.id, PROGRESS_EVENT, |job| {
+                    job.state = JobState::Running
+                });
+            task(worker_handle);
+        });
+        handle
+    }
+
+    /// Makes new ids start after `last`, so they don't collide with jobs
+    /// persisted by a previous run.
+    pub fn continue_after(&self, last: JobId) {
+        self.inner.next_id.fetch_max(last + 1, Ordering::Relaxed);
+    }
+
+    pub fn set_max_parallel(&self, max_parallel: usize) {
+        self.inner.pool.set_max_parallel(max_parallel);
+    }
+
+    /// Limits for background jobs queued from now on that don't set their own,
+    /// so a batch leaves room for everything else.
+    pub fn set_background_limits(&self, limits: Limits) {
+        *self.inner.background_limits.lock().unwrap() = limits;
+    }
+
+    pub fn set_retry_policy(&self, policy: RetryPolicy) {
+        *self.inner.retry_policy.lock().unwrap() = policy;
+    }
+
+    /// Cancels every queued and running job except recordings, which only
+    /// stop when the user stops them. Queued jobs never start; running ones
+    /// see [`JobHandle::is_cancelled`] and stop at the next chance they get.
+    /// Returns how many were cancelled.
+    pub fn cancel_all(&self) -> usize {
+        self.inner.pool.clear();
+        let cancelled: Vec<JobId> = self
+            .list()
+            .into_iter()
+            .filter(|job| !job.state.is_finished() && job.kind != JobKind::Recording)
+            .map(|job| job.id)
+            .collect();
+        for &id in &cancelled {
+            self.update(id, CANCELLED_EVENT, |job| job.state = JobState::Cancelled);
+        }
+        cancelled.len()
+    }
+
+    /// Stops starting queued jobs until [`resume_queue`](Self::resume_queue).
+    /// Running jobs carry on.
+    pub fn pause_queue(&self) {
+        self.inner.pool.pause();
+    }
+
+    pub fn resume_queue(&self) {
+        self.inner.pool.resume();
+    }
+
+    pub fn queue_paused(&self) -> bool {
+        self.inner.pool.is_paused()
+    }
+
+    fn insert(
+        &self,
+        kind: JobKind,
+        priority: Priority,
+        limits: Limits,
+        state: JobState,
+    ) -> JobHandle {
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let job = Job {
+            id,
+            kind,
+            priority,
+            limits,
+            progress: 0.0,
+            eta_ms: None,
+            state,
+            result: None,
+            error: None,
+            failures: Vec::new(),
+        };
+        self.inner.events.emit(PROGRESS_EVENT, &job);
+        self.inner.jobs.lock().unwrap().insert(id, job);
+        let handle = JobHandle {
+            id,
+            jobs: self.clone(),
+        };
+        if state == JobState::Running {
+            handle.mark_started();
+        }
+        handle
+    }
+
+    pub fn get(&self, id: JobId) -> Option<Job> {
+        self.inner.jobs.lock().unwrap().get(&id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<Job> {
+        let mut jobs: Vec<Job> = self.inner.jobs.lock().unwrap().values().cloned().collect();
+        jobs.sort_by_key(|job| job.id);
+        jobs
+    }
+
+    /// Fails every job that is still queued or running, e.g. because the app is quitting.
+    pub fn interrupt_running(&self, reason: &str) {
+        let running: Vec<JobId> = self
+            .list()
+            .into_iter()
+            .filter(|job| !job.state.is_finished())
+            .map(|job| job.id)
+            .collect();
+        for id in running {
+            self.update(id, FAILED_EVENT, |job| {
+                job.state = JobState::Failed;
+                job.error = Some(reason.to_string());
+            });
+        }
+    }
+
+    fn update(&self, id: JobId, event: &str, f: impl FnOnce(&mut Job)) {
+        let snapshot = {
+            let mut jobs = self.inner.jobs.lock().unwrap();
+            let Some(job) = jobs.get_mut(&id) else {
+                return;
+            };
+            if job.state.is_finished() {
+                return;
+            }
+            f(job);
+            if job.state.is_finished() {
+                job.eta_ms = None;
+                self.inner.started.lock().unwrap().remove(&id);
+            }
+            job.clone()
+        };
+        self.inner.events.emit(event, &snapshot);
+    }
+}
+
+/// Handle held by whoever is doing the work for a job.
+#[derive(Clone)]
+pub struct JobHandle {
+    id: JobId,
+    jobs: Jobs,
+}
+
+impl JobHandle {
+    pub fn id(&self) -> JobId {
+        self.id
+    }
+
+    /// What the job may use; the task is expected to stay within it.
+    pub fn limits(&self) -> Limits {
+        self.jobs
+            .get(self.id)
+            .map(|job| job.limits)
+            .unwrap_or_default()
+    }
+
+    /// Whether the job was cancelled, so whoever's doing the work should
+    /// stop. Anything it reports afterwards is ignored.
+    pub fn is_cancelled(&self) -> bool {
+        self.jobs
+            .get(self.id)
+            .is_some_and(|job| job.state == JobState::Cancelled)
+    }
+
+    fn mark_started(&self) {
+        self.jobs
+            .inner
+            .started
+            .lock()
+            .unwrap()
+            .insert(self.id, Instant::now());
+    }
+
+    pub fn progress(&self, progress: f32) {
+        let progress = progress.clamp(0.0, 100.0);
+        let elapsed = self
+            .jobs
+            .inner
+            .started
+            .lock()
+            .unwrap()
+            .get(&self.id)
+            .map(Instant::elapsed);
+        self.jobs.update(self.id, PROGRESS_EVENT, |job| {
+            job.progress = progress;
+            job.eta_ms = elapsed.and_then(|elapsed| eta(elapsed, progress));
+        });
+    }
+
+    pub fn done(&self, result: serde_json::Value) {
+        self.jobs.update(self.id, DONE_EVENT, |job| {
+            job.progress = 100.0;
+            job.state = JobState::Done;
+            job.result = Some(result);
+        });
+    }
+
+    pub fn fail(&self, error: impl ToString) {
+        self.jobs.update(self.id, FAILED_EVENT, |job| {
+            job.state = JobState::Failed;
+            job.error = Some(error.to_string());
+        });
+    }
+
+    /// Runs `attempt`, trying again under the retry policy while it fails in
+    /// a way that might pass, with a growing wait in between. Each retried
+    /// failure goes into the job's history. `attempt` gets the number of the
+    /// try, counting from 1, so it can clean up after the last one.
+    pub fn retrying<T>(
+        &self,
+        mut attempt: impl FnMut(u32) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let policy = *self.jobs.inner.retry_policy.lock().unwrap();
+        let mut number = 1;
+        loop {
+            let result = attempt(number);
+            match &result {
+                Err(err)
+                    if number < policy.max_attempts
+                        && retry::is_transient(err)
+                        && !self.is_cancelled() =>
+                {
+                    let failure = format!("{:#}", err);
+                    self.jobs
+                        .update(self.id, PROGRESS_EVENT, |job| job.failures.push(failure));
+                    std::thread::sleep(policy.backoff(number));
+                    number += 1;
+                }
+                _ => return result,
+            }
+        }
+    }
+
+    /// Marks the job done or failed depending on `result`, passing it through.
+    pub fn finish<T: Serialize>(&self, result: anyhow::Result<T>) -> anyhow::Result<T> {
+        match &result {
+            Ok(value) => self.done(serde_json::to_value(value).unwrap_or_default()),
+            Err(err) => self.fail(err),
+        }
+        result
+    }
+}
+
+/// Time left if the rest goes at the pace so far. For a transcription that's
+/// its measured real-time factor applied to the audio still to go.
+fn eta(elapsed: Duration, progress: f32) -> Option<u64> {
+    // The first percent is mostly model loading, which says little about pace.
+    if progress < 1.0 {
+        return None;
+    }
+    let remaining = elapsed.as_secs_f64() * f64::from(100.0 - progress) / f64::from(progress);
+    Some((remaining * 1000.0).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Recorded(Arc<Mutex<Vec<(String, JobState, f32)>>>);
+
+    impl JobEvents for Recorded {
+        fn emit(&self, event: &str, job: &Job) {
+            self.0
+                .lock()
+                .unwrap()
+                .push((event.to_string(), job.state, job.progress));
+        }
+    }
+
+    #[test]
+    fn emits_progress_then_done() {
+        let events = Recorded::default();
+        let jobs = Jobs::new(events.clone(), 1);
+        let job = jobs.start(JobKind::Transcription);
+        job.progress(50.0);
+        job.done(serde_json::json!(["hello"]));
+
+        let seen = events.0.lock().unwrap().clone();
+        assert_eq!(
+            seen,
+            vec![
+                (PROGRESS_EVENT.to_string(), JobState::Running, 0.0),
+                (PROGRESS_EVENT.to_string(), JobState::Running, 50.0),
+                (DONE_EVENT.to_string(), JobState::Done, 100.0),
+            ]
+        );
+        assert_eq!(
+            jobs.get(job.id()).unwrap().result,
+            Some(serde_json::json!(["hello"]))
+        );
+    }
+
+    #[test]
+    fn eta_extrapolates_the_pace_so_far() {
+        assert_eq!(eta(Duration::from_secs(30), 0.5), None);
+        assert_eq!(eta(Duration::from_secs(30), 25.0), Some(90_000));
+        assert_eq!(eta(Duration::from_secs(30), 100.0), Some(0));
+    }
+
+    #[test]
+    fn finished_jobs_ignore_further_updates() {
+        let events = Recorded::default();
+        let jobs = Jobs::new(events.clone(), 1);
+        let job = jobs.start(JobKind::Export);
+        job.fail("disk full");
+        job.progress(10.0);
+        job.done(serde_json::Value::Null);
+
+        let job = jobs.get(job.id()).unwrap();
+        assert_eq!(job.state, JobState::Failed);
+        assert_eq!(job.error.as_deref(), Some("disk full"));
+        assert_eq!(events.0.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn enqueued_jobs_run_on_the_pool() {
+        let events = Recorded::default();
+        let jobs = Jobs::new(events.clone(), 1);
+        let (tx, rx) = std::sync::mpsc::channel();
+        let job = jobs.enqueue(JobKind::Transcription, Priority::Normal, move |job| {
+            job.done(serde_json::json!("ok"));
+            tx.send(()).unwrap();
+        });
+        rx.recv().unwrap();
+
+        assert_eq!(jobs.get(job.id()).unwrap().state, JobState::Done);
+        let states: Vec<JobState> = events.0.lock().unwrap().iter().map(|e| e.1).collect();
+        assert_eq!(
+            states,
+            vec![JobState::Queued, JobState::Running, JobState::Done]
+        );
+    }
+
+    #[test]
+    fn interrupt_fails_only_running_jobs() {
+        let jobs = Jobs::new(Recorded::default(), 1);
+        let done = jobs.start(JobKind::Transcription);
+        done.done(serde_json::Value::Null);
+        let running = jobs.start(JobKind::Recording);
+
+        jobs.interrupt_running("app closed");
+
+        assert_eq!(jobs.get(done.id()).unwrap().state, JobState::Done);
+        let running = jobs.get(running.id()).unwrap();
+        assert_eq!(running.state, JobState::Failed);
+        assert_eq!(running.error.as_deref(), Some("app closed"));
+    }
+
+    #[test]
+    fn transient_failures_are_retried_and_recorded() {
+        let jobs = Jobs::new(Recorded::default(), 1);
+        jobs.set_retry_policy(RetryPolicy {
+            max_attempts: 3,
+            backoff_ms: 1,
+            max_backoff_ms: 1,
+        });
+        let job = jobs.start(JobKind::Transcription);
+        let result = job.retrying(|attempt| match attempt {
+            1 => Err(anyhow::anyhow!("out of memory")),
+            _ => Ok(attempt),
+        });
+        assert_eq!(result.unwrap(), 2);
+        assert_eq!(jobs.get(job.id()).unwrap().failures, ["out of memory"]);
+
+        let job = jobs.start(JobKind::Transcription);
+        let mut attempts = 0;
+        let result: anyhow::Result<()> = job.retrying(|_| {
+            attempts += 1;
+            Err(anyhow::anyhow!("audio file doesn't exist"))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn background_jobs_get_background_limits() {
+        let jobs = Jobs::new(Recorded::default(), 1);
+        jobs.pause_queue();
+        let capped = Limits {
+            threads: Some(2),
+            cpu_only: true,
+        };
+        jobs.set_background_limits(capped);
+        let background = jobs.enqueue(JobKind::Transcription, Priority::Background, |_| {});
+        let live = jobs.enqueue(JobKind::Transcription, Priority::Live, |_| {});
+        let explicit = jobs.enqueue(
+            JobKind::Transcription,
+            JobOptions {
+                priority: Priority::Background,
+                limits: Some(Limits::default()),
+            },
+            |_| {},
+        );
+        assert_eq!(background.limits(), capped);
+        assert_eq!(live.limits(), Limits::default());
+        assert_eq!(explicit.limits(), Limits::default());
+    }
+
+    #[test]
+    fn cancel_all_spares_recordings_and_drops_the_queue() {
+        let jobs = Jobs::new(Recorded::default(), 1);
+        let recording = jobs.start(JobKind::Recording);
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        let (ran_tx, ran_rx) = std::sync::mpsc::channel();
+        let running = jobs.enqueue(JobKind::Transcription, Priority::Normal, move |job| {
+            release_rx.recv().unwrap();
+            ran_tx.send(job.is_cancelled()).unwrap();
+            job.done(serde_json::Value::Null);
+        });
+        while jobs.get(running.id()).unwrap().state != JobState::Running {
+            std::thread::yield_now();
+        }
+        let queued = jobs.enqueue(JobKind::Transcription, Priority::Normal, |_| {
+            panic!("cancelled jobs never start")
+        });
+
+        assert_eq!(jobs.cancel_all(), 2);
+        release_tx.send(()).unwrap();
+        assert!(ran_rx.recv().unwrap());
+
+        assert_eq!(jobs.get(recording.id()).unwrap().state, JobState::Running);
+        assert_eq!(jobs.get(running.id()).unwrap().state, JobState::Cancelled);
+        assert_eq!(jobs.get(queued.id()).unwrap().state, JobState::Cancelled);
+    }
+
+    #[test]
+    fn ids_are_unique_and_listed_in_order() {
+        let jobs = Jobs::new(Recorded::default(), 1);
+        let a = jobs.start(JobKind::Recording);
+        let b = jobs.start(JobKind::Download);
+        assert_ne!(a.id(), b.id());
+        let ids: Vec<JobId> = jobs.list().iter().map(|job| job.id).collect();
+        assert_eq!(ids, vec![a.id(), b.id()]);
+    }
+
+    #[test]
+    fn continue_after_skips_persisted_ids() {
+        let jobs = Jobs::new(Recorded::default(), 1);
+        jobs.continue_after(41);
+        assert_eq!(jobs.start(JobKind::Recording).id(), 42);
+    }
+}
@@ -0,0 +1,9 @@
+pub mod job;
+pub mod pool;
+pub mod retry;
+pub mod worker;
+
+pub use job::{Job, JobEvents, JobHandle, JobId, JobKind, JobOptions, JobState, Jobs, Limits};
+pub use pool::{Pool, Priority};
+pub use retry::RetryPolicy;
+pub use worker::Worker;
@@ -0,0 +1,239 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+type Task = Box<dyn FnOnce() + Send>;
+
+/// Which queued tasks start first. Tasks of the same priority start in the
+/// order they were submitted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// Batches nobody is watching, like imports.
+    Background,
+    #[default]
+    Normal,
+    /// Someone's waiting on it as it happens, like captions of the take
+    /// that just stopped.
+    Live,
+}
+
+impl Priority {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Priority::Background => "background",
+            Priority::Normal => "normal",
+            Priority::Live => "live",
+        }
+    }
+
+    pub fn parse(priority: &str) -> Option<Self> {
+        match priority {
+            "background" => Some(Priority::Background),
+            "normal" => Some(Priority::Normal),
+            "live" => Some(Priority::Live),
+            _ => None,
+        }
+    }
+}
+
+struct State {
+    pending: VecDeque<(Priority, Task)>,
+    running: usize,
+    running_live: usize,
+    max_parallel: usize,
+    paused: bool,
+}
+
+/// Runs queued tasks on at most `max_parallel` threads at a time, so a batch
+/// of files doesn't start every transcription at once. Higher priorities
+/// jump the queue, and one live task at a time may run on top of the limit
+/// so it never waits behind a batch.
+#[derive(Clone)]
+pub struct Pool {
+    state: Arc<Mutex<State>>,
+}
+
+impl Pool {
+    pub fn new(max_parallel: usize) -> Self {
+        Pool {
+            state: Arc::new(Mutex::new(State {
+                pending: VecDeque::new(),
+                running: 0,
+                running_live: 0,
+                paused: false,
+                max_parallel: max_parallel.max(1),
+            })),
+        }
+    }
+
+    pub fn submit(&self, priority: Priority, task: impl FnOnce() + Send + 'static) {
+        self.state
+            .lock()
+            .unwrap()
+            .pending
+            .push_back((priority, Box::new(task)));
+        self.dispatch();
+    }
+
+    /// Changes the limit. Lowering it lets running tasks finish; raising it
+    /// starts queued tasks straight away.
+    pub fn set_max_parallel(&self, max_parallel: usize) {
+        self.state.lock().unwrap().max_parallel = max_parallel.max(1);
+        self.dispatch();
+    }
+
+    /// Stops starting queued tasks until [`resume`](Self::resume).
+    pub fn pause(&self) {
+        self.state.lock().unwrap().paused = true;
+    }
+
+    pub fn resume(&self) {
+        self.state.lock().unwrap().paused = false;
+        self.dispatch();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.lock().unwrap().paused
+    }
+
+    /// Drops every queued task without running it.
+    pub fn clear(&self) {
+        let pending = std::mem::take(&mut self.state.lock().unwrap().pending);
+        // Dropped outside the lock, in case a task owns something that
+        // submits on drop.
+        drop(pending);
+    }
+
+    pub fn max_parallel(&self) -> usize {
+        self.state.lock().unwrap().max_parallel
+    }
+
+    pub fn running(&self) -> usize {
+        self.state.lock().unwrap().running
+    }
+
+    pub fn pending(&self) -> usize {
+        self.state.lock().unwrap().pending.len()
+    }
+
+    fn dispatch(&self) {
+        let mut state = self.state.lock().unwrap();
+        while !state.paused {
+            let Some(priority) = state.pending.iter().map(|(priority, _)| *priority).max() else {
+                break;
+            };
+            let live = priority == Priority::Live;
+            let extra_slot = live && state.running_live == 0;
+            if state.running >= state.max_parallel && !extra_slot {
+                break;
+            }
+            let next = state
+                .pending
+                .iter()
+                .position(|(queued, _)| *queued == priority)
+                .unwrap();
+            let (_, task) = state.pending.remove(next).unwrap();
+            state.running += 1;
+            state.running_live += live as usize;
+            let pool = self.clone();
+            thread::spawn(move || {
+                // Catch panics so a crashing task can't leak its slot.
+                let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(task));
+                {
+                    let mut state = pool.state.lock().unwrap();
+                    state.running -= 1;
+                    state.running_live -= live as usize;
+                }
+                pool.dispatch();
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn never_exceeds_max_parallel() {
+        let pool = Pool::new(2);
+        let current = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (done_tx, done_rx) = mpsc::channel();
+        for _ in 0..8 {
+            let (current, peak, done_tx) = (current.clone(), peak.clone(), done_tx.clone());
+            pool.submit(Priority::Normal, move || {
+                let now = current.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(10));
+                current.fetch_sub(1, Ordering::SeqCst);
+                done_tx.send(()).unwrap();
+            });
+        }
+        for _ in 0..8 {
+            done_rx.recv().unwrap();
+        }
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn panicking_task_frees_its_slot() {
+        let pool = Pool::new(1);
+        pool.submit(Priority::Normal, || panic!("boom"));
+        let (tx, rx) = mpsc::channel();
+        pool.submit(Priority::Normal, move || tx.send(()).unwrap());
+        rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    }
+
+    #[test]
+    fn higher_priorities_start_first() {
+        let pool = Pool::new(1);
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        pool.submit(Priority::Normal, move || release_rx.recv().unwrap());
+        let (order_tx, order_rx) = mpsc::channel();
+        for (priority, name) in [
+            (Priority::Background, "background"),
+            (Priority::Normal, "first normal"),
+            (Priority::Normal, "second normal"),
+        ] {
+            let order_tx = order_tx.clone();
+            pool.submit(priority, move || order_tx.send(name).unwrap());
+        }
+        release_tx.send(()).unwrap();
+        let order: Vec<&str> = (0..3).map(|_| order_rx.recv().unwrap()).collect();
+        assert_eq!(order, ["first normal", "second normal", "background"]);
+    }
+
+    #[test]
+    fn live_task_does_not_wait_for_a_full_pool() {
+        let pool = Pool::new(1);
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        pool.submit(Priority::Background, move || release_rx.recv().unwrap());
+        let (tx, rx) = mpsc::channel();
+        pool.submit(Priority::Live, move || tx.send(()).unwrap());
+        rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        release_tx.send(()).unwrap();
+    }
+
+    #[test]
+    fn paused_pool_holds_tasks_until_resumed() {
+        let pool = Pool::new(1);
+        pool.pause();
+        let (tx, rx) = mpsc::channel();
+        pool.submit(Priority::Live, move || tx.send(()).unwrap());
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+        assert_eq!(pool.pending(), 1);
+        pool.resume();
+        rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    }
+
+    #[test]
+    fn zero_limit_is_clamped_to_one() {
+        assert_eq!(Pool::new(0).max_parallel(), 1);
+    }
+}
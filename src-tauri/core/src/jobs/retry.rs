@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::time::Duration;
+
+/// How often to retry a job that failed for a reason that might go away,
+/// like a model file another process has locked or running out of memory
+/// while something else held it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Tries in all, counting the first. 1 never retries.
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubled for each one after.
+    pub backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            backoff_ms: 2_000,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// How long to wait after failed attempt number `attempt`, counting
+    /// from 1.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u64 << attempt.saturating_sub(1).min(16);
+        Duration::from_millis(
+            self.backoff_ms
+                .saturating_mul(factor)
+                .min(self.max_backoff_ms),
+        )
+    }
+}
+
+/// Whether `err` looks like it could pass if tried again: a locked or busy
+/// file, an interrupted read, or memory running out. Anything else, like a
+/// missing file or a corrupt one, would only fail the same way.
+pub fn is_transient(err: &anyhow::Error) -> bool {
+    let io_transient = err.chain().any(|cause| {
+        cause.downcast_ref::<io::Error>().is_some_and(|err| {
+            matches!(
+                err.kind(),
+                io::ErrorKind::WouldBlock
+                    | io::ErrorKind::Interrupted
+                    | io::ErrorKind::TimedOut
+                    | io::ErrorKind::PermissionDenied
+                    | io::ErrorKind::OutOfMemory
+            )
+        })
+    });
+    let message = format!("{:#}", err).to_lowercase();
+    io_transient
+        || [
+            "out of memory",
+            "failed to allocate",
+            "failed to create state",
+            "resource busy",
+            "being used by another process",
+        ]
+        .iter()
+        .any(|needle| message.contains(needle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_secs(2));
+        assert_eq!(policy.backoff(2), Duration::from_secs(4));
+        assert_eq!(policy.backoff(10), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn only_passing_failures_are_transient() {
+        let locked = anyhow::Error::new(io::Error::from(io::ErrorKind::PermissionDenied))
+            .context("failed to open model");
+        assert!(is_transient(&locked));
+        assert!(is_transient(&anyhow!("ggml: failed to allocate buffer")));
+        assert!(!is_transient(&anyhow!("audio file doesn't exist")));
+    }
+}
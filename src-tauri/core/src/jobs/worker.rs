@@ -0,0 +1,54 @@
+use anyhow::{anyhow, Result};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+/// Owns some state on a dedicated thread and applies commands to it in order.
+///
+/// Used for things like the recorder, whose cpal stream isn't `Send` and so
+/// has to live on the thread that created it.
+pub struct Worker<C> {
+    sender: Sender<C>,
+}
+
+impl<C: Send + 'static> Worker<C> {
+    pub fn spawn<S, I, F>(init: I, mut handle: F) -> Self
+    where
+        I: FnOnce() -> S + Send + 'static,
+        F: FnMut(&mut S, C) + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut state = init();
+            for command in receiver {
+                handle(&mut state, command);
+            }
+        });
+        Worker { sender }
+    }
+
+    pub fn send(&self, command: C) -> Result<()> {
+        self.sender
+            .send(command)
+            .map_err(|_| anyhow!("worker thread has stopped"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_commands_in_order() {
+        let (done_tx, done_rx) = mpsc::channel();
+        let worker = Worker::spawn(Vec::new, move |seen: &mut Vec<u32>, n: u32| {
+            seen.push(n);
+            if seen.len() == 3 {
+                done_tx.send(seen.clone()).unwrap();
+            }
+        });
+        for n in [1, 2, 3] {
+            worker.send(n).unwrap();
+        }
+        assert_eq!(done_rx.recv().unwrap(), vec![1, 2, 3]);
+    }
+}
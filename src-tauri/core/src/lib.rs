@@ -0,0 +1,16 @@
+pub mod audio;
+pub mod calendar;
+pub mod i18n;
+pub mod jobs;
+pub mod library;
+pub mod llm;
+pub mod settings;
+pub mod summarize;
+pub mod transcribe;
+
+#[cfg(test)]
+pub(crate) fn fixture(name: &str) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
@@ -0,0 +1,89 @@
+use anyhow::Result;
+use rusqlite::params;
+
+use super::{Library, TranscriptId};
+use crate::transcribe::action_items::ActionItem;
+
+impl Library {
+    /// Stores a transcript version's action items, replacing any extracted
+    /// before.
+    pub fn save_action_items(
+        &self,
+        transcript_id: TranscriptId,
+        items: &[ActionItem],
+    ) -> Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM action_items WHERE transcript_id = ?1",
+            [transcript_id],
+        )?;
+        for (position, item) in items.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO action_items (transcript_id, position, text, owner, due, segment, start)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    transcript_id,
+                    position,
+                    item.text,
+                    item.owner,
+                    item.due,
+                    item.segment,
+                    item.start
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// A transcript version's action items, in the order they came up.
+    pub fn action_items(&self, transcript_id: TranscriptId) -> Result<Vec<ActionItem>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT text, owner, due, segment, start FROM action_items
+             WHERE transcript_id = ?1 ORDER BY position",
+        )?;
+        let items = stmt
+            .query_map([transcript_id], |row| {
+                Ok(ActionItem {
+                    text: row.get(0)?,
+                    owner: row.get(1)?,
+                    due: row.get(2)?,
+                    segment: row.get(3)?,
+                    start: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::tests::transcript_fixture;
+    use crate::transcribe::Transcript;
+
+    #[test]
+    fn action_items_are_replaced() {
+        let library = Library::open_in_memory().unwrap();
+        let (_, transcript) = transcript_fixture(&library, &Transcript { segments: vec![] });
+        let item = |text: &str, owner: Option<&str>| ActionItem {
+            text: text.to_string(),
+            owner: owner.map(str::to_string),
+            due: None,
+            segment: 0,
+            start: 0,
+        };
+        library
+            .save_action_items(transcript, &[item("Old", None)])
+            .unwrap();
+        let items = vec![
+            item("Send the slides", None),
+            item("Update the roadmap", Some("Sarah")),
+        ];
+        library.save_action_items(transcript, &items).unwrap();
+        assert_eq!(library.action_items(transcript).unwrap(), items);
+    }
+}
@@ -0,0 +1,322 @@
+//! Portable library archives: a zip holding `manifest.json` plus every audio
+//! file under `audio/`. Ids and paths are not stored; importing assigns new
+//! ones, so an archive can be merged into a library that already has data.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::{now_ms, unique_path, Library};
+use crate::transcribe::Segment;
+
+const MANIFEST: &str = "manifest.json";
+const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    recordings: Vec<BackupRecording>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BackupRecording {
+    /// Entry name of the audio inside the archive.
+    file: String,
+    title: String,
+    created_at: i64,
+    duration_ms: i64,
+    sample_rate: u32,
+    channels: u16,
+    device: Option<String>,
+    favorite: bool,
+    tags: Vec<String>,
+    metadata: HashMap<String, String>,
+    /// Name of the session the recording belonged to.
+    #[serde(default)]
+    session: Option<String>,
+    /// Oldest first.
+    transcripts: Vec<BackupTranscript>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BackupTranscript {
+    model: String,
+    segments: Vec<Segment>,
+    created_at: i64,
+}
+
+impl Library {
+    fn backup_transcripts(&self, id: i64) -> Result<Vec<BackupTranscript>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT model, segments, created_at FROM transcripts WHERE recording_id = ?1
+             ORDER BY created_at, id",
+        )?;
+        let rows = stmt.query_map([id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })?;
+        rows.map(|row| -> Result<BackupTranscript> {
+            let (model, segments, created_at) = row?;
+            Ok(BackupTranscript {
+                model,
+                segments: serde_json::from_str(&segments)?,
+                created_at,
+            })
+        })
+        .collect()
+    }
+
+    /// Writes every recording outside the trash, with its audio, transcripts,
+    /// tags and metadata, to a zip at `dest`. Recordings whose file has gone
+    /// missing are left out. Returns how many were written.
+    pub fn export_backup(&self, dest: &Path) -> Result<usize> {
+        let partial = dest.with_extension("partial");
+        let mut zip = ZipWriter::new(File::create(&partial).context("failed to create archive")?);
+        let mut manifest = Manifest {
+            version: VERSION,
+            recordings: Vec::new(),
+        };
+        let sessions: HashMap<_, _> = self
+            .sessions()?
+            .into_iter()
+            .map(|session| (session.id, session.name))
+            .collect();
+        for recording in self.recordings()? {
+            let Ok(mut audio) = File::open(&recording.path) else {
+                continue;
+            };
+            let file_name = recording
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let file = format!("audio/{}-{}", recording.id, file_name);
+            // WAV barely compresses, so don't spend time deflating it.
+            zip.start_file(
+                file.as_str(),
+                FileOptions::default().compression_method(CompressionMethod::Stored),
+            )?;
+            io::copy(&mut audio, &mut zip)?;
+            manifest.recordings.push(BackupRecording {
+                file,
+                transcripts: self.backup_transcripts(recording.id)?,
+                metadata: self.metadata(recording.id)?,
+                session: recording
+                    .session_id
+                    .and_then(|id| sessions.get(&id).cloned()),
+                title: recording.title,
+                created_at: recording.created_at,
+                duration_ms: recording.duration_ms,
+                sample_rate: recording.sample_rate,
+                channels: recording.channels,
+                device: recording.device,
+                favorite: recording.favorite,
+                tags: recording.tags,
+            });
+        }
+        zip.start_file(MANIFEST, FileOptions::default())?;
+        serde_json::to_writer_pretty(&mut zip, &manifest)?;
+        zip.finish()?;
+        fs::rename(&partial, dest)?;
+        Ok(manifest.recordings.len())
+    }
+
+    /// Adds every recording in the archive at `source` to this library,
+    /// unpacking the audio into `audio_dir`. Each recording gets a new id, and
+    /// its file is renamed if the name is already taken. Recordings join an
+    /// existing session of the same name, or a new one. Returns how many
+    /// were imported.
+    pub fn import_backup(&self, source: &Path, audio_dir: &Path) -> Result<usize> {
+        let mut archive = ZipArchive::new(File::open(source).context("failed to open archive")?)
+            .context("not a library archive")?;
+        let manifest: Manifest = serde_json::from_reader(
+            archive
+                .by_name(MANIFEST)
+                .context("archive has no manifest")?,
+        )
+        .context("failed to parse manifest")?;
+        if manifest.version > VERSION {
+            bail!("archive was made by a newer version of the app");
+        }
+        fs::create_dir_all(audio_dir)?;
+
+        for recording in &manifest.recordings {
+            // Only the file name is used, so entries can't escape `audio_dir`.
+            let file_name = Path::new(&recording.file)
+                .file_name()
+                .context("archive entry has no file name")?;
+            let dest = unique_path(&audio_dir.join(file_name));
+            let mut entry = archive
+                .by_name(&recording.file)
+                .with_context(|| format!("archive is missing {}", recording.file))?;
+            io::copy(&mut entry, &mut File::create(&dest)?)?;
+
+            let inserted = self.insert_backup_recording(recording, &dest);
+            if inserted.is_err() {
+                let _ = fs::remove_file(&dest);
+            }
+            inserted?;
+        }
+        Ok(manifest.recordings.len())
+    }
+
+    fn insert_backup_recording(&self, recording: &BackupRecording, path: &Path) -> Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO recordings
+                 (title, path, created_at, duration_ms, sample_rate, channels, device, favorite)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                recording.title,
+                path.to_string_lossy(),
+                recording.created_at,
+                recording.duration_ms,
+                recording.sample_rate,
+                recording.channels,
+                recording.device,
+                recording.favorite,
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+        if let Some(name) = &recording.session {
+            let existing: Option<i64> = tx
+                .query_row("SELECT id FROM sessions WHERE name = ?1", [name], |row| {
+                    row.get(0)
+                })
+                .optional()?;
+            let session_id = match existing {
+                Some(session_id) => session_id,
+                None => {
+                    tx.execute(
+                        "INSERT INTO sessions (name, created_at) VALUES (?1, ?2)",
+                        params![name, now_ms()],
+                    )?;
+                    tx.last_insert_rowid()
+                }
+            };
+            tx.execute(
+                "UPDATE recordings SET session_id = ?2 WHERE id = ?1",
+                params![id, session_id],
+            )?;
+        }
+        for tag in &recording.tags {
+            tx.execute(
+                "INSERT OR IGNORE INTO recording_tags (recording_id, tag) VALUES (?1, ?2)",
+                params![id, tag],
+            )?;
+        }
+        for (key, value) in &recording.metadata {
+            tx.execute(
+                "INSERT INTO recording_metadata (recording_id, key, value) VALUES (?1, ?2, ?3)",
+                params![id, key, value],
+            )?;
+        }
+        for transcript in &recording.transcripts {
+            tx.execute(
+                "INSERT INTO transcripts (recording_id, model, segments, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    id,
+                    transcript.model,
+                    serde_json::to_string(&transcript.segments)?,
+                    transcript.created_at
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+    use crate::transcribe::Transcript;
+    use std::path::PathBuf;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("app-core-backup-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn round_trips_into_another_library() {
+        let dir = temp_dir("roundtrip");
+        let source = Library::open_in_memory().unwrap();
+        let recording = source
+            .import_file(&fixture("mono_16k.wav"), Some(&dir.join("source")))
+            .unwrap();
+        source.add_tag(recording.id, "work").unwrap();
+        source.set_favorite(recording.id, true).unwrap();
+        source
+            .set_metadata(recording.id, "meeting", "standup")
+            .unwrap();
+        let transcript = Transcript {
+            segments: vec![Segment {
+                start: 0,
+                end: 100,
+                text: " Budget review.".to_string(),
+                speaker_turn_next: false,
+            }],
+        };
+        source
+            .save_transcript(recording.id, "small.en", &transcript)
+            .unwrap();
+        let archive = dir.join("library.zip");
+        assert_eq!(source.export_backup(&archive).unwrap(), 1);
+
+        let target = Library::open_in_memory().unwrap();
+        // An existing recording takes the id the archived one had.
+        target.import_file(&fixture("mono_8k.wav"), None).unwrap();
+        assert_eq!(
+            target.import_backup(&archive, &dir.join("target")).unwrap(),
+            1
+        );
+
+        let imported = target
+            .recordings()
+            .unwrap()
+            .into_iter()
+            .find(|r| r.title == "mono_16k")
+            .unwrap();
+        assert_ne!(imported.id, recording.id);
+        assert!(imported.path.starts_with(dir.join("target")));
+        assert_eq!(
+            fs::read(&imported.path).unwrap(),
+            fs::read(&recording.path).unwrap()
+        );
+        assert_eq!(
+            (imported.favorite, imported.tags),
+            (true, vec!["work".to_string()])
+        );
+        assert_eq!(target.metadata(imported.id).unwrap()["meeting"], "standup");
+        assert_eq!(target.sessions().unwrap()[0].name, "Standup");
+        assert!(imported.session_id.is_some());
+        assert_eq!(target.transcript(imported.id).unwrap(), Some(transcript));
+        assert_eq!(target.search_transcripts("budget", None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_archives() {
+        let library = Library::open_in_memory().unwrap();
+        let err = library
+            .import_backup(&fixture("mono_16k.wav"), &temp_dir("invalid"))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "not a library archive");
+    }
+}
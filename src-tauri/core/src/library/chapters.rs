@@ -0,0 +1,82 @@
+use anyhow::Result;
+use rusqlite::params;
+
+use super::{Library, TranscriptId};
+use crate::transcribe::chapters::Chapter;
+
+impl Library {
+    /// Stores a transcript version's chapters, replacing any generated
+    /// before.
+    pub fn save_chapters(&self, transcript_id: TranscriptId, chapters: &[Chapter]) -> Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM chapters WHERE transcript_id = ?1",
+            [transcript_id],
+        )?;
+        for (position, chapter) in chapters.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO chapters (transcript_id, position, title, segment, start, \"end\")
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    transcript_id,
+                    position,
+                    chapter.title,
+                    chapter.segment,
+                    chapter.start,
+                    chapter.end
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// A transcript version's chapters, in order.
+    pub fn chapters(&self, transcript_id: TranscriptId) -> Result<Vec<Chapter>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT title, segment, start, \"end\" FROM chapters
+             WHERE transcript_id = ?1 ORDER BY position",
+        )?;
+        let chapters = stmt
+            .query_map([transcript_id], |row| {
+                Ok(Chapter {
+                    title: row.get(0)?,
+                    segment: row.get(1)?,
+                    start: row.get(2)?,
+                    end: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(chapters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::tests::transcript_fixture;
+    use crate::transcribe::Transcript;
+
+    #[test]
+    fn chapters_are_replaced() {
+        let library = Library::open_in_memory().unwrap();
+        let (_, transcript) = transcript_fixture(&library, &Transcript { segments: vec![] });
+        let chapter = |title: &str, segment, start, end| Chapter {
+            title: title.to_string(),
+            segment,
+            start,
+            end,
+        };
+        library
+            .save_chapters(transcript, &[chapter("Old", 0, 0, 10)])
+            .unwrap();
+        let chapters = vec![
+            chapter("Budget", 0, 0, 6000),
+            chapter("Hiring", 4, 6000, 12_000),
+        ];
+        library.save_chapters(transcript, &chapters).unwrap();
+        assert_eq!(library.chapters(transcript).unwrap(), chapters);
+    }
+}
@@ -0,0 +1,146 @@
+use anyhow::{anyhow, Result};
+use rusqlite::params;
+use serde::Serialize;
+
+use super::{Library, RecordingId, TranscriptId};
+use crate::transcribe::entities::{Entity, EntityKind};
+
+const MAX_HITS: usize = 100;
+
+/// A mention of a person, organization or date found by
+/// [`Library::search_entities`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EntityHit {
+    pub recording_id: RecordingId,
+    pub recording_title: String,
+    pub transcript_id: TranscriptId,
+    #[serde(flatten)]
+    pub entity: Entity,
+}
+
+fn entity(row: &rusqlite::Row, offset: usize) -> rusqlite::Result<Entity> {
+    let kind: String = row.get(offset)?;
+    Ok(Entity {
+        kind: EntityKind::parse(&kind).ok_or_else(|| {
+            rusqlite::Error::FromSqlConversionFailure(
+                offset,
+                rusqlite::types::Type::Text,
+                anyhow!("unknown entity kind {}", kind).into(),
+            )
+        })?,
+        text: row.get(offset + 1)?,
+        segment: row.get(offset + 2)?,
+        start: row.get(offset + 3)?,
+        end: row.get(offset + 4)?,
+    })
+}
+
+impl Library {
+    /// Stores the entities tagged in a transcript version, replacing any
+    /// tagged before.
+    pub fn save_entities(&self, transcript_id: TranscriptId, entities: &[Entity]) -> Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM entities WHERE transcript_id = ?1",
+            [transcript_id],
+        )?;
+        for entity in entities {
+            tx.execute(
+                "INSERT INTO entities (transcript_id, kind, text, segment, start, \"end\")
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    transcript_id,
+                    entity.kind.as_str(),
+                    entity.text,
+                    entity.segment,
+                    entity.start,
+                    entity.end
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// The entities of a transcript version, in the order they're mentioned.
+    pub fn entities(&self, transcript_id: TranscriptId) -> Result<Vec<Entity>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT kind, text, segment, start, \"end\" FROM entities
+             WHERE transcript_id = ?1 ORDER BY segment, start",
+        )?;
+        let entities = stmt
+            .query_map([transcript_id], |row| entity(row, 0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(entities)
+    }
+
+    /// Finds mentions whose text contains `query`, ignoring case, in the
+    /// latest transcript of each recording, optionally only of one kind.
+    /// Trashed recordings are left out.
+    pub fn search_entities(&self, query: &str, kind: Option<EntityKind>) -> Result<Vec<EntityHit>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT r.id, r.title, t.id, e.kind, e.text, e.segment, e.start, e.\"end\"
+             FROM entities e
+             JOIN transcripts t ON t.id = e.transcript_id
+             JOIN recordings r ON r.id = t.recording_id
+             WHERE e.text LIKE '%' || ?1 || '%' AND (?2 IS NULL OR e.kind = ?2)
+                 AND r.trashed_at IS NULL
+                 AND t.id = (SELECT id FROM transcripts WHERE recording_id = r.id
+                             ORDER BY created_at DESC, id DESC LIMIT 1)
+             ORDER BY r.created_at DESC, e.segment, e.start LIMIT ?3",
+        )?;
+        let rows = stmt.query_map(
+            params![query.trim(), kind.map(EntityKind::as_str), MAX_HITS],
+            |row| {
+                Ok(EntityHit {
+                    recording_id: row.get(0)?,
+                    recording_title: row.get(1)?,
+                    transcript_id: row.get(2)?,
+                    entity: entity(row, 3)?,
+                })
+            },
+        )?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::tests::transcript_fixture;
+    use crate::transcribe::{Segment, Transcript};
+
+    #[test]
+    fn entities_are_searchable_in_latest_transcripts() {
+        let library = Library::open_in_memory().unwrap();
+        let transcript = Transcript {
+            segments: vec![Segment {
+                start: 0,
+                end: 100,
+                text: " Ask Sarah Chen at Acme Corp.".to_string(),
+                speaker_turn_next: false,
+            }],
+        };
+        let (recording, old) = transcript_fixture(&library, &transcript);
+        let entities = crate::transcribe::entities::tag_entities(&transcript);
+        library.save_entities(old, &entities).unwrap();
+        assert_eq!(library.entities(old).unwrap(), entities);
+
+        let hits = library.search_entities("sarah", None).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].entity.text, "Sarah Chen");
+        assert!(library
+            .search_entities("sarah", Some(EntityKind::Organization))
+            .unwrap()
+            .is_empty());
+
+        // Only the current transcript counts.
+        library
+            .save_transcript(recording, "small", &transcript)
+            .unwrap();
+        assert!(library.search_entities("sarah", None).unwrap().is_empty());
+    }
+}
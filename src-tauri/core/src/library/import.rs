@@ -0,0 +1,82 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use super::{unique_path, Library, NewRecording, Recording};
+
+impl Library {
+    /// Adds an existing WAV file to the library. With `copy_into` the file is
+    /// copied into that folder first; otherwise the library references it in
+    /// place. Files already in the library are returned as they are.
+    ///
+    /// The recording is dated by the file's modification time, since that's
+    /// usually when it was recorded.
+    pub fn import_file(&self, source: &Path, copy_into: Option<&Path>) -> Result<Recording> {
+        let mut recording = NewRecording::from_file(source)?;
+        if let Some(modified) = fs::metadata(source)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        {
+            recording.created_at = modified.as_millis() as i64;
+        }
+
+        match copy_into {
+            Some(dir) => {
+                fs::create_dir_all(dir)?;
+                let file_name = source.file_name().context("path has no file name")?;
+                let dest = unique_path(&dir.join(file_name));
+                fs::copy(source, &dest).context("failed to copy file")?;
+                recording.path = dest;
+            }
+            None => {
+                let source = source.canonicalize()?;
+                if let Some(existing) = self.recording_by_path(&source)? {
+                    return Ok(existing);
+                }
+                recording.path = source;
+            }
+        }
+        let id = self.add_recording(&recording)?;
+        Ok(self.recording(id)?.expect("just inserted"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    #[test]
+    fn referenced_files_are_registered_once() {
+        let library = Library::open_in_memory().unwrap();
+        let first = library.import_file(&fixture("mono_8k.wav"), None).unwrap();
+        let second = library.import_file(&fixture("mono_8k.wav"), None).unwrap();
+        assert_eq!(first.id, second.id);
+        assert_eq!(first.path, fixture("mono_8k.wav").canonicalize().unwrap());
+        assert_eq!((first.duration_ms, first.sample_rate), (250, 8000));
+    }
+
+    #[test]
+    fn copies_into_folder() {
+        let dir = std::env::temp_dir().join(format!("app-core-import-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let library = Library::open_in_memory().unwrap();
+        let first = library
+            .import_file(&fixture("stereo_16k.wav"), Some(&dir))
+            .unwrap();
+        let second = library
+            .import_file(&fixture("stereo_16k.wav"), Some(&dir))
+            .unwrap();
+        assert_eq!(first.path, dir.join("stereo_16k.wav"));
+        assert_eq!(second.path, dir.join("stereo_16k (1).wav"));
+        assert_eq!(first.channels, 2);
+    }
+
+    #[test]
+    fn rejects_non_wav() {
+        let library = Library::open_in_memory().unwrap();
+        assert!(library.import_file(&fixture("missing.wav"), None).is_err());
+    }
+}
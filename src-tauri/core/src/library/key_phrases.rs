@@ -0,0 +1,104 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, OptionalExtension};
+
+use super::{Library, RecordingId, TranscriptId};
+use crate::transcribe::key_phrases::KeyPhrase;
+
+impl Library {
+    /// Stores a transcript version's key phrases in rank order, replacing any
+    /// extracted before.
+    pub fn save_key_phrases(
+        &self,
+        transcript_id: TranscriptId,
+        phrases: &[KeyPhrase],
+    ) -> Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM key_phrases WHERE transcript_id = ?1",
+            [transcript_id],
+        )?;
+        for (rank, phrase) in phrases.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO key_phrases (transcript_id, rank, phrase, score, count)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    transcript_id,
+                    rank,
+                    phrase.phrase,
+                    phrase.score,
+                    phrase.count
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// A transcript version's key phrases, best first.
+    pub fn key_phrases(&self, transcript_id: TranscriptId) -> Result<Vec<KeyPhrase>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT phrase, score, count FROM key_phrases WHERE transcript_id = ?1 ORDER BY rank",
+        )?;
+        let phrases = stmt
+            .query_map([transcript_id], |row| {
+                Ok(KeyPhrase {
+                    phrase: row.get(0)?,
+                    score: row.get(1)?,
+                    count: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(phrases)
+    }
+
+    /// Tags the transcript's recording with its `top` key phrases.
+    pub fn tag_with_key_phrases(&self, transcript_id: TranscriptId, top: usize) -> Result<()> {
+        let recording_id: RecordingId = self
+            .conn()
+            .query_row(
+                "SELECT recording_id FROM transcripts WHERE id = ?1",
+                [transcript_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .with_context(|| format!("no transcript version {}", transcript_id))?;
+        for phrase in self.key_phrases(transcript_id)?.iter().take(top) {
+            self.add_tag(recording_id, &phrase.phrase)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::tests::transcript_fixture;
+    use crate::transcribe::Transcript;
+
+    #[test]
+    fn key_phrases_are_replaced_and_become_tags() {
+        let library = Library::open_in_memory().unwrap();
+        let (_, transcript) = transcript_fixture(&library, &Transcript { segments: vec![] });
+        let phrase = |phrase: &str, score| KeyPhrase {
+            phrase: phrase.to_string(),
+            score,
+            count: 1,
+        };
+        library
+            .save_key_phrases(transcript, &[phrase("old", 1.0)])
+            .unwrap();
+        let phrases = vec![phrase("budget review", 8.0), phrase("board meeting", 4.0)];
+        library.save_key_phrases(transcript, &phrases).unwrap();
+        assert_eq!(library.key_phrases(transcript).unwrap(), phrases);
+
+        library.tag_with_key_phrases(transcript, 1).unwrap();
+        let tagged = library.recordings_tagged("budget review").unwrap();
+        assert_eq!(tagged.len(), 1);
+        assert!(library
+            .recordings_tagged("board meeting")
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,272 @@
+use anyhow::Result;
+use rusqlite::types::Value;
+use serde::{Deserialize, Serialize};
+
+use super::{Library, RecordingId, SessionId};
+
+const MAX_PAGE: u32 = 500;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortBy {
+    #[default]
+    Date,
+    Duration,
+    Name,
+}
+
+/// One page of the library, as requested by the list view.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ListQuery {
+    pub offset: u32,
+    /// Capped at 500.
+    pub limit: u32,
+    pub sort_by: SortBy,
+    pub descending: bool,
+    pub has_transcript: Option<bool>,
+    pub tag: Option<String>,
+    pub device: Option<String>,
+    pub favorites_only: bool,
+    /// List the trash instead of the library.
+    pub trashed: bool,
+    pub session_id: Option<SessionId>,
+}
+
+impl Default for ListQuery {
+    fn default() -> Self {
+        ListQuery {
+            offset: 0,
+            limit: 50,
+            sort_by: SortBy::Date,
+            descending: true,
+            has_transcript: None,
+            tag: None,
+            device: None,
+            favorites_only: false,
+            trashed: false,
+            session_id: None,
+        }
+    }
+}
+
+/// A row of the list view: just what's shown without opening the recording.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecordingSummary {
+    pub id: RecordingId,
+    pub title: String,
+    pub created_at: i64,
+    pub duration_ms: i64,
+    pub device: Option<String>,
+    pub favorite: bool,
+    pub has_transcript: bool,
+    /// False once the retention policy has deleted the audio.
+    pub has_audio: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Page {
+    pub items: Vec<RecordingSummary>,
+    /// Number of recordings matching the filters across all pages.
+    pub total: u32,
+}
+
+impl ListQuery {
+    fn where_clause(&self) -> (String, Vec<Value>) {
+        let mut conditions = vec![if self.trashed {
+            "trashed_at IS NOT NULL".to_string()
+        } else {
+            "trashed_at IS NULL".to_string()
+        }];
+        let mut params = Vec::new();
+        match self.has_transcript {
+            Some(true) => conditions.push(format!("EXISTS ({})", HAS_TRANSCRIPT)),
+            Some(false) => conditions.push(format!("NOT EXISTS ({})", HAS_TRANSCRIPT)),
+            None => {}
+        }
+        if let Some(tag) = &self.tag {
+            params.push(Value::Text(tag.trim().to_string()));
+            conditions.push(format!(
+                "id IN (SELECT recording_id FROM recording_tags WHERE tag = ?{})",
+                params.len()
+            ));
+        }
+        if let Some(device) = &self.device {
+            params.push(Value::Text(device.clone()));
+            conditions.push(format!("device = ?{}", params.len()));
+        }
+        if let Some(session_id) = self.session_id {
+            params.push(Value::Integer(session_id));
+            conditions.push(format!("session_id = ?{}", params.len()));
+        }
+        if self.favorites_only {
+            conditions.push("favorite".to_string());
+        }
+        (conditions.join(" AND "), params)
+    }
+
+    fn order_clause(&self) -> String {
+        let column = match self.sort_by {
+            SortBy::Date => "created_at",
+            SortBy::Duration => "duration_ms",
+            SortBy::Name => "title COLLATE NOCASE",
+        };
+        let direction = if self.descending { "DESC" } else { "ASC" };
+        format!("{} {}, id {}", column, direction, direction)
+    }
+}
+
+const HAS_TRANSCRIPT: &str = "SELECT 1 FROM transcripts WHERE recording_id = recordings.id";
+
+impl Library {
+    pub fn list_recordings(&self, query: &ListQuery) -> Result<Page> {
+        let (filter, mut params) = query.where_clause();
+        let conn = self.conn();
+        let total: u32 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM recordings WHERE {}", filter),
+            rusqlite::params_from_iter(&params),
+            |row| row.get(0),
+        )?;
+
+        params.push(Value::Integer(query.limit.min(MAX_PAGE) as i64));
+        params.push(Value::Integer(query.offset as i64));
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, title, created_at, duration_ms, device, favorite, EXISTS ({}),
+                 audio_deleted_at IS NULL
+             FROM recordings WHERE {} ORDER BY {} LIMIT ?{} OFFSET ?{}",
+            HAS_TRANSCRIPT,
+            filter,
+            query.order_clause(),
+            params.len() - 1,
+            params.len()
+        ))?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(&params), |row| {
+            Ok(RecordingSummary {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                created_at: row.get(2)?,
+                duration_ms: row.get(3)?,
+                device: row.get(4)?,
+                favorite: row.get(5)?,
+                has_transcript: row.get(6)?,
+                has_audio: row.get(7)?,
+            })
+        })?;
+        Ok(Page {
+            items: rows.collect::<rusqlite::Result<_>>()?,
+            total,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::NewRecording;
+    use crate::transcribe::Transcript;
+    use std::path::PathBuf;
+
+    fn add(library: &Library, title: &str, created_at: i64, duration_ms: i64) -> RecordingId {
+        library
+            .add_recording(&NewRecording {
+                title: title.to_string(),
+                path: PathBuf::from(format!("/{}.wav", title)),
+                created_at,
+                duration_ms,
+                sample_rate: 16000,
+                channels: 1,
+                device: Some(
+                    if duration_ms > 1000 {
+                        "USB"
+                    } else {
+                        "Built-in"
+                    }
+                    .to_string(),
+                ),
+            })
+            .unwrap()
+    }
+
+    fn ids(page: &Page) -> Vec<RecordingId> {
+        page.items.iter().map(|item| item.id).collect()
+    }
+
+    #[test]
+    fn pages_newest_first_with_total() {
+        let library = Library::open_in_memory().unwrap();
+        let a = add(&library, "a", 1, 500);
+        let b = add(&library, "b", 2, 500);
+        let c = add(&library, "c", 3, 500);
+        let query = ListQuery {
+            limit: 2,
+            ..ListQuery::default()
+        };
+        let first = library.list_recordings(&query).unwrap();
+        assert_eq!((ids(&first), first.total), (vec![c, b], 3));
+        let second = library
+            .list_recordings(&ListQuery { offset: 2, ..query })
+            .unwrap();
+        assert_eq!(ids(&second), vec![a]);
+    }
+
+    #[test]
+    fn sorts_by_name_and_duration() {
+        let library = Library::open_in_memory().unwrap();
+        let b = add(&library, "beta", 1, 3000);
+        let a = add(&library, "Alpha", 2, 2000);
+        let query = |sort_by| ListQuery {
+            sort_by,
+            descending: false,
+            ..ListQuery::default()
+        };
+        assert_eq!(
+            ids(&library.list_recordings(&query(SortBy::Name)).unwrap()),
+            vec![a, b]
+        );
+        assert_eq!(
+            ids(&library.list_recordings(&query(SortBy::Duration)).unwrap()),
+            vec![a, b]
+        );
+    }
+
+    #[test]
+    fn filters_combine() {
+        let library = Library::open_in_memory().unwrap();
+        let short = add(&library, "short", 1, 500);
+        let long = add(&library, "long", 2, 5000);
+        let transcribed = add(&library, "transcribed", 3, 5000);
+        library
+            .save_transcript(transcribed, "small.en", &Transcript::default())
+            .unwrap();
+        library.add_tag(long, "work").unwrap();
+        library.add_tag(transcribed, "work").unwrap();
+
+        let page = library
+            .list_recordings(&ListQuery {
+                has_transcript: Some(false),
+                device: Some("USB".to_string()),
+                ..ListQuery::default()
+            })
+            .unwrap();
+        assert_eq!(ids(&page), vec![long]);
+
+        let page = library
+            .list_recordings(&ListQuery {
+                tag: Some("work".to_string()),
+                has_transcript: Some(true),
+                ..ListQuery::default()
+            })
+            .unwrap();
+        assert_eq!(ids(&page), vec![transcribed]);
+        assert!(page.items[0].has_transcript);
+
+        library.set_favorite(short, true).unwrap();
+        let page = library
+            .list_recordings(&ListQuery {
+                favorites_only: true,
+                ..ListQuery::default()
+            })
+            .unwrap();
+        assert_eq!(ids(&page), vec![short]);
+    }
+}
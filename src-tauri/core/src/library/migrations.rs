@@ -0,0 +1,191 @@
+use anyhow::Result;
+use rusqlite::Connection;
+
+/// Schema changes, applied in order. The database's `user_version` records how
+/// many have run; append new steps, never edit old ones.
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    "CREATE TABLE recordings (
+        id INTEGER PRIMARY KEY,
+        title TEXT NOT NULL,
+        path TEXT NOT NULL UNIQUE,
+        created_at INTEGER NOT NULL,
+        duration_ms INTEGER NOT NULL,
+        sample_rate INTEGER NOT NULL,
+        channels INTEGER NOT NULL,
+        device TEXT
+    );
+    CREATE TABLE transcripts (
+        id INTEGER PRIMARY KEY,
+        recording_id INTEGER NOT NULL REFERENCES recordings(id) ON DELETE CASCADE,
+        model TEXT NOT NULL,
+        segments TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX transcripts_recording ON transcripts(recording_id);
+    CREATE TABLE recording_metadata (
+        recording_id INTEGER NOT NULL REFERENCES recordings(id) ON DELETE CASCADE,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL,
+        PRIMARY KEY (recording_id, key)
+    );
+    CREATE TABLE jobs (
+        id INTEGER PRIMARY KEY,
+        kind TEXT NOT NULL,
+        state TEXT NOT NULL,
+        progress REAL NOT NULL,
+        result TEXT,
+        error TEXT,
+        updated_at INTEGER NOT NULL
+    );",
+    // 2: full-text index over the latest transcript of each recording
+    "CREATE VIRTUAL TABLE transcript_segments USING fts5(
+        text,
+        recording_id UNINDEXED,
+        transcript_id UNINDEXED,
+        start_cs UNINDEXED,
+        end_cs UNINDEXED
+    );
+    CREATE TRIGGER transcripts_index AFTER INSERT ON transcripts BEGIN
+        DELETE FROM transcript_segments WHERE recording_id = new.recording_id;
+        INSERT INTO transcript_segments (text, recording_id, transcript_id, start_cs, end_cs)
+            SELECT json_extract(value, '$.text'), new.recording_id, new.id,
+                json_extract(value, '$.start'), json_extract(value, '$.end')
+            FROM json_each(new.segments);
+    END;
+    CREATE TRIGGER transcripts_unindex AFTER DELETE ON transcripts BEGIN
+        DELETE FROM transcript_segments WHERE transcript_id = old.id;
+    END;
+    INSERT INTO transcript_segments (text, recording_id, transcript_id, start_cs, end_cs)
+        SELECT json_extract(value, '$.text'), t.recording_id, t.id,
+            json_extract(value, '$.start'), json_extract(value, '$.end')
+        FROM transcripts t, json_each(t.segments)
+        WHERE t.id = (SELECT id FROM transcripts WHERE recording_id = t.recording_id
+                      ORDER BY created_at DESC, id DESC LIMIT 1);",
+    // 3: favorites and tags
+    "ALTER TABLE recordings ADD COLUMN favorite INTEGER NOT NULL DEFAULT 0;
+    CREATE TABLE recording_tags (
+        recording_id INTEGER NOT NULL REFERENCES recordings(id) ON DELETE CASCADE,
+        tag TEXT NOT NULL COLLATE NOCASE,
+        PRIMARY KEY (recording_id, tag)
+    );
+    CREATE INDEX recording_tags_tag ON recording_tags(tag);",
+    // 4: trash
+    "ALTER TABLE recordings ADD COLUMN trashed_at INTEGER;
+    ALTER TABLE recordings ADD COLUMN trashed_from TEXT;",
+    // 5: sessions
+    "CREATE TABLE sessions (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    ALTER TABLE recordings ADD COLUMN session_id INTEGER REFERENCES sessions(id) ON DELETE SET NULL;
+    CREATE INDEX recordings_session ON recordings(session_id);",
+    // 6: hand-edited transcript versions
+    "ALTER TABLE transcripts ADD COLUMN edited INTEGER NOT NULL DEFAULT 0;",
+    // 7: audio removed by the retention policy
+    "ALTER TABLE recordings ADD COLUMN audio_deleted_at INTEGER;",
+    // 8: LLM summaries of transcript versions
+    "CREATE TABLE summaries (
+        transcript_id INTEGER NOT NULL REFERENCES transcripts(id) ON DELETE CASCADE,
+        style TEXT NOT NULL,
+        model TEXT NOT NULL,
+        bullets TEXT NOT NULL,
+        action_items TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        PRIMARY KEY (transcript_id, style)
+    );",
+    // 9: translated transcript versions
+    "CREATE TABLE translations (
+        transcript_id INTEGER NOT NULL REFERENCES transcripts(id) ON DELETE CASCADE,
+        language TEXT NOT NULL,
+        provider TEXT NOT NULL,
+        segments TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        PRIMARY KEY (transcript_id, language)
+    );",
+    // 10: remote copies of recordings and transcripts
+    "CREATE TABLE uploads (
+        recording_id INTEGER NOT NULL REFERENCES recordings(id) ON DELETE CASCADE,
+        kind TEXT NOT NULL,
+        url TEXT NOT NULL,
+        uploaded_at INTEGER NOT NULL,
+        PRIMARY KEY (recording_id, kind)
+    );",
+    // 11: ranked key phrases of transcript versions
+    "CREATE TABLE key_phrases (
+        transcript_id INTEGER NOT NULL REFERENCES transcripts(id) ON DELETE CASCADE,
+        rank INTEGER NOT NULL,
+        phrase TEXT NOT NULL,
+        score REAL NOT NULL,
+        count INTEGER NOT NULL,
+        PRIMARY KEY (transcript_id, rank)
+    );",
+    // 12: people, organizations and dates tagged in transcript versions
+    "CREATE TABLE entities (
+        transcript_id INTEGER NOT NULL REFERENCES transcripts(id) ON DELETE CASCADE,
+        kind TEXT NOT NULL,
+        text TEXT NOT NULL,
+        segment INTEGER NOT NULL,
+        start INTEGER NOT NULL,
+        \"end\" INTEGER NOT NULL
+    );
+    CREATE INDEX entities_transcript ON entities(transcript_id);",
+    // 13: chapters of transcript versions
+    "CREATE TABLE chapters (
+        transcript_id INTEGER NOT NULL REFERENCES transcripts(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        title TEXT NOT NULL,
+        segment INTEGER NOT NULL,
+        start INTEGER NOT NULL,
+        \"end\" INTEGER NOT NULL,
+        PRIMARY KEY (transcript_id, position)
+    );",
+    // 14: action items of transcript versions
+    "CREATE TABLE action_items (
+        transcript_id INTEGER NOT NULL REFERENCES transcripts(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        text TEXT NOT NULL,
+        owner TEXT,
+        due TEXT,
+        segment INTEGER NOT NULL,
+        start INTEGER NOT NULL,
+        PRIMARY KEY (transcript_id, position)
+    );",
+    // 15: transcriptions waiting or running, to pick up again after a restart
+    "CREATE TABLE transcription_queue (
+        id INTEGER PRIMARY KEY,
+        path TEXT NOT NULL,
+        priority TEXT NOT NULL,
+        queued_at INTEGER NOT NULL
+    );",
+    // 16: errors of retried job attempts
+    "ALTER TABLE jobs ADD COLUMN failures TEXT;",
+];
+
+pub fn run(conn: &mut Connection) -> Result<()> {
+    let applied: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(applied) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrations_are_idempotent() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn).unwrap();
+        run(&mut conn).unwrap();
+        let version: usize = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+    }
+}
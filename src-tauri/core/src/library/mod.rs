@@ -0,0 +1,440 @@
+//! SQLite-backed store of recordings, their transcripts and job history.
+
+mod action_items;
+mod backup;
+mod chapters;
+mod entities;
+mod import;
+mod key_phrases;
+mod list;
+mod migrations;
+mod queue;
+mod rename;
+mod retention;
+mod search;
+mod sessions;
+mod summaries;
+mod tags;
+mod translations;
+mod trash;
+mod uploads;
+mod versions;
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::audio::wav;
+use crate::jobs::{Job, JobId};
+use crate::transcribe::Transcript;
+
+pub use entities::EntityHit;
+pub use list::{ListQuery, Page, RecordingSummary, SortBy};
+pub use queue::QueuedTranscription;
+pub use retention::{PlannedRemoval, RemovalReason, RetentionPlan, RetentionPolicy};
+pub use search::SearchHit;
+pub use sessions::{Session, SessionId};
+pub use summaries::TranscriptSummary;
+pub use tags::TagCount;
+pub use translations::TranscriptTranslation;
+pub use uploads::{Upload, UploadKind, UploadSettings};
+pub use versions::{TranscriptId, TranscriptVersion};
+
+pub type RecordingId = i64;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Recording {
+    pub id: RecordingId,
+    pub title: String,
+    pub path: PathBuf,
+    /// Unix time in milliseconds.
+    pub created_at: i64,
+    pub duration_ms: i64,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub device: Option<String>,
+    pub favorite: bool,
+    /// Sorted alphabetically.
+    pub tags: Vec<String>,
+    /// Unix time in milliseconds the recording was moved to the trash.
+    pub trashed_at: Option<i64>,
+    pub session_id: Option<SessionId>,
+    /// Unix time in milliseconds the retention policy deleted the audio.
+    pub audio_deleted_at: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewRecording {
+    pub title: String,
+    pub path: PathBuf,
+    pub created_at: i64,
+    pub duration_ms: i64,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub device: Option<String>,
+}
+
+impl NewRecording {
+    /// Describes the WAV file at `path`, titled after its file name.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let info = wav::info(path)?;
+        Ok(NewRecording {
+            title: path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            path: path.to_path_buf(),
+            created_at: now_ms(),
+            duration_ms: info.duration_ms as i64,
+            sample_rate: info.sample_rate,
+            channels: info.channels,
+            device: None,
+        })
+    }
+}
+
+/// Appends ` (n)` before the extension until the name is free.
+pub fn unique_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path.extension().map(|e| e.to_string_lossy());
+    (1..)
+        .map(|n| {
+            let name = match &ext {
+                Some(ext) => format!("{} ({}).{}", stem, n, ext),
+                None => format!("{} ({})", stem, n),
+            };
+            path.with_file_name(name)
+        })
+        .find(|candidate| !candidate.exists())
+        .unwrap()
+}
+
+pub fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+const RECORDING_COLUMNS: &str =
+    "id, title, path, created_at, duration_ms, sample_rate, channels, device, favorite,
+     (SELECT json_group_array(tag) FROM
+         (SELECT tag FROM recording_tags WHERE recording_id = recordings.id ORDER BY tag)),
+     trashed_at, session_id, audio_deleted_at";
+
+fn recording_from_row(row: &Row) -> rusqlite::Result<Recording> {
+    Ok(Recording {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        path: PathBuf::from(row.get::<_, String>(2)?),
+        created_at: row.get(3)?,
+        duration_ms: row.get(4)?,
+        sample_rate: row.get(5)?,
+        channels: row.get(6)?,
+        device: row.get(7)?,
+        favorite: row.get(8)?,
+        tags: serde_json::from_str(&row.get::<_, String>(9)?).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(9, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+        trashed_at: row.get(10)?,
+        session_id: row.get(11)?,
+        audio_deleted_at: row.get(12)?,
+    })
+}
+
+/// Handle to the library database, shared by commands and job workers.
+#[derive(Clone)]
+pub struct Library {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl Library {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        Self::init(Connection::open(path).context("failed to open library database")?)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(mut conn: Connection) -> Result<Self> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        migrations::run(&mut conn)?;
+        Ok(Library {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap()
+    }
+
+    pub fn add_recording(&self, recording: &NewRecording) -> Result<RecordingId> {
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO recordings (title, path, created_at, duration_ms, sample_rate, channels, device)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                recording.title,
+                recording.path.to_string_lossy(),
+                recording.created_at,
+                recording.duration_ms,
+                recording.sample_rate,
+                recording.channels,
+                recording.device,
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn recording(&self, id: RecordingId) -> Result<Option<Recording>> {
+        Ok(self
+            .conn()
+            .query_row(
+                &format!("SELECT {} FROM recordings WHERE id = ?1", RECORDING_COLUMNS),
+                [id],
+                recording_from_row,
+            )
+            .optional()?)
+    }
+
+    pub fn recording_by_path(&self, path: &Path) -> Result<Option<Recording>> {
+        Ok(self
+            .conn()
+            .query_row(
+                &format!(
+                    "SELECT {} FROM recordings WHERE path = ?1",
+                    RECORDING_COLUMNS
+                ),
+                [path.to_string_lossy()],
+                recording_from_row,
+            )
+            .optional()?)
+    }
+
+    /// Every recording outside the trash, newest first.
+    pub fn recordings(&self) -> Result<Vec<Recording>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM recordings WHERE trashed_at IS NULL ORDER BY created_at DESC, id DESC",
+            RECORDING_COLUMNS
+        ))?;
+        let rows = stmt.query_map([], recording_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Looks up the recording for `path`, registering the file if it's new.
+    pub fn ensure_recording(&self, path: &Path) -> Result<Recording> {
+        if let Some(recording) = self.recording_by_path(path)? {
+            return Ok(recording);
+        }
+        let id = self.add_recording(&NewRecording::from_file(path)?)?;
+        Ok(self.recording(id)?.expect("just inserted"))
+    }
+
+    pub fn save_transcript(
+        &self,
+        recording_id: RecordingId,
+        model: &str,
+        transcript: &Transcript,
+    ) -> Result<TranscriptId> {
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO transcripts (recording_id, model, segments, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                recording_id,
+                model,
+                serde_json::to_string(&transcript.segments)?,
+                now_ms()
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// The most recent transcript of a recording.
+    pub fn transcript(&self, recording_id: RecordingId) -> Result<Option<Transcript>> {
+        let segments: Option<String> = self
+            .conn()
+            .query_row(
+                "SELECT segments FROM transcripts WHERE recording_id = ?1
+                 ORDER BY created_at DESC, id DESC LIMIT 1",
+                [recording_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        segments
+            .map(|json| {
+                Ok(Transcript {
+                    segments: serde_json::from_str(&json)?,
+                })
+            })
+            .transpose()
+    }
+
+    pub fn set_metadata(&self, recording_id: RecordingId, key: &str, value: &str) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO recording_metadata (recording_id, key, value) VALUES (?1, ?2, ?3)
+             ON CONFLICT (recording_id, key) DO UPDATE SET value = excluded.value",
+            params![recording_id, key, value],
+        )?;
+        Ok(())
+    }
+
+    pub fn metadata(&self, recording_id: RecordingId) -> Result<HashMap<String, String>> {
+        let conn = self.conn();
+        let mut stmt =
+            conn.prepare("SELECT key, value FROM recording_metadata WHERE recording_id = ?1")?;
+        let rows = stmt.query_map([recording_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// The highest job id stored so far, or 0.
+    pub fn last_job_id(&self) -> Result<JobId> {
+        let id: i64 =
+            self.conn()
+                .query_row("SELECT COALESCE(MAX(id), 0) FROM jobs", [], |row| {
+                    row.get(0)
+                })?;
+        Ok(id as JobId)
+    }
+
+    /// Records the latest snapshot of a job.
+    pub fn save_job(&self, job: &Job) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO jobs (id, kind, state, progress, result, error, updated_at, failures)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT (id) DO UPDATE SET state = excluded.state, progress = excluded.progress,
+                 result = excluded.result, error = excluded.error, updated_at = excluded.updated_at,
+                 failures = excluded.failures",
+            params![
+                job.id as i64,
+                serde_json::to_value(job.kind)?.as_str(),
+                serde_json::to_value(job.state)?.as_str(),
+                job.progress,
+                job.result.as_ref().map(|r| r.to_string()),
+                job.error,
+                now_ms(),
+                (!job.failures.is_empty())
+                    .then(|| serde_json::to_string(&job.failures))
+                    .transpose()?,
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::fixture;
+    use crate::jobs::{JobKind, JobState, Limits, Priority};
+    use crate::transcribe::Segment;
+
+    pub(crate) fn recording(path: &str, created_at: i64) -> NewRecording {
+        NewRecording {
+            title: path.to_string(),
+            path: PathBuf::from(path),
+            created_at,
+            duration_ms: 1000,
+            sample_rate: 48000,
+            channels: 1,
+            device: None,
+        }
+    }
+
+    /// A recording with `transcript` saved as its only version.
+    pub(crate) fn transcript_fixture(
+        library: &Library,
+        transcript: &Transcript,
+    ) -> (RecordingId, TranscriptId) {
+        let recording = library.add_recording(&recording("/a.wav", 0)).unwrap();
+        let id = library
+            .save_transcript(recording, "small", transcript)
+            .unwrap();
+        (recording, id)
+    }
+
+    #[test]
+    fn recordings_are_listed_newest_first() {
+        let library = Library::open_in_memory().unwrap();
+        let old = library.add_recording(&recording("/a.wav", 1)).unwrap();
+        let new = library.add_recording(&recording("/b.wav", 2)).unwrap();
+        let ids: Vec<_> = library.recordings().unwrap().iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![new, old]);
+    }
+
+    #[test]
+    fn ensure_recording_registers_once() {
+        let library = Library::open_in_memory().unwrap();
+        let path = fixture("mono_16k.wav");
+        let first = library.ensure_recording(&path).unwrap();
+        let second = library.ensure_recording(&path).unwrap();
+        assert_eq!(first.id, second.id);
+        assert_eq!(first.duration_ms, 250);
+        assert_eq!(first.title, "mono_16k");
+    }
+
+    #[test]
+    fn latest_transcript_wins() {
+        let library = Library::open_in_memory().unwrap();
+        let id = library.add_recording(&recording("/a.wav", 1)).unwrap();
+        let transcript = |text: &str| Transcript {
+            segments: vec![Segment {
+                start: 0,
+                end: 100,
+                text: text.to_string(),
+                speaker_turn_next: false,
+            }],
+        };
+        assert_eq!(library.transcript(id).unwrap(), None);
+        library
+            .save_transcript(id, "small.en", &transcript("first"))
+            .unwrap();
+        library
+            .save_transcript(id, "small.en", &transcript("second"))
+            .unwrap();
+        assert_eq!(library.transcript(id).unwrap(), Some(transcript("second")));
+    }
+
+    #[test]
+    fn metadata_upserts() {
+        let library = Library::open_in_memory().unwrap();
+        let id = library.add_recording(&recording("/a.wav", 1)).unwrap();
+        library.set_metadata(id, "meeting", "standup").unwrap();
+        library.set_metadata(id, "meeting", "retro").unwrap();
+        assert_eq!(library.metadata(id).unwrap()["meeting"], "retro");
+    }
+
+    #[test]
+    fn saved_jobs_advance_last_job_id() {
+        let library = Library::open_in_memory().unwrap();
+        assert_eq!(library.last_job_id().unwrap(), 0);
+        let job = Job {
+            id: 7,
+            kind: JobKind::Transcription,
+            priority: Priority::Normal,
+            limits: Limits::default(),
+            progress: 100.0,
+            eta_ms: None,
+            state: JobState::Done,
+            result: None,
+            error: None,
+            failures: vec!["out of memory".to_string()],
+        };
+        library.save_job(&job).unwrap();
+        library.save_job(&job).unwrap();
+        assert_eq!(library.last_job_id().unwrap(), 7);
+    }
+}
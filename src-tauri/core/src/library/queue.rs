@@ -0,0 +1,87 @@
+use anyhow::Result;
+use rusqlite::params;
+use std::path::{Path, PathBuf};
+
+use super::{now_ms, Library};
+use crate::jobs::Priority;
+
+/// A transcription that was waiting or running when the app last stopped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedTranscription {
+    pub id: i64,
+    pub path: PathBuf,
+    pub priority: Priority,
+}
+
+impl Library {
+    /// Remembers a transcription of `path` until [`unqueue_transcription`]
+    /// is called with the returned id, so it can be started again if the app
+    /// quits first.
+    ///
+    /// [`unqueue_transcription`]: Library::unqueue_transcription
+    pub fn queue_transcription(&self, path: &Path, priority: Priority) -> Result<i64> {
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO transcription_queue (path, priority, queued_at) VALUES (?1, ?2, ?3)",
+            params![path.to_string_lossy(), priority.as_str(), now_ms()],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn unqueue_transcription(&self, id: i64) -> Result<()> {
+        self.conn()
+            .execute("DELETE FROM transcription_queue WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    /// Forgets every queued transcription, e.g. once they've all been
+    /// cancelled.
+    pub fn clear_transcription_queue(&self) -> Result<()> {
+        self.conn().execute("DELETE FROM transcription_queue", [])?;
+        Ok(())
+    }
+
+    /// Transcriptions that haven't been unqueued, oldest first.
+    pub fn queued_transcriptions(&self) -> Result<Vec<QueuedTranscription>> {
+        let conn = self.conn();
+        let mut stmt =
+            conn.prepare("SELECT id, path, priority FROM transcription_queue ORDER BY id")?;
+        let rows = stmt.query_map([], |row| {
+            let path: String = row.get(1)?;
+            let priority: String = row.get(2)?;
+            Ok(QueuedTranscription {
+                id: row.get(0)?,
+                path: PathBuf::from(path),
+                priority: Priority::parse(&priority).unwrap_or_default(),
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queue_survives_until_unqueued() {
+        let library = Library::open_in_memory().unwrap();
+        let a = library
+            .queue_transcription(Path::new("/a.wav"), Priority::Background)
+            .unwrap();
+        let b = library
+            .queue_transcription(Path::new("/b.wav"), Priority::Live)
+            .unwrap();
+        library.unqueue_transcription(a).unwrap();
+        assert_eq!(
+            library.queued_transcriptions().unwrap(),
+            vec![QueuedTranscription {
+                id: b,
+                path: PathBuf::from("/b.wav"),
+                priority: Priority::Live,
+            }]
+        );
+        library.clear_transcription_queue().unwrap();
+        assert!(library.queued_transcriptions().unwrap().is_empty());
+    }
+}
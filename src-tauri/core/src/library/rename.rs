@@ -0,0 +1,116 @@
+use anyhow::{bail, Result};
+use rusqlite::params;
+
+use super::trash::move_file;
+use super::{unique_path, Library, RecordingId};
+
+/// Makes `title` safe to use as a file name on every platform.
+fn file_stem_for(title: &str) -> String {
+    let stem: String = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c if c.is_control() => '-',
+            c => c,
+        })
+        .collect();
+    let stem = stem.trim().trim_end_matches('.').trim();
+    if stem.is_empty() {
+        "recording".to_string()
+    } else {
+        stem.to_string()
+    }
+}
+
+impl Library {
+    /// Changes a recording's display title and, with `rename_file`, its file
+    /// name to match. The file is moved back if the database update fails.
+    pub fn rename_recording(&self, id: RecordingId, title: &str, rename_file: bool) -> Result<()> {
+        let title = title.trim();
+        if title.is_empty() {
+            bail!("title can't be empty");
+        }
+        let (path, trashed_from) = self.path_of(id)?;
+        if !rename_file {
+            self.conn().execute(
+                "UPDATE recordings SET title = ?2 WHERE id = ?1",
+                params![id, title],
+            )?;
+            return Ok(());
+        }
+        if trashed_from.is_some() {
+            bail!("restore the recording before renaming its file");
+        }
+
+        let mut file_name = file_stem_for(title);
+        if let Some(ext) = path.extension() {
+            file_name = format!("{}.{}", file_name, ext.to_string_lossy());
+        }
+        let target = path.with_file_name(file_name);
+        let dest = if target == path {
+            target
+        } else {
+            unique_path(&target)
+        };
+        move_file(&path, &dest)?;
+        let updated = self.conn().execute(
+            "UPDATE recordings SET title = ?2, path = ?3 WHERE id = ?1",
+            params![id, title, dest.to_string_lossy()],
+        );
+        if let Err(err) = updated {
+            let _ = move_file(&dest, &path);
+            return Err(err.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::NewRecording;
+    use std::fs;
+
+    #[test]
+    fn file_stems_drop_reserved_characters() {
+        assert_eq!(file_stem_for("Q3: plan/review?"), "Q3- plan-review-");
+        assert_eq!(file_stem_for(" ... "), "recording");
+    }
+
+    #[test]
+    fn renames_title_and_file() {
+        let dir = std::env::temp_dir().join(format!("app-core-rename-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("recording-1.wav");
+        fs::write(&path, b"audio").unwrap();
+        fs::write(dir.join("Standup.wav"), b"taken").unwrap();
+
+        let library = Library::open_in_memory().unwrap();
+        let id = library
+            .add_recording(&NewRecording {
+                title: "recording-1".to_string(),
+                path: path.clone(),
+                created_at: 1,
+                duration_ms: 1000,
+                sample_rate: 16000,
+                channels: 1,
+                device: None,
+            })
+            .unwrap();
+
+        library.rename_recording(id, "Notes", false).unwrap();
+        let recording = library.recording(id).unwrap().unwrap();
+        assert_eq!(
+            (recording.title.as_str(), &recording.path),
+            ("Notes", &path)
+        );
+
+        library.rename_recording(id, " Standup ", true).unwrap();
+        let recording = library.recording(id).unwrap().unwrap();
+        assert_eq!(recording.title, "Standup");
+        assert_eq!(recording.path, dir.join("Standup (1).wav"));
+        assert!(recording.path.exists() && !path.exists());
+        assert!(library.rename_recording(id, "  ", false).is_err());
+    }
+}
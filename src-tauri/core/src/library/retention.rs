@@ -0,0 +1,216 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use super::{now_ms, Library, RecordingId};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Rules for freeing disk space by deleting recordings' audio. Rows,
+/// transcripts and favorites are always kept.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Delete the audio of transcribed recordings older than this.
+    pub delete_audio_after_days: Option<u32>,
+    /// Delete the oldest audio until the library's files fit in this many GB.
+    pub max_total_gb: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemovalReason {
+    Age,
+    Quota,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlannedRemoval {
+    pub recording_id: RecordingId,
+    pub title: String,
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub reason: RemovalReason,
+}
+
+/// What a policy would delete right now.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RetentionPlan {
+    pub removals: Vec<PlannedRemoval>,
+    /// Size of all library audio before the removals.
+    pub total_bytes: u64,
+    pub freed_bytes: u64,
+}
+
+struct Candidate {
+    removal: PlannedRemoval,
+    created_at: i64,
+    favorite: bool,
+    has_transcript: bool,
+}
+
+impl Library {
+    fn audio_files(&self) -> Result<Vec<Candidate>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, title, path, created_at, favorite,
+                 EXISTS (SELECT 1 FROM transcripts WHERE recording_id = recordings.id)
+             FROM recordings WHERE audio_deleted_at IS NULL ORDER BY created_at, id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let path = PathBuf::from(row.get::<_, String>(2)?);
+            Ok(Candidate {
+                removal: PlannedRemoval {
+                    recording_id: row.get(0)?,
+                    title: row.get(1)?,
+                    bytes: fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0),
+                    path,
+                    reason: RemovalReason::Age,
+                },
+                created_at: row.get(3)?,
+                favorite: row.get(4)?,
+                has_transcript: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Works out which audio `policy` would delete, without touching anything.
+    pub fn plan_retention(&self, policy: &RetentionPolicy) -> Result<RetentionPlan> {
+        let candidates = self.audio_files()?;
+        let total_bytes: u64 = candidates.iter().map(|c| c.removal.bytes).sum();
+        let mut plan = RetentionPlan {
+            total_bytes,
+            ..RetentionPlan::default()
+        };
+        let cutoff = policy
+            .delete_audio_after_days
+            .map(|days| now_ms() - days as i64 * DAY_MS);
+        let max_bytes = policy.max_total_gb.map(|gb| (gb.max(0.0) * GB) as u64);
+
+        // Candidates are oldest first, so both rules remove the oldest audio first.
+        for candidate in candidates {
+            if candidate.favorite || candidate.removal.bytes == 0 {
+                continue;
+            }
+            let reason = if cutoff.map_or(false, |cutoff| {
+                candidate.has_transcript && candidate.created_at < cutoff
+            }) {
+                RemovalReason::Age
+            } else if max_bytes.map_or(false, |max| total_bytes - plan.freed_bytes > max) {
+                RemovalReason::Quota
+            } else {
+                continue;
+            };
+            plan.freed_bytes += candidate.removal.bytes;
+            plan.removals.push(PlannedRemoval {
+                reason,
+                ..candidate.removal
+            });
+        }
+        Ok(plan)
+    }
+
+    /// Deletes the audio `policy` selects and marks those recordings as
+    /// having no audio. Returns what was removed.
+    pub fn apply_retention(&self, policy: &RetentionPolicy) -> Result<RetentionPlan> {
+        let plan = self.plan_retention(policy)?;
+        for removal in &plan.removals {
+            match fs::remove_file(&removal.path) {
+                Ok(()) => {}
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+            self.conn().execute(
+                "UPDATE recordings SET audio_deleted_at = ?2 WHERE id = ?1",
+                rusqlite::params![removal.recording_id, now_ms()],
+            )?;
+        }
+        Ok(plan)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::NewRecording;
+    use crate::transcribe::Transcript;
+    use std::path::Path;
+
+    fn add(library: &Library, path: &Path, days_old: i64, bytes: usize) -> RecordingId {
+        fs::write(path, vec![0u8; bytes]).unwrap();
+        library
+            .add_recording(&NewRecording {
+                title: path.file_stem().unwrap().to_string_lossy().into_owned(),
+                path: path.to_path_buf(),
+                created_at: now_ms() - days_old * DAY_MS,
+                duration_ms: 1000,
+                sample_rate: 16000,
+                channels: 1,
+                device: None,
+            })
+            .unwrap()
+    }
+
+    fn reasons(plan: &RetentionPlan) -> Vec<(&str, RemovalReason)> {
+        plan.removals
+            .iter()
+            .map(|r| (r.title.as_str(), r.reason))
+            .collect()
+    }
+
+    #[test]
+    fn age_rule_keeps_untranscribed_and_favorite_audio() {
+        let dir = std::env::temp_dir().join(format!("app-core-retention-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let library = Library::open_in_memory().unwrap();
+        let old = add(&library, &dir.join("old.wav"), 40, 10);
+        let favorite = add(&library, &dir.join("favorite.wav"), 40, 10);
+        add(&library, &dir.join("untranscribed.wav"), 40, 10);
+        let recent = add(&library, &dir.join("recent.wav"), 1, 10);
+        for id in [old, favorite, recent] {
+            library
+                .save_transcript(id, "small.en", &Transcript::default())
+                .unwrap();
+        }
+        library.set_favorite(favorite, true).unwrap();
+        let policy = RetentionPolicy {
+            delete_audio_after_days: Some(30),
+            max_total_gb: None,
+        };
+
+        let plan = library.plan_retention(&policy).unwrap();
+        assert_eq!(reasons(&plan), vec![("old", RemovalReason::Age)]);
+        assert_eq!((plan.total_bytes, plan.freed_bytes), (40, 10));
+        assert!(dir.join("old.wav").exists(), "preview must not delete");
+
+        library.apply_retention(&policy).unwrap();
+        assert!(!dir.join("old.wav").exists());
+        let recording = library.recording(old).unwrap().unwrap();
+        assert!(recording.audio_deleted_at.is_some());
+        assert!(library.transcript(old).unwrap().is_some());
+        assert!(library.plan_retention(&policy).unwrap().removals.is_empty());
+    }
+
+    #[test]
+    fn quota_removes_oldest_first() {
+        let dir = std::env::temp_dir().join(format!("app-core-quota-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let library = Library::open_in_memory().unwrap();
+        add(&library, &dir.join("a.wav"), 3, 600);
+        add(&library, &dir.join("b.wav"), 2, 600);
+        add(&library, &dir.join("c.wav"), 1, 600);
+        let policy = RetentionPolicy {
+            delete_audio_after_days: None,
+            max_total_gb: Some(1000.0 / GB),
+        };
+        let plan = library.plan_retention(&policy).unwrap();
+        assert_eq!(
+            reasons(&plan),
+            vec![("a", RemovalReason::Quota), ("b", RemovalReason::Quota)]
+        );
+    }
+}
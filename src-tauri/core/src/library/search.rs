@@ -0,0 +1,162 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use super::{Library, RecordingId, SessionId};
+
+const MAX_HITS: usize = 100;
+
+/// A transcript segment matching a search, with enough context to jump to it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    pub recording_id: RecordingId,
+    pub recording_title: String,
+    /// Start time in centiseconds, as reported by whisper.
+    pub start: i64,
+    /// End time in centiseconds, as reported by whisper.
+    pub end: i64,
+    pub text: String,
+}
+
+/// Turns free text into an FTS5 query: every word must appear, and the last
+/// one may be a prefix so results show up while typing. Quoting each word
+/// keeps FTS operators and punctuation typed by the user from being parsed.
+fn fts_query(query: &str) -> Option<String> {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+    Some(format!("{}*", words.join(" ")))
+}
+
+impl Library {
+    /// Finds segments of the latest transcripts containing every word of
+    /// `query`, best matches first, optionally only within `session`.
+    /// Trashed recordings are left out.
+    pub fn search_transcripts(
+        &self,
+        query: &str,
+        session: Option<SessionId>,
+    ) -> Result<Vec<SearchHit>> {
+        let Some(query) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT s.recording_id, r.title, s.start_cs, s.end_cs, s.text
+             FROM transcript_segments s JOIN recordings r ON r.id = s.recording_id
+             WHERE transcript_segments MATCH ?1 AND r.trashed_at IS NULL
+                 AND (?3 IS NULL OR r.session_id = ?3)
+             ORDER BY rank LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![query, MAX_HITS, session], |row| {
+            Ok(SearchHit {
+                recording_id: row.get(0)?,
+                recording_title: row.get(1)?,
+                start: row.get(2)?,
+                end: row.get(3)?,
+                text: row.get(4)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::NewRecording;
+    use crate::transcribe::{Segment, Transcript};
+    use std::path::PathBuf;
+
+    fn transcript(texts: &[&str]) -> Transcript {
+        Transcript {
+            segments: texts
+                .iter()
+                .enumerate()
+                .map(|(i, text)| Segment {
+                    start: i as i64 * 100,
+                    end: i as i64 * 100 + 100,
+                    text: text.to_string(),
+                    speaker_turn_next: false,
+                })
+                .collect(),
+        }
+    }
+
+    fn library_with_recording() -> (Library, RecordingId) {
+        let library = Library::open_in_memory().unwrap();
+        let id = library
+            .add_recording(&NewRecording {
+                title: "Standup".to_string(),
+                path: PathBuf::from("/standup.wav"),
+                created_at: 1,
+                duration_ms: 1000,
+                sample_rate: 16000,
+                channels: 1,
+                device: None,
+            })
+            .unwrap();
+        (library, id)
+    }
+
+    #[test]
+    fn quotes_words_and_prefixes_the_last() {
+        assert_eq!(
+            fts_query(r#" budget "Q3 "#).as_deref(),
+            Some(r#""budget" """Q3"*"#)
+        );
+        assert_eq!(fts_query("  ( "), None);
+    }
+
+    #[test]
+    fn finds_segments_with_timestamps() {
+        let (library, id) = library_with_recording();
+        library
+            .save_transcript(
+                id,
+                "small.en",
+                &transcript(&[" Hello.", " The budget is due."]),
+            )
+            .unwrap();
+        let hits = library.search_transcripts("budg", None).unwrap();
+        assert_eq!(
+            hits,
+            vec![SearchHit {
+                recording_id: id,
+                recording_title: "Standup".to_string(),
+                start: 100,
+                end: 200,
+                text: " The budget is due.".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn only_the_latest_transcript_is_searched() {
+        let (library, id) = library_with_recording();
+        library
+            .save_transcript(id, "small.en", &transcript(&[" old words"]))
+            .unwrap();
+        library
+            .save_transcript(id, "small.en", &transcript(&[" new words"]))
+            .unwrap();
+        assert!(library.search_transcripts("old", None).unwrap().is_empty());
+        assert_eq!(library.search_transcripts("words", None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn operators_in_queries_are_literal() {
+        let (library, id) = library_with_recording();
+        library
+            .save_transcript(id, "small.en", &transcript(&[" this and that"]))
+            .unwrap();
+        assert!(library
+            .search_transcripts("that OR nothing", None)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+use anyhow::{bail, Result};
+use rusqlite::params;
+use serde::Serialize;
+
+use super::{now_ms, recording_from_row, Library, Recording, RecordingId, RECORDING_COLUMNS};
+
+pub type SessionId = i64;
+
+/// A named group of recordings, like every instance of a weekly meeting.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Session {
+    pub id: SessionId,
+    pub name: String,
+    pub created_at: i64,
+    /// Recordings in the session, outside the trash.
+    pub recordings: u32,
+    pub duration_ms: i64,
+}
+
+impl Library {
+    pub fn create_session(&self, name: &str) -> Result<SessionId> {
+        let name = name.trim();
+        if name.is_empty() {
+            bail!("session name can't be empty");
+        }
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO sessions (name, created_at) VALUES (?1, ?2)",
+            params![name, now_ms()],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn rename_session(&self, id: SessionId, name: &str) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            bail!("session name can't be empty");
+        }
+        self.conn().execute(
+            "UPDATE sessions SET name = ?2 WHERE id = ?1",
+            params![id, name],
+        )?;
+        Ok(())
+    }
+
+    /// Deletes the session; its recordings stay in the library, ungrouped.
+    pub fn delete_session(&self, id: SessionId) -> Result<()> {
+        self.conn()
+            .execute("DELETE FROM sessions WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    /// Moves a recording into `session`, or out of any session with `None`.
+    pub fn set_session(&self, recording_id: RecordingId, session: Option<SessionId>) -> Result<()> {
+        self.conn().execute(
+            "UPDATE recordings SET session_id = ?2 WHERE id = ?1",
+            params![recording_id, session],
+        )?;
+        Ok(())
+    }
+
+    /// Every session, most recently created first.
+    pub fn sessions(&self) -> Result<Vec<Session>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT s.id, s.name, s.created_at, COUNT(r.id), COALESCE(SUM(r.duration_ms), 0)
+             FROM sessions s
+             LEFT JOIN recordings r ON r.session_id = s.id AND r.trashed_at IS NULL
+             GROUP BY s.id ORDER BY s.created_at DESC, s.id DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Session {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                created_at: row.get(2)?,
+                recordings: row.get(3)?,
+                duration_ms: row.get(4)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// The session's recordings outside the trash, oldest first.
+    pub fn session_recordings(&self, id: SessionId) -> Result<Vec<Recording>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM recordings WHERE session_id = ?1 AND trashed_at IS NULL
+             ORDER BY created_at, id",
+            RECORDING_COLUMNS
+        ))?;
+        let rows = stmt.query_map([id], recording_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::{ListQuery, NewRecording};
+    use crate::transcribe::{Segment, Transcript};
+    use std::path::PathBuf;
+
+    fn add(library: &Library, path: &str, created_at: i64) -> RecordingId {
+        library
+            .add_recording(&NewRecording {
+                title: path.to_string(),
+                path: PathBuf::from(path),
+                created_at,
+                duration_ms: 1000,
+                sample_rate: 16000,
+                channels: 1,
+                device: None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn sessions_count_their_recordings() {
+        let library = Library::open_in_memory().unwrap();
+        let weekly = library.create_session(" Weekly sync ").unwrap();
+        let empty = library.create_session("Empty").unwrap();
+        let a = add(&library, "/a.wav", 2);
+        let b = add(&library, "/b.wav", 1);
+        add(&library, "/c.wav", 3);
+        library.set_session(a, Some(weekly)).unwrap();
+        library.set_session(b, Some(weekly)).unwrap();
+
+        let sessions = library.sessions().unwrap();
+        assert_eq!(
+            sessions
+                .iter()
+                .map(|s| (s.id, s.name.as_str(), s.recordings, s.duration_ms))
+                .collect::<Vec<_>>(),
+            vec![(empty, "Empty", 0, 0), (weekly, "Weekly sync", 2, 2000)]
+        );
+        let ids: Vec<_> = library
+            .session_recordings(weekly)
+            .unwrap()
+            .iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(ids, vec![b, a]);
+        let page = library
+            .list_recordings(&ListQuery {
+                session_id: Some(weekly),
+                ..ListQuery::default()
+            })
+            .unwrap();
+        assert_eq!(page.total, 2);
+    }
+
+    #[test]
+    fn deleting_a_session_keeps_its_recordings() {
+        let library = Library::open_in_memory().unwrap();
+        let session = library.create_session("Retro").unwrap();
+        let id = add(&library, "/a.wav", 1);
+        library.set_session(id, Some(session)).unwrap();
+        library.delete_session(session).unwrap();
+        assert_eq!(library.recording(id).unwrap().unwrap().session_id, None);
+    }
+
+    #[test]
+    fn search_can_be_limited_to_a_session() {
+        let library = Library::open_in_memory().unwrap();
+        let session = library.create_session("Standup").unwrap();
+        let inside = add(&library, "/a.wav", 1);
+        let outside = add(&library, "/b.wav", 2);
+        library.set_session(inside, Some(session)).unwrap();
+        let transcript = Transcript {
+            segments: vec![Segment {
+                start: 0,
+                end: 100,
+                text: " Blockers?".to_string(),
+                speaker_turn_next: false,
+            }],
+        };
+        for id in [inside, outside] {
+            library
+                .save_transcript(id, "small.en", &transcript)
+                .unwrap();
+        }
+        assert_eq!(
+            library.search_transcripts("blockers", None).unwrap().len(),
+            2
+        );
+        let hits = library
+            .search_transcripts("blockers", Some(session))
+            .unwrap();
+        assert_eq!(
+            hits.iter().map(|h| h.recording_id).collect::<Vec<_>>(),
+            vec![inside]
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::Result;
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+
+use super::{now_ms, Library, TranscriptId};
+use crate::summarize::{Summary, SummaryStyle};
+
+/// A summary saved for one transcript version.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TranscriptSummary {
+    pub transcript_id: TranscriptId,
+    pub style: SummaryStyle,
+    pub model: String,
+    pub created_at: i64,
+    #[serde(flatten)]
+    pub summary: Summary,
+}
+
+impl Library {
+    /// Stores `summary`, replacing any earlier one of the same style.
+    pub fn save_summary(
+        &self,
+        transcript_id: TranscriptId,
+        style: SummaryStyle,
+        model: &str,
+        summary: &Summary,
+    ) -> Result<TranscriptSummary> {
+        let created_at = now_ms();
+        self.conn().execute(
+            "INSERT OR REPLACE INTO summaries
+             (transcript_id, style, model, bullets, action_items, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                transcript_id,
+                style.as_str(),
+                model,
+                serde_json::to_string(&summary.bullets)?,
+                serde_json::to_string(&summary.action_items)?,
+                created_at
+            ],
+        )?;
+        Ok(TranscriptSummary {
+            transcript_id,
+            style,
+            model: model.to_string(),
+            created_at,
+            summary: summary.clone(),
+        })
+    }
+
+    pub fn summary(
+        &self,
+        transcript_id: TranscriptId,
+        style: SummaryStyle,
+    ) -> Result<Option<TranscriptSummary>> {
+        let row: Option<(String, String, String, i64)> = self
+            .conn()
+            .query_row(
+                "SELECT model, bullets, action_items, created_at FROM summaries
+                 WHERE transcript_id = ?1 AND style = ?2",
+                params![transcript_id, style.as_str()],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()?;
+        row.map(|(model, bullets, action_items, created_at)| {
+            Ok(TranscriptSummary {
+                transcript_id,
+                style,
+                model,
+                created_at,
+                summary: Summary {
+                    bullets: serde_json::from_str(&bullets)?,
+                    action_items: serde_json::from_str(&action_items)?,
+                },
+            })
+        })
+        .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::tests::transcript_fixture;
+    use crate::transcribe::Transcript;
+
+    #[test]
+    fn summaries_are_kept_per_style_and_replaced() {
+        let library = Library::open_in_memory().unwrap();
+        let (_, transcript) = transcript_fixture(&library, &Transcript { segments: vec![] });
+        let first = Summary {
+            bullets: vec!["one".to_string()],
+            action_items: vec![],
+        };
+        let second = Summary {
+            bullets: vec!["two".to_string()],
+            action_items: vec!["call Bo".to_string()],
+        };
+        library
+            .save_summary(transcript, SummaryStyle::Brief, "llama", &first)
+            .unwrap();
+        library
+            .save_summary(transcript, SummaryStyle::Brief, "llama", &second)
+            .unwrap();
+
+        let saved = library
+            .summary(transcript, SummaryStyle::Brief)
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved.summary, second);
+        assert_eq!(
+            library.summary(transcript, SummaryStyle::Detailed).unwrap(),
+            None
+        );
+    }
+}
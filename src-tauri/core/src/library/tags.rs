@@ -0,0 +1,146 @@
+use anyhow::{bail, Result};
+use rusqlite::params;
+use serde::Serialize;
+
+use super::{recording_from_row, Library, Recording, RecordingId, RECORDING_COLUMNS};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub recordings: u32,
+}
+
+impl Library {
+    pub fn set_favorite(&self, id: RecordingId, favorite: bool) -> Result<()> {
+        self.conn().execute(
+            "UPDATE recordings SET favorite = ?2 WHERE id = ?1",
+            params![id, favorite],
+        )?;
+        Ok(())
+    }
+
+    /// Tags a recording. Tags are trimmed and compared case-insensitively;
+    /// adding one twice is a no-op.
+    pub fn add_tag(&self, id: RecordingId, tag: &str) -> Result<()> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            bail!("tag can't be empty");
+        }
+        self.conn().execute(
+            "INSERT OR IGNORE INTO recording_tags (recording_id, tag) VALUES (?1, ?2)",
+            params![id, tag],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_tag(&self, id: RecordingId, tag: &str) -> Result<()> {
+        self.conn().execute(
+            "DELETE FROM recording_tags WHERE recording_id = ?1 AND tag = ?2",
+            params![id, tag.trim()],
+        )?;
+        Ok(())
+    }
+
+    /// Every tag in use, with how many recordings carry it.
+    pub fn tags(&self) -> Result<Vec<TagCount>> {
+        let conn = self.conn();
+        let mut stmt =
+            conn.prepare("SELECT tag, COUNT(*) FROM recording_tags GROUP BY tag ORDER BY tag")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(TagCount {
+                tag: row.get(0)?,
+                recordings: row.get(1)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Recordings outside the trash carrying `tag`, newest first.
+    pub fn recordings_tagged(&self, tag: &str) -> Result<Vec<Recording>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM recordings WHERE trashed_at IS NULL AND id IN
+                 (SELECT recording_id FROM recording_tags WHERE tag = ?1)
+             ORDER BY created_at DESC, id DESC",
+            RECORDING_COLUMNS
+        ))?;
+        let rows = stmt.query_map([tag.trim()], recording_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::NewRecording;
+    use std::path::PathBuf;
+
+    fn add(library: &Library, path: &str) -> RecordingId {
+        library
+            .add_recording(&NewRecording {
+                title: path.to_string(),
+                path: PathBuf::from(path),
+                created_at: 1,
+                duration_ms: 1000,
+                sample_rate: 16000,
+                channels: 1,
+                device: None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn tags_are_listed_sorted_and_deduplicated() {
+        let library = Library::open_in_memory().unwrap();
+        let id = add(&library, "/a.wav");
+        library.add_tag(id, " work ").unwrap();
+        library.add_tag(id, "Work").unwrap();
+        library.add_tag(id, "1:1").unwrap();
+        assert_eq!(
+            library.recording(id).unwrap().unwrap().tags,
+            ["1:1", "work"]
+        );
+        assert!(library.add_tag(id, "  ").is_err());
+    }
+
+    #[test]
+    fn filters_and_counts_by_tag() {
+        let library = Library::open_in_memory().unwrap();
+        let a = add(&library, "/a.wav");
+        let b = add(&library, "/b.wav");
+        library.add_tag(a, "work").unwrap();
+        library.add_tag(b, "work").unwrap();
+        library.add_tag(b, "home").unwrap();
+        library.remove_tag(a, "WORK").unwrap();
+
+        let tagged: Vec<_> = library
+            .recordings_tagged("work")
+            .unwrap()
+            .iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(tagged, vec![b]);
+        assert_eq!(
+            library.tags().unwrap(),
+            vec![
+                TagCount {
+                    tag: "home".to_string(),
+                    recordings: 1
+                },
+                TagCount {
+                    tag: "work".to_string(),
+                    recordings: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn favorites_round_trip() {
+        let library = Library::open_in_memory().unwrap();
+        let id = add(&library, "/a.wav");
+        assert!(!library.recording(id).unwrap().unwrap().favorite);
+        library.set_favorite(id, true).unwrap();
+        assert!(library.recording(id).unwrap().unwrap().favorite);
+    }
+}
@@ -0,0 +1,129 @@
+use anyhow::Result;
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+
+use super::{now_ms, Library, TranscriptId};
+use crate::transcribe::Transcript;
+
+/// A transcript version translated into another language.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TranscriptTranslation {
+    pub transcript_id: TranscriptId,
+    pub language: String,
+    /// The translator that produced it.
+    pub provider: String,
+    pub created_at: i64,
+    pub transcript: Transcript,
+}
+
+impl Library {
+    /// Stores a translation, replacing any earlier one into the same language.
+    pub fn save_translation(
+        &self,
+        transcript_id: TranscriptId,
+        language: &str,
+        provider: &str,
+        transcript: &Transcript,
+    ) -> Result<TranscriptTranslation> {
+        let created_at = now_ms();
+        self.conn().execute(
+            "INSERT OR REPLACE INTO translations
+             (transcript_id, language, provider, segments, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                transcript_id,
+                language,
+                provider,
+                serde_json::to_string(&transcript.segments)?,
+                created_at
+            ],
+        )?;
+        Ok(TranscriptTranslation {
+            transcript_id,
+            language: language.to_string(),
+            provider: provider.to_string(),
+            created_at,
+            transcript: transcript.clone(),
+        })
+    }
+
+    pub fn translation(
+        &self,
+        transcript_id: TranscriptId,
+        language: &str,
+    ) -> Result<Option<TranscriptTranslation>> {
+        let row: Option<(String, String, i64)> = self
+            .conn()
+            .query_row(
+                "SELECT provider, segments, created_at FROM translations
+                 WHERE transcript_id = ?1 AND language = ?2",
+                params![transcript_id, language],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+        row.map(|(provider, segments, created_at)| {
+            Ok(TranscriptTranslation {
+                transcript_id,
+                language: language.to_string(),
+                provider,
+                created_at,
+                transcript: Transcript {
+                    segments: serde_json::from_str(&segments)?,
+                },
+            })
+        })
+        .transpose()
+    }
+
+    /// Languages a transcript version has been translated into.
+    pub fn translation_languages(&self, transcript_id: TranscriptId) -> Result<Vec<String>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT language FROM translations WHERE transcript_id = ?1 ORDER BY language",
+        )?;
+        let languages = stmt
+            .query_map([transcript_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(languages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::tests::transcript_fixture;
+    use crate::transcribe::Segment;
+
+    #[test]
+    fn translations_round_trip_per_language() {
+        let library = Library::open_in_memory().unwrap();
+        let original = Transcript {
+            segments: vec![Segment {
+                start: 0,
+                end: 100,
+                text: " Hello".to_string(),
+                speaker_turn_next: false,
+            }],
+        };
+        let (_, id) = transcript_fixture(&library, &original);
+        let german = Transcript {
+            segments: vec![Segment {
+                text: " Hallo".to_string(),
+                ..original.segments[0].clone()
+            }],
+        };
+        library
+            .save_translation(id, "de", "libretranslate", &german)
+            .unwrap();
+        library
+            .save_translation(id, "fr", "libretranslate", &original)
+            .unwrap();
+
+        assert_eq!(
+            library.translation(id, "de").unwrap().unwrap().transcript,
+            german
+        );
+        assert_eq!(library.translation(id, "es").unwrap(), None);
+        assert_eq!(library.translation_languages(id).unwrap(), ["de", "fr"]);
+    }
+}
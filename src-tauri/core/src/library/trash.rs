@@ -0,0 +1,212 @@
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, OptionalExtension};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use super::{now_ms, unique_path, Library, RecordingId};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Renames `from` to `to`, copying across file systems when a rename can't.
+pub(crate) fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if let Some(dir) = to.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to).with_context(|| format!("failed to move {}", from.display()))?;
+    fs::remove_file(from)?;
+    Ok(())
+}
+
+impl Library {
+    /// A recording's current path, and where it came from if it's in the trash.
+    pub(super) fn path_of(&self, id: RecordingId) -> Result<(PathBuf, Option<PathBuf>)> {
+        let row: Option<(String, Option<String>)> = self
+            .conn()
+            .query_row(
+                "SELECT path, trashed_from FROM recordings WHERE id = ?1",
+                [id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((path, trashed_from)) = row else {
+            bail!("no recording with id {}", id);
+        };
+        Ok((PathBuf::from(path), trashed_from.map(PathBuf::from)))
+    }
+
+    /// Moves a recording's file into `trash_dir` and hides it from the
+    /// library until it's restored or purged. If the database can't be
+    /// updated the file is moved back.
+    pub fn trash_recording(&self, id: RecordingId, trash_dir: &Path) -> Result<()> {
+        let (path, trashed_from) = self.path_of(id)?;
+        if trashed_from.is_some() {
+            return Ok(());
+        }
+        let file_name = path.file_name().context("recording has no file name")?;
+        let dest = unique_path(&trash_dir.join(file_name));
+        let file_exists = path.exists();
+        if file_exists {
+            move_file(&path, &dest)?;
+        }
+        let updated = self.conn().execute(
+            "UPDATE recordings SET path = ?2, trashed_from = ?3, trashed_at = ?4 WHERE id = ?1",
+            params![id, dest.to_string_lossy(), path.to_string_lossy(), now_ms()],
+        );
+        if let Err(err) = updated {
+            if file_exists {
+                let _ = move_file(&dest, &path);
+            }
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    /// Moves a trashed recording back to where it was, or next to it if that
+    /// name has been taken since.
+    pub fn restore_recording(&self, id: RecordingId) -> Result<()> {
+        let (path, trashed_from) = self.path_of(id)?;
+        let Some(original) = trashed_from else {
+            return Ok(());
+        };
+        let dest = unique_path(&original);
+        let file_exists = path.exists();
+        if file_exists {
+            move_file(&path, &dest)?;
+        }
+        let updated = self.conn().execute(
+            "UPDATE recordings SET path = ?2, trashed_from = NULL, trashed_at = NULL WHERE id = ?1",
+            params![id, dest.to_string_lossy()],
+        );
+        if let Err(err) = updated {
+            if file_exists {
+                let _ = move_file(&dest, &path);
+            }
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    /// Removes a recording, its transcripts and its file for good. The row is
+    /// only deleted once the file is gone (or was already missing).
+    pub fn delete_recording(&self, id: RecordingId) -> Result<()> {
+        let (path, _) = self.path_of(id)?;
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM recordings WHERE id = ?1", [id])?;
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to delete {}", path.display()))
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Permanently deletes recordings trashed more than `older_than_days`
+    /// days ago, returning how many went. Zero empties the whole trash.
+    pub fn purge_trash(&self, older_than_days: u32) -> Result<usize> {
+        let cutoff = now_ms() - older_than_days as i64 * DAY_MS;
+        let ids: Vec<RecordingId> = {
+            let conn = self.conn();
+            let mut stmt = conn.prepare(
+                "SELECT id FROM recordings WHERE trashed_at IS NOT NULL AND trashed_at <= ?1",
+            )?;
+            let rows = stmt.query_map([cutoff], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        for &id in &ids {
+            self.delete_recording(id)?;
+        }
+        Ok(ids.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::{ListQuery, NewRecording};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("app-core-trash-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn add(library: &Library, path: &Path) -> RecordingId {
+        fs::write(path, b"audio").unwrap();
+        library
+            .add_recording(&NewRecording {
+                title: "take".to_string(),
+                path: path.to_path_buf(),
+                created_at: 1,
+                duration_ms: 1000,
+                sample_rate: 16000,
+                channels: 1,
+                device: None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn trash_and_restore_move_the_file() {
+        let dir = temp_dir("restore");
+        let library = Library::open_in_memory().unwrap();
+        let original = dir.join("take.wav");
+        let id = add(&library, &original);
+
+        library.trash_recording(id, &dir.join("trash")).unwrap();
+        let trashed = library.recording(id).unwrap().unwrap();
+        assert!(!original.exists());
+        assert!(trashed.path.exists());
+        assert!(trashed.trashed_at.is_some());
+        assert_eq!(
+            library
+                .list_recordings(&ListQuery::default())
+                .unwrap()
+                .total,
+            0
+        );
+
+        library.restore_recording(id).unwrap();
+        let restored = library.recording(id).unwrap().unwrap();
+        assert_eq!(restored.path, original);
+        assert!(original.exists());
+        assert_eq!(restored.trashed_at, None);
+    }
+
+    #[test]
+    fn delete_removes_row_and_file() {
+        let dir = temp_dir("delete");
+        let library = Library::open_in_memory().unwrap();
+        let path = dir.join("take.wav");
+        let id = add(&library, &path);
+        library.delete_recording(id).unwrap();
+        assert!(!path.exists());
+        assert_eq!(library.recording(id).unwrap(), None);
+        assert!(library.delete_recording(id).is_err());
+    }
+
+    #[test]
+    fn purge_only_removes_old_trash() {
+        let dir = temp_dir("purge");
+        let library = Library::open_in_memory().unwrap();
+        let kept = add(&library, &dir.join("kept.wav"));
+        let trashed = add(&library, &dir.join("trashed.wav"));
+        library
+            .trash_recording(trashed, &dir.join("trash"))
+            .unwrap();
+
+        assert_eq!(library.purge_trash(30).unwrap(), 0);
+        assert_eq!(library.purge_trash(0).unwrap(), 1);
+        assert_eq!(library.recording(trashed).unwrap(), None);
+        assert!(library.recording(kept).unwrap().is_some());
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::Result;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use super::{now_ms, Library, RecordingId};
+
+/// Where to upload recordings: any S3-compatible bucket (AWS, R2, MinIO…).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UploadSettings {
+    /// e.g. `https://s3.eu-west-1.amazonaws.com`. Empty disables uploads.
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    pub access_key: String,
+    pub secret_key: String,
+    /// Prepended to every object key, e.g. `recordings/`.
+    pub prefix: String,
+    /// Address the bucket as `endpoint/bucket` rather than `bucket.endpoint`,
+    /// which MinIO and most self-hosted servers need.
+    pub path_style: bool,
+    /// Upload each recording and its transcript once transcription finishes.
+    pub auto_upload: bool,
+}
+
+impl UploadSettings {
+    pub fn is_configured(&self) -> bool {
+        !self.endpoint.is_empty() && !self.bucket.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadKind {
+    Audio,
+    Transcript,
+}
+
+impl UploadKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            UploadKind::Audio => "audio",
+            UploadKind::Transcript => "transcript",
+        }
+    }
+}
+
+/// A file of a recording that's been uploaded.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Upload {
+    pub kind: UploadKind,
+    pub url: String,
+    pub uploaded_at: i64,
+}
+
+impl Library {
+    /// Remembers where a recording's file was uploaded, replacing the URL
+    /// of an earlier upload of the same kind.
+    pub fn save_upload(
+        &self,
+        recording_id: RecordingId,
+        kind: UploadKind,
+        url: &str,
+    ) -> Result<()> {
+        self.conn().execute(
+            "INSERT OR REPLACE INTO uploads (recording_id, kind, url, uploaded_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![recording_id, kind.as_str(), url, now_ms()],
+        )?;
+        Ok(())
+    }
+
+    pub fn uploads(&self, recording_id: RecordingId) -> Result<Vec<Upload>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT kind, url, uploaded_at FROM uploads WHERE recording_id = ?1 ORDER BY kind",
+        )?;
+        let rows = stmt.query_map([recording_id], |row| {
+            let kind: String = row.get(0)?;
+            Ok(Upload {
+                kind: if kind == "audio" {
+                    UploadKind::Audio
+                } else {
+                    UploadKind::Transcript
+                },
+                url: row.get(1)?,
+                uploaded_at: row.get(2)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::NewRecording;
+    use std::path::PathBuf;
+
+    #[test]
+    fn uploads_are_replaced_per_kind() {
+        let library = Library::open_in_memory().unwrap();
+        let id = library
+            .add_recording(&NewRecording {
+                title: "a".to_string(),
+                path: PathBuf::from("/a.wav"),
+                created_at: 0,
+                duration_ms: 0,
+                sample_rate: 16_000,
+                channels: 1,
+                device: None,
+            })
+            .unwrap();
+        library
+            .save_upload(id, UploadKind::Audio, "https://s3/a1")
+            .unwrap();
+        library
+            .save_upload(id, UploadKind::Transcript, "https://s3/t")
+            .unwrap();
+        library
+            .save_upload(id, UploadKind::Audio, "https://s3/a2")
+            .unwrap();
+
+        let uploads: Vec<_> = library
+            .uploads(id)
+            .unwrap()
+            .into_iter()
+            .map(|upload| (upload.kind, upload.url))
+            .collect();
+        assert_eq!(
+            uploads,
+            [
+                (UploadKind::Audio, "https://s3/a2".to_string()),
+                (UploadKind::Transcript, "https://s3/t".to_string())
+            ]
+        );
+    }
+}
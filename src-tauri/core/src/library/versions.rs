@@ -0,0 +1,199 @@
+use anyhow::{anyhow, bail, Result};
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+
+use super::{now_ms, Library, RecordingId};
+use crate::transcribe::Transcript;
+
+pub type TranscriptId = i64;
+
+/// One saved transcript of a recording. Every re-transcription and edit
+/// adds a version; the newest is the current transcript.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TranscriptVersion {
+    pub id: TranscriptId,
+    pub model: String,
+    /// Whether the user edited this version by hand rather than it coming
+    /// straight from the model.
+    pub edited: bool,
+    pub created_at: i64,
+    pub current: bool,
+    pub segments: usize,
+}
+
+impl Library {
+    /// Versions of a recording's transcript, newest first.
+    pub fn transcript_versions(&self, recording_id: RecordingId) -> Result<Vec<TranscriptVersion>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, model, edited, created_at, json_array_length(segments)
+             FROM transcripts WHERE recording_id = ?1 ORDER BY created_at DESC, id DESC",
+        )?;
+        let rows = stmt.query_map([recording_id], |row| {
+            Ok(TranscriptVersion {
+                id: row.get(0)?,
+                model: row.get(1)?,
+                edited: row.get(2)?,
+                created_at: row.get(3)?,
+                current: false,
+                segments: row.get(4)?,
+            })
+        })?;
+        let mut versions = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        if let Some(latest) = versions.first_mut() {
+            latest.current = true;
+        }
+        Ok(versions)
+    }
+
+    pub fn transcript_version(&self, id: TranscriptId) -> Result<Option<Transcript>> {
+        let segments: Option<String> = self
+            .conn()
+            .query_row(
+                "SELECT segments FROM transcripts WHERE id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        segments
+            .map(|json| {
+                Ok(Transcript {
+                    segments: serde_json::from_str(&json)?,
+                })
+            })
+            .transpose()
+    }
+
+    /// [`Library::transcript_version`] for callers that can't go on without
+    /// it: a missing version is an error.
+    pub fn require_transcript_version(&self, id: TranscriptId) -> Result<Transcript> {
+        self.transcript_version(id)?
+            .ok_or_else(|| anyhow!("no transcript version {}", id))
+    }
+
+    /// Saves a hand-edited transcript as the new current version, crediting
+    /// the model of the version it was edited from.
+    pub fn edit_transcript(
+        &self,
+        recording_id: RecordingId,
+        transcript: &Transcript,
+    ) -> Result<TranscriptId> {
+        let conn = self.conn();
+        let model: Option<String> = conn
+            .query_row(
+                "SELECT model FROM transcripts WHERE recording_id = ?1
+                 ORDER BY created_at DESC, id DESC LIMIT 1",
+                [recording_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(model) = model else {
+            bail!("recording {} has no transcript to edit", recording_id);
+        };
+        conn.execute(
+            "INSERT INTO transcripts (recording_id, model, segments, created_at, edited)
+             VALUES (?1, ?2, ?3, ?4, 1)",
+            params![
+                recording_id,
+                model,
+                serde_json::to_string(&transcript.segments)?,
+                now_ms()
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Makes an older version current again by saving a copy of it, so the
+    /// versions in between stay in the history.
+    pub fn restore_transcript_version(
+        &self,
+        recording_id: RecordingId,
+        id: TranscriptId,
+    ) -> Result<TranscriptId> {
+        let conn = self.conn();
+        let inserted = conn.execute(
+            "INSERT INTO transcripts (recording_id, model, segments, created_at, edited)
+             SELECT recording_id, model, segments, ?3, edited FROM transcripts
+             WHERE id = ?2 AND recording_id = ?1",
+            params![recording_id, id, now_ms()],
+        )?;
+        if inserted == 0 {
+            bail!(
+                "no transcript version {} for recording {}",
+                id,
+                recording_id
+            );
+        }
+        Ok(conn.last_insert_rowid())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::NewRecording;
+    use crate::transcribe::Segment;
+    use std::path::PathBuf;
+
+    fn transcript(text: &str) -> Transcript {
+        Transcript {
+            segments: vec![Segment {
+                start: 0,
+                end: 100,
+                text: text.to_string(),
+                speaker_turn_next: false,
+            }],
+        }
+    }
+
+    #[test]
+    fn edits_and_restores_keep_history() {
+        let library = Library::open_in_memory().unwrap();
+        let id = library
+            .add_recording(&NewRecording {
+                title: "a".to_string(),
+                path: PathBuf::from("/a.wav"),
+                created_at: 1,
+                duration_ms: 1000,
+                sample_rate: 16000,
+                channels: 1,
+                device: None,
+            })
+            .unwrap();
+        assert!(library.edit_transcript(id, &transcript(" x")).is_err());
+
+        let original = library
+            .save_transcript(id, "small.en", &transcript(" teh plan"))
+            .unwrap();
+        library
+            .edit_transcript(id, &transcript(" the plan"))
+            .unwrap();
+        assert_eq!(
+            library.transcript(id).unwrap(),
+            Some(transcript(" the plan"))
+        );
+
+        library.restore_transcript_version(id, original).unwrap();
+        assert_eq!(
+            library.transcript(id).unwrap(),
+            Some(transcript(" teh plan"))
+        );
+        assert_eq!(library.search_transcripts("teh", None).unwrap().len(), 1);
+
+        let versions = library.transcript_versions(id).unwrap();
+        assert_eq!(
+            versions
+                .iter()
+                .map(|v| (v.model.as_str(), v.edited, v.current, v.segments))
+                .collect::<Vec<_>>(),
+            vec![
+                ("small.en", false, true, 1),
+                ("small.en", true, false, 1),
+                ("small.en", false, false, 1),
+            ]
+        );
+        assert!(library
+            .restore_transcript_version(id + 1, original)
+            .is_err());
+    }
+}
@@ -0,0 +1,83 @@
+//! Text completion with a local llama.cpp model, used for summaries and
+//! translation. Inference needs the `llm` feature; without it every call
+//! fails with an explanation.
+
+use anyhow::Result;
+use std::path::Path;
+
+/// Greedily completes `prompt` with the GGUF model at `model_path`, stopping
+/// at end of text.
+pub fn complete(model_path: &Path, prompt: &str) -> Result<String> {
+    llama::complete(model_path, prompt)
+}
+
+#[cfg(feature = "llm")]
+mod llama {
+    use anyhow::{Context, Result};
+    use llama_cpp_2::context::params::LlamaContextParams;
+    use llama_cpp_2::llama_backend::LlamaBackend;
+    use llama_cpp_2::llama_batch::LlamaBatch;
+    use llama_cpp_2::model::params::LlamaModelParams;
+    use llama_cpp_2::model::{AddBos, LlamaModel, Special};
+    use llama_cpp_2::token::data_array::LlamaTokenDataArray;
+    use std::num::NonZeroU32;
+    use std::path::Path;
+    use std::sync::OnceLock;
+
+    const CONTEXT_TOKENS: u32 = 8192;
+    const MAX_ANSWER_TOKENS: i32 = 1024;
+
+    /// llama.cpp's backend may only be initialized once per process.
+    fn backend() -> Result<&'static LlamaBackend> {
+        static BACKEND: OnceLock<LlamaBackend> = OnceLock::new();
+        if let Some(backend) = BACKEND.get() {
+            return Ok(backend);
+        }
+        let backend = LlamaBackend::init().context("failed to start llama.cpp")?;
+        Ok(BACKEND.get_or_init(|| backend))
+    }
+
+    pub fn complete(model_path: &Path, prompt: &str) -> Result<String> {
+        let backend = backend()?;
+        let model = LlamaModel::load_from_file(backend, model_path, &LlamaModelParams::default())
+            .with_context(|| format!("failed to load {}", model_path.display()))?;
+        let params = LlamaContextParams::default().with_n_ctx(NonZeroU32::new(CONTEXT_TOKENS));
+        let mut ctx = model.new_context(backend, params)?;
+
+        let tokens = model.str_to_token(prompt, AddBos::Always)?;
+        let mut batch = LlamaBatch::new(CONTEXT_TOKENS as usize, 1);
+        let last = tokens.len() as i32 - 1;
+        for (i, token) in (0_i32..).zip(tokens) {
+            batch.add(token, i, &[0], i == last)?;
+        }
+        ctx.decode(&mut batch)?;
+
+        let mut position = batch.n_tokens();
+        let limit = (position + MAX_ANSWER_TOKENS).min(CONTEXT_TOKENS as i32);
+        let mut output = String::new();
+        while position < limit {
+            let candidates =
+                LlamaTokenDataArray::from_iter(ctx.candidates_ith(batch.n_tokens() - 1), false);
+            let token = ctx.sample_token_greedy(candidates);
+            if token == model.token_eos() {
+                break;
+            }
+            output.push_str(&model.token_to_str(token, Special::Tokenize)?);
+            batch.clear();
+            batch.add(token, position, &[0], true)?;
+            position += 1;
+            ctx.decode(&mut batch)?;
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(not(feature = "llm"))]
+mod llama {
+    use anyhow::{bail, Result};
+    use std::path::Path;
+
+    pub fn complete(_model_path: &Path, _prompt: &str) -> Result<String> {
+        bail!("this build doesn't include a local LLM; rebuild with the `llm` feature")
+    }
+}
@@ -0,0 +1,247 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::audio::devices::DeviceProfile;
+use crate::audio::ResampleQuality;
+use crate::i18n::Language;
+use crate::jobs::{Limits, RetryPolicy};
+use crate::library::{RetentionPolicy, UploadSettings};
+use crate::transcribe::merge::MergeSettings;
+use crate::transcribe::registry::DEFAULT_MODEL;
+use crate::transcribe::translate::TranslationSettings;
+
+/// User preferences persisted as JSON in the app config directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Accelerator for the system-wide start/stop recording shortcut, e.g.
+    /// `CmdOrCtrl+Shift+R`. Empty disables it.
+    pub toggle_recording_shortcut: String,
+    /// Start hidden in the tray when the user logs in.
+    pub launch_at_login: bool,
+    /// How many transcriptions, downloads and exports may run at once.
+    pub max_parallel_jobs: usize,
+    /// Language for notifications, menus and errors from the Rust side.
+    pub language: Language,
+    /// Trashed recordings are deleted for good after this many days.
+    pub trash_retention_days: u32,
+    /// Queue a transcription of every recording as soon as it stops.
+    pub auto_transcribe: bool,
+    /// When to delete old audio to save disk space. Off by default.
+    pub retention: RetentionPolicy,
+    /// Serve the local HTTP API on 127.0.0.1.
+    pub http_api_enabled: bool,
+    pub http_api_port: u16,
+    /// Bearer token HTTP API clients must send. Generated on first launch.
+    pub http_api_token: String,
+    /// Stream transcript segments over a WebSocket at `/live` on the HTTP API.
+    pub http_api_live_captions: bool,
+    /// Finished transcripts are POSTed here as JSON. Empty disables it.
+    pub webhook_url: String,
+    /// Signs webhook bodies with HMAC-SHA256 when set.
+    pub webhook_secret: String,
+    /// GGUF model used to summarize and, with the local provider, translate
+    /// transcripts.
+    pub summary_model_path: String,
+    /// Which service translates transcripts.
+    pub translation: TranslationSettings,
+    /// Title new recordings after the calendar event happening when they start.
+    pub calendar_titles: bool,
+    /// `.ics` file or URL to read events from. Empty uses the system calendar
+    /// on macOS.
+    pub calendar_ics: String,
+    /// Obsidian vault or other notes folder transcripts are exported to.
+    pub notes_folder: String,
+    /// S3-compatible bucket recordings are uploaded to.
+    pub upload: UploadSettings,
+    /// Normalize each new recording to this integrated loudness in LUFS
+    /// (e.g. -16) so levels match across microphones. `None` leaves it as is.
+    pub recording_loudness_lufs: Option<f64>,
+    /// High-pass new recordings at this frequency while capturing, 80–120 Hz.
+    pub recording_high_pass_hz: Option<f32>,
+    /// Microphones to record from, most preferred first. The first one that's
+    /// plugged in is used; empty, or none available, uses the system default.
+    pub preferred_input_devices: Vec<String>,
+    /// Gain, channels, sample rate and denoising remembered per input device
+    /// name, so switching microphones brings their settings back.
+    pub device_profiles: BTreeMap<String, DeviceProfile>,
+    /// Warn with `recording://no-signal` when the microphone has been
+    /// near-silent this many seconds into a take, e.g. because it's muted.
+    /// Zero disables the warning.
+    pub no_signal_warning_secs: u64,
+    /// Start a new take when the system wakes from sleep in the middle of
+    /// one, instead of just notifying that it stopped.
+    pub resume_recording_after_sleep: bool,
+    /// Listen for `wake_phrase` while not recording and start a take when
+    /// it's heard.
+    pub wake_word_enabled: bool,
+    pub wake_phrase: String,
+    /// Small whisper model, e.g. `ggml-tiny.en.bin`, that listens for the
+    /// wake phrase.
+    pub wake_word_model_path: String,
+    /// Frames per input buffer while recording. Raise it if a USB interface
+    /// crackles; `None` leaves it to the driver.
+    pub recording_buffer_frames: Option<u32>,
+    /// Windows only: record in WASAPI exclusive mode, for interfaces that
+    /// misbehave in shared mode. Other apps can't use the microphone while
+    /// it's recording, and devices that refuse fall back to shared mode.
+    pub recording_exclusive_mode: bool,
+    /// How carefully audio is resampled to 16 kHz for transcription.
+    pub resample_quality: ResampleQuality,
+    /// Name of the device recordings play back on. Empty follows the system
+    /// default output.
+    pub playback_device: String,
+    /// How often playback reports its position while playing, in ms.
+    pub playback_position_interval_ms: u64,
+    /// Load the whisper model in the background at launch, so the first
+    /// transcription doesn't wait for it.
+    pub warm_up_model: bool,
+    /// Join short segments into sentences before transcripts are returned or
+    /// exported.
+    pub segment_merging: MergeSettings,
+    /// Registry id of the whisper model to transcribe with.
+    pub transcription_model: String,
+    /// Run whisper's encoder with OpenVINO, in builds with the `openvino`
+    /// feature, for models that have an OpenVINO encoder next to them.
+    pub openvino: bool,
+    /// How many transcriptions can run whisper at once, e.g. live captions
+    /// next to a batch job. Each needs its own working memory, so it's also
+    /// capped by what fits in half the machine's memory.
+    pub whisper_states: usize,
+    /// Caps on background jobs like imports, so a batch leaves the machine
+    /// usable. Jobs someone's waiting on aren't capped.
+    pub background_job_limits: Limits,
+    /// Retries for jobs that fail in a way that might pass, like a locked
+    /// model file.
+    pub retry: RetryPolicy,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            toggle_recording_shortcut: "CmdOrCtrl+Shift+R".to_string(),
+            launch_at_login: false,
+            max_parallel_jobs: 2,
+            language: Language::default(),
+            trash_retention_days: 30,
+            auto_transcribe: false,
+            retention: RetentionPolicy::default(),
+            http_api_enabled: false,
+            http_api_port: 7654,
+            http_api_token: String::new(),
+            http_api_live_captions: false,
+            webhook_url: String::new(),
+            webhook_secret: String::new(),
+            summary_model_path: String::new(),
+            translation: TranslationSettings::default(),
+            calendar_titles: false,
+            calendar_ics: String::new(),
+            notes_folder: String::new(),
+            upload: UploadSettings::default(),
+            recording_loudness_lufs: None,
+            recording_high_pass_hz: None,
+            preferred_input_devices: Vec::new(),
+            device_profiles: BTreeMap::new(),
+            no_signal_warning_secs: 10,
+            resume_recording_after_sleep: false,
+            wake_word_enabled: false,
+            wake_phrase: "start recording".to_string(),
+            wake_word_model_path: String::new(),
+            recording_buffer_frames: None,
+            recording_exclusive_mode: false,
+            resample_quality: ResampleQuality::default(),
+            playback_device: String::new(),
+            playback_position_interval_ms: 250,
+            warm_up_model: false,
+            segment_merging: MergeSettings::default(),
+            transcription_model: DEFAULT_MODEL.to_string(),
+            openvino: false,
+            whisper_states: 2,
+            background_job_limits: Limits {
+                threads: Some(2),
+                cpu_only: false,
+            },
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+pub struct SettingsStore {
+    path: PathBuf,
+    settings: Mutex<Settings>,
+}
+
+impl SettingsStore {
+    /// Loads settings from `path`, falling back to defaults if it doesn't exist yet.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let settings = if path.exists() {
+            let json = fs::read_to_string(&path).context("failed to read settings")?;
+            serde_json::from_str(&json).context("failed to parse settings")?
+        } else {
+            Settings::default()
+        };
+        Ok(SettingsStore {
+            path,
+            settings: Mutex::new(settings),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self) -> Settings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    /// Replaces and persists the settings, returning the previous value.
+    pub fn set(&self, settings: Settings) -> Result<Settings> {
+        let mut current = self.settings.lock().unwrap();
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).context("failed to create settings directory")?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&settings)?)
+            .context("failed to write settings")?;
+        Ok(std::mem::replace(&mut *current, settings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("app-core-settings-{}", std::process::id()));
+        dir.join(name)
+    }
+
+    #[test]
+    fn missing_file_uses_defaults() {
+        let store = SettingsStore::load(temp_path("missing.json")).unwrap();
+        assert_eq!(store.get(), Settings::default());
+    }
+
+    #[test]
+    fn set_persists_and_returns_previous() {
+        let path = temp_path("roundtrip.json");
+        let store = SettingsStore::load(&path).unwrap();
+        let updated = Settings {
+            toggle_recording_shortcut: "Alt+R".to_string(),
+            ..Settings::default()
+        };
+        assert_eq!(store.set(updated.clone()).unwrap(), Settings::default());
+        assert_eq!(SettingsStore::load(&path).unwrap().get(), updated);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn unknown_and_missing_fields_are_tolerated() {
+        let settings: Settings = serde_json::from_str(r#"{"something_else": 1}"#).unwrap();
+        assert_eq!(settings, Settings::default());
+    }
+}
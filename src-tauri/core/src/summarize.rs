@@ -0,0 +1,152 @@
+//! Local summaries of transcripts with a llama.cpp model.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::llm;
+use crate::transcribe::Transcript;
+
+/// Longer transcripts are cut so the prompt fits the model's context.
+const MAX_TRANSCRIPT_BYTES: usize = 24_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryStyle {
+    /// A handful of bullets with the gist.
+    Brief,
+    /// Bullets covering every topic discussed.
+    Detailed,
+}
+
+impl SummaryStyle {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SummaryStyle::Brief => "brief",
+            SummaryStyle::Detailed => "detailed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub bullets: Vec<String>,
+    pub action_items: Vec<String>,
+}
+
+/// The instruction the model completes; its answer is read by [`parse`].
+pub fn prompt(transcript: &Transcript, style: SummaryStyle) -> String {
+    let length = match style {
+        SummaryStyle::Brief => "3 to 5 short bullet points",
+        SummaryStyle::Detailed => "up to 12 bullet points covering every topic",
+    };
+    let mut text = transcript.turns().join("\n");
+    if text.len() > MAX_TRANSCRIPT_BYTES {
+        let mut end = MAX_TRANSCRIPT_BYTES;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+    format!(
+        "Summarize the transcript below in {length}. Then list any action items \
+         (tasks someone agreed to do). Answer in exactly this format:\n\n\
+         Summary:\n- ...\n\nAction items:\n- ...\n\n\
+         Write \"- None\" under Action items if there are none.\n\n\
+         Transcript:\n{text}\n\nSummary:\n"
+    )
+}
+
+/// Reads the bullets under the `Summary:` and `Action items:` headings. The
+/// prompt already opens the summary section, so bullets before any heading
+/// count as summary.
+pub fn parse(output: &str) -> Summary {
+    let mut summary = Summary::default();
+    let mut in_action_items = false;
+    for line in output.lines().map(str::trim) {
+        let heading = line.trim_end_matches(':').to_ascii_lowercase();
+        if heading == "summary" {
+            in_action_items = false;
+            continue;
+        }
+        if heading == "action items" {
+            in_action_items = true;
+            continue;
+        }
+        let Some(item) = ["- ", "* ", "• "]
+            .iter()
+            .find_map(|bullet| line.strip_prefix(bullet))
+            .map(str::trim)
+        else {
+            continue;
+        };
+        if item.is_empty() || item.eq_ignore_ascii_case("none") {
+            continue;
+        }
+        if in_action_items {
+            summary.action_items.push(item.to_string());
+        } else {
+            summary.bullets.push(item.to_string());
+        }
+    }
+    summary
+}
+
+/// Summarizes `transcript` with the GGUF model at `model`.
+pub fn summarize(transcript: &Transcript, style: SummaryStyle, model: &Path) -> Result<Summary> {
+    let output = llm::complete(model, &prompt(transcript, style))?;
+    Ok(parse(&output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcribe::Segment;
+
+    #[test]
+    fn parses_both_sections() {
+        let output = "- Budget is approved.\n- Launch moves to May.\n\n\
+                      Action items:\n- Ana sends the contract\n* Bo books the venue\n";
+        assert_eq!(
+            parse(output),
+            Summary {
+                bullets: vec![
+                    "Budget is approved.".to_string(),
+                    "Launch moves to May.".to_string()
+                ],
+                action_items: vec![
+                    "Ana sends the contract".to_string(),
+                    "Bo books the venue".to_string()
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn none_and_chatter_are_ignored() {
+        let output = "Sure! Here it is.\nSummary:\n- Small talk.\nAction Items:\n- None\n";
+        assert_eq!(
+            parse(output),
+            Summary {
+                bullets: vec!["Small talk.".to_string()],
+                action_items: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn prompt_includes_transcript_and_caps_length() {
+        let transcript = Transcript {
+            segments: vec![Segment {
+                start: 0,
+                end: 100,
+                text: "é".repeat(MAX_TRANSCRIPT_BYTES),
+                speaker_turn_next: false,
+            }],
+        };
+        let prompt = prompt(&transcript, SummaryStyle::Brief);
+        assert!(prompt.contains("3 to 5"));
+        assert!(prompt.ends_with("Summary:\n"));
+        assert!(prompt.len() < MAX_TRANSCRIPT_BYTES + 1000);
+    }
+}
@@ -0,0 +1,304 @@
+use serde::{Deserialize, Serialize};
+
+use super::entities::{find_entities, EntityKind};
+use super::Transcript;
+
+/// Something someone in the meeting agreed to do.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionItem {
+    pub text: String,
+    /// Who it was handed to by name. `None` when whoever said it took it on
+    /// themselves, or it's on the group.
+    pub owner: Option<String>,
+    /// When it's due, as said: "Friday", "next week".
+    pub due: Option<String>,
+    /// Index of the segment it was said in.
+    pub segment: usize,
+    /// Start time of that segment in centiseconds, like [`Segment::start`].
+    ///
+    /// [`Segment::start`]: super::Segment::start
+    pub start: i64,
+}
+
+/// Spelled-out markers, mostly from people dictating notes.
+const MARKERS: &[&str] = &[
+    "action item:",
+    "action item is to ",
+    "todo:",
+    "to-do:",
+    "to do:",
+];
+
+/// Someone taking a task on themselves.
+const SELF_CUES: &[&str] = &[
+    "i'll ",
+    "i will ",
+    "i'm going to ",
+    "i am going to ",
+    "i need to ",
+    "let me ",
+];
+
+/// A task for the group.
+const GROUP_CUES: &[&str] = &[
+    "we need to ",
+    "we should ",
+    "we have to ",
+    "we'll ",
+    "we will ",
+    "we're going to ",
+    "someone needs to ",
+    "someone should ",
+    "don't forget to ",
+    "remember to ",
+];
+
+/// Following a name: "Sarah will ...".
+const ASSIGN_CUES: &[&str] = &[" will ", " is going to ", " needs to ", " should "];
+
+/// Following a name: "Tom, can you ...?"
+const REQUEST_CUES: &[&str] = &[", can you ", ", could you ", ", would you ", ", please "];
+
+/// Capitalized words that come before "will" without being anyone's name.
+const NOT_OWNERS: &[&str] = &[
+    "i",
+    "i'm",
+    "i'll",
+    "i've",
+    "i'd",
+    "it",
+    "it's",
+    "this",
+    "that",
+    "there",
+    "these",
+    "those",
+    "he",
+    "she",
+    "they",
+    "we",
+    "you",
+    "everyone",
+    "everybody",
+    "someone",
+    "somebody",
+    "nobody",
+    "anyone",
+    "which",
+    "what",
+    "who",
+    "okay",
+    "ok",
+    "so",
+    "and",
+    "but",
+    "then",
+    "well",
+    "yes",
+    "yeah",
+    "no",
+    "oh",
+    "the",
+    "a",
+    "an",
+];
+
+/// Verbs that follow "I'll" or "let me" in conversation, not in commitments.
+const NOT_TASKS: &[&str] = &["be", "know", "think", "see", "say", "admit", "guess"];
+
+/// Splits after sentence punctuation, keeping it so questions can be told
+/// apart.
+fn sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut from = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let at_break = !matches!(chars.peek(), Some((_, next)) if !next.is_whitespace());
+        if matches!(c, '.' | '!' | '?') && at_break {
+            sentences.push(text[from..i + 1].trim());
+            from = i + 1;
+        }
+    }
+    sentences.push(text[from..].trim());
+    sentences.retain(|sentence| !sentence.is_empty());
+    sentences
+}
+
+/// Where `cue` starts a word in `lower`, and where the text after it starts.
+fn find_cue(lower: &str, cue: &str) -> Option<usize> {
+    lower
+        .match_indices(cue)
+        .find(|(at, _)| *at == 0 || lower[..*at].ends_with([' ', ',']))
+        .map(|(at, _)| at + cue.len())
+}
+
+/// A named owner and where their task starts, from "Sarah Chen will ..." or
+/// "Tom, can you ...".
+fn assigned(text: &str, lower: &str, question: bool) -> Option<(String, usize)> {
+    let words: Vec<(usize, &str)> = text
+        .split(' ')
+        .scan(0, |at, word| {
+            let start = *at;
+            *at += word.len() + 1;
+            Some((start, word))
+        })
+        .filter(|(_, word)| !word.is_empty())
+        .collect();
+    let is_name = |word: &str| {
+        let word = word.trim_end_matches(',');
+        word.chars().next().is_some_and(char::is_uppercase)
+            && word
+                .chars()
+                .all(|c| c.is_alphabetic() || c == '-' || c == '\'')
+            && !NOT_OWNERS.contains(&word.to_ascii_lowercase().as_str())
+    };
+    let mut i = 0;
+    while i < words.len() {
+        if !is_name(words[i].1) {
+            i += 1;
+            continue;
+        }
+        let mut end = i + 1;
+        while end < words.len() && !words[end - 1].1.ends_with(',') && is_name(words[end].1) {
+            end += 1;
+        }
+        let (last_start, last) = words[end - 1];
+        let name_end = last_start + last.trim_end_matches(',').len();
+        let rest = &lower[name_end..];
+        let mut cues = REQUEST_CUES
+            .iter()
+            .chain(ASSIGN_CUES.iter().filter(|_| !question));
+        if let Some(cue) = cues.find(|cue| rest.starts_with(*cue)) {
+            let name = text[words[i].0..name_end].to_string();
+            return Some((name, name_end + cue.len()));
+        }
+        i = end;
+    }
+    None
+}
+
+/// The task and its owner in one sentence, if it hands one out.
+fn action_item(sentence: &str) -> Option<(Option<String>, String)> {
+    let text = sentence.replace('\u{2019}', "'");
+    let lower = text.to_ascii_lowercase();
+    let question = text.ends_with('?');
+    let (owner, from) = match assigned(&text, &lower, question) {
+        Some((owner, from)) => (Some(owner), from),
+        None if question => return None,
+        None => {
+            let from = MARKERS
+                .iter()
+                .chain(SELF_CUES)
+                .chain(GROUP_CUES)
+                .find_map(|cue| find_cue(&lower, cue))?;
+            (None, from)
+        }
+    };
+    let task = text[from..]
+        .trim()
+        .trim_end_matches(['.', '!', '?', ','])
+        .trim_end();
+    let first = task.split(' ').next()?.to_ascii_lowercase();
+    if task.split(' ').count() < 2 || NOT_TASKS.contains(&first.as_str()) {
+        return None;
+    }
+    let mut chars = task.chars();
+    let task = chars.next()?.to_uppercase().chain(chars).collect();
+    Some((owner, task))
+}
+
+/// Finds action items in a meeting transcript by the way people hand out
+/// and take on work: "I'll ...", "we need to ...", "Sarah will ...",
+/// "Tom, can you ...?" and spelled-out "action item:" markers. Due dates are
+/// picked out of the task itself.
+pub fn find_action_items(transcript: &Transcript) -> Vec<ActionItem> {
+    let mut items = Vec::new();
+    for (index, segment) in transcript.segments.iter().enumerate() {
+        for sentence in sentences(&segment.text) {
+            let Some((owner, text)) = action_item(sentence) else {
+                continue;
+            };
+            let due = find_entities(&text, index)
+                .into_iter()
+                .find(|entity| entity.kind == EntityKind::Date)
+                .map(|entity| entity.text);
+            items.push(ActionItem {
+                text,
+                owner,
+                due,
+                segment: index,
+                start: segment.start,
+            });
+        }
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcribe::Segment;
+
+    fn transcript(texts: &[&str]) -> Transcript {
+        Transcript {
+            segments: texts
+                .iter()
+                .enumerate()
+                .map(|(i, text)| Segment {
+                    start: i as i64 * 500,
+                    end: (i as i64 + 1) * 500,
+                    text: text.to_string(),
+                    speaker_turn_next: false,
+                })
+                .collect(),
+        }
+    }
+
+    fn item(text: &str, owner: Option<&str>, due: Option<&str>, segment: usize) -> ActionItem {
+        ActionItem {
+            text: text.to_string(),
+            owner: owner.map(str::to_string),
+            due: due.map(str::to_string),
+            segment,
+            start: segment as i64 * 500,
+        }
+    }
+
+    #[test]
+    fn finds_tasks_owners_and_due_dates() {
+        let transcript = transcript(&[
+            " Okay, I\u{2019}ll send the slides to everyone by Friday.",
+            " Sarah Chen will update the roadmap. Great.",
+            " Tom, can you book the room for next week?",
+            " Action item: fix the login bug.",
+        ]);
+        assert_eq!(
+            find_action_items(&transcript),
+            vec![
+                item(
+                    "Send the slides to everyone by Friday",
+                    None,
+                    Some("Friday"),
+                    0
+                ),
+                item("Update the roadmap", Some("Sarah Chen"), None, 1),
+                item(
+                    "Book the room for next week",
+                    Some("Tom"),
+                    Some("next week"),
+                    2
+                ),
+                item("Fix the login bug", None, None, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_questions_and_figures_of_speech() {
+        let transcript = transcript(&[
+            " Should I send it? I'll be honest, it went well.",
+            " It will rain tomorrow. Let me think.",
+        ]);
+        assert!(find_action_items(&transcript).is_empty());
+    }
+}
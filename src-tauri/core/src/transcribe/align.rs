@@ -0,0 +1,296 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use whisper_rs::{FullParams, SamplingStrategy};
+
+use super::{model, WHISPER_SAMPLE_RATE, WINDOW_SAMPLES};
+use crate::audio::decode::MonoStream;
+use crate::audio::ResampleQuality;
+use crate::i18n::{t, Msg};
+
+/// When a word of a script is spoken.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WordTiming {
+    pub word: String,
+    /// Start time in centiseconds, like [`Segment::start`].
+    ///
+    /// [`Segment::start`]: super::Segment::start
+    pub start: i64,
+    /// End time in centiseconds.
+    pub end: i64,
+    /// Whether whisper heard this very word. Otherwise its timing is that of
+    /// whatever whisper heard in its place, or spread over the gap between
+    /// its neighbors if it heard nothing.
+    pub matched: bool,
+}
+
+/// How far, in words, the alignment may stray from an even pace through the
+/// script. Keeps memory linear in the script's length.
+const BAND: usize = 500;
+
+/// Lines a script up with its recording: whisper transcribes the audio one
+/// word at a time with timestamps, and the script's words are matched to
+/// what it heard, edit-distance style, so each one gets a start and end even
+/// where whisper misheard or missed it.
+pub fn align_text(
+    audio_path: &Path,
+    model_path: &Path,
+    quality: ResampleQuality,
+    script: &str,
+) -> Result<Vec<WordTiming>> {
+    let heard = heard_words(audio_path, model_path, quality)?;
+    Ok(align_words(script, &heard))
+}
+
+/// Every word whisper hears in the file, with its timestamps.
+fn heard_words(
+    audio_path: &Path,
+    model_path: &Path,
+    quality: ResampleQuality,
+) -> Result<Vec<WordTiming>> {
+    if !audio_path.exists() {
+        bail!("{}", t(Msg::AudioFileMissing));
+    }
+    if !model_path.exists() {
+        bail!("{}", t(Msg::ModelFileMissing));
+    }
+    let model = model::load(model_path)?;
+    let mut stream = MonoStream::open(audio_path, WHISPER_SAMPLE_RATE, quality)?;
+    let mut words = Vec::new();
+    let mut offset = 0;
+    loop {
+        let samples = stream.read(WINDOW_SAMPLES)?;
+        if samples.is_empty() {
+            break;
+        }
+        let mut state = model.state()?;
+        let mut params = FullParams::new(SamplingStrategy::default());
+        // One word per segment, timed by its tokens.
+        params.set_token_timestamps(true);
+        params.set_max_len(1);
+        params.set_split_on_word(true);
+        state
+            .full(params, &samples)
+            .context("failed to transcribe audio")?;
+        for i in 0..state.full_n_segments().context("failed to get segments")? {
+            let word = state
+                .full_get_segment_text(i)
+                .context("failed to get segment")?;
+            if word.trim().is_empty() {
+                continue;
+            }
+            words.push(WordTiming {
+                word: word.trim().to_string(),
+                start: state
+                    .full_get_segment_t0(i)
+                    .context("failed to get start")?
+                    + offset,
+                end: state.full_get_segment_t1(i).context("failed to get end")? + offset,
+                matched: true,
+            });
+        }
+        offset += samples.len() as i64 * 100 / WHISPER_SAMPLE_RATE as i64;
+    }
+    Ok(words)
+}
+
+fn normalize(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// How a cell of the alignment was reached.
+#[derive(Clone, Copy)]
+enum Step {
+    /// Script word paired with a heard word.
+    Pair,
+    /// Script word whisper didn't hear.
+    Skip,
+    /// Heard word that isn't in the script.
+    Extra,
+}
+
+/// For each script word, the heard word paired with it, by least edit
+/// distance within a band around the diagonal.
+fn pairs(script: &[String], heard: &[String]) -> Vec<Option<usize>> {
+    let (n, m) = (script.len(), heard.len());
+    let band = BAND + m.div_ceil(n.max(1));
+    let range = |i: usize| {
+        let center = i * m / n.max(1);
+        (center.saturating_sub(band), (center + band).min(m))
+    };
+    let mut prev = vec![u32::MAX; m + 1];
+    let mut row = vec![u32::MAX; m + 1];
+    let mut steps: Vec<(usize, Vec<Step>)> = Vec::with_capacity(n + 1);
+    for i in 0..=n {
+        let (lo, hi) = range(i);
+        // `row` still holds the row before last; only its band was touched.
+        if i >= 2 {
+            let (old_lo, old_hi) = range(i - 2);
+            row[old_lo..=old_hi].fill(u32::MAX);
+        }
+        let mut row_steps = Vec::with_capacity(hi - lo + 1);
+        for j in lo..=hi {
+            let mut best = (u32::MAX, Step::Pair);
+            if i == 0 && j == 0 {
+                best.0 = 0;
+            }
+            if i > 0 && j > 0 && prev[j - 1] != u32::MAX {
+                let cost = prev[j - 1] + u32::from(script[i - 1] != heard[j - 1]);
+                best = cheaper(best, cost, Step::Pair);
+            }
+            if i > 0 && prev[j] != u32::MAX {
+                best = cheaper(best, prev[j] + 1, Step::Skip);
+            }
+            if j > lo && row[j - 1] != u32::MAX {
+                best = cheaper(best, row[j - 1] + 1, Step::Extra);
+            }
+            row[j] = best.0;
+            row_steps.push(best.1);
+        }
+        steps.push((lo, row_steps));
+        std::mem::swap(&mut prev, &mut row);
+    }
+
+    let mut pairs = vec![None; n];
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        let (lo, row_steps) = &steps[i];
+        match row_steps[j - lo] {
+            Step::Pair => {
+                pairs[i - 1] = Some(j - 1);
+                i -= 1;
+                j -= 1;
+            }
+            Step::Skip => i -= 1,
+            Step::Extra => j -= 1,
+        }
+    }
+    pairs
+}
+
+fn cheaper(best: (u32, Step), cost: u32, step: Step) -> (u32, Step) {
+    if cost < best.0 {
+        (cost, step)
+    } else {
+        best
+    }
+}
+
+/// Gives each word of `script` the timing of the heard word it lines up
+/// with. Words with nothing heard for them share the gap between their
+/// neighbors evenly.
+pub fn align_words(script: &str, heard: &[WordTiming]) -> Vec<WordTiming> {
+    let words: Vec<&str> = script.split_whitespace().collect();
+    let normalized: Vec<String> = words.iter().map(|word| normalize(word)).collect();
+    let heard_normalized: Vec<String> = heard.iter().map(|word| normalize(&word.word)).collect();
+    let pairs = pairs(&normalized, &heard_normalized);
+
+    let mut timings: Vec<WordTiming> = words
+        .iter()
+        .zip(&pairs)
+        .enumerate()
+        .map(|(i, (word, pair))| {
+            let (start, end, matched) = match pair {
+                Some(j) => (
+                    heard[*j].start,
+                    heard[*j].end,
+                    normalized[i] == heard_normalized[*j],
+                ),
+                None => (0, 0, false),
+            };
+            WordTiming {
+                word: word.to_string(),
+                start,
+                end,
+                matched,
+            }
+        })
+        .collect();
+
+    let mut i = 0;
+    while i < timings.len() {
+        if pairs[i].is_some() {
+            i += 1;
+            continue;
+        }
+        let run_end = (i..timings.len())
+            .find(|&k| pairs[k].is_some())
+            .unwrap_or(timings.len());
+        let from = if i > 0 { timings[i - 1].end } else { 0 };
+        let to = match timings.get(run_end) {
+            Some(next) => next.start,
+            None => heard.last().map_or(from, |last| last.end.max(from)),
+        };
+        let count = (run_end - i) as i64;
+        for (k, timing) in timings[i..run_end].iter_mut().enumerate() {
+            let k = k as i64;
+            timing.start = from + (to - from) * k / count;
+            timing.end = from + (to - from) * (k + 1) / count;
+        }
+        i = run_end;
+    }
+    timings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heard(words: &[(&str, i64)]) -> Vec<WordTiming> {
+        words
+            .iter()
+            .map(|&(word, start)| WordTiming {
+                word: word.to_string(),
+                start,
+                end: start + 10,
+                matched: true,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn misheard_and_missed_words_still_get_timings() {
+        let heard = heard(&[
+            ("The", 0),
+            ("quick", 10),
+            ("brown", 20),
+            ("box", 30),
+            ("jumps", 40),
+            ("the", 70),
+            ("lazy", 80),
+            ("dog", 90),
+        ]);
+        let aligned = align_words("The quick brown fox jumps over the lazy dog.", &heard);
+        let timings: Vec<(&str, i64, i64, bool)> = aligned
+            .iter()
+            .map(|w| (w.word.as_str(), w.start, w.end, w.matched))
+            .collect();
+        assert_eq!(
+            timings,
+            vec![
+                ("The", 0, 10, true),
+                ("quick", 10, 20, true),
+                ("brown", 20, 30, true),
+                ("fox", 30, 40, false),
+                ("jumps", 40, 50, true),
+                ("over", 50, 70, false),
+                ("the", 70, 80, true),
+                ("lazy", 80, 90, true),
+                ("dog.", 90, 100, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn nothing_heard_spreads_words_over_nothing() {
+        let aligned = align_words("Hello world", &[]);
+        assert_eq!(aligned.len(), 2);
+        assert!(aligned
+            .iter()
+            .all(|w| w.start == 0 && w.end == 0 && !w.matched));
+        assert!(align_words("", &heard(&[("hi", 0)])).is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+//! Timing a model on a recording, to see what a faster model or the Core ML
+//! encoder buys.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use super::{model, transcribe_file};
+use crate::audio::{wav, ResampleQuality};
+use crate::jobs::Limits;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Benchmark {
+    pub model: PathBuf,
+    /// Whether the encoder ran with Core ML.
+    pub coreml: bool,
+    /// Whether the encoder ran with OpenVINO.
+    pub openvino: bool,
+    pub audio_ms: u64,
+    pub elapsed_ms: u64,
+    /// Seconds of audio transcribed per second, so 10 is ten times faster
+    /// than real time.
+    pub realtime_factor: f64,
+    /// How many times faster than the same model without Core ML, once both
+    /// have been benchmarked since the app started.
+    pub coreml_speedup: Option<f64>,
+}
+
+/// The best real-time factor of each model on the plain CPU encoder.
+static CPU_BASELINES: Mutex<Option<HashMap<PathBuf, f64>>> = Mutex::new(None);
+
+/// Transcribes the WAV at `audio` with the model at `model` and reports how
+/// fast it went. The model is loaded first so that isn't counted. Blocks.
+pub fn run(audio: &Path, model: &Path, quality: ResampleQuality) -> Result<Benchmark> {
+    let loaded = model::load(model)?;
+    let audio_ms = wav::info(audio)?.duration_ms;
+    let started = Instant::now();
+    transcribe_file(
+        audio,
+        model,
+        quality,
+        Limits::default(),
+        |_| {},
+        |_| {},
+        || false,
+    )?;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    Ok(compare(Benchmark {
+        model: model.to_path_buf(),
+        coreml: loaded.coreml(),
+        openvino: loaded.openvino(),
+        audio_ms,
+        elapsed_ms,
+        realtime_factor: audio_ms as f64 / elapsed_ms.max(1) as f64,
+        coreml_speedup: None,
+    }))
+}
+
+/// Fills in the Core ML speedup against an earlier run of the same model
+/// without it, or records a plain CPU run as that baseline.
+fn compare(mut benchmark: Benchmark) -> Benchmark {
+    let mut baselines = CPU_BASELINES.lock().unwrap();
+    let baselines = baselines.get_or_insert_with(HashMap::new);
+    if benchmark.coreml {
+        benchmark.coreml_speedup = baselines
+            .get(&benchmark.model)
+            .map(|baseline| benchmark.realtime_factor / baseline);
+    } else if !benchmark.openvino {
+        let best = baselines.entry(benchmark.model.clone()).or_insert(0.0);
+        *best = best.max(benchmark.realtime_factor);
+    }
+    benchmark
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn benchmark(model: &str, coreml: bool, realtime_factor: f64) -> Benchmark {
+        Benchmark {
+            model: PathBuf::from(model),
+            coreml,
+            openvino: false,
+            audio_ms: 0,
+            elapsed_ms: 0,
+            realtime_factor,
+            coreml_speedup: None,
+        }
+    }
+
+    #[test]
+    fn coreml_speedup_is_against_the_same_model_on_cpu() {
+        assert_eq!(compare(benchmark("a.bin", true, 9.0)).coreml_speedup, None);
+        compare(benchmark("a.bin", false, 2.0));
+        compare(benchmark("a.bin", false, 3.0));
+        compare(benchmark("b.bin", false, 1.0));
+        assert_eq!(
+            compare(benchmark("a.bin", true, 9.0)).coreml_speedup,
+            Some(3.0)
+        );
+    }
+}
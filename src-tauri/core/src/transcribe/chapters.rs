@@ -0,0 +1,275 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::key_phrases::{extract_key_phrases, STOPWORDS};
+use super::{Segment, Transcript};
+
+/// A stretch of a transcript about one topic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    pub title: String,
+    /// Index of the chapter's first segment.
+    pub segment: usize,
+    /// Start time in centiseconds, like [`Segment::start`].
+    pub start: i64,
+    /// End time in centiseconds, like [`Segment::end`].
+    pub end: i64,
+}
+
+/// Segments compared on either side of a possible boundary.
+const WINDOW: usize = 4;
+
+/// Chapters shorter than this, in centiseconds, are just a tangent.
+const MIN_CHAPTER: i64 = 60 * 100;
+
+/// Boundaries shallower than this are noise in an otherwise steady topic.
+const MIN_DEPTH: f64 = 0.1;
+
+/// Words a title is cut to when no key phrase stands out.
+const FALLBACK_TITLE_WORDS: usize = 5;
+
+fn bag(segments: &[Segment]) -> HashMap<String, f64> {
+    let mut bag = HashMap::new();
+    for segment in segments {
+        for token in segment.text.split_whitespace() {
+            let word = token
+                .trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase();
+            if !word.is_empty() && !STOPWORDS.contains(&word.as_str()) {
+                *bag.entry(word).or_default() += 1.0;
+            }
+        }
+    }
+    bag
+}
+
+fn cosine(a: &HashMap<String, f64>, b: &HashMap<String, f64>) -> f64 {
+    let dot: f64 = a
+        .iter()
+        .filter_map(|(word, x)| b.get(word).map(|y| x * y))
+        .sum();
+    let norm = |bag: &HashMap<String, f64>| bag.values().map(|x| x * x).sum::<f64>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+/// How far similarity drops at each gap compared to the highest points on
+/// either side of it.
+fn depths(similarity: &[f64]) -> Vec<f64> {
+    (0..similarity.len())
+        .map(|i| {
+            let mut left = similarity[i];
+            for &s in similarity[..i].iter().rev() {
+                if s < left {
+                    break;
+                }
+                left = s;
+            }
+            let mut right = similarity[i];
+            for &s in &similarity[i + 1..] {
+                if s < right {
+                    break;
+                }
+                right = s;
+            }
+            (left - similarity[i]) + (right - similarity[i])
+        })
+        .collect()
+}
+
+fn title(segments: &[Segment]) -> String {
+    let transcript = Transcript {
+        segments: segments.to_vec(),
+    };
+    let title = match extract_key_phrases(&transcript, 1).into_iter().next() {
+        Some(phrase) => phrase.phrase,
+        None => segments
+            .iter()
+            .flat_map(|segment| segment.text.split_whitespace())
+            .take(FALLBACK_TITLE_WORDS)
+            .collect::<Vec<_>>()
+            .join(" ")
+            .trim_end_matches(|c: char| !c.is_alphanumeric())
+            .to_string(),
+    };
+    let mut chars = title.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => title,
+    }
+}
+
+/// Splits a transcript into chapters where the topic shifts, TextTiling
+/// style: the words of the segments before each gap are compared with those
+/// after it, and the gaps where that similarity dips deepest become chapter
+/// boundaries. Each chapter is titled with its top key phrase.
+pub fn generate_chapters(transcript: &Transcript) -> Vec<Chapter> {
+    let segments = &transcript.segments;
+    if segments.is_empty() {
+        return Vec::new();
+    }
+
+    // Gap `i` sits between segments `i` and `i + 1`.
+    let similarity: Vec<f64> = (0..segments.len() - 1)
+        .map(|i| {
+            let before = &segments[(i + 1).saturating_sub(WINDOW)..=i];
+            let after = &segments[i + 1..(i + 1 + WINDOW).min(segments.len())];
+            cosine(&bag(before), &bag(after))
+        })
+        .collect();
+    let depths = depths(&similarity);
+    let cutoff = if depths.is_empty() {
+        0.0
+    } else {
+        let mean = depths.iter().sum::<f64>() / depths.len() as f64;
+        let variance = depths.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / depths.len() as f64;
+        (mean - variance.sqrt() / 2.0).max(MIN_DEPTH)
+    };
+
+    let mut gaps: Vec<usize> = (0..depths.len()).filter(|&i| depths[i] >= cutoff).collect();
+    gaps.sort_by(|&a, &b| depths[b].total_cmp(&depths[a]).then(a.cmp(&b)));
+    let first = segments[0].start;
+    let last = segments[segments.len() - 1].end;
+    let mut starts: Vec<usize> = Vec::new();
+    for gap in gaps {
+        let at = segments[gap + 1].start;
+        let far_enough = at - first >= MIN_CHAPTER
+            && last - at >= MIN_CHAPTER
+            && starts
+                .iter()
+                .all(|&start| (segments[start].start - at).abs() >= MIN_CHAPTER);
+        if far_enough {
+            starts.push(gap + 1);
+        }
+    }
+    starts.push(0);
+    starts.sort_unstable();
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| {
+            let end = starts.get(i + 1).copied().unwrap_or(segments.len());
+            Chapter {
+                title: title(&segments[start..end]),
+                segment: start,
+                start: segments[start].start,
+                end: segments[end - 1].end,
+            }
+        })
+        .collect()
+}
+
+/// YouTube ignores chapters shorter than this, in centiseconds.
+const MIN_YOUTUBE_CHAPTER: i64 = 10 * 100;
+
+/// Renders chapters as a chapter list to paste into a YouTube description:
+/// one `MM:SS Title` line each, or `H:MM:SS` for videos over an hour. The
+/// first chapter is moved to 00:00 and ones shorter than ten seconds are
+/// folded into the chapter before, since YouTube rejects the list
+/// otherwise. It also wants at least three chapters to show any.
+pub fn render_youtube_chapters(chapters: &[Chapter]) -> String {
+    let mut kept: Vec<(i64, &str)> = Vec::new();
+    for chapter in chapters {
+        match kept.last() {
+            None => kept.push((0, &chapter.title)),
+            Some(&(start, _)) if chapter.start - start < MIN_YOUTUBE_CHAPTER => {}
+            Some(_) => kept.push((chapter.start, &chapter.title)),
+        }
+    }
+    let hours = kept.last().is_some_and(|&(start, _)| start >= 3600 * 100);
+    kept.iter()
+        .map(|&(start, title)| {
+            let secs = start / 100;
+            let time = if hours {
+                format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+            } else {
+                format!("{:02}:{:02}", secs / 60, secs % 60)
+            };
+            format!("{} {}", time, title)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ten-second segments, one per text.
+    fn transcript(texts: &[&str]) -> Transcript {
+        Transcript {
+            segments: texts
+                .iter()
+                .enumerate()
+                .map(|(i, text)| Segment {
+                    start: i as i64 * 1000,
+                    end: (i as i64 + 1) * 1000,
+                    text: text.to_string(),
+                    speaker_turn_next: false,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn splits_where_the_topic_shifts() {
+        let mut texts = vec![" The budget forecast looks fine this quarter."; 10];
+        texts.extend([" Hiring interviews with senior engineers start soon."; 10]);
+        let chapters = generate_chapters(&transcript(&texts));
+        assert_eq!(
+            chapters,
+            vec![
+                Chapter {
+                    title: "Budget forecast looks fine".to_string(),
+                    segment: 0,
+                    start: 0,
+                    end: 10_000,
+                },
+                Chapter {
+                    title: "Senior engineers start soon".to_string(),
+                    segment: 10,
+                    start: 10_000,
+                    end: 20_000,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn short_or_steady_transcripts_are_one_chapter() {
+        let chapters = generate_chapters(&transcript(&[" Budget forecast.", " Hiring plans."]));
+        assert_eq!(chapters.len(), 1);
+        assert_eq!((chapters[0].start, chapters[0].end), (0, 2000));
+
+        let steady = generate_chapters(&transcript(&[" Budget forecast review."; 20]));
+        assert_eq!(steady.len(), 1);
+        assert!(generate_chapters(&Transcript { segments: vec![] }).is_empty());
+    }
+
+    #[test]
+    fn youtube_chapters_start_at_zero_and_skip_short_ones() {
+        let chapter = |title: &str, start| Chapter {
+            title: title.to_string(),
+            segment: 0,
+            start,
+            end: start,
+        };
+        let chapters = [
+            chapter("Intro", 300),
+            chapter("Aside", 800),
+            chapter("Budget", 9_050),
+            chapter("Hiring", 62_000),
+        ];
+        assert_eq!(
+            render_youtube_chapters(&chapters),
+            "00:00 Intro\n01:30 Budget\n10:20 Hiring"
+        );
+        let long = [chapter("Intro", 0), chapter("Q&A", 370_500)];
+        assert_eq!(render_youtube_chapters(&long), "0:00:00 Intro\n1:01:45 Q&A");
+    }
+}
@@ -0,0 +1,175 @@
+use super::{Segment, Transcript};
+
+/// Hiragana, katakana and CJK ideographs.
+fn is_cjk_letter(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}')
+}
+
+fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3040}'..='\u{30FF}')
+}
+
+/// CJK symbols and punctuation, and full-width forms.
+fn is_wide_punctuation(c: char) -> bool {
+    matches!(c, '\u{3000}'..='\u{303F}' | '\u{FF00}'..='\u{FFEF}')
+}
+
+fn is_cjk(c: char) -> bool {
+    is_cjk_letter(c) || is_wide_punctuation(c)
+}
+
+/// Guesses Chinese or Japanese from the script most of the transcript is
+/// written in: any kana means Japanese, mostly ideographs Chinese.
+pub fn detect_language(transcript: &Transcript) -> Option<&'static str> {
+    let (mut letters, mut ideographs, mut kana) = (0, 0, 0);
+    for c in transcript
+        .segments
+        .iter()
+        .flat_map(|segment| segment.text.chars())
+    {
+        if c.is_alphabetic() {
+            letters += 1;
+        }
+        if is_kana(c) {
+            kana += 1;
+        } else if is_cjk_letter(c) {
+            ideographs += 1;
+        }
+    }
+    if kana > 0 && (kana + ideographs) * 2 > letters {
+        Some("ja")
+    } else if ideographs * 2 > letters {
+        Some("zh")
+    } else {
+        None
+    }
+}
+
+/// The full-width form of sentence punctuation in `language`.
+fn wide(c: char, language: &str) -> Option<char> {
+    Some(match c {
+        ',' if language == "ja" => '、',
+        ',' => '，',
+        '.' => '。',
+        '?' => '？',
+        '!' => '！',
+        ':' => '：',
+        ';' => '；',
+        _ => return None,
+    })
+}
+
+fn postprocess_text(text: &str, language: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    for (i, &c) in chars.iter().enumerate() {
+        let prev = out.chars().last();
+        let next = chars[i + 1..].iter().copied().find(|c| !c.is_whitespace());
+        if c.is_whitespace() {
+            let drop = match prev {
+                None => next.is_some_and(is_cjk),
+                Some(prev) => {
+                    is_wide_punctuation(prev) || (is_cjk(prev) && next.is_some_and(is_cjk))
+                }
+            };
+            if !drop {
+                out.push(c);
+            }
+            continue;
+        }
+        let follows_cjk = prev.is_some_and(is_cjk_letter);
+        let ends_clause =
+            !matches!(chars.get(i + 1), Some(&next) if !next.is_whitespace() && !is_cjk(next));
+        match wide(c, language) {
+            Some(wide) if follows_cjk && ends_clause => out.push(wide),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Tidies Chinese and Japanese transcripts, where whisper writes spaces
+/// between words and ASCII punctuation: spaces between CJK characters are
+/// dropped and punctuation after them made full-width. `language` is the one
+/// whisper detected, if it's known; otherwise it's guessed from the script,
+/// which takes kanji-only Japanese for Chinese. Other languages come back
+/// unchanged.
+pub fn postprocess(transcript: &Transcript, language: Option<&str>) -> Transcript {
+    let language = language
+        .filter(|language| !language.is_empty())
+        .map(|language| language.get(..2).unwrap_or(language).to_ascii_lowercase())
+        .or_else(|| detect_language(transcript).map(str::to_string));
+    let Some(language) = language.filter(|language| language == "zh" || language == "ja") else {
+        return transcript.clone();
+    };
+    Transcript {
+        segments: transcript
+            .segments
+            .iter()
+            .map(|segment| Segment {
+                text: postprocess_text(&segment.text, &language),
+                ..segment.clone()
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript(texts: &[&str]) -> Transcript {
+        Transcript {
+            segments: texts
+                .iter()
+                .map(|text| Segment {
+                    start: 0,
+                    end: 0,
+                    text: text.to_string(),
+                    speaker_turn_next: false,
+                })
+                .collect(),
+        }
+    }
+
+    fn texts(transcript: &Transcript) -> Vec<&str> {
+        transcript
+            .segments
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn chinese_loses_spaces_and_gets_wide_punctuation() {
+        let transcript = transcript(&[" 我们 今天 开会, 讨论 Rust 项目.", " 版本 3.5 好吗?"]);
+        assert_eq!(detect_language(&transcript), Some("zh"));
+        assert_eq!(
+            texts(&postprocess(&transcript, None)),
+            vec!["我们今天开会，讨论 Rust 项目。", "版本 3.5 好吗？"]
+        );
+    }
+
+    #[test]
+    fn japanese_uses_ideographic_comma() {
+        let transcript = transcript(&[" 今日は 晴れ です. ありがとう, ございます"]);
+        assert_eq!(
+            texts(&postprocess(&transcript, Some("ja"))),
+            vec!["今日は晴れです。ありがとう、ございます"]
+        );
+    }
+
+    #[test]
+    fn other_languages_are_untouched() {
+        let english = transcript(&[" Hello, world."]);
+        assert_eq!(detect_language(&english), None);
+        assert_eq!(postprocess(&english, None), english);
+        // Korean separates words with spaces.
+        let korean = transcript(&[" 안녕 하세요."]);
+        assert_eq!(postprocess(&korean, Some("ko")), korean);
+    }
+}
@@ -0,0 +1,124 @@
+use serde::Serialize;
+
+use super::Segment;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Same,
+    Added,
+    Removed,
+}
+
+/// One segment of a diff between two transcript versions. Timestamps are
+/// from the version the segment appears in.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub start: i64,
+    pub end: i64,
+    pub text: String,
+}
+
+fn change(kind: ChangeKind, segment: &Segment) -> Change {
+    Change {
+        kind,
+        start: segment.start,
+        end: segment.end,
+        text: segment.text.trim().to_string(),
+    }
+}
+
+/// Compares two versions segment by segment (on trimmed text), using the
+/// longest common subsequence so an edit in the middle doesn't mark
+/// everything after it as changed. Removals come before the additions that
+/// replace them.
+pub fn diff(old: &[Segment], new: &[Segment]) -> Vec<Change> {
+    let (n, m) = (old.len(), new.len());
+    // lcs[i][j] is the LCS length of old[i..] and new[j..].
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i].text.trim() == new[j].text.trim() {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old[i].text.trim() == new[j].text.trim() {
+            changes.push(change(ChangeKind::Same, &new[j]));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            changes.push(change(ChangeKind::Removed, &old[i]));
+            i += 1;
+        } else {
+            changes.push(change(ChangeKind::Added, &new[j]));
+            j += 1;
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(texts: &[&str]) -> Vec<Segment> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| Segment {
+                start: i as i64 * 100,
+                end: i as i64 * 100 + 100,
+                text: text.to_string(),
+                speaker_turn_next: false,
+            })
+            .collect()
+    }
+
+    fn kinds(changes: &[Change]) -> Vec<(ChangeKind, &str)> {
+        changes.iter().map(|c| (c.kind, c.text.as_str())).collect()
+    }
+
+    #[test]
+    fn edited_segment_is_removed_then_added() {
+        let old = segments(&[" One.", " Tow.", " Three."]);
+        let new = segments(&[" One.", " Two.", " Three."]);
+        assert_eq!(
+            kinds(&diff(&old, &new)),
+            vec![
+                (ChangeKind::Same, "One."),
+                (ChangeKind::Removed, "Tow."),
+                (ChangeKind::Added, "Two."),
+                (ChangeKind::Same, "Three."),
+            ]
+        );
+    }
+
+    #[test]
+    fn insertions_and_deletions_at_the_ends() {
+        let old = segments(&[" Intro.", " Body."]);
+        let new = segments(&[" Body.", " Outro."]);
+        assert_eq!(
+            kinds(&diff(&old, &new)),
+            vec![
+                (ChangeKind::Removed, "Intro."),
+                (ChangeKind::Same, "Body."),
+                (ChangeKind::Added, "Outro."),
+            ]
+        );
+    }
+
+    #[test]
+    fn whitespace_only_changes_are_ignored() {
+        let old = segments(&[" Hello."]);
+        let new = segments(&["Hello. "]);
+        assert_eq!(kinds(&diff(&old, &new)), vec![(ChangeKind::Same, "Hello.")]);
+    }
+}
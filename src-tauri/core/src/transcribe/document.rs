@@ -0,0 +1,268 @@
+use anyhow::{Context, Result};
+use chrono::{Local, TimeZone};
+use docx_rs::{BreakType, Docx, Paragraph, Run};
+use printpdf::{BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfLayerReference};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use super::format::timestamp;
+use super::Transcript;
+
+/// Page layouts, as opposed to the plain-text [`super::format::Format`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentFormat {
+    Pdf,
+    Docx,
+}
+
+/// What goes on the title page.
+pub struct DocumentInfo<'a> {
+    pub title: &'a str,
+    /// Unix time in milliseconds.
+    pub recorded_at: Option<i64>,
+    pub duration_ms: Option<i64>,
+}
+
+impl DocumentInfo<'_> {
+    fn details(&self) -> Vec<String> {
+        let mut details = Vec::new();
+        if let Some(date) = self
+            .recorded_at
+            .and_then(|ms| Local.timestamp_millis_opt(ms).single())
+        {
+            details.push(format!("Recorded {}", date.format("%Y-%m-%d %H:%M")));
+        }
+        if let Some(ms) = self.duration_ms {
+            details.push(format!("Duration {}", &timestamp(ms / 10, '.')[..8]));
+        }
+        details
+    }
+}
+
+/// A speaker turn with its label and start time.
+struct Turn {
+    speaker: String,
+    start: i64,
+    text: String,
+}
+
+/// tinydiarize only marks where the speaker changes, not who speaks, so turns
+/// alternate between two labels, which matches the common one-on-one case.
+fn turns(transcript: &Transcript) -> Vec<Turn> {
+    let mut turns = Vec::new();
+    let mut current: Option<Turn> = None;
+    for segment in &transcript.segments {
+        let turn = current.get_or_insert_with(|| Turn {
+            speaker: format!("Speaker {}", turns.len() % 2 + 1),
+            start: segment.start,
+            text: String::new(),
+        });
+        turn.text.push_str(&segment.text);
+        if segment.speaker_turn_next {
+            turns.extend(current.take());
+        }
+    }
+    turns.extend(current);
+    turns.retain(|turn| !turn.text.trim().is_empty());
+    turns
+}
+
+/// Writes the transcript as a title page followed by one paragraph per
+/// speaker turn, each labeled with the speaker and its start time.
+pub fn write_document(
+    transcript: &Transcript,
+    info: &DocumentInfo,
+    format: DocumentFormat,
+    path: &Path,
+) -> Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    match format {
+        DocumentFormat::Pdf => write_pdf(transcript, info, file),
+        DocumentFormat::Docx => write_docx(transcript, info, file),
+    }
+}
+
+fn write_docx(transcript: &Transcript, info: &DocumentInfo, file: File) -> Result<()> {
+    let mut docx = Docx::new()
+        .add_paragraph(Paragraph::new().add_run(Run::new().add_text(info.title).size(48).bold()));
+    for detail in info.details() {
+        docx = docx.add_paragraph(Paragraph::new().add_run(Run::new().add_text(detail)));
+    }
+    docx = docx.add_paragraph(Paragraph::new().add_run(Run::new().add_break(BreakType::Page)));
+    for turn in turns(transcript) {
+        docx = docx.add_paragraph(
+            Paragraph::new()
+                .add_run(
+                    Run::new()
+                        .add_text(format!("{} [{}]", turn.speaker, timestamp(turn.start, '.')))
+                        .bold(),
+                )
+                .add_run(Run::new().add_break(BreakType::TextWrapping))
+                .add_run(Run::new().add_text(turn.text.trim())),
+        );
+    }
+    docx.build().pack(file)?;
+    Ok(())
+}
+
+const PAGE_WIDTH: Mm = Mm(210.0);
+const PAGE_HEIGHT: Mm = Mm(297.0);
+const MARGIN: f32 = 20.0;
+const LINE_HEIGHT: f32 = 6.0;
+/// Roughly what fits between the margins in 11pt Helvetica.
+const CHARS_PER_LINE: usize = 85;
+
+/// Lays text out top to bottom, starting a new page when one fills up.
+struct PdfWriter {
+    doc: printpdf::PdfDocumentReference,
+    layer: PdfLayerReference,
+    y: f32,
+}
+
+impl PdfWriter {
+    fn line(&mut self, text: &str, size: f32, font: &IndirectFontRef) {
+        if self.y < MARGIN {
+            self.new_page();
+        }
+        self.layer
+            .use_text(text, size, Mm(MARGIN), Mm(self.y), font);
+        self.y -= LINE_HEIGHT * size / 11.0;
+    }
+
+    fn new_page(&mut self) {
+        let (page, layer) = self.doc.add_page(PAGE_WIDTH, PAGE_HEIGHT, "Layer 1");
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.y = PAGE_HEIGHT.0 - MARGIN;
+    }
+}
+
+fn write_pdf(transcript: &Transcript, info: &DocumentInfo, file: File) -> Result<()> {
+    let (doc, page, layer) = PdfDocument::new(info.title, PAGE_WIDTH, PAGE_HEIGHT, "Layer 1");
+    let regular = doc.add_builtin_font(BuiltinFont::Helvetica)?;
+    let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold)?;
+    let layer = doc.get_page(page).get_layer(layer);
+    let mut writer = PdfWriter {
+        doc,
+        layer,
+        y: PAGE_HEIGHT.0 / 2.0 + 20.0,
+    };
+
+    writer.line(info.title, 24.0, &bold);
+    for detail in info.details() {
+        writer.line(&detail, 11.0, &regular);
+    }
+    writer.new_page();
+    for turn in turns(transcript) {
+        writer.line(
+            &format!("{} [{}]", turn.speaker, timestamp(turn.start, '.')),
+            11.0,
+            &bold,
+        );
+        for line in wrap(turn.text.trim(), CHARS_PER_LINE) {
+            writer.line(&line, 11.0, &regular);
+        }
+        writer.y -= LINE_HEIGHT / 2.0;
+    }
+    writer.doc.save(&mut BufWriter::new(file))?;
+    Ok(())
+}
+
+/// Greedy word wrap; words longer than a line get a line of their own.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcribe::Segment;
+    use std::io::Read;
+
+    fn transcript() -> Transcript {
+        Transcript {
+            segments: vec![
+                Segment {
+                    start: 0,
+                    end: 150,
+                    text: " Hello there.".to_string(),
+                    speaker_turn_next: true,
+                },
+                Segment {
+                    start: 150,
+                    end: 300,
+                    text: " Hi!".to_string(),
+                    speaker_turn_next: false,
+                },
+            ],
+        }
+    }
+
+    fn info() -> DocumentInfo<'static> {
+        DocumentInfo {
+            title: "Standup",
+            recorded_at: Some(0),
+            duration_ms: Some(3_000),
+        }
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("app-core-document-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn turns_alternate_speakers() {
+        let turns = turns(&transcript());
+        assert_eq!(turns.len(), 2);
+        assert_eq!(turns[1].speaker, "Speaker 2");
+        assert_eq!(turns[1].start, 150);
+    }
+
+    #[test]
+    fn wrap_breaks_on_words() {
+        assert_eq!(wrap("aa bb cc", 5), ["aa bb", "cc"]);
+        assert_eq!(wrap("toolongword x", 4), ["toolongword", "x"]);
+    }
+
+    #[test]
+    fn docx_contains_turns() {
+        let path = temp_path("standup.docx");
+        write_document(&transcript(), &info(), DocumentFormat::Docx, &path).unwrap();
+        let mut archive = zip::ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        let mut xml = String::new();
+        archive
+            .by_name("word/document.xml")
+            .unwrap()
+            .read_to_string(&mut xml)
+            .unwrap();
+        assert!(xml.contains("Standup"));
+        assert!(xml.contains("Speaker 2 [00:00:01.500]"));
+        assert!(xml.contains("Hi!"));
+    }
+
+    #[test]
+    fn pdf_is_written() {
+        let path = temp_path("standup.pdf");
+        write_document(&transcript(), &info(), DocumentFormat::Pdf, &path).unwrap();
+        assert!(std::fs::read(&path).unwrap().starts_with(b"%PDF"));
+    }
+}
@@ -0,0 +1,359 @@
+use serde::{Deserialize, Serialize};
+
+use super::Transcript;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    Person,
+    Organization,
+    Date,
+}
+
+impl EntityKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EntityKind::Person => "person",
+            EntityKind::Organization => "organization",
+            EntityKind::Date => "date",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "person" => Some(EntityKind::Person),
+            "organization" => Some(EntityKind::Organization),
+            "date" => Some(EntityKind::Date),
+            _ => None,
+        }
+    }
+}
+
+/// A name or date mentioned in a transcript.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entity {
+    pub kind: EntityKind,
+    pub text: String,
+    /// Index of the segment it's in.
+    pub segment: usize,
+    /// Character offsets into the segment's text, end exclusive.
+    pub start: usize,
+    pub end: usize,
+}
+
+const MONTHS: &[&str] = &[
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+const WEEKDAYS: &[&str] = &[
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+const RELATIVE_DAYS: &[&str] = &["today", "tonight", "tomorrow", "yesterday"];
+const RELATIVE_MODIFIERS: &[&str] = &["next", "last", "this"];
+const PERIODS: &[&str] = &["week", "month", "quarter", "year"];
+const HONORIFICS: &[&str] = &["mr", "mrs", "ms", "dr", "prof"];
+const ORG_SUFFIXES: &[&str] = &[
+    "inc",
+    "corp",
+    "corporation",
+    "llc",
+    "ltd",
+    "co",
+    "company",
+    "group",
+    "bank",
+    "university",
+    "institute",
+    "foundation",
+    "labs",
+    "agency",
+    "partners",
+    "associates",
+];
+/// Capitalized words that are never names.
+const NOT_NAMES: &[&str] = &[
+    "i", "i'm", "i'll", "i've", "i'd", "ok", "okay", "am", "pm", "tv", "the", "a", "an", "and",
+    "but", "or", "so", "then", "well", "yes", "yeah", "no", "oh", "hi", "hello", "thanks",
+];
+
+/// A word of a segment, with its surrounding punctuation trimmed off.
+struct Token<'a> {
+    word: &'a str,
+    start: usize,
+    end: usize,
+    /// First word of a sentence, where capitals say nothing about names.
+    sentence_start: bool,
+    ends_sentence: bool,
+    /// Followed by punctuation, so it can't run on into the next word.
+    breaks: bool,
+}
+
+impl Token<'_> {
+    fn lower(&self) -> String {
+        self.word.to_lowercase()
+    }
+
+    fn capitalized(&self) -> bool {
+        self.word.chars().next().is_some_and(char::is_uppercase)
+            && !NOT_NAMES.contains(&self.lower().as_str())
+    }
+
+    fn acronym(&self) -> bool {
+        (2..=5).contains(&self.word.len())
+            && self.word.chars().all(|c| c.is_ascii_uppercase())
+            && !NOT_NAMES.contains(&self.lower().as_str())
+    }
+
+    fn day_number(&self) -> bool {
+        let digits = ["st", "nd", "rd", "th"]
+            .iter()
+            .find_map(|suffix| self.word.strip_suffix(suffix))
+            .unwrap_or(self.word);
+        !digits.is_empty()
+            && digits.len() <= 2
+            && digits.chars().all(|c| c.is_ascii_digit())
+            && digits
+                .parse::<u32>()
+                .is_ok_and(|day| (1..=31).contains(&day))
+    }
+
+    fn year(&self) -> bool {
+        self.word.len() == 4 && self.word.chars().all(|c| c.is_ascii_digit())
+    }
+
+    fn numeric_date(&self) -> bool {
+        let parts: Vec<&str> = self.word.split(['/', '-']).collect();
+        (2..=3).contains(&parts.len())
+            && parts.iter().all(|part| {
+                !part.is_empty() && part.len() <= 4 && part.chars().all(|c| c.is_ascii_digit())
+            })
+    }
+}
+
+fn tokens(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut sentence_start = true;
+    for raw in text.split_whitespace() {
+        let byte = raw.as_ptr() as usize - text.as_ptr() as usize;
+        let lead = raw.len() - raw.trim_start_matches(|c: char| !c.is_alphanumeric()).len();
+        let mut word = raw
+            .trim_start_matches(|c: char| !c.is_alphanumeric())
+            .trim_end_matches(|c: char| !c.is_alphanumeric());
+        for possessive in ["'s", "’s"] {
+            word = word.strip_suffix(possessive).unwrap_or(word);
+        }
+        // "Dr." doesn't end a sentence.
+        let ends_sentence =
+            raw.ends_with(['.', '!', '?']) && !HONORIFICS.contains(&word.to_lowercase().as_str());
+        if !word.is_empty() {
+            let start = text[..byte + lead].chars().count();
+            tokens.push(Token {
+                word,
+                start,
+                end: start + word.chars().count(),
+                sentence_start,
+                ends_sentence,
+                breaks: ends_sentence || raw.ends_with([',', ';', ':']),
+            });
+        }
+        sentence_start = ends_sentence;
+    }
+    tokens
+}
+
+/// How many tokens from `i` make up a date, if they do.
+fn date_at(tokens: &[Token], i: usize) -> Option<usize> {
+    let lower = tokens[i].lower();
+    let next = |offset: usize| {
+        tokens
+            .get(i + offset)
+            .filter(|_| !tokens[i + offset - 1].breaks)
+    };
+    let is_month = |token: &Token| token.capitalized() && MONTHS.contains(&token.lower().as_str());
+    if RELATIVE_DAYS.contains(&lower.as_str()) || tokens[i].numeric_date() {
+        return Some(1);
+    }
+    if WEEKDAYS.contains(&lower.as_str()) {
+        return Some(1);
+    }
+    if RELATIVE_MODIFIERS.contains(&lower.as_str()) {
+        let following = next(1)?.lower();
+        if WEEKDAYS.contains(&following.as_str()) || PERIODS.contains(&following.as_str()) {
+            return Some(2);
+        }
+        return None;
+    }
+    if is_month(&tokens[i]) {
+        let mut len = 1;
+        if next(len).is_some_and(Token::day_number) {
+            len += 1;
+        } else if lower == "may" {
+            // Usually the verb, even capitalized at the start of a sentence.
+            return None;
+        }
+        // A comma before the year is fine: "March 14th, 2024".
+        let year = tokens
+            .get(i + len)
+            .filter(|_| !tokens[i + len - 1].ends_sentence);
+        if year.is_some_and(Token::year) {
+            len += 1;
+        }
+        return Some(len);
+    }
+    // "the 3rd of May"
+    if tokens[i].day_number()
+        && next(1).is_some_and(|t| t.lower() == "of")
+        && next(2).is_some_and(is_month)
+    {
+        return Some(3);
+    }
+    None
+}
+
+/// Tags the people, organizations and dates in one segment's text. It's
+/// rule-based: dates by their words and shapes, people and organizations by
+/// capitalization, honorifics, acronyms and suffixes like "Inc", so expect
+/// the odd miss on names whisper didn't capitalize.
+pub fn find_entities(text: &str, segment: usize) -> Vec<Entity> {
+    let tokens = tokens(text);
+    let mut entities = Vec::new();
+    let entity = |kind, from: &Token, to: &Token| Entity {
+        kind,
+        text: text
+            .chars()
+            .skip(from.start)
+            .take(to.end - from.start)
+            .collect(),
+        segment,
+        start: from.start,
+        end: to.end,
+    };
+    let mut i = 0;
+    while i < tokens.len() {
+        if let Some(len) = date_at(&tokens, i) {
+            entities.push(entity(EntityKind::Date, &tokens[i], &tokens[i + len - 1]));
+            i += len;
+            continue;
+        }
+        let honorific = HONORIFICS.contains(&tokens[i].lower().as_str());
+        if !tokens[i].capitalized() && !honorific {
+            i += 1;
+            continue;
+        }
+        let mut end = i + 1;
+        while end < tokens.len()
+            && !tokens[end - 1].breaks
+            && tokens[end].capitalized()
+            && date_at(&tokens, end).is_none()
+        {
+            end += 1;
+        }
+        let run = &tokens[i..end];
+        let last = run.last().unwrap().lower();
+        let kind = if honorific && run.len() == 1 {
+            None
+        } else if ORG_SUFFIXES.contains(&last.as_str()) && run.len() > 1
+            || run.iter().any(Token::acronym)
+        {
+            Some(EntityKind::Organization)
+        } else if honorific || !run[0].sentence_start || run.len() > 1 {
+            Some(EntityKind::Person)
+        } else {
+            None
+        };
+        if let Some(kind) = kind {
+            entities.push(entity(kind, &run[0], run.last().unwrap()));
+        }
+        i = end;
+    }
+    entities
+}
+
+/// Every entity in the transcript, in order.
+pub fn tag_entities(transcript: &Transcript) -> Vec<Entity> {
+    transcript
+        .segments
+        .iter()
+        .enumerate()
+        .flat_map(|(index, segment)| find_entities(&segment.text, index))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(text: &str) -> Vec<(EntityKind, String)> {
+        find_entities(text, 0)
+            .into_iter()
+            .map(|entity| {
+                // Offsets point at exactly the entity's text.
+                let slice: String = text
+                    .chars()
+                    .skip(entity.start)
+                    .take(entity.end - entity.start)
+                    .collect();
+                assert_eq!(slice, entity.text);
+                (entity.kind, entity.text)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tags_people_organizations_and_dates() {
+        use EntityKind::*;
+        assert_eq!(
+            found(" So I talked to Sarah Chen at Acme Corp on March 14th, 2024."),
+            vec![
+                (Person, "Sarah Chen".to_string()),
+                (Organization, "Acme Corp".to_string()),
+                (Date, "March 14th, 2024".to_string()),
+            ]
+        );
+        assert_eq!(
+            found(" Dr. Patel from NASA wants it by next Friday."),
+            vec![
+                (Person, "Dr. Patel".to_string()),
+                (Organization, "NASA".to_string()),
+                (Date, "next Friday".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn sentence_starts_and_modal_may_are_not_entities() {
+        assert_eq!(found(" Budgets are tight. We may slip."), vec![]);
+        assert_eq!(
+            found(" Ship it tomorrow or on the 3rd of May."),
+            vec![
+                (EntityKind::Date, "tomorrow".to_string()),
+                (EntityKind::Date, "3rd of May".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn offsets_count_characters_not_bytes() {
+        let entities = find_entities(" Café talk with José Núñez", 3);
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].text, "José Núñez");
+        assert_eq!((entities[0].start, entities[0].end), (16, 26));
+        assert_eq!(entities[0].segment, 3);
+    }
+}
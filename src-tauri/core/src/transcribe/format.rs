@@ -0,0 +1,311 @@
+use serde::{Deserialize, Serialize};
+
+use super::{karaoke, Segment, Transcript};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    Text,
+    Markdown,
+    Srt,
+    Vtt,
+    /// Advanced SubStation Alpha subtitles, styled per speaker.
+    Ass,
+    /// JSON word timings grouped by segment, for highlighting words as they
+    /// play. See [`karaoke`].
+    Karaoke,
+}
+
+/// Renders a transcript for pasting or saving.
+pub fn render(transcript: &Transcript, format: Format) -> String {
+    match format {
+        Format::Text => render_text(transcript),
+        Format::Markdown => render_markdown(transcript),
+        Format::Srt => render_srt(&transcript.segments),
+        Format::Vtt => render_vtt(&transcript.segments),
+        Format::Ass => render_ass(&transcript.segments),
+        Format::Karaoke => karaoke::render(transcript),
+    }
+}
+
+/// One transcript within a multi-recording document.
+pub struct Part<'a> {
+    pub title: &'a str,
+    pub transcript: &'a Transcript,
+    /// Where the part starts in the combined timeline, in centiseconds.
+    pub offset: i64,
+}
+
+/// Renders several transcripts as one document, e.g. every recording of a
+/// session. Text and Markdown get a heading per part; subtitles become one
+/// continuous track with each part shifted by its offset.
+pub fn render_parts(parts: &[Part], format: Format) -> String {
+    match format {
+        Format::Text => parts
+            .iter()
+            .map(|part| format!("{}\n\n{}", part.title, render_text(part.transcript)))
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        Format::Markdown => parts
+            .iter()
+            .map(|part| format!("## {}\n\n{}", part.title, render_markdown(part.transcript)))
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        Format::Srt | Format::Vtt | Format::Ass | Format::Karaoke => {
+            let segments: Vec<Segment> = parts
+                .iter()
+                .flat_map(|part| {
+                    part.transcript.segments.iter().map(|segment| Segment {
+                        start: segment.start + part.offset,
+                        end: segment.end + part.offset,
+                        ..segment.clone()
+                    })
+                })
+                .collect();
+            match format {
+                Format::Srt => render_srt(&segments),
+                Format::Vtt => render_vtt(&segments),
+                Format::Ass => render_ass(&segments),
+                _ => karaoke::render(&Transcript { segments }),
+            }
+        }
+    }
+}
+
+fn render_text(transcript: &Transcript) -> String {
+    transcript
+        .turns()
+        .iter()
+        .map(|turn| turn.trim())
+        .filter(|turn| !turn.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// One paragraph per speaker turn, each led by its start time.
+fn render_markdown(transcript: &Transcript) -> String {
+    let mut paragraphs = Vec::new();
+    let mut turn_start = None;
+    let mut text = String::new();
+    for segment in &transcript.segments {
+        turn_start.get_or_insert(segment.start);
+        text.push_str(&segment.text);
+        if segment.speaker_turn_next {
+            push_paragraph(&mut paragraphs, turn_start.take(), &mut text);
+        }
+    }
+    push_paragraph(&mut paragraphs, turn_start, &mut text);
+    paragraphs.join("\n\n")
+}
+
+fn push_paragraph(paragraphs: &mut Vec<String>, start: Option<i64>, text: &mut String) {
+    let trimmed = text.trim();
+    if let (Some(start), false) = (start, trimmed.is_empty()) {
+        paragraphs.push(format!("**[{}]** {}", timestamp(start, '.'), trimmed));
+    }
+    text.clear();
+}
+
+fn render_srt(segments: &[Segment]) -> String {
+    segments
+        .iter()
+        .enumerate()
+        .map(|(i, segment)| {
+            format!(
+                "{}\n{} --> {}\n{}\n",
+                i + 1,
+                timestamp(segment.start, ','),
+                timestamp(segment.end, ','),
+                segment.text.trim()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn render_vtt(segments: &[Segment]) -> String {
+    let cues: Vec<String> = segments
+        .iter()
+        .map(|segment| {
+            format!(
+                "{} --> {}\n{}\n",
+                timestamp(segment.start, '.'),
+                timestamp(segment.end, '.'),
+                segment.text.trim()
+            )
+        })
+        .collect();
+    format!("WEBVTT\n\n{}", cues.join("\n"))
+}
+
+/// Script header and one style per speaker: white in the bottom left for the
+/// first, yellow in the bottom right for the second, on a 1080p canvas.
+const ASS_HEADER: &str = "[Script Info]
+ScriptType: v4.00+
+PlayResX: 1920
+PlayResY: 1080
+WrapStyle: 0
+ScaledBorderAndShadow: yes
+
+[V4+ Styles]
+Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding
+Style: Speaker1,Arial,56,&H00FFFFFF,&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,2,1,1,80,80,60,1
+Style: Speaker2,Arial,56,&H0000FFFF,&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,2,1,3,80,80,60,1
+
+[Events]
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+";
+
+/// Each cue is styled by its speaker. tinydiarize only marks where the
+/// speaker changes, so like documents this assumes two people taking turns.
+fn render_ass(segments: &[Segment]) -> String {
+    let mut out = ASS_HEADER.to_string();
+    let mut speaker = 0;
+    for segment in segments {
+        // Braces would start override tags; ASS has no way to escape them.
+        let text = segment
+            .text
+            .trim()
+            .replace('{', "(")
+            .replace('}', ")")
+            .replace('\n', "\\N");
+        out.push_str(&format!(
+            "Dialogue: 0,{},{},Speaker{},Speaker {},0,0,0,,{}\n",
+            ass_timestamp(segment.start),
+            ass_timestamp(segment.end),
+            speaker + 1,
+            speaker + 1,
+            text
+        ));
+        if segment.speaker_turn_next {
+            speaker = 1 - speaker;
+        }
+    }
+    out
+}
+
+/// ASS counts in centiseconds too, as `H:MM:SS.cc`.
+fn ass_timestamp(centiseconds: i64) -> String {
+    let cs = centiseconds.max(0);
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        cs / 360_000,
+        cs / 6000 % 60,
+        cs / 100 % 60,
+        cs % 100
+    )
+}
+
+/// Formats whisper's centisecond timestamps as `HH:MM:SS<sep>mmm`.
+pub fn timestamp(centiseconds: i64, millis_separator: char) -> String {
+    let ms = centiseconds.max(0) * 10;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        millis_separator,
+        ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript() -> Transcript {
+        Transcript {
+            segments: vec![
+                Segment {
+                    start: 0,
+                    end: 150,
+                    text: " Hello there.".to_string(),
+                    speaker_turn_next: true,
+                },
+                Segment {
+                    start: 150,
+                    end: 6_123,
+                    text: " Hi!".to_string(),
+                    speaker_turn_next: false,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn text_separates_turns_with_blank_lines() {
+        assert_eq!(render(&transcript(), Format::Text), "Hello there.\n\nHi!");
+    }
+
+    #[test]
+    fn markdown_prefixes_turns_with_start_time() {
+        assert_eq!(
+            render(&transcript(), Format::Markdown),
+            "**[00:00:00.000]** Hello there.\n\n**[00:00:01.500]** Hi!"
+        );
+    }
+
+    #[test]
+    fn srt_numbers_cues() {
+        assert_eq!(
+            render(&transcript(), Format::Srt),
+            "1\n00:00:00,000 --> 00:00:01,500\nHello there.\n\n\
+             2\n00:00:01,500 --> 00:01:01,230\nHi!\n"
+        );
+    }
+
+    #[test]
+    fn vtt_has_header_and_dotted_timestamps() {
+        assert_eq!(
+            render(&transcript(), Format::Vtt),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:01.500\nHello there.\n\n\
+             00:00:01.500 --> 00:01:01.230\nHi!\n"
+        );
+    }
+
+    #[test]
+    fn ass_styles_cues_by_speaker() {
+        let ass = render(&transcript(), Format::Ass);
+        assert!(ass.starts_with("[Script Info]\n"));
+        assert!(ass.contains("Style: Speaker2,"));
+        assert!(ass.ends_with(
+            "Dialogue: 0,0:00:00.00,0:00:01.50,Speaker1,Speaker 1,0,0,0,,Hello there.\n\
+             Dialogue: 0,0:00:01.50,0:01:01.23,Speaker2,Speaker 2,0,0,0,,Hi!\n"
+        ));
+    }
+
+    #[test]
+    fn parts_get_headings_and_shifted_cues() {
+        let transcript = transcript();
+        let parts = [
+            Part {
+                title: "Monday",
+                transcript: &transcript,
+                offset: 0,
+            },
+            Part {
+                title: "Tuesday",
+                transcript: &transcript,
+                offset: 10_000,
+            },
+        ];
+        assert_eq!(
+            render_parts(&parts, Format::Text),
+            "Monday\n\nHello there.\n\nHi!\n\nTuesday\n\nHello there.\n\nHi!"
+        );
+        assert!(
+            render_parts(&parts, Format::Markdown).starts_with("## Monday\n\n**[00:00:00.000]**")
+        );
+        let srt = render_parts(&parts, Format::Srt);
+        assert!(
+            srt.contains("\n3\n00:01:40,000 --> 00:01:41,500\nHello there.\n"),
+            "{}",
+            srt
+        );
+    }
+
+    #[test]
+    fn timestamp_handles_hours() {
+        assert_eq!(timestamp(366_100, ','), "01:01:01,000");
+    }
+}
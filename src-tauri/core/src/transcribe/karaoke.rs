@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+
+use super::align::WordTiming;
+use super::{Segment, Transcript};
+
+/// Word timings grouped by segment, for highlighting each word as the player
+/// reaches it. Times are in milliseconds, like the player's position.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Karaoke {
+    /// Whether word times were spread over each segment by length rather
+    /// than lined up with the audio.
+    pub estimated: bool,
+    pub segments: Vec<KaraokeSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KaraokeSegment {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+    pub words: Vec<KaraokeWord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KaraokeWord {
+    pub word: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+/// The transcript's text with one word per whitespace-separated token, as
+/// [`align_words`](super::align::align_words) expects it.
+pub fn script(transcript: &Transcript) -> String {
+    transcript
+        .segments
+        .iter()
+        .map(|segment| segment.text.trim())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Builds karaoke timings from word timings aligned to [`script`], or
+/// estimates them from each segment's span when there are none: longer
+/// words get proportionally longer.
+pub fn karaoke(transcript: &Transcript, aligned: Option<&[WordTiming]>) -> Karaoke {
+    let mut aligned = aligned.map(|words| words.iter());
+    let segments = transcript
+        .segments
+        .iter()
+        .map(|segment| {
+            let words = match aligned.as_mut() {
+                Some(timings) => segment
+                    .text
+                    .split_whitespace()
+                    .zip(timings)
+                    .map(|(word, timing)| KaraokeWord {
+                        word: word.to_string(),
+                        start_ms: timing.start * 10,
+                        end_ms: timing.end * 10,
+                    })
+                    .collect(),
+                None => estimate(segment),
+            };
+            KaraokeSegment {
+                start_ms: segment.start * 10,
+                end_ms: segment.end * 10,
+                text: segment.text.trim().to_string(),
+                words,
+            }
+        })
+        .collect();
+    Karaoke {
+        estimated: aligned.is_none(),
+        segments,
+    }
+}
+
+fn estimate(segment: &Segment) -> Vec<KaraokeWord> {
+    let words: Vec<&str> = segment.text.split_whitespace().collect();
+    let total: i64 = words.iter().map(|word| word.chars().count() as i64).sum();
+    let (start, span) = (
+        segment.start * 10,
+        (segment.end - segment.start).max(0) * 10,
+    );
+    let mut before = 0;
+    words
+        .iter()
+        .map(|word| {
+            let len = word.chars().count() as i64;
+            let timing = KaraokeWord {
+                word: word.to_string(),
+                start_ms: start + span * before / total,
+                end_ms: start + span * (before + len) / total,
+            };
+            before += len;
+            timing
+        })
+        .collect()
+}
+
+/// [`karaoke`] with estimated timings, as JSON.
+pub fn render(transcript: &Transcript) -> String {
+    serde_json::to_string_pretty(&karaoke(transcript, None)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript() -> Transcript {
+        Transcript {
+            segments: vec![
+                Segment {
+                    start: 0,
+                    end: 100,
+                    text: " Hi there".to_string(),
+                    speaker_turn_next: false,
+                },
+                Segment {
+                    start: 100,
+                    end: 200,
+                    text: " Bye".to_string(),
+                    speaker_turn_next: false,
+                },
+            ],
+        }
+    }
+
+    fn word(word: &str, start_ms: i64, end_ms: i64) -> KaraokeWord {
+        KaraokeWord {
+            word: word.to_string(),
+            start_ms,
+            end_ms,
+        }
+    }
+
+    #[test]
+    fn estimates_spread_words_by_length() {
+        let karaoke = karaoke(&transcript(), None);
+        assert!(karaoke.estimated);
+        assert_eq!(karaoke.segments[0].text, "Hi there");
+        assert_eq!(
+            karaoke.segments[0].words,
+            vec![word("Hi", 0, 285), word("there", 285, 1000)]
+        );
+        assert_eq!(karaoke.segments[1].words, vec![word("Bye", 1000, 2000)]);
+    }
+
+    #[test]
+    fn aligned_words_are_grouped_by_segment() {
+        let transcript = transcript();
+        assert_eq!(script(&transcript), "Hi there Bye");
+        let aligned: Vec<WordTiming> = [(10, 30), (40, 90), (120, 150)]
+            .iter()
+            .zip(["Hi", "there", "Bye"])
+            .map(|(&(start, end), word)| WordTiming {
+                word: word.to_string(),
+                start,
+                end,
+                matched: true,
+            })
+            .collect();
+        let karaoke = karaoke(&transcript, Some(&aligned));
+        assert!(!karaoke.estimated);
+        assert_eq!(
+            karaoke.segments[0].words,
+            vec![word("Hi", 100, 300), word("there", 400, 900)]
+        );
+        assert_eq!(karaoke.segments[1].words, vec![word("Bye", 1200, 1500)]);
+    }
+}
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::Transcript;
+
+/// A phrase that stands out in a transcript.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyPhrase {
+    pub phrase: String,
+    pub score: f64,
+    /// How often it was said.
+    pub count: usize,
+}
+
+/// Candidates longer than this are run-on speech rather than a phrase.
+const MAX_PHRASE_WORDS: usize = 4;
+
+/// Words that split candidate phrases: the usual English stopwords plus the
+/// fillers people say but never write.
+pub(crate) const STOPWORDS: &[&str] = &[
+    "a", "about", "after", "again", "all", "also", "am", "an", "and", "any", "are", "as", "at",
+    "be", "because", "been", "before", "being", "but", "by", "can", "could", "did", "do", "does",
+    "doing", "don't", "for", "from", "get", "go", "going", "got", "had", "has", "have", "he",
+    "her", "here", "him", "his", "how", "i", "i'm", "if", "in", "into", "is", "it", "it's", "its",
+    "just", "know", "let's", "like", "maybe", "me", "mean", "more", "my", "no", "not", "now", "of",
+    "oh", "ok", "okay", "on", "one", "or", "our", "out", "really", "right", "so", "some", "that",
+    "that's", "the", "their", "them", "then", "there", "these", "they", "think", "this", "those",
+    "to", "uh", "um", "up", "very", "was", "we", "we're", "well", "were", "what", "when", "where",
+    "which", "who", "why", "will", "with", "would", "yeah", "yes", "you", "your",
+];
+
+/// Splits speech into runs of content words, breaking at stopwords and
+/// sentence punctuation.
+fn candidates(text: &str) -> Vec<Vec<String>> {
+    let mut phrases = vec![Vec::new()];
+    for token in text.split_whitespace() {
+        let ends_clause = token.ends_with(['.', ',', ';', ':', '!', '?']);
+        let word: String = token
+            .trim_matches(|c: char| !c.is_alphanumeric())
+            .to_lowercase();
+        if word.is_empty() || STOPWORDS.contains(&word.as_str()) {
+            phrases.push(Vec::new());
+        } else {
+            phrases.last_mut().unwrap().push(word);
+        }
+        if ends_clause {
+            phrases.push(Vec::new());
+        }
+    }
+    phrases.retain(|phrase| !phrase.is_empty() && phrase.len() <= MAX_PHRASE_WORDS);
+    phrases
+}
+
+/// Ranks the transcript's key phrases with RAKE (rapid automatic keyword
+/// extraction): words score by how many other words they appear alongside
+/// relative to how often they appear, and phrases by the sum of their words.
+/// Single words said only once are left out as noise.
+pub fn extract_key_phrases(transcript: &Transcript, limit: usize) -> Vec<KeyPhrase> {
+    let text = transcript
+        .segments
+        .iter()
+        .map(|segment| segment.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let phrases = candidates(&text);
+
+    let mut frequency: HashMap<&str, f64> = HashMap::new();
+    let mut degree: HashMap<&str, f64> = HashMap::new();
+    for phrase in &phrases {
+        for word in phrase {
+            *frequency.entry(word).or_default() += 1.0;
+            *degree.entry(word).or_default() += phrase.len() as f64;
+        }
+    }
+    let mut counts: HashMap<String, (f64, usize)> = HashMap::new();
+    for phrase in &phrases {
+        let score = phrase
+            .iter()
+            .map(|word| degree[word.as_str()] / frequency[word.as_str()])
+            .sum();
+        counts.entry(phrase.join(" ")).or_insert((score, 0)).1 += 1;
+    }
+
+    let mut ranked: Vec<KeyPhrase> = counts
+        .into_iter()
+        .filter(|(phrase, (_, count))| phrase.contains(' ') || *count > 1)
+        .map(|(phrase, (score, count))| KeyPhrase {
+            phrase,
+            score,
+            count,
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(b.count.cmp(&a.count))
+            .then_with(|| a.phrase.cmp(&b.phrase))
+    });
+    ranked.truncate(limit);
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcribe::Segment;
+
+    fn transcript(texts: &[&str]) -> Transcript {
+        Transcript {
+            segments: texts
+                .iter()
+                .map(|text| Segment {
+                    start: 0,
+                    end: 0,
+                    text: text.to_string(),
+                    speaker_turn_next: false,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn ranks_repeated_multiword_phrases_first() {
+        let transcript = transcript(&[
+            " The quarterly budget review is due Friday.",
+            " Um, we need the quarterly budget review before the board meeting.",
+            " So the board meeting is on Monday. Budget, budget.",
+        ]);
+        let phrases = extract_key_phrases(&transcript, 5);
+        assert_eq!(phrases[0].phrase, "quarterly budget review");
+        assert_eq!(phrases[0].count, 2);
+        assert_eq!(phrases[1].phrase, "board meeting");
+        // "Monday" only comes up once, "budget" on its own twice.
+        assert!(phrases.iter().all(|p| p.phrase != "monday"));
+        assert!(phrases.iter().any(|p| p.phrase == "budget" && p.count == 2));
+    }
+
+    #[test]
+    fn fillers_and_punctuation_split_phrases() {
+        assert_eq!(
+            candidates("Uh, launch plan. Yeah okay design review!"),
+            vec![
+                vec!["launch".to_string(), "plan".to_string()],
+                vec!["design".to_string(), "review".to_string()],
+            ]
+        );
+    }
+}
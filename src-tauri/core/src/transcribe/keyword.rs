@@ -0,0 +1,87 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+/// Listens for one phrase in short utterances with its own small whisper
+/// model (say `ggml-tiny.en.bin`), kept apart from the transcription model
+/// so the two don't evict each other.
+pub struct KeywordSpotter {
+    ctx: WhisperContext,
+    phrase: String,
+}
+
+impl KeywordSpotter {
+    pub fn new(model_path: &Path, phrase: &str) -> Result<Self> {
+        let ctx = WhisperContext::new_with_params(
+            &model_path.to_string_lossy(),
+            WhisperContextParameters::default(),
+        )
+        .context("failed to open wake word model")?;
+        Ok(KeywordSpotter {
+            ctx,
+            phrase: phrase.to_string(),
+        })
+    }
+
+    /// Whether the phrase was said in `samples`, mono at 16 kHz.
+    pub fn heard(&self, samples: &[f32]) -> Result<bool> {
+        let mut state = self.ctx.create_state().context("failed to create state")?;
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        // Priming with the phrase makes whisper more likely to spell it the
+        // same way when it's said.
+        params.set_initial_prompt(&self.phrase);
+        params.set_single_segment(true);
+        params.set_no_timestamps(true);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        state
+            .full(params, samples)
+            .context("failed to transcribe utterance")?;
+        let mut text = String::new();
+        for i in 0..state.full_n_segments().context("failed to get segments")? {
+            text.push_str(
+                &state
+                    .full_get_segment_text(i)
+                    .context("failed to get segment")?,
+            );
+        }
+        Ok(contains_phrase(&text, &self.phrase))
+    }
+}
+
+/// Lowercase words without punctuation.
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Whether `text` contains the words of `phrase` in a row, ignoring case and
+/// punctuation.
+pub fn contains_phrase(text: &str, phrase: &str) -> bool {
+    let phrase = words(phrase);
+    !phrase.is_empty()
+        && words(text)
+            .windows(phrase.len())
+            .any(|window| window == phrase.as_slice())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_phrase_ignoring_case_and_punctuation() {
+        assert!(contains_phrase(" Start recording.", "start recording"));
+        assert!(contains_phrase("OK, start, recording!", "Start recording"));
+        assert!(!contains_phrase("Start the recording", "start recording"));
+        assert!(!contains_phrase("restart recording", "start recording"));
+        assert!(!contains_phrase("anything", ""));
+    }
+}
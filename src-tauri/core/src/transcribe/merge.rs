@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Segment, Transcript};
+
+/// How whisper's often choppy segments are joined into sentences before
+/// transcripts are returned or exported.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MergeSettings {
+    pub enabled: bool,
+    /// Segments further apart than this, in ms, stay separate.
+    pub max_gap_ms: u64,
+    /// A merged segment never grows past this many characters.
+    pub max_chars: usize,
+}
+
+impl Default for MergeSettings {
+    fn default() -> Self {
+        MergeSettings {
+            enabled: false,
+            max_gap_ms: 1000,
+            max_chars: 300,
+        }
+    }
+}
+
+/// Closes a sentence, in Latin and CJK scripts.
+const SENTENCE_ENDS: &[char] = &['.', '!', '?', '。', '！', '？', '…'];
+
+fn ends_sentence(text: &str) -> bool {
+    text.trim_end()
+        .trim_end_matches(['"', '\'', ')', '”', '’'])
+        .ends_with(SENTENCE_ENDS)
+}
+
+/// Joins each segment onto the one before while that one hasn't finished its
+/// sentence, the pause between them is short and the result isn't too long.
+/// Speaker turns always start a new segment.
+pub fn merge_segments(transcript: &Transcript, settings: &MergeSettings) -> Transcript {
+    if !settings.enabled {
+        return transcript.clone();
+    }
+    let max_gap = (settings.max_gap_ms / 10) as i64;
+    let mut segments: Vec<Segment> = Vec::with_capacity(transcript.segments.len());
+    for segment in &transcript.segments {
+        if let Some(last) = segments.last_mut() {
+            let joins = !last.speaker_turn_next
+                && !ends_sentence(&last.text)
+                && segment.start - last.end <= max_gap
+                && last.text.chars().count() + segment.text.chars().count() <= settings.max_chars;
+            if joins {
+                last.text.push_str(&segment.text);
+                last.end = segment.end;
+                last.speaker_turn_next = segment.speaker_turn_next;
+                continue;
+            }
+        }
+        segments.push(segment.clone());
+    }
+    Transcript { segments }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start: i64, end: i64, text: &str, speaker_turn_next: bool) -> Segment {
+        Segment {
+            start,
+            end,
+            text: text.to_string(),
+            speaker_turn_next,
+        }
+    }
+
+    #[test]
+    fn joins_unfinished_sentences_across_short_pauses() {
+        let transcript = Transcript {
+            segments: vec![
+                segment(0, 100, " So the plan", false),
+                segment(120, 200, " is to ship Friday.", false),
+                segment(210, 300, " Then we", false),
+                // Too long a pause.
+                segment(500, 600, " rest.", true),
+                // A new speaker.
+                segment(600, 700, " Sounds good", false),
+            ],
+        };
+        let settings = MergeSettings {
+            enabled: true,
+            ..MergeSettings::default()
+        };
+        assert_eq!(
+            merge_segments(&transcript, &settings).segments,
+            vec![
+                segment(0, 200, " So the plan is to ship Friday.", false),
+                segment(210, 300, " Then we", false),
+                segment(500, 600, " rest.", true),
+                segment(600, 700, " Sounds good", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn respects_length_limit_and_can_be_off() {
+        let transcript = Transcript {
+            segments: vec![
+                segment(0, 100, " one two", false),
+                segment(100, 200, " three", false),
+            ],
+        };
+        let short = MergeSettings {
+            enabled: true,
+            max_chars: 10,
+            ..MergeSettings::default()
+        };
+        assert_eq!(merge_segments(&transcript, &short), transcript);
+        assert_eq!(
+            merge_segments(&transcript, &MergeSettings::default()),
+            transcript
+        );
+    }
+}
@@ -0,0 +1,385 @@
+pub mod action_items;
+pub mod align;
+pub mod benchmark;
+pub mod chapters;
+pub mod cjk;
+pub mod diff;
+pub mod document;
+pub mod entities;
+pub mod format;
+pub mod karaoke;
+pub mod key_phrases;
+pub mod keyword;
+pub mod merge;
+pub mod model;
+pub mod note;
+pub mod profile;
+pub mod registry;
+pub mod translate;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::ffi::{c_int, c_void, CStr};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use whisper_rs::{FullParams, SamplingStrategy};
+
+use crate::audio::decode::MonoStream;
+use crate::audio::{wav, ResampleQuality};
+use crate::i18n::{t, Msg};
+use crate::jobs::Limits;
+use profile::Profiler;
+
+/// Sample rate whisper expects its input at.
+pub const WHISPER_SAMPLE_RATE: u32 = 16000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+    /// Start time in centiseconds, as reported by whisper.
+    pub start: i64,
+    /// End time in centiseconds, as reported by whisper.
+    pub end: i64,
+    pub text: String,
+    /// Set by tinydiarize when the next segment is spoken by someone else.
+    pub speaker_turn_next: bool,
+}
+
+/// Joins segment text into one string per speaker turn.
+pub fn group_speaker_turns(segments: &[Segment]) -> Vec<String> {
+    let mut full_text = vec![String::new()];
+    for segment in segments {
+        full_text.last_mut().unwrap().push_str(&segment.text);
+        if segment.speaker_turn_next {
+            full_text.push(String::new());
+        }
+    }
+    full_text
+}
+
+/// The result of a transcription job.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Transcript {
+    pub segments: Vec<Segment>,
+}
+
+impl Transcript {
+    pub fn turns(&self) -> Vec<String> {
+        group_speaker_turns(&self.segments)
+    }
+
+    /// Index of the segment being spoken `ms` into the recording, if any.
+    pub fn segment_at(&self, ms: u64) -> Option<usize> {
+        let cs = (ms / 10) as i64;
+        let next = self.segments.partition_point(|segment| segment.start <= cs);
+        let index = next.checked_sub(1)?;
+        (cs < self.segments[index].end).then_some(index)
+    }
+}
+
+/// Segments fresh from whisper, with the language it heard.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transcription {
+    pub segments: Vec<Segment>,
+    /// Language code whisper detected in the first window, e.g. `ja`.
+    /// English-only models always report `en`.
+    pub language: Option<&'static str>,
+}
+
+/// Audio is fed to whisper this many seconds at a time, so memory stays
+/// flat however long the recording is. A word right on a boundary may be
+/// split between two segments.
+const WINDOW_SECS: usize = 600;
+
+const WINDOW_SAMPLES: usize = WINDOW_SECS * WHISPER_SAMPLE_RATE as usize;
+
+/// Transcribes a WAV file, reporting whisper's progress percentage to
+/// `on_progress` and each segment, speaker turn included, to `on_segment` as
+/// soon as it's decoded. The file is decoded a window at a time rather than
+/// all up front. Gives up as soon as `cancelled` returns true. Whisper stays
+/// within `limits`.
+pub fn transcribe_file(
+    audio_path: &Path,
+    model_path: &Path,
+    quality: ResampleQuality,
+    limits: Limits,
+    on_progress: impl FnMut(i32) + 'static,
+    on_segment: impl FnMut(Segment) + 'static,
+    cancelled: impl Fn() -> bool + 'static,
+) -> Result<Transcription> {
+    if !audio_path.exists() {
+        bail!("{}", t(Msg::AudioFileMissing));
+    }
+    if !model_path.exists() {
+        bail!("{}", t(Msg::ModelFileMissing));
+    }
+
+    let duration_ms = wav::info(audio_path)?.duration_ms as usize;
+    let windows = duration_ms.div_ceil(WINDOW_SECS * 1000).max(1);
+    let mut stream = MonoStream::open(audio_path, WHISPER_SAMPLE_RATE, quality)?;
+    let mut transcriber = Transcriber::new(model_path, limits, on_progress, on_segment, cancelled)?;
+    let mut reading = Duration::ZERO;
+    for index in 0.. {
+        let started = Instant::now();
+        let samples = stream.read(WINDOW_SAMPLES)?;
+        reading += started.elapsed();
+        if samples.is_empty() {
+            break;
+        }
+        transcriber.window(&samples, index, windows.max(index + 1))?;
+    }
+    // Decoding and resampling are interleaved, so they're reported as totals.
+    let mut profiler = transcriber.profiler;
+    profiler.record("decode", reading.saturating_sub(stream.resample_time()));
+    profiler.record("resample", stream.resample_time());
+    profiler.finish();
+    Ok(Transcription {
+        segments: transcriber.segments,
+        language: transcriber.language,
+    })
+}
+
+/// Transcribes mono samples at [`WHISPER_SAMPLE_RATE`] that are already in
+/// memory, e.g. straight from the microphone, like [`transcribe_file`].
+pub fn transcribe_samples(
+    samples: &[f32],
+    model_path: &Path,
+    limits: Limits,
+    on_progress: impl FnMut(i32) + 'static,
+    on_segment: impl FnMut(Segment) + 'static,
+    cancelled: impl Fn() -> bool + 'static,
+) -> Result<Transcription> {
+    let mut transcriber = Transcriber::new(model_path, limits, on_progress, on_segment, cancelled)?;
+    let windows = samples.len().div_ceil(WINDOW_SAMPLES).max(1);
+    for (index, window) in samples.chunks(WINDOW_SAMPLES).enumerate() {
+        transcriber.window(window, index, windows)?;
+    }
+    transcriber.profiler.finish();
+    Ok(Transcription {
+        segments: transcriber.segments,
+        language: transcriber.language,
+    })
+}
+
+/// Runs whisper over consecutive windows of one recording, shifting each
+/// window's timestamps and progress into place.
+struct Transcriber {
+    model: Arc<model::Model>,
+    threads: Option<usize>,
+    on_progress: Rc<RefCell<dyn FnMut(i32)>>,
+    on_segment: Rc<RefCell<dyn FnMut(Segment)>>,
+    cancelled: Rc<dyn Fn() -> bool>,
+    /// Where the next window starts, in centiseconds.
+    offset: i64,
+    segments: Vec<Segment>,
+    language: Option<&'static str>,
+    profiler: Profiler,
+}
+
+impl Transcriber {
+    fn new(
+        model_path: &Path,
+        limits: Limits,
+        on_progress: impl FnMut(i32) + 'static,
+        on_segment: impl FnMut(Segment) + 'static,
+        cancelled: impl Fn() -> bool + 'static,
+    ) -> Result<Self> {
+        let mut profiler = Profiler::new();
+        Ok(Transcriber {
+            model: profiler.time("model load", || {
+                model::load_with(model_path, !limits.cpu_only)
+            })?,
+            threads: limits.threads,
+            on_progress: Rc::new(RefCell::new(on_progress)),
+            on_segment: Rc::new(RefCell::new(on_segment)),
+            cancelled: Rc::new(cancelled),
+            offset: 0,
+            segments: Vec::new(),
+            language: None,
+            profiler,
+        })
+    }
+
+    /// Transcribes window `index` of `count`.
+    fn window(&mut self, samples: &[f32], index: usize, count: usize) -> Result<()> {
+        if (self.cancelled)() {
+            bail!("transcription was cancelled");
+        }
+        let mut state = self.model.state()?;
+        let mut params = FullParams::new(SamplingStrategy::default());
+        params.set_initial_prompt("experience");
+        if self.model.multilingual() {
+            // Left at whisper's default, everything is transcribed as English.
+            params.set_language(Some("auto"));
+        }
+        if let Some(threads) = self.threads {
+            params.set_n_threads(threads.max(1) as c_int);
+        }
+        let on_progress = self.on_progress.clone();
+        params.set_progress_callback_safe(move |p: i32| {
+            (on_progress.borrow_mut())((index as i32 * 100 + p) / count as i32)
+        });
+        let offset = self.offset;
+        let sink = SegmentSink {
+            on_segment: self.on_segment.clone(),
+            offset,
+        };
+        // SAFETY: `sink` outlives `state.full` below, the only place whisper
+        // calls back with it.
+        unsafe {
+            params.set_new_segment_callback(Some(new_segments));
+            params.set_new_segment_callback_user_data(&sink as *const SegmentSink as *mut c_void);
+        }
+        params.set_tdrz_enable(true);
+        let cancelled = self.cancelled.clone();
+        params.set_abort_callback_safe(move || cancelled());
+
+        let st = std::time::Instant::now();
+        let result = state.full(params, samples);
+        if (self.cancelled)() {
+            bail!("transcription was cancelled");
+        }
+        result.context("failed to transcribe audio")?;
+        let et = std::time::Instant::now();
+
+        if self.language.is_none() {
+            self.language = state
+                .full_lang_id_from_state()
+                .ok()
+                .and_then(whisper_rs::get_lang_str);
+        }
+        let num_segments = state
+            .full_n_segments()
+            .context("failed to get number of segments")?;
+        for i in 0..num_segments {
+            let segment = Segment {
+                text: state
+                    .full_get_segment_text(i)
+                    .context("failed to get segment")?,
+                start: state
+                    .full_get_segment_t0(i)
+                    .context("failed to get start timestamp")?
+                    + offset,
+                end: state
+                    .full_get_segment_t1(i)
+                    .context("failed to get end timestamp")?
+                    + offset,
+                speaker_turn_next: state.full_get_segment_speaker_turn_next(i),
+            };
+            println!("[{} - {}]: {}", segment.start, segment.end, segment.text);
+            self.segments.push(segment);
+        }
+        println!("Transcription took {}ms", (et - st).as_millis());
+        // whisper.cpp encodes and decodes each 30 s slice in turn, so the two
+        // can only be timed together from here.
+        self.profiler
+            .record(format!("window {} (encode + decode)", index + 1), et - st);
+        self.offset += samples.len() as i64 * 100 / WHISPER_SAMPLE_RATE as i64;
+        Ok(())
+    }
+}
+
+/// What whisper's new-segment callback reports to.
+struct SegmentSink {
+    on_segment: Rc<RefCell<dyn FnMut(Segment)>>,
+    /// Start of the window, in centiseconds.
+    offset: i64,
+}
+
+/// Reads newly decoded segments straight from whisper's state, since the
+/// safe callback in whisper-rs leaves out the tinydiarize speaker turn.
+unsafe extern "C" fn new_segments(
+    _ctx: *mut whisper_rs_sys::whisper_context,
+    state: *mut whisper_rs_sys::whisper_state,
+    n_new: c_int,
+    user_data: *mut c_void,
+) {
+    let sink = &*(user_data as *const SegmentSink);
+    let total = whisper_rs_sys::whisper_full_n_segments_from_state(state);
+    for i in (total - n_new).max(0)..total {
+        let text = whisper_rs_sys::whisper_full_get_segment_text_from_state(state, i);
+        if text.is_null() {
+            continue;
+        }
+        let segment = Segment {
+            start: whisper_rs_sys::whisper_full_get_segment_t0_from_state(state, i) + sink.offset,
+            end: whisper_rs_sys::whisper_full_get_segment_t1_from_state(state, i) + sink.offset,
+            text: CStr::from_ptr(text).to_string_lossy().into_owned(),
+            speaker_turn_next:
+                whisper_rs_sys::whisper_full_get_segment_speaker_turn_next_from_state(state, i),
+        };
+        (sink.on_segment.borrow_mut())(segment);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, speaker_turn_next: bool) -> Segment {
+        Segment {
+            start: 0,
+            end: 0,
+            text: text.to_string(),
+            speaker_turn_next,
+        }
+    }
+
+    #[test]
+    fn groups_by_speaker_turn() {
+        let segments = [
+            segment(" Hello", false),
+            segment(" there.", true),
+            segment(" Hi!", false),
+        ];
+        assert_eq!(
+            group_speaker_turns(&segments),
+            vec![" Hello there.", " Hi!"]
+        );
+    }
+
+    #[test]
+    fn trailing_turn_leaves_empty_group() {
+        let segments = [segment(" One.", true)];
+        assert_eq!(group_speaker_turns(&segments), vec![" One.", ""]);
+    }
+
+    #[test]
+    fn no_segments_yields_single_empty_group() {
+        assert_eq!(group_speaker_turns(&[]), vec![String::new()]);
+    }
+
+    #[test]
+    fn finds_segment_being_spoken() {
+        let timed = |start, end| Segment {
+            start,
+            end,
+            ..segment("", false)
+        };
+        let transcript = Transcript {
+            segments: vec![timed(0, 150), timed(150, 300), timed(400, 500)],
+        };
+        assert_eq!(transcript.segment_at(0), Some(0));
+        assert_eq!(transcript.segment_at(1500), Some(1));
+        assert_eq!(transcript.segment_at(3500), None);
+        assert_eq!(transcript.segment_at(4999), Some(2));
+        assert_eq!(transcript.segment_at(6000), None);
+    }
+
+    #[test]
+    fn missing_audio_is_reported() {
+        let err = transcribe_file(
+            Path::new("missing.wav"),
+            Path::new("missing.bin"),
+            ResampleQuality::Fast,
+            Limits::default(),
+            |_| {},
+            |_| {},
+            || false,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "audio file doesn't exist");
+    }
+}
@@ -0,0 +1,425 @@
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use whisper_rs::{WhisperContext, WhisperContextParameters, WhisperState};
+
+use super::registry::{self, Verification};
+use crate::i18n::{t, Msg};
+
+/// The last model used, kept loaded so the next transcription with it
+/// starts right away. There's a copy for each of GPU and CPU-only use that's
+/// been asked for, so jobs with different limits don't keep evicting each
+/// other's.
+static LOADED: Mutex<Vec<(PathBuf, Arc<Model>)>> = Mutex::new(Vec::new());
+
+static POOL_SIZE: AtomicUsize = AtomicUsize::new(2);
+
+static OPENVINO: AtomicBool = AtomicBool::new(false);
+
+/// Device OpenVINO runs the encoder on. The CPU is always there; Intel GPUs
+/// would need drivers we can't count on.
+#[cfg(feature = "openvino")]
+const OPENVINO_DEVICE: &str = "CPU";
+
+/// Returns the whisper model at `path`, loading it unless it's the one
+/// already in memory. Concurrent callers wait for a load in progress rather
+/// than loading a second copy.
+pub fn load(path: &Path) -> Result<Arc<Model>> {
+    load_with(path, true)
+}
+
+/// Like [`load`], but with `gpu` false the model runs on the CPU only. The
+/// two can't share weights, so the first CPU-only job next to GPU ones loads
+/// a second copy, which then stays loaded alongside the first.
+pub fn load_with(path: &Path, gpu: bool) -> Result<Arc<Model>> {
+    let mut loaded = LOADED.lock().unwrap();
+    let cached = loaded
+        .iter()
+        .find(|(loaded_path, model)| loaded_path == path && model.gpu == gpu);
+    if let Some((_, model)) = cached {
+        return Ok(model.clone());
+    }
+    if !path.exists() {
+        bail!("{}", t(Msg::ModelFileMissing));
+    }
+    if !has_ggml_magic(path)? {
+        return Err(CorruptModel::new(path).into());
+    }
+    // Free other models before loading this one.
+    loaded.retain(|(loaded_path, _)| loaded_path == path);
+    // whisper.cpp picks the Core ML encoder up while it loads, or never.
+    let coreml = uses_coreml(path);
+    let mut params = WhisperContextParameters::default();
+    params.use_gpu(gpu);
+    #[allow(unused_mut)]
+    let mut ctx = match WhisperContext::new_with_params(&path.to_string_lossy(), params) {
+        Ok(ctx) => ctx,
+        // Running out of memory or a GPU that won't start fail here too, so
+        // the file is only blamed when it no longer matches its checksum.
+        Err(err) if registry::verify(path)? == Verification::Corrupt => {
+            eprintln!("Failed to load {}: {:?}", path.display(), err);
+            return Err(CorruptModel::new(path).into());
+        }
+        Err(err) => {
+            return Err(anyhow::Error::new(err)
+                .context(format!("failed to load the model at {}", path.display())))
+        }
+    };
+    #[allow(unused_mut)]
+    let mut openvino = false;
+    #[cfg(feature = "openvino")]
+    if uses_openvino(path) {
+        let encoder = openvino_encoder_path(path);
+        match ctx.init_openvino_encoder(Some(&encoder.to_string_lossy()), OPENVINO_DEVICE, None) {
+            Ok(()) => openvino = true,
+            Err(err) => eprintln!("Failed to start OpenVINO, using the CPU encoder: {:?}", err),
+        }
+    }
+    let model = Arc::new(Model {
+        ctx,
+        gpu,
+        coreml,
+        openvino,
+        max_states: max_states(path),
+        pool: Mutex::new(Pool::default()),
+        returned: Condvar::new(),
+    });
+    loaded.push((path.to_path_buf(), model.clone()));
+    Ok(model)
+}
+
+/// Loading the model at `path` failed because the file is damaged, rather
+/// than missing. Downloading it again fixes it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptModel {
+    pub path: PathBuf,
+}
+
+impl CorruptModel {
+    fn new(path: &Path) -> Self {
+        CorruptModel {
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// The corrupt model behind `err`, if that's why it failed.
+    pub fn find(err: &anyhow::Error) -> Option<&CorruptModel> {
+        err.chain().find_map(|cause| cause.downcast_ref())
+    }
+}
+
+impl fmt::Display for CorruptModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(t(Msg::ModelFileCorrupt))
+    }
+}
+
+impl std::error::Error for CorruptModel {}
+
+/// Whether the file at `path` starts like a ggml whisper model, which
+/// catches most files that were cut short or aren't models at all before
+/// whisper gets to them.
+fn has_ggml_magic(path: &Path) -> Result<bool> {
+    let mut magic = [0; 4];
+    Ok(File::open(path)?.read_exact(&mut magic).is_ok() && u32::from_le_bytes(magic) == GGML_MAGIC)
+}
+
+/// "ggml" as whisper.cpp writes it, little-endian.
+const GGML_MAGIC: u32 = 0x6767_6d6c;
+
+/// Drops the cached models, freeing their memory once nothing's using them.
+pub fn unload() {
+    LOADED.lock().unwrap().clear();
+}
+
+/// Whether the model at `path` is loaded, without waiting for a load in
+/// progress.
+pub fn is_loaded(path: &Path) -> bool {
+    LOADED
+        .try_lock()
+        .is_ok_and(|loaded| loaded.iter().any(|(p, _)| p == path))
+}
+
+/// Sets how many whisper states, and so transcriptions, can run on the
+/// loaded model at once. Takes effect as states are handed back.
+pub fn set_pool_size(size: usize) {
+    POOL_SIZE.store(size.max(1), Ordering::Relaxed);
+    for (_, model) in LOADED.lock().unwrap().iter() {
+        model.returned.notify_all();
+    }
+}
+
+/// A loaded whisper model, with the states transcriptions borrow to run on
+/// it. A state holds a transcription's working buffers, so several can share
+/// the weights, e.g. live captions alongside a batch job.
+pub struct Model {
+    ctx: WhisperContext,
+    gpu: bool,
+    coreml: bool,
+    openvino: bool,
+    /// Most states that fit in memory next to the model.
+    max_states: usize,
+    pool: Mutex<Pool>,
+    returned: Condvar,
+}
+
+#[derive(Default)]
+struct Pool {
+    idle: Vec<WhisperState>,
+    busy: usize,
+}
+
+impl Model {
+    /// Whether its encoder runs with Core ML. Fixed when it's loaded, so an
+    /// encoder downloaded since only counts once the model is loaded again.
+    pub fn coreml(&self) -> bool {
+        self.coreml
+    }
+
+    /// Whether its encoder runs with OpenVINO.
+    pub fn openvino(&self) -> bool {
+        self.openvino
+    }
+
+    /// Whether it handles languages other than English.
+    pub fn multilingual(&self) -> bool {
+        self.ctx.is_multilingual()
+    }
+
+    fn limit(&self) -> usize {
+        POOL_SIZE
+            .load(Ordering::Relaxed)
+            .min(self.max_states)
+            .max(1)
+    }
+
+    /// Borrows a state to transcribe with, waiting while the pool's all in
+    /// use. States are kept for reuse, so only the first few borrowers pay
+    /// to allocate one.
+    pub fn state(&self) -> Result<PooledState<'_>> {
+        let mut pool = self.pool.lock().unwrap();
+        while pool.busy >= self.limit() {
+            pool = self.returned.wait(pool).unwrap();
+        }
+        pool.busy += 1;
+        let idle = pool.idle.pop();
+        drop(pool);
+        let state = match idle {
+            Some(state) => state,
+            None => self.ctx.create_state().map_err(|err| {
+                self.give_back(None);
+                anyhow::Error::new(err).context("failed to create state")
+            })?,
+        };
+        Ok(PooledState {
+            model: self,
+            state: Some(state),
+        })
+    }
+
+    fn give_back(&self, state: Option<WhisperState>) {
+        let mut pool = self.pool.lock().unwrap();
+        pool.busy -= 1;
+        if let Some(state) = state {
+            // Keep no more than the pool may hand out, in case it shrank.
+            if pool.idle.len() + pool.busy < self.limit() {
+                pool.idle.push(state);
+            }
+        }
+        self.returned.notify_one();
+    }
+}
+
+/// A state borrowed from a [`Model`], returned to it when dropped.
+pub struct PooledState<'a> {
+    model: &'a Model,
+    state: Option<WhisperState>,
+}
+
+impl Deref for PooledState<'_> {
+    type Target = WhisperState;
+
+    fn deref(&self) -> &WhisperState {
+        self.state.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledState<'_> {
+    fn deref_mut(&mut self) -> &mut WhisperState {
+        self.state.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledState<'_> {
+    fn drop(&mut self) {
+        self.model.give_back(self.state.take());
+    }
+}
+
+/// How many states of the model at `path` fit in half of the machine's
+/// memory, counting each as big as the model file, which overestimates
+/// them for all but the smallest models.
+fn max_states(path: &Path) -> usize {
+    let size = path.metadata().map(|meta| meta.len()).unwrap_or(0).max(1);
+    match physical_memory() {
+        Some(memory) => ((memory / 2).saturating_sub(size) / size).max(1) as usize,
+        None => usize::MAX,
+    }
+}
+
+#[cfg(unix)]
+fn physical_memory() -> Option<u64> {
+    // SAFETY: sysconf only reads system configuration.
+    let (pages, page_size) = unsafe {
+        (
+            libc::sysconf(libc::_SC_PHYS_PAGES),
+            libc::sysconf(libc::_SC_PAGESIZE),
+        )
+    };
+    (pages > 0 && page_size > 0).then(|| pages as u64 * page_size as u64)
+}
+
+#[cfg(not(unix))]
+fn physical_memory() -> Option<u64> {
+    None
+}
+
+/// Whether this build can run whisper's encoder with Core ML, which is only
+/// compiled in for Apple Silicon with the `coreml` feature.
+pub fn coreml_supported() -> bool {
+    cfg!(all(
+        feature = "coreml",
+        target_os = "macos",
+        target_arch = "aarch64"
+    ))
+}
+
+/// Where whisper.cpp looks for the Core ML encoder of the model at `path`:
+/// `ggml-base.en.bin` next to `ggml-base.en-encoder.mlmodelc`. Quantized
+/// models share the encoder of the model they were quantized from.
+pub fn coreml_encoder_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let stem = match stem.rsplit_once("-q") {
+        Some((base, quant)) if quant.contains('_') => base.to_string(),
+        _ => stem,
+    };
+    path.with_file_name(format!("{}-encoder.mlmodelc", stem))
+}
+
+/// Whether loading the model at `path` picks up a Core ML encoder, which
+/// whisper.cpp does on its own when one sits next to the model.
+pub fn uses_coreml(path: &Path) -> bool {
+    coreml_supported() && coreml_encoder_path(path).is_dir()
+}
+
+/// Whether this build can run whisper's encoder with OpenVINO, which needs
+/// the `openvino` feature and the OpenVINO runtime installed.
+pub fn openvino_supported() -> bool {
+    cfg!(feature = "openvino")
+}
+
+/// Turns the OpenVINO encoder on or off for models loaded from now on. The
+/// model in memory is dropped when that changes, so the next transcription
+/// loads it again the new way.
+pub fn set_openvino(enabled: bool) {
+    if OPENVINO.swap(enabled, Ordering::Relaxed) != enabled {
+        unload();
+    }
+}
+
+/// Where whisper.cpp looks for the OpenVINO encoder of the model at `path`:
+/// `ggml-base.en.bin` next to `ggml-base.en-encoder-openvino.xml`. These
+/// aren't downloadable; they're converted from the original model with
+/// whisper.cpp's `convert-whisper-to-openvino.py`.
+pub fn openvino_encoder_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}-encoder-openvino.xml", stem))
+}
+
+/// Whether loading the model at `path` runs its encoder with OpenVINO: the
+/// setting is on, the build supports it and the encoder is next to the model.
+pub fn uses_openvino(path: &Path) -> bool {
+    OPENVINO.load(Ordering::Relaxed)
+        && openvino_supported()
+        && openvino_encoder_path(path).is_file()
+}
+
+/// Which encoder backends this build and machine can use, and which the
+/// model at a given path has encoders for, so the UI only offers what works.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Capabilities {
+    pub coreml_supported: bool,
+    pub coreml_encoder: bool,
+    pub openvino_supported: bool,
+    pub openvino_encoder: bool,
+    /// OpenVINO makes the most difference on Intel CPUs.
+    pub intel_cpu: bool,
+}
+
+pub fn capabilities(path: &Path) -> Capabilities {
+    Capabilities {
+        coreml_supported: coreml_supported(),
+        coreml_encoder: coreml_encoder_path(path).is_dir(),
+        openvino_supported: openvino_supported(),
+        openvino_encoder: openvino_encoder_path(path).is_file(),
+        intel_cpu: intel_cpu(),
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn intel_cpu() -> bool {
+    // SAFETY: every x86_64 CPU has cpuid.
+    let id = unsafe { std::arch::x86_64::__cpuid(0) };
+    [id.ebx, id.edx, id.ecx].map(u32::to_le_bytes).concat() == b"GenuineIntel"
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn intel_cpu() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coreml_encoder_sits_next_to_the_model() {
+        assert_eq!(
+            coreml_encoder_path(Path::new("/models/ggml-base.en.bin")),
+            Path::new("/models/ggml-base.en-encoder.mlmodelc")
+        );
+        assert_eq!(
+            coreml_encoder_path(Path::new("/models/ggml-large-v3-turbo-q5_0.bin")),
+            Path::new("/models/ggml-large-v3-turbo-encoder.mlmodelc")
+        );
+    }
+
+    #[test]
+    fn files_that_are_not_models_are_corrupt() {
+        let path = std::env::temp_dir().join(format!("app-core-model-{}.bin", std::process::id()));
+        std::fs::write(&path, b"<html>rate limited</html>").unwrap();
+        let err = load(&path).err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(CorruptModel::find(&err), Some(&CorruptModel::new(&path)));
+    }
+
+    #[test]
+    fn openvino_encoder_sits_next_to_the_model() {
+        assert_eq!(
+            openvino_encoder_path(Path::new("/models/ggml-small.en.bin")),
+            Path::new("/models/ggml-small.en-encoder-openvino.xml")
+        );
+    }
+}
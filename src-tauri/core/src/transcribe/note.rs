@@ -0,0 +1,114 @@
+use chrono::{Local, TimeZone};
+
+use super::format::{self, timestamp, Format};
+use super::Transcript;
+use crate::library::Recording;
+
+/// A Markdown note for a notes app like Obsidian: YAML frontmatter with the
+/// recording's date, duration, tags and a link to the audio, then the
+/// transcript.
+pub fn render_note(recording: &Recording, transcript: &Transcript) -> String {
+    let date = Local
+        .timestamp_millis_opt(recording.created_at)
+        .single()
+        .map(|date| date.format("%Y-%m-%dT%H:%M:%S").to_string())
+        .unwrap_or_default();
+    let quote = |text: &str| serde_json::to_string(text).unwrap_or_default();
+    let tags: Vec<String> = recording.tags.iter().map(|tag| quote(tag)).collect();
+    let audio = file_url(&recording.path.to_string_lossy());
+    format!(
+        "---\ntitle: {}\ndate: {}\nduration: {}\ntags: [{}]\naudio: {}\n---\n\n\
+         # {}\n\n[Audio]({})\n\n{}\n",
+        quote(&recording.title),
+        date,
+        &timestamp(recording.duration_ms / 10, '.')[..8],
+        tags.join(", "),
+        quote(&audio),
+        recording.title,
+        audio,
+        format::render(transcript, Format::Markdown)
+    )
+}
+
+/// A file name for the note that's safe on every platform.
+pub fn note_file_name(title: &str) -> String {
+    let name: String = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c if c.is_control() => '-',
+            c => c,
+        })
+        .collect();
+    let name = name.trim().trim_start_matches('.');
+    if name.is_empty() {
+        "Transcript.md".to_string()
+    } else {
+        format!("{}.md", name)
+    }
+}
+
+/// `file://` URL for an absolute path, percent-encoding anything that isn't
+/// safe in a Markdown link.
+fn file_url(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let mut url = String::from(if path.starts_with('/') {
+        "file://"
+    } else {
+        "file:///"
+    });
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                url.push(byte as char)
+            }
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcribe::Segment;
+    use std::path::PathBuf;
+
+    #[test]
+    fn note_has_frontmatter_and_transcript() {
+        let recording = Recording {
+            id: 1,
+            title: "Weekly \"sync\"".to_string(),
+            path: PathBuf::from("/Users/me/My Recordings/sync.wav"),
+            created_at: 0,
+            duration_ms: 61_000,
+            sample_rate: 16_000,
+            channels: 1,
+            device: None,
+            favorite: false,
+            tags: vec!["work".to_string(), "1:1".to_string()],
+            trashed_at: None,
+            session_id: None,
+            audio_deleted_at: None,
+        };
+        let transcript = Transcript {
+            segments: vec![Segment {
+                start: 0,
+                end: 100,
+                text: " Hello.".to_string(),
+                speaker_turn_next: false,
+            }],
+        };
+        let note = render_note(&recording, &transcript);
+        assert!(note.starts_with("---\ntitle: \"Weekly \\\"sync\\\"\"\ndate: "));
+        assert!(note.contains("\nduration: 00:01:01\ntags: [\"work\", \"1:1\"]\n"));
+        assert!(note.contains("audio: \"file:///Users/me/My%20Recordings/sync.wav\"\n---\n"));
+        assert!(note.ends_with("**[00:00:00.000]** Hello.\n"));
+    }
+
+    #[test]
+    fn file_names_are_sanitized() {
+        assert_eq!(note_file_name("1:1 / Ana?"), "1-1 - Ana-.md");
+        assert_eq!(note_file_name("  "), "Transcript.md");
+    }
+}
@@ -0,0 +1,116 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// One step of a transcription and how long it took.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Stage {
+    pub name: String,
+    pub duration_ms: f64,
+    /// The process's peak resident memory when the stage ended.
+    pub peak_rss_bytes: Option<u64>,
+}
+
+/// Where a transcription spent its time, for performance bug reports.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Profile {
+    pub stages: Vec<Stage>,
+    pub total_ms: f64,
+    pub peak_rss_bytes: Option<u64>,
+}
+
+static LAST: Mutex<Option<Profile>> = Mutex::new(None);
+
+/// The profile of the most recently finished transcription.
+pub fn last() -> Option<Profile> {
+    LAST.lock().unwrap().clone()
+}
+
+/// Collects stages as a transcription runs.
+pub struct Profiler {
+    started: Instant,
+    stages: Vec<Stage>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler {
+            started: Instant::now(),
+            stages: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, name: impl Into<String>, duration: Duration) {
+        self.stages.push(Stage {
+            name: name.into(),
+            duration_ms: duration.as_secs_f64() * 1000.0,
+            peak_rss_bytes: peak_rss_bytes(),
+        });
+    }
+
+    pub fn time<T>(&mut self, name: impl Into<String>, f: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let result = f();
+        self.record(name, started.elapsed());
+        result
+    }
+
+    /// Wraps up the profile and keeps it as the [`last`] one.
+    pub fn finish(self) -> Profile {
+        let profile = Profile {
+            stages: self.stages,
+            total_ms: self.started.elapsed().as_secs_f64() * 1000.0,
+            peak_rss_bytes: peak_rss_bytes(),
+        };
+        *LAST.lock().unwrap() = Some(profile.clone());
+        profile
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The most memory the process has had resident at once.
+#[cfg(unix)]
+pub fn peak_rss_bytes() -> Option<u64> {
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::zeroed();
+    // SAFETY: getrusage only writes to the struct we hand it.
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    let max_rss = unsafe { usage.assume_init() }.ru_maxrss as u64;
+    // Linux reports kilobytes, macOS bytes.
+    Some(if cfg!(target_os = "macos") {
+        max_rss
+    } else {
+        max_rss * 1024
+    })
+}
+
+#[cfg(not(unix))]
+pub fn peak_rss_bytes() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_stages_and_keeps_the_last_profile() {
+        let mut profiler = Profiler::new();
+        let answer = profiler.time("decode", || 42);
+        profiler.record("window 1", Duration::from_millis(5));
+        let profile = profiler.finish();
+        assert_eq!(answer, 42);
+        let names: Vec<&str> = profile.stages.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["decode", "window 1"]);
+        assert_eq!(profile.stages[1].duration_ms, 5.0);
+        assert!(last().is_some());
+        #[cfg(unix)]
+        assert!(profile.peak_rss_bytes.unwrap() > 0);
+    }
+}
@@ -0,0 +1,364 @@
+//! The whisper models the app knows how to download and run.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+use super::model::coreml_encoder_path;
+
+const WHISPER_CPP: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+const TINYDIARIZE: &str = "https://huggingface.co/akashmjn/tinydiarize-whisper.cpp/resolve/main";
+
+/// A ggml whisper model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ModelInfo {
+    /// Stable name used in settings, e.g. `large-v3-turbo`.
+    pub id: &'static str,
+    pub file_name: &'static str,
+    /// Where [`download`] gets it from.
+    pub base_url: &'static str,
+    /// Download size in MB.
+    pub size_mb: u32,
+    /// Roughly how much memory transcribing with it takes, in MB.
+    pub ram_mb: u32,
+    /// Only transcribes English; the others detect and handle ~100 languages.
+    pub english_only: bool,
+    /// Marks speaker turns with tinydiarize.
+    pub tdrz: bool,
+    /// Large-v3 with a pruned decoder: close to large quality at several
+    /// times the speed.
+    pub turbo: bool,
+    /// Has a Core ML encoder to download alongside it for Apple Silicon.
+    pub coreml: bool,
+}
+
+impl ModelInfo {
+    pub fn url(&self) -> String {
+        format!("{}/{}", self.base_url, self.file_name)
+    }
+
+    /// Where the zipped Core ML encoder is, if there is one.
+    pub fn coreml_url(&self) -> Option<String> {
+        let encoder = coreml_encoder_path(Path::new(self.file_name));
+        self.coreml.then(|| {
+            format!(
+                "{}/{}.zip",
+                self.base_url,
+                encoder.file_name().unwrap().to_string_lossy()
+            )
+        })
+    }
+}
+
+const fn model(
+    id: &'static str,
+    file_name: &'static str,
+    size_mb: u32,
+    ram_mb: u32,
+    english_only: bool,
+) -> ModelInfo {
+    ModelInfo {
+        id,
+        file_name,
+        base_url: WHISPER_CPP,
+        size_mb,
+        ram_mb,
+        english_only,
+        tdrz: false,
+        turbo: false,
+        coreml: true,
+    }
+}
+
+/// Every model the app offers, smallest first.
+pub const MODELS: &[ModelInfo] = &[
+    model("tiny", "ggml-tiny.bin", 75, 390, false),
+    model("tiny.en", "ggml-tiny.en.bin", 75, 390, true),
+    model("base", "ggml-base.bin", 142, 500, false),
+    model("base.en", "ggml-base.en.bin", 142, 500, true),
+    model("small", "ggml-small.bin", 466, 1000, false),
+    model("small.en", "ggml-small.en.bin", 466, 1000, true),
+    ModelInfo {
+        base_url: TINYDIARIZE,
+        tdrz: true,
+        coreml: false,
+        ..model("small.en-tdrz", "ggml-small.en-tdrz.bin", 465, 1000, true)
+    },
+    model("medium", "ggml-medium.bin", 1500, 2600, false),
+    model("medium.en", "ggml-medium.en.bin", 1500, 2600, true),
+    ModelInfo {
+        turbo: true,
+        ..model(
+            "large-v3-turbo-q5_0",
+            "ggml-large-v3-turbo-q5_0.bin",
+            547,
+            1300,
+            false,
+        )
+    },
+    ModelInfo {
+        turbo: true,
+        ..model(
+            "large-v3-turbo",
+            "ggml-large-v3-turbo.bin",
+            1600,
+            2300,
+            false,
+        )
+    },
+    model("large-v3", "ggml-large-v3.bin", 3100, 4700, false),
+];
+
+/// The model the app used before there was a choice, and still the default
+/// since it's the only one that marks speaker turns.
+pub const DEFAULT_MODEL: &str = "small.en-tdrz";
+
+pub fn find(id: &str) -> Option<&'static ModelInfo> {
+    MODELS.iter().find(|model| model.id == id)
+}
+
+/// Downloads `model` into `dir`, reporting bytes received and the total
+/// when known. The file only gets its real name once it's complete and
+/// matches the SHA-256 the server lists for it, so an interrupted, damaged
+/// or unverifiable download never looks like a usable model. The hash is
+/// kept next to the model for [`verify`]. Blocks until done.
+pub fn download(
+    model: &ModelInfo,
+    dir: &Path,
+    on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(model.file_name);
+    let sha256 = fetch(&model.url(), &path.with_extension("bin.part"), on_progress)?;
+    fs::write(checksum_path(&path), &sha256)?;
+    fs::rename(path.with_extension("bin.part"), &path)?;
+    Ok(path)
+}
+
+/// How a model file compares with the checksum recorded when it was
+/// downloaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verification {
+    Intact,
+    Corrupt,
+    /// It wasn't downloaded by the app, so there's nothing to compare with.
+    Unknown,
+}
+
+/// Hashes the model at `path` and compares it with the checksum saved by
+/// [`download`]. Reads the whole file, which takes a few seconds for the
+/// large models.
+pub fn verify(path: &Path) -> Result<Verification> {
+    let Ok(expected) = fs::read_to_string(checksum_path(path)) else {
+        return Ok(Verification::Unknown);
+    };
+    Ok(if sha256_of(path)? == expected.trim() {
+        Verification::Intact
+    } else {
+        Verification::Corrupt
+    })
+}
+
+/// Checks a copy of `model` at `path` that wasn't downloaded by the app
+/// against the SHA-256 the server lists, and records it for [`verify`] if
+/// it matches. Reads the whole file.
+pub fn adopt(model: &ModelInfo, path: &Path) -> Result<Verification> {
+    let expected = expected_sha256(&model.url())?;
+    if sha256_of(path)? != expected {
+        return Ok(Verification::Corrupt);
+    }
+    fs::write(checksum_path(path), expected)?;
+    Ok(Verification::Intact)
+}
+
+fn sha256_of(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Where [`download`] keeps the SHA-256 of the model at `path`.
+pub fn checksum_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".sha256");
+    path.with_file_name(name)
+}
+
+/// Downloads and unpacks the Core ML encoder of `model` next to it in `dir`,
+/// like [`download`]. Returns `None` for models without one.
+pub fn download_coreml(
+    model: &ModelInfo,
+    dir: &Path,
+    on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<Option<PathBuf>> {
+    let Some(url) = model.coreml_url() else {
+        return Ok(None);
+    };
+    fs::create_dir_all(dir)?;
+    let encoder = coreml_encoder_path(&dir.join(model.file_name));
+    let archive = encoder.with_extension("mlmodelc.zip.part");
+    fetch(&url, &archive, on_progress)?;
+    let unpacked = encoder.with_extension("mlmodelc.part");
+    let result = unzip(&archive, &unpacked).and_then(|()| {
+        let inner = unpacked.join(encoder.file_name().unwrap());
+        if !inner.is_dir() {
+            bail!("{} doesn't contain a Core ML encoder", url);
+        }
+        if encoder.exists() {
+            fs::remove_dir_all(&encoder)?;
+        }
+        fs::rename(inner, &encoder)?;
+        Ok(())
+    });
+    let _ = fs::remove_file(&archive);
+    let _ = fs::remove_dir_all(&unpacked);
+    result?;
+    Ok(Some(encoder))
+}
+
+/// The SHA-256 Hugging Face lists for a file it stores with LFS, which is
+/// every model. It's only on the redirect to the download, hence not
+/// following it.
+fn expected_sha256(url: &str) -> Result<String> {
+    let agent = ureq::AgentBuilder::new().redirects(0).build();
+    let response = agent
+        .head(url)
+        .call()
+        .with_context(|| format!("failed to look up the checksum of {}", url))?;
+    let etag = response
+        .header("X-Linked-Etag")
+        .map(|etag| etag.trim_matches('"'))
+        .filter(|etag| etag.len() == 64 && etag.chars().all(|c| c.is_ascii_hexdigit()));
+    match etag {
+        Some(etag) => Ok(etag.to_ascii_lowercase()),
+        None => bail!("{} doesn't list a checksum to verify it against", url),
+    }
+}
+
+/// Downloads `url` to `dest`, removing what's there if it fails partway or
+/// doesn't match its listed checksum. Fails without downloading anything
+/// when there's no checksum to check against. Returns the SHA-256 of what
+/// was downloaded.
+fn fetch(url: &str, dest: &Path, mut on_progress: impl FnMut(u64, Option<u64>)) -> Result<String> {
+    let expected = expected_sha256(url)?;
+    let response = ureq::get(url)
+        .call()
+        .with_context(|| format!("failed to download {}", url))?;
+    let total = response
+        .header("Content-Length")
+        .and_then(|length| length.parse().ok());
+    let mut file =
+        File::create(dest).with_context(|| format!("failed to create {}", dest.display()))?;
+    let mut hasher = Sha256::new();
+    let received = match copy(response.into_reader(), &mut file, &mut hasher, |received| {
+        on_progress(received, total)
+    }) {
+        Ok(received) => received,
+        Err(err) => {
+            let _ = fs::remove_file(dest);
+            return Err(err.context(format!("failed to download {}", url)));
+        }
+    };
+    if total.is_some_and(|total| total != received) {
+        let _ = fs::remove_file(dest);
+        bail!("download of {} ended early", url);
+    }
+    let sha256 = hex::encode(hasher.finalize());
+    if sha256 != expected {
+        let _ = fs::remove_file(dest);
+        bail!("download of {} is corrupt: its checksum doesn't match", url);
+    }
+    Ok(sha256)
+}
+
+/// Unpacks the zip at `archive` into `dir`. Entries that would land outside
+/// `dir` are skipped.
+fn unzip(archive: &Path, dir: &Path) -> Result<()> {
+    let mut archive = ZipArchive::new(File::open(archive)?).context("not a zip archive")?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let Some(name) = entry.enclosed_name().map(|name| dir.join(name)) else {
+            continue;
+        };
+        if entry.is_dir() {
+            fs::create_dir_all(&name)?;
+            continue;
+        }
+        if let Some(parent) = name.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut entry, &mut File::create(&name)?)?;
+    }
+    Ok(())
+}
+
+/// Copies `reader` into `file` and `hasher`, reporting the bytes copied so far.
+fn copy(
+    mut reader: impl Read,
+    file: &mut File,
+    hasher: &mut Sha256,
+    mut on_progress: impl FnMut(u64),
+) -> Result<u64> {
+    let mut buf = vec![0; 1 << 16];
+    let mut received = 0;
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            file.flush()?;
+            return Ok(received);
+        }
+        file.write_all(&buf[..n])?;
+        hasher.update(&buf[..n]);
+        received += n as u64;
+        on_progress(received);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_and_file_names_are_unique() {
+        for (i, model) in MODELS.iter().enumerate() {
+            assert!(MODELS[i + 1..]
+                .iter()
+                .all(|other| other.id != model.id && other.file_name != model.file_name));
+        }
+        assert!(find(DEFAULT_MODEL).is_some_and(|model| model.tdrz));
+        assert!(find("large-v3-turbo").is_some_and(|model| model.turbo));
+        assert_eq!(
+            find("large-v3").unwrap().url(),
+            "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3.bin"
+        );
+    }
+
+    #[test]
+    fn verifies_against_the_saved_checksum() {
+        let dir = std::env::temp_dir().join(format!("app-core-registry-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("ggml-test.bin");
+        fs::write(&path, b"weights").unwrap();
+        assert_eq!(verify(&path).unwrap(), Verification::Unknown);
+        let sha256 = hex::encode(Sha256::digest(b"weights"));
+        fs::write(checksum_path(&path), sha256).unwrap();
+        assert_eq!(verify(&path).unwrap(), Verification::Intact);
+        fs::write(&path, b"weight").unwrap();
+        assert_eq!(verify(&path).unwrap(), Verification::Corrupt);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn coreml_encoders_follow_the_model() {
+        assert_eq!(
+            find("large-v3-turbo-q5_0").unwrap().coreml_url().unwrap(),
+            "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3-turbo-encoder.mlmodelc.zip"
+        );
+        assert_eq!(find(DEFAULT_MODEL).unwrap().coreml_url(), None);
+    }
+}
@@ -0,0 +1,216 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::PathBuf;
+
+use super::{Segment, Transcript};
+use crate::llm;
+
+/// Segments are sent to the translator this many at a time.
+const BATCH_SIZE: usize = 40;
+
+/// A service or model that translates text.
+pub trait Translator {
+    /// Short label stored with the translation, e.g. the model name.
+    fn name(&self) -> String;
+
+    /// Translates every text into `language`, answering in the same order.
+    fn translate(&self, texts: &[String], language: &str) -> Result<Vec<String>>;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranslationProvider {
+    /// The local LLM from `summary_model_path`.
+    #[default]
+    Local,
+    /// A LibreTranslate-compatible server at `api_url`.
+    LibreTranslate,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranslationSettings {
+    pub provider: TranslationProvider,
+    pub api_url: String,
+    /// Sent as `api_key` when not empty.
+    pub api_key: String,
+}
+
+/// Translates each segment's text into `language`, keeping its timing and
+/// speaker turns.
+pub fn translate(
+    transcript: &Transcript,
+    language: &str,
+    translator: &dyn Translator,
+) -> Result<Transcript> {
+    let mut segments = Vec::with_capacity(transcript.segments.len());
+    for batch in transcript.segments.chunks(BATCH_SIZE) {
+        let texts: Vec<String> = batch
+            .iter()
+            .map(|segment| segment.text.trim().to_string())
+            .collect();
+        let translated = translator.translate(&texts, language)?;
+        if translated.len() != texts.len() {
+            bail!(
+                "{} answered {} translations for {} segments",
+                translator.name(),
+                translated.len(),
+                texts.len()
+            );
+        }
+        segments.extend(batch.iter().zip(translated).map(|(segment, text)| Segment {
+            text: format!(" {}", text.trim()),
+            ..segment.clone()
+        }));
+    }
+    Ok(Transcript { segments })
+}
+
+/// Translates with the local llama.cpp model.
+pub struct LocalTranslator {
+    pub model: PathBuf,
+}
+
+impl Translator for LocalTranslator {
+    fn name(&self) -> String {
+        self.model
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn translate(&self, texts: &[String], language: &str) -> Result<Vec<String>> {
+        let output = llm::complete(&self.model, &numbered_prompt(texts, language))?;
+        parse_numbered(&output, texts.len())
+    }
+}
+
+fn numbered_prompt(texts: &[String], language: &str) -> String {
+    let lines: Vec<String> = texts
+        .iter()
+        .enumerate()
+        .map(|(i, text)| format!("{}. {}", i + 1, text))
+        .collect();
+    format!(
+        "Translate each numbered line below into {language}. Answer with the same \
+         numbers, one line each, and nothing else.\n\n{}\n\nTranslation:\n",
+        lines.join("\n")
+    )
+}
+
+/// Reads `N. text` lines back into order, failing if any number is missing.
+fn parse_numbered(output: &str, count: usize) -> Result<Vec<String>> {
+    let mut translations = vec![None; count];
+    for line in output.lines().map(str::trim) {
+        let Some((number, text)) = line.split_once(['.', ')']) else {
+            continue;
+        };
+        let Ok(number) = number.trim().parse::<usize>() else {
+            continue;
+        };
+        if let Some(slot) = number.checked_sub(1).and_then(|i| translations.get_mut(i)) {
+            slot.get_or_insert_with(|| text.trim().to_string());
+        }
+    }
+    translations
+        .into_iter()
+        .enumerate()
+        .map(|(i, text)| text.with_context(|| format!("the model skipped line {}", i + 1)))
+        .collect()
+}
+
+/// Translates with a LibreTranslate-compatible HTTP API.
+pub struct LibreTranslate {
+    pub url: String,
+    pub api_key: String,
+}
+
+#[derive(Deserialize)]
+struct LibreTranslateResponse {
+    #[serde(rename = "translatedText")]
+    translated_text: Vec<String>,
+}
+
+impl Translator for LibreTranslate {
+    fn name(&self) -> String {
+        "libretranslate".to_string()
+    }
+
+    fn translate(&self, texts: &[String], language: &str) -> Result<Vec<String>> {
+        let mut body = json!({
+            "q": texts,
+            "source": "auto",
+            "target": language,
+            "format": "text",
+        });
+        if !self.api_key.is_empty() {
+            body["api_key"] = json!(self.api_key);
+        }
+        let url = format!("{}/translate", self.url.trim_end_matches('/'));
+        let response: LibreTranslateResponse = ureq::post(&url)
+            .send_json(body)
+            .with_context(|| format!("translation request to {} failed", url))?
+            .into_json()?;
+        Ok(response.translated_text)
+    }
+}
+
+/// The translator `settings` ask for; the local one runs `model`.
+pub fn translator(settings: &TranslationSettings, model: PathBuf) -> Box<dyn Translator> {
+    match settings.provider {
+        TranslationProvider::Local => Box::new(LocalTranslator { model }),
+        TranslationProvider::LibreTranslate => Box::new(LibreTranslate {
+            url: settings.api_url.clone(),
+            api_key: settings.api_key.clone(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Shouting;
+
+    impl Translator for Shouting {
+        fn name(&self) -> String {
+            "shouting".to_string()
+        }
+
+        fn translate(&self, texts: &[String], _language: &str) -> Result<Vec<String>> {
+            Ok(texts.iter().map(|text| text.to_uppercase()).collect())
+        }
+    }
+
+    #[test]
+    fn keeps_timing_and_turns() {
+        let segments: Vec<Segment> = (0..BATCH_SIZE as i64 + 5)
+            .map(|i| Segment {
+                start: i * 100,
+                end: i * 100 + 90,
+                text: format!(" line {}", i),
+                speaker_turn_next: i == 2,
+            })
+            .collect();
+        let translated = translate(&Transcript { segments }, "de", &Shouting).unwrap();
+        assert_eq!(translated.segments.len(), BATCH_SIZE + 5);
+        assert_eq!(
+            translated.segments[2],
+            Segment {
+                start: 200,
+                end: 290,
+                text: " LINE 2".to_string(),
+                speaker_turn_next: true,
+            }
+        );
+    }
+
+    #[test]
+    fn numbered_answers_are_put_back_in_order() {
+        let output = "Sure:\n2. Welt\n1) Hallo\n";
+        assert_eq!(parse_numbered(output, 2).unwrap(), ["Hallo", "Welt"]);
+        assert!(parse_numbered("1. Hallo", 2).is_err());
+    }
+}
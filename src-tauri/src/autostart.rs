@@ -0,0 +1,32 @@
+use anyhow::{anyhow, Result};
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_autostart::{MacosLauncher, ManagerExt};
+
+/// Passed by the OS login item so the app starts hidden in the tray.
+pub const MINIMIZED_ARG: &str = "--minimized";
+
+pub fn plugin() -> tauri::plugin::TauriPlugin<Wry> {
+    tauri_plugin_autostart::init(MacosLauncher::LaunchAgent, Some(vec![MINIMIZED_ARG]))
+}
+
+/// Brings the OS login item in line with the `launch_at_login` setting.
+pub fn apply(app: &AppHandle, enabled: bool) -> Result<()> {
+    let autolaunch = app.autolaunch();
+    if autolaunch.is_enabled().map_err(|e| anyhow!(e))? == enabled {
+        return Ok(());
+    }
+    if enabled {
+        autolaunch.enable().map_err(|e| anyhow!(e))
+    } else {
+        autolaunch.disable().map_err(|e| anyhow!(e))
+    }
+}
+
+/// Hides the main window when launched from the login item.
+pub fn hide_if_minimized(app: &AppHandle) {
+    if std::env::args().any(|arg| arg == MINIMIZED_ARG) {
+        if let Some(window) = app.get_window("main") {
+            let _ = window.hide();
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::Result;
+use app_core::calendar::{self, Event};
+use app_core::library;
+use app_core::settings::Settings;
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use app_core::calendar::Event;
+    use block::ConcreteBlock;
+    use objc::runtime::{Object, BOOL, NO, YES};
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+    use std::sync::mpsc;
+
+    #[link(name = "EventKit", kind = "framework")]
+    extern "C" {}
+
+    /// `EKEntityTypeEvent`.
+    const ENTITY_EVENT: usize = 0;
+    /// `EKAuthorizationStatusAuthorized`, `FullAccess` since macOS 14.
+    const AUTHORIZED: isize = 3;
+    /// `EKAuthorizationStatusNotDetermined`.
+    const NOT_DETERMINED: isize = 0;
+
+    unsafe fn string(ns_string: *mut Object) -> Option<String> {
+        if ns_string.is_null() {
+            return None;
+        }
+        let utf8: *const c_char = msg_send![ns_string, UTF8String];
+        Some(CStr::from_ptr(utf8).to_string_lossy().into_owned())
+    }
+
+    unsafe fn millis(date: *mut Object) -> i64 {
+        let seconds: f64 = msg_send![date, timeIntervalSince1970];
+        (seconds * 1000.0) as i64
+    }
+
+    /// Asks for calendar access the first time and blocks until the user answers.
+    unsafe fn authorized(store: *mut Object) -> bool {
+        let status: isize =
+            msg_send![class!(EKEventStore), authorizationStatusForEntityType: ENTITY_EVENT];
+        if status != NOT_DETERMINED {
+            return status == AUTHORIZED;
+        }
+        let (tx, rx) = mpsc::channel();
+        let handler = ConcreteBlock::new(move |granted: BOOL, _error: *mut Object| {
+            let _ = tx.send(granted == YES);
+        })
+        .copy();
+        let _: () = msg_send![
+            store,
+            requestAccessToEntityType: ENTITY_EVENT
+            completion: &*handler
+        ];
+        rx.recv().unwrap_or(false)
+    }
+
+    /// Events overlapping the next few minutes from every calendar.
+    pub fn events_now() -> Vec<Event> {
+        unsafe {
+            let store: *mut Object = msg_send![class!(EKEventStore), new];
+            let mut events = Vec::new();
+            if authorized(store) {
+                let start: *mut Object = msg_send![class!(NSDate), date];
+                let end: *mut Object =
+                    msg_send![class!(NSDate), dateWithTimeIntervalSinceNow: 5.0 * 60.0];
+                let nil: *mut Object = std::ptr::null_mut();
+                let predicate: *mut Object = msg_send![
+                    store,
+                    predicateForEventsWithStartDate: start
+                    endDate: end
+                    calendars: nil
+                ];
+                let found: *mut Object = msg_send![store, eventsMatchingPredicate: predicate];
+                let count: usize = msg_send![found, count];
+                for i in 0..count {
+                    let event: *mut Object = msg_send![found, objectAtIndex: i];
+                    let all_day: BOOL = msg_send![event, isAllDay];
+                    if all_day != NO {
+                        continue;
+                    }
+                    let participants: *mut Object = msg_send![event, attendees];
+                    let mut attendees = Vec::new();
+                    if !participants.is_null() {
+                        let count: usize = msg_send![participants, count];
+                        for j in 0..count {
+                            let participant: *mut Object =
+                                msg_send![participants, objectAtIndex: j];
+                            attendees.extend(string(msg_send![participant, name]));
+                        }
+                    }
+                    events.push(Event {
+                        title: string(msg_send![event, title]).unwrap_or_default(),
+                        start: millis(msg_send![event, startDate]),
+                        end: millis(msg_send![event, endDate]),
+                        attendees,
+                    });
+                }
+            }
+            let _: () = msg_send![store, release];
+            events
+        }
+    }
+}
+
+fn events(settings: &Settings) -> Result<Vec<Event>> {
+    if !settings.calendar_ics.is_empty() {
+        return calendar::load_ics(&settings.calendar_ics);
+    }
+    #[cfg(target_os = "macos")]
+    {
+        Ok(macos::events_now())
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        Ok(Vec::new())
+    }
+}
+
+/// A title for a recording starting now, named after the meeting in the
+/// user's calendar. `None` when the setting is off or nothing is on.
+pub fn meeting_title(settings: &Settings) -> Option<String> {
+    if !settings.calendar_titles {
+        return None;
+    }
+    match events(settings) {
+        Ok(events) => {
+            calendar::current_event(&events, library::now_ms()).map(Event::recording_title)
+        }
+        Err(err) => {
+            eprintln!("Failed to read calendar: {:?}", err);
+            None
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use app_core::jobs::JobId;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast;
+
+/// Emitted for every segment whisper decodes, while the job is still running.
+pub const SEGMENT_EVENT: &str = "transcription://segment";
+
+/// Emitted with a [`SpeakerChange`] as soon as whisper marks a speaker turn,
+/// so live captions can break into turns as they happen.
+pub const SPEAKER_CHANGE_EVENT: &str = "transcription://speaker-change";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeakerChange {
+    pub job_id: JobId,
+    /// When the previous speaker stopped, in centiseconds.
+    pub at: i64,
+}
+
+/// How many captions a slow subscriber may fall behind before it skips ahead.
+const BACKLOG: usize = 256;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Caption {
+    pub job_id: JobId,
+    /// Start time in centiseconds, as reported by whisper.
+    pub start: i64,
+    /// End time in centiseconds, as reported by whisper.
+    pub end: i64,
+    pub text: String,
+    /// The next caption is spoken by someone else.
+    pub speaker_turn_next: bool,
+}
+
+/// Fans decoded segments out to the webview and to live caption sockets.
+pub struct Captions {
+    sender: broadcast::Sender<Caption>,
+}
+
+impl Default for Captions {
+    fn default() -> Self {
+        Captions {
+            sender: broadcast::channel(BACKLOG).0,
+        }
+    }
+}
+
+impl Captions {
+    pub fn publish(&self, app: &AppHandle, caption: Caption) {
+        let _ = app.emit_all(SEGMENT_EVENT, &caption);
+        if caption.speaker_turn_next {
+            let change = SpeakerChange {
+                job_id: caption.job_id,
+                at: caption.end,
+            };
+            let _ = app.emit_all(SPEAKER_CHANGE_EVENT, change);
+        }
+        // Fails only when nobody is subscribed.
+        let _ = self.sender.send(caption);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Caption> {
+        self.sender.subscribe()
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::{anyhow, bail, Context, Result};
+use percent_encoding::percent_decode_str;
+use std::path::PathBuf;
+use tauri::AppHandle;
+use url::Url;
+
+use crate::{instance, recording};
+
+pub const SCHEME: &str = "transcriber";
+
+/// Actions reachable through `transcriber://` links, e.g. from Shortcuts,
+/// Automator or a shell script.
+#[derive(Debug, PartialEq)]
+enum Action {
+    /// `transcriber://transcribe?path=/abs/file.wav`
+    Transcribe(PathBuf),
+    /// `transcriber://record/start`, `.../stop` and `.../toggle`
+    StartRecording,
+    StopRecording,
+    ToggleRecording,
+}
+
+fn parse(link: &str) -> Result<Action> {
+    let url = Url::parse(link)?;
+    if url.scheme() != SCHEME {
+        bail!("unsupported scheme {}", url.scheme());
+    }
+    match (url.host_str(), url.path()) {
+        (Some("transcribe"), "" | "/") => {
+            let encoded = url
+                .query()
+                .unwrap_or_default()
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("path="))
+                .ok_or_else(|| anyhow!("missing path parameter"))?;
+            // Decoded strictly: query_pairs would swap bytes that aren't
+            // UTF-8 for U+FFFD and name a file that isn't there.
+            let path = percent_decode_str(&encoded.replace('+', " "))
+                .decode_utf8()
+                .context("path isn't valid UTF-8")?;
+            let path = PathBuf::from(path.as_ref());
+            if !path.is_absolute() {
+                bail!("path must be absolute");
+            }
+            Ok(Action::Transcribe(path))
+        }
+        (Some("record"), "/start") => Ok(Action::StartRecording),
+        (Some("record"), "/stop") => Ok(Action::StopRecording),
+        (Some("record"), "/toggle") => Ok(Action::ToggleRecording),
+        _ => bail!("unknown link {}", link),
+    }
+}
+
+fn handle(app: &AppHandle, link: &str) -> Result<()> {
+    match parse(link)? {
+        Action::Transcribe(path) => {
+            if !path.is_file() {
+                bail!("{} is not a file", path.display());
+            }
+            instance::open_file(app, path);
+            Ok(())
+        }
+        Action::StartRecording => recording::start(app),
+        Action::StopRecording => recording::stop(app),
+        Action::ToggleRecording => recording::toggle(app),
+    }
+}
+
+/// Must run before the Tauri builder so a second launch via a link is
+/// handed to this process.
+pub fn prepare(identifier: &str) {
+    tauri_plugin_deep_link::prepare(identifier);
+}
+
+pub fn register(app: &AppHandle) -> Result<()> {
+    let app = app.clone();
+    tauri_plugin_deep_link::register(SCHEME, move |link| {
+        if let Err(err) = handle(&app, &link) {
+            eprintln!("Failed to handle {}: {:?}", link, err);
+        }
+    })
+    .map_err(|e| anyhow!(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use url::form_urlencoded::byte_serialize;
+
+    fn transcribe_link(path: &Path) -> String {
+        let encoded: String = byte_serialize(path.to_str().unwrap().as_bytes()).collect();
+        format!("transcriber://transcribe?path={}", encoded)
+    }
+
+    #[test]
+    fn parses_each_action() {
+        let path = std::env::temp_dir().join("Team sync #3.wav");
+        assert_eq!(
+            parse(&transcribe_link(&path)).unwrap(),
+            Action::Transcribe(path)
+        );
+        assert_eq!(
+            parse("transcriber://record/start").unwrap(),
+            Action::StartRecording
+        );
+        assert_eq!(
+            parse("transcriber://record/stop").unwrap(),
+            Action::StopRecording
+        );
+        assert_eq!(
+            parse("transcriber://record/toggle").unwrap(),
+            Action::ToggleRecording
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_actions_and_bad_paths() {
+        for link in [
+            "transcriber://record/pause",
+            "transcriber://record",
+            "transcriber://export?path=/tmp/a.wav",
+            "https://transcribe?path=/tmp/a.wav",
+            "transcriber://transcribe",
+            "transcriber://transcribe?path=relative.wav",
+            "not a link",
+        ] {
+            assert!(parse(link).is_err(), "{} was accepted", link);
+        }
+    }
+
+    #[test]
+    fn rejects_paths_that_do_not_decode_to_utf8() {
+        let link = transcribe_link(&std::env::temp_dir().join("a.wav"));
+        let err = parse(&link.replace("a.wav", "%FF.wav")).unwrap_err();
+        assert_eq!(err.to_string(), "path isn't valid UTF-8");
+    }
+}
@@ -0,0 +1,42 @@
+use anyhow::{bail, Result};
+use app_core::audio::wav;
+use std::path::PathBuf;
+use tauri::api::dialog::blocking::FileDialogBuilder;
+
+const AUDIO_EXTENSIONS: &[&str] = &["wav"];
+
+/// Shows a native picker for audio to transcribe. Every chosen file is checked
+/// to be readable by the pipeline; an empty list means the user cancelled.
+pub fn pick_audio_files() -> Result<Vec<PathBuf>> {
+    let paths = FileDialogBuilder::new()
+        .set_title("Choose audio to transcribe")
+        .add_filter("Audio", AUDIO_EXTENSIONS)
+        .pick_files()
+        .unwrap_or_default();
+    for path in &paths {
+        if let Err(err) = wav::validate(path) {
+            bail!("{}: {:#}", path.display(), err);
+        }
+    }
+    Ok(paths)
+}
+
+/// Shows a native save dialog for an export. Ensures the chosen path has
+/// `extension` and that its folder exists; `None` means the user cancelled.
+pub fn pick_export_path(default_name: &str, extension: &str) -> Result<Option<PathBuf>> {
+    let Some(mut path) = FileDialogBuilder::new()
+        .set_title("Export")
+        .set_file_name(default_name)
+        .add_filter(extension.to_uppercase(), &[extension])
+        .save_file()
+    else {
+        return Ok(None);
+    };
+    if path.extension().map_or(true, |ext| ext != extension) {
+        path.set_extension(extension);
+    }
+    match path.parent() {
+        Some(dir) if dir.is_dir() => Ok(Some(path)),
+        _ => bail!("{} is not in an existing folder", path.display()),
+    }
+}
@@ -0,0 +1,162 @@
+use anyhow::{bail, Context, Result};
+use app_core::jobs::{JobId, Jobs};
+use app_core::library::{Library, RecordingId, SessionId, TranscriptId};
+use app_core::transcribe::chapters;
+use app_core::transcribe::document::{self, DocumentFormat, DocumentInfo};
+use app_core::transcribe::format::{self, Format, Part};
+use app_core::transcribe::merge::{merge_segments, MergeSettings};
+use app_core::transcribe::note;
+use app_core::transcribe::Transcript;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::transcription;
+
+/// Any format a single transcript can be exported to. Serialized as the bare
+/// format name, e.g. `"srt"` or `"pdf"`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+pub enum ExportFormat {
+    Text(Format),
+    Document(DocumentFormat),
+}
+
+impl From<Format> for ExportFormat {
+    fn from(format: Format) -> Self {
+        ExportFormat::Text(format)
+    }
+}
+
+/// Where the transcript to export comes from.
+pub enum Source {
+    Job(JobId),
+    Recording(RecordingId),
+}
+
+/// Renders a finished transcription job, or a recording's current
+/// transcript, to `path`. Documents get a title page with the recording's
+/// details when they're known. Segments are merged per `merge` first.
+pub fn export_transcript(
+    jobs: &Jobs,
+    library: &Library,
+    source: Source,
+    path: &Path,
+    format: ExportFormat,
+    merge: &MergeSettings,
+) -> Result<()> {
+    let (transcript, recording) = match source {
+        Source::Job(id) => (transcription::transcript(jobs, id)?, None),
+        Source::Recording(id) => {
+            let Some(recording) = library.recording(id)? else {
+                bail!("no recording with id {}", id);
+            };
+            let Some(transcript) = library.transcript(id)? else {
+                bail!("{} has no transcript yet", recording.title);
+            };
+            (transcript, Some(recording))
+        }
+    };
+    let transcript = merge_segments(&transcript, merge);
+    match format {
+        ExportFormat::Text(format) => write(path, &transcript, format),
+        ExportFormat::Document(format) => {
+            let title = recording.as_ref().map_or_else(
+                || {
+                    path.file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned()
+                },
+                |r| r.title.clone(),
+            );
+            let info = DocumentInfo {
+                title: &title,
+                recorded_at: recording.as_ref().map(|r| r.created_at),
+                duration_ms: recording.as_ref().map(|r| r.duration_ms),
+            };
+            document::write_document(&transcript, &info, format, path)
+        }
+    }
+}
+
+fn write(path: &Path, transcript: &Transcript, format: Format) -> Result<()> {
+    fs::write(path, format::render(transcript, format))
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Renders the latest transcript of every recording in a session, in
+/// recording order, as one document at `path`. Untranscribed recordings are
+/// skipped but still advance the SRT timeline.
+pub fn export_session(
+    library: &Library,
+    session: SessionId,
+    path: &Path,
+    format: Format,
+    merge: &MergeSettings,
+) -> Result<()> {
+    let mut transcripts = Vec::new();
+    let mut offset = 0;
+    for recording in library.session_recordings(session)? {
+        if let Some(transcript) = library.transcript(recording.id)? {
+            transcripts.push((recording.title, merge_segments(&transcript, merge), offset));
+        }
+        offset += recording.duration_ms / 10;
+    }
+    let parts: Vec<Part> = transcripts
+        .iter()
+        .map(|(title, transcript, offset)| Part {
+            title,
+            transcript,
+            offset: *offset,
+        })
+        .collect();
+    fs::write(path, format::render_parts(&parts, format))
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Renders a transcript version's chapters as a YouTube description chapter
+/// list, writing it to `path` too when given. Chapters are generated and
+/// stored first if there are none yet.
+pub fn export_youtube_chapters(
+    library: &Library,
+    transcript_id: TranscriptId,
+    path: Option<&Path>,
+) -> Result<String> {
+    let mut stored = library.chapters(transcript_id)?;
+    if stored.is_empty() {
+        let transcript = library.require_transcript_version(transcript_id)?;
+        stored = chapters::generate_chapters(&transcript);
+        library.save_chapters(transcript_id, &stored)?;
+    }
+    let text = chapters::render_youtube_chapters(&stored);
+    if let Some(path) = path {
+        fs::write(path, &text).with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    Ok(text)
+}
+
+/// Writes a recording's current transcript as a Markdown note in
+/// `notes_folder`, replacing the note from an earlier export of the same
+/// title. Returns the note's path.
+pub fn export_to_notes(
+    library: &Library,
+    recording_id: RecordingId,
+    notes_folder: &Path,
+    merge: &MergeSettings,
+) -> Result<PathBuf> {
+    if notes_folder.as_os_str().is_empty() {
+        bail!("choose a notes folder in settings first");
+    }
+    let Some(recording) = library.recording(recording_id)? else {
+        bail!("no recording with id {}", recording_id);
+    };
+    let Some(transcript) = library.transcript(recording_id)? else {
+        bail!("{} has no transcript yet", recording.title);
+    };
+    let path = notes_folder.join(note::note_file_name(&recording.title));
+    let transcript = merge_segments(&transcript, merge);
+    fs::write(&path, note::render_note(&recording, &transcript))
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(path)
+}
@@ -0,0 +1,65 @@
+use anyhow::Result;
+use app_core::audio::wav;
+use app_core::jobs::JobId;
+use app_core::library::Library;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, FileDropEvent, Manager};
+
+use crate::{import, instance};
+
+pub const IMPORTED_EVENT: &str = "drop://imported";
+pub const REJECTED_EVENT: &str = "drop://rejected";
+
+#[derive(Clone, Serialize)]
+struct Imported {
+    source: PathBuf,
+    path: PathBuf,
+    job_id: JobId,
+}
+
+#[derive(Clone, Serialize)]
+struct Rejected {
+    source: PathBuf,
+    reason: String,
+}
+
+/// Validates each dropped file, copies it into the library's imports folder
+/// and queues a transcription of the copy.
+pub fn handle(app: &AppHandle, event: &FileDropEvent) {
+    let FileDropEvent::Dropped(paths) = event else {
+        return;
+    };
+    for source in paths {
+        match import(app, source) {
+            Ok(path) => {
+                let job_id = instance::open_file(app, path.clone());
+                let _ = app.emit_all(
+                    IMPORTED_EVENT,
+                    Imported {
+                        source: source.clone(),
+                        path,
+                        job_id,
+                    },
+                );
+            }
+            Err(err) => {
+                let _ = app.emit_all(
+                    REJECTED_EVENT,
+                    Rejected {
+                        source: source.clone(),
+                        reason: format!("{:#}", err),
+                    },
+                );
+            }
+        }
+    }
+}
+
+fn import(app: &AppHandle, source: &Path) -> Result<PathBuf> {
+    wav::validate(source)?;
+    let recording = app
+        .state::<Library>()
+        .import_file(source, Some(&import::imports_dir(app)?))?;
+    Ok(recording.path)
+}
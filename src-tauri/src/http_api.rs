@@ -0,0 +1,458 @@
+use anyhow::Result;
+use app_core::audio::wav::{is_wav, WAV_HEADER_LEN};
+use app_core::jobs::{Job, JobId, Jobs, Priority};
+use app_core::library;
+use app_core::settings::{Settings, SettingsStore};
+use app_core::transcribe::format::{self, Format};
+use app_core::transcribe::Transcript;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{DefaultBodyLimit, Multipart, Path as UrlPath, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use rand::distributions::{Alphanumeric, DistString};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use subtle::ConstantTimeEq;
+use tauri::{AppHandle, Manager};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::oneshot;
+
+use crate::captions::{Caption, Captions};
+use crate::{import, transcription};
+
+/// The optional localhost API that lets other tools queue transcriptions:
+///
+/// - `POST /transcribe` with `{"path": "/abs/file.wav"}` queues a job and
+///   answers `202 {"job_id": 1}`.
+/// - `GET /jobs` and `GET /jobs/{id}` return job snapshots.
+/// - `POST /v1/audio/transcriptions` takes the same multipart upload as
+///   OpenAI's endpoint (`file`, `response_format`) and answers when the
+///   transcript is ready, so OpenAI client libraries can use the app as a
+///   local backend. The file has to be WAV. `model`, `language` and other
+///   fields are ignored.
+/// - `GET /live`, when live captions are enabled, upgrades to a WebSocket that
+///   sends each [`Caption`] as a JSON text message while transcriptions run.
+///
+/// Every request needs `Authorization: Bearer <http_api_token>` from settings.
+/// `/live` also takes `?token=<http_api_token>`, for clients like browser
+/// overlays that can't set headers on a WebSocket; anywhere else a token in
+/// the URL would only end up in logs.
+#[derive(Default)]
+pub struct HttpApi {
+    running: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+/// Uploads are written to disk as they arrive, so this only guards against
+/// runaway clients.
+const MAX_UPLOAD_BYTES: usize = 1024 * 1024 * 1024;
+
+#[derive(Clone)]
+struct ApiState {
+    app: AppHandle,
+    token: Arc<str>,
+}
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+/// Fills in a random API token the first time the app runs.
+pub fn ensure_token(settings: &SettingsStore) -> Result<()> {
+    let current = settings.get();
+    if current.http_api_token.is_empty() {
+        settings.set(Settings {
+            http_api_token: Alphanumeric.sample_string(&mut rand::thread_rng(), 32),
+            ..current
+        })?;
+    }
+    Ok(())
+}
+
+impl HttpApi {
+    /// Starts, stops or restarts the server to match `settings`.
+    pub fn apply(&self, app: &AppHandle, settings: &Settings) {
+        let mut running = self.running.lock().unwrap();
+        if let Some(stop) = running.take() {
+            let _ = stop.send(());
+        }
+        if !settings.http_api_enabled {
+            return;
+        }
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let port = settings.http_api_port;
+        let state = ApiState {
+            app: app.clone(),
+            token: settings.http_api_token.as_str().into(),
+        };
+        let live_captions = settings.http_api_live_captions;
+        tauri::async_runtime::spawn(async move {
+            if let Err(err) = serve(router(state, live_captions), port, stop_rx).await {
+                eprintln!("HTTP API on port {} failed: {:?}", port, err);
+            }
+        });
+        *running = Some(stop_tx);
+    }
+}
+
+fn router(state: ApiState, live_captions: bool) -> Router {
+    let mut router = Router::new()
+        .route("/transcribe", post(transcribe))
+        .route("/jobs", get(list_jobs))
+        .route("/jobs/:id", get(get_job))
+        .route(
+            "/v1/audio/transcriptions",
+            post(openai_transcription).layer(DefaultBodyLimit::max(MAX_UPLOAD_BYTES)),
+        );
+    if live_captions {
+        router = router.route("/live", get(live));
+    }
+    router
+        .route_layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .with_state(state)
+}
+
+async fn serve(router: Router, port: u16, stop: oneshot::Receiver<()>) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port)).await?;
+    axum::serve(listener, router)
+        .with_graceful_shutdown(async {
+            let _ = stop.await;
+        })
+        .await?;
+    Ok(())
+}
+
+async fn authorize(
+    State(state): State<ApiState>,
+    Query(query): Query<TokenQuery>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or(query
+            .token
+            .as_deref()
+            .filter(|_| request.uri().path() == "/live"));
+    // Compared in constant time so response timing doesn't leak the token.
+    let matches =
+        token.is_some_and(|token| bool::from(token.as_bytes().ct_eq(state.token.as_bytes())));
+    if !matches {
+        return ApiError(StatusCode::UNAUTHORIZED, "missing or wrong token".into()).into_response();
+    }
+    next.run(request).await
+}
+
+#[derive(Deserialize)]
+struct TranscribeRequest {
+    path: PathBuf,
+}
+
+#[derive(Serialize)]
+struct Queued {
+    job_id: JobId,
+}
+
+async fn transcribe(
+    State(state): State<ApiState>,
+    Json(request): Json<TranscribeRequest>,
+) -> Result<(StatusCode, Json<Queued>), ApiError> {
+    if !request.path.is_absolute() || !request.path.is_file() {
+        return Err(ApiError(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "{} is not an absolute path to a file",
+                request.path.display()
+            ),
+        ));
+    }
+    let (job_id, _) = transcription::start(&state.app, request.path, Priority::Background);
+    Ok((StatusCode::ACCEPTED, Json(Queued { job_id })))
+}
+
+async fn list_jobs(State(state): State<ApiState>) -> Json<Vec<Job>> {
+    Json(state.app.state::<Jobs>().list())
+}
+
+async fn get_job(
+    State(state): State<ApiState>,
+    UrlPath(id): UrlPath<JobId>,
+) -> Result<Json<Job>, ApiError> {
+    state
+        .app
+        .state::<Jobs>()
+        .get(id)
+        .map(Json)
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, format!("no job with id {}", id)))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ResponseFormat {
+    Json,
+    Text,
+    Srt,
+    Vtt,
+    VerboseJson,
+}
+
+/// Takes an upload like OpenAI's endpoint. Only WAV can be transcribed, so
+/// anything else, e.g. the mp3 OpenAI clients send by default, is turned
+/// away before it's written. The upload is kept as a recording once it's
+/// transcribed and deleted if anything fails.
+async fn openai_transcription(
+    State(state): State<ApiState>,
+    multipart: Multipart,
+) -> Result<Response, ApiError> {
+    let mut upload = None;
+    let result = match read_upload(&state, multipart, &mut upload).await {
+        Ok(response_format) => match &upload {
+            Some(path) => transcription::run(&state.app, path.clone())
+                .await
+                .map(|transcript| openai_response(&transcript, response_format))
+                .map_err(internal),
+            None => Err(bad_request("missing file field".into())),
+        },
+        Err(err) => Err(err),
+    };
+    if result.is_err() {
+        if let Some(path) = upload {
+            let _ = tokio::fs::remove_file(path).await;
+        }
+    }
+    result
+}
+
+fn bad_request(message: String) -> ApiError {
+    ApiError(StatusCode::BAD_REQUEST, message)
+}
+
+fn internal(err: anyhow::Error) -> ApiError {
+    ApiError(StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err))
+}
+
+/// Reads the form, writing the file into the imports folder and setting
+/// `upload` to it as soon as it's created, so the caller can clean up
+/// whatever goes wrong afterwards.
+async fn read_upload(
+    state: &ApiState,
+    mut multipart: Multipart,
+    upload: &mut Option<PathBuf>,
+) -> Result<ResponseFormat, ApiError> {
+    let mut response_format = ResponseFormat::Json;
+    while let Some(mut field) = multipart
+        .next_field()
+        .await
+        .map_err(|err| bad_request(err.body_text()))?
+    {
+        match field.name() {
+            Some("file") if upload.is_none() => {
+                let name = field
+                    .file_name()
+                    .and_then(|name| std::path::Path::new(name).file_name())
+                    .map(|name| name.to_owned())
+                    .unwrap_or_else(|| "upload.wav".into());
+                let mut header = Vec::new();
+                let mut file = None;
+                while let Some(chunk) = field
+                    .chunk()
+                    .await
+                    .map_err(|err| bad_request(err.body_text()))?
+                {
+                    if let Some(file) = &mut file {
+                        file.write_all(&chunk)
+                            .await
+                            .map_err(|err| internal(err.into()))?;
+                        continue;
+                    }
+                    header.extend_from_slice(&chunk);
+                    if header.len() < WAV_HEADER_LEN {
+                        continue;
+                    }
+                    if !is_wav(&header) {
+                        return Err(not_wav());
+                    }
+                    let dir = import::imports_dir(&state.app).map_err(internal)?;
+                    tokio::fs::create_dir_all(&dir)
+                        .await
+                        .map_err(|err| internal(err.into()))?;
+                    let path = library::unique_path(&dir.join(&name));
+                    let created = tokio::fs::File::create(&path)
+                        .await
+                        .map_err(|err| internal(err.into()))?;
+                    *upload = Some(path);
+                    file.insert(created)
+                        .write_all(&header)
+                        .await
+                        .map_err(|err| internal(err.into()))?;
+                }
+                let Some(mut file) = file else {
+                    return Err(not_wav());
+                };
+                file.flush().await.map_err(|err| internal(err.into()))?;
+            }
+            Some("response_format") => {
+                let text = field
+                    .text()
+                    .await
+                    .map_err(|err| bad_request(err.body_text()))?;
+                response_format = serde_json::from_value(json!(text))
+                    .map_err(|_| bad_request(format!("unsupported response_format {:?}", text)))?;
+            }
+            _ => {}
+        }
+    }
+    Ok(response_format)
+}
+
+fn not_wav() -> ApiError {
+    bad_request("only WAV audio can be transcribed; convert the file to WAV first".into())
+}
+
+/// Shapes a transcript like OpenAI's API does. Its timestamps are seconds.
+fn openai_response(transcript: &Transcript, response_format: ResponseFormat) -> Response {
+    let text = format::render(transcript, Format::Text);
+    match response_format {
+        ResponseFormat::Json => Json(json!({ "text": text })).into_response(),
+        ResponseFormat::Text => text.into_response(),
+        ResponseFormat::Srt => format::render(transcript, Format::Srt).into_response(),
+        ResponseFormat::Vtt => format::render(transcript, Format::Vtt).into_response(),
+        ResponseFormat::VerboseJson => {
+            let seconds = |centiseconds: i64| centiseconds as f64 / 100.0;
+            let segments: Vec<_> = transcript
+                .segments
+                .iter()
+                .enumerate()
+                .map(|(id, segment)| {
+                    json!({
+                        "id": id,
+                        "start": seconds(segment.start),
+                        "end": seconds(segment.end),
+                        "text": segment.text,
+                    })
+                })
+                .collect();
+            let duration = transcript.segments.last().map_or(0, |segment| segment.end);
+            Json(json!({
+                "task": "transcribe",
+                "duration": seconds(duration),
+                "text": text,
+                "segments": segments,
+            }))
+            .into_response()
+        }
+    }
+}
+
+async fn live(State(state): State<ApiState>, upgrade: WebSocketUpgrade) -> Response {
+    let mut captions = state.app.state::<Captions>().subscribe();
+    upgrade.on_upgrade(|mut socket: WebSocket| async move {
+        loop {
+            let caption = match captions.recv().await {
+                Ok(caption) => caption,
+                // A slow client just misses some captions.
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            let Ok(text) = serde_json::to_string(&caption) else {
+                continue;
+            };
+            if socket.send(Message::Text(text)).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use app_core::transcribe::Segment;
+
+    fn transcript() -> Transcript {
+        let segment = |start, end, text: &str, speaker_turn_next| Segment {
+            start,
+            end,
+            text: text.to_string(),
+            speaker_turn_next,
+        };
+        Transcript {
+            segments: vec![
+                segment(0, 150, " Hello there.", true),
+                segment(150, 325, " Hi!", false),
+            ],
+        }
+    }
+
+    /// The content type and body of `response`.
+    async fn read(response: Response) -> (String, String) {
+        let content_type = response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (content_type, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn json_and_text_carry_the_plain_transcript() {
+        let (content_type, body) = read(openai_response(&transcript(), ResponseFormat::Json)).await;
+        assert_eq!(content_type, "application/json");
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&body).unwrap(),
+            json!({ "text": "Hello there.\n\nHi!" })
+        );
+
+        let (content_type, body) = read(openai_response(&transcript(), ResponseFormat::Text)).await;
+        assert!(content_type.starts_with("text/plain"));
+        assert_eq!(body, "Hello there.\n\nHi!");
+    }
+
+    #[tokio::test]
+    async fn srt_has_a_numbered_cue_per_segment() {
+        let (_, body) = read(openai_response(&transcript(), ResponseFormat::Srt)).await;
+        assert_eq!(
+            body,
+            "1\n00:00:00,000 --> 00:00:01,500\nHello there.\n\n\
+             2\n00:00:01,500 --> 00:00:03,250\nHi!\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn verbose_json_times_segments_in_seconds() {
+        let (content_type, body) =
+            read(openai_response(&transcript(), ResponseFormat::VerboseJson)).await;
+        assert_eq!(content_type, "application/json");
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&body).unwrap(),
+            json!({
+                "task": "transcribe",
+                "duration": 3.25,
+                "text": "Hello there.\n\nHi!",
+                "segments": [
+                    { "id": 0, "start": 0.0, "end": 1.5, "text": " Hello there." },
+                    { "id": 1, "start": 1.5, "end": 3.25, "text": " Hi!" },
+                ],
+            })
+        );
+    }
+}
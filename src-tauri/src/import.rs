@@ -0,0 +1,63 @@
+use anyhow::{Context, Result};
+use app_core::jobs::{JobId, Priority};
+use app_core::library::{Library, Recording};
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::transcription;
+
+/// Outcome of importing one file; exactly one of `recording` and `error` is set.
+#[derive(Clone, Serialize)]
+pub struct Imported {
+    pub source: PathBuf,
+    pub recording: Option<Recording>,
+    pub job_id: Option<JobId>,
+    pub error: Option<String>,
+}
+
+/// Where copied-in audio lives.
+pub fn imports_dir(app: &AppHandle) -> Result<PathBuf> {
+    Ok(app
+        .path_resolver()
+        .app_data_dir()
+        .context("no app data directory")?
+        .join("imports"))
+}
+
+/// Registers each file in the library, copying it into the imports folder
+/// when `copy` is set, and queues a transcription of it when `transcribe` is.
+/// One bad file doesn't stop the rest.
+pub fn import_audio(
+    app: &AppHandle,
+    paths: Vec<PathBuf>,
+    copy: bool,
+    transcribe: bool,
+) -> Result<Vec<Imported>> {
+    let library = app.state::<Library>();
+    let copy_into = if copy { Some(imports_dir(app)?) } else { None };
+    Ok(paths
+        .into_iter()
+        .map(
+            |source| match library.import_file(&source, copy_into.as_deref()) {
+                Ok(recording) => {
+                    let job_id = transcribe.then(|| {
+                        transcription::start(app, recording.path.clone(), Priority::Background).0
+                    });
+                    Imported {
+                        source,
+                        recording: Some(recording),
+                        job_id,
+                        error: None,
+                    }
+                }
+                Err(err) => Imported {
+                    source,
+                    recording: None,
+                    job_id: None,
+                    error: Some(format!("{:#}", err)),
+                },
+            },
+        )
+        .collect())
+}
@@ -0,0 +1,86 @@
+use app_core::jobs::{JobId, Priority};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::transcription;
+
+/// Emitted when a file from outside the app is opened for transcription.
+pub const OPEN_FILE_EVENT: &str = "app://open-file";
+
+#[derive(Clone, Serialize)]
+struct OpenFile {
+    path: PathBuf,
+    job_id: JobId,
+}
+
+/// Keeps a single running instance. A second launch focuses the existing
+/// window and hands it any file arguments instead of starting another app.
+pub fn plugin() -> tauri::plugin::TauriPlugin<Wry> {
+    tauri_plugin_single_instance::init(|app, argv, cwd| {
+        if let Some(window) = app.get_window("main") {
+            let _ = window.unminimize();
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+        open_args(app, argv.iter().skip(1), Path::new(&cwd));
+    })
+}
+
+/// Transcribes every existing file among `args`, resolved against `cwd`.
+pub fn open_args<S: AsRef<str>>(app: &AppHandle, args: impl Iterator<Item = S>, cwd: &Path) {
+    for path in files_in(args, cwd) {
+        open_file(app, path);
+    }
+}
+
+/// The arguments that name files, relative ones resolved against `cwd`, the
+/// directory the launch came from rather than ours. Flags and anything
+/// that isn't a file are skipped.
+fn files_in<S: AsRef<str>>(args: impl Iterator<Item = S>, cwd: &Path) -> Vec<PathBuf> {
+    args.map(|arg| cwd.join(arg.as_ref()))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Queues a transcription of `path` and tells the frontend to show it.
+pub fn open_file(app: &AppHandle, path: PathBuf) -> JobId {
+    let (job_id, _) = transcription::start(app, path.clone(), Priority::Normal);
+    let _ = app.emit_all(OPEN_FILE_EVENT, OpenFile { path, job_id });
+    job_id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn resolves_relative_paths_against_the_launch_directory() {
+        let cwd = std::env::temp_dir().join(format!("tauri-app-instance-{}", std::process::id()));
+        fs::create_dir_all(cwd.join("takes")).unwrap();
+        fs::write(cwd.join("memo.wav"), b"").unwrap();
+        fs::write(cwd.join("takes/standup.wav"), b"").unwrap();
+        let absolute = cwd.join("takes/standup.wav");
+
+        let files = files_in(
+            [
+                "memo.wav",
+                "--minimized",
+                "missing.wav",
+                "takes",
+                absolute.to_str().unwrap(),
+            ]
+            .into_iter(),
+            &cwd,
+        );
+        fs::remove_dir_all(&cwd).unwrap();
+        assert_eq!(files, vec![cwd.join("memo.wav"), absolute]);
+    }
+
+    #[test]
+    fn nothing_to_open_without_file_arguments() {
+        let args: [&str; 0] = [];
+        assert!(files_in(args.into_iter(), &std::env::temp_dir()).is_empty());
+    }
+}
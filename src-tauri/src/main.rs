@@ -1,417 +1,1365 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use anyhow::anyhow;
-use anyhow::Result;
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::Stream;
-use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
-use rubato::{
-    Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction,
-};
-use serde::Serialize;
-use std::fs::File;
-use std::io::BufWriter;
-use std::ops::Deref;
-use std::sync::mpsc::{self, Receiver, Sender};
-use std::thread;
-use std::{
-    path::Path,
-    sync::{Arc, Mutex},
+mod autostart;
+mod calendar;
+mod captions;
+mod deep_link;
+mod dialogs;
+mod export;
+mod file_drop;
+mod http_api;
+mod import;
+mod instance;
+mod menu;
+mod models;
+mod notifications;
+mod permissions;
+mod playback;
+mod power;
+mod recording;
+mod remote;
+mod retention;
+mod share;
+mod shortcut;
+mod shutdown;
+mod transcription;
+mod tray;
+mod updater;
+mod upload;
+mod wake_word;
+mod webhook;
+
+use app_core::audio::devices::AudioDevice;
+use app_core::audio::dynamics::Declip;
+use app_core::audio::edit::{ConvertOptions, TimeRange, TrimMode};
+use app_core::audio::loudness::Normalization;
+use app_core::audio::mic_test::MicTest;
+use app_core::audio::repair::Repair;
+use app_core::audio::silence::{SilenceOptions, SilenceSplit};
+use app_core::audio::spectrogram::{Spectrogram, SpectrogramParams};
+use app_core::audio::waveform::Peak;
+use app_core::audio::{self, Encoding, Recorder, StreamInfo};
+use app_core::i18n;
+use app_core::jobs::{job, Job, JobEvents, JobId, Jobs, Priority};
+use app_core::library::{
+    EntityHit, Library, ListQuery, NewRecording, Page, RecordingId, RetentionPlan, SearchHit,
+    Session, SessionId, TagCount, TranscriptId, TranscriptSummary, TranscriptTranslation,
+    TranscriptVersion, Upload,
 };
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+use app_core::settings::{Settings, SettingsStore};
+use app_core::summarize::{self, SummaryStyle};
+use app_core::transcribe::action_items::{self, ActionItem};
+use app_core::transcribe::align::WordTiming;
+use app_core::transcribe::benchmark::{self, Benchmark};
+use app_core::transcribe::chapters::{self, Chapter};
+use app_core::transcribe::diff::{self, Change};
+use app_core::transcribe::entities::{self, Entity, EntityKind};
+use app_core::transcribe::format::{self, Format};
+use app_core::transcribe::karaoke::{self, Karaoke};
+use app_core::transcribe::key_phrases::{self, KeyPhrase};
+use app_core::transcribe::merge::merge_segments;
+use app_core::transcribe::model::{self, Capabilities};
+use app_core::transcribe::profile::{self, Profile};
+use app_core::transcribe::registry::Verification;
+use app_core::transcribe::translate;
+use app_core::transcribe::Transcript;
+use captions::Captions;
+use export::{ExportFormat, Source};
+use http_api::HttpApi;
+use models::ModelEntry;
+use notifications::Notifier;
+use permissions::MicPermission;
+use playback::Playback;
+use recording::Recording;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{ClipboardManager, Manager};
+use wake_word::WakeWord;
 
-fn parse_wav_file(path: &Path) -> Vec<i16> {
-    let reader = WavReader::open(path).expect("failed to read file");
+/// Forwards job events to every window and records finished jobs in the library.
+struct AppEvents(tauri::AppHandle);
 
-    if reader.spec().channels != 1 {
-        panic!("expected mono audio file");
-    }
-    if reader.spec().sample_format != SampleFormat::Int {
-        panic!("expected integer sample format");
-    }
-    if reader.spec().bits_per_sample != 16 {
-        panic!("expected 16 bits per sample");
+impl JobEvents for AppEvents {
+    fn emit(&self, event: &str, job: &Job) {
+        let _ = self.0.emit_all(event, job);
+        if event != job::PROGRESS_EVENT {
+            if let Err(err) = self.0.state::<Library>().save_job(job) {
+                eprintln!("Failed to save job {}: {:?}", job.id, err);
+            }
+        }
+        self.0.state::<Notifier>().job_event(&self.0, event, job);
+        webhook::job_event(&self.0, event, job);
     }
+}
 
-    reader
-        .into_samples::<i16>()
-        .map(|x| x.expect("sample"))
-        .collect::<Vec<_>>()
+/// Shapes `transcribe` can answer in.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TranscribeFormat {
+    Text,
+    Json,
+    Srt,
+    Vtt,
 }
 
-fn parse_and_resample_wav_file(path: &Path, target_sample_rate: f64) -> Vec<i16> {
-    let mut reader = WavReader::open(path).expect("failed to read file");
-    let spec = reader.spec();
+#[derive(Serialize)]
+#[serde(untagged)]
+enum TranscribeOutput {
+    /// The speaker turns, when no format is asked for.
+    Turns(Vec<String>),
+    Rendered(String),
+    Transcript(Transcript),
+}
 
-    if spec.channels != 1 {
-        panic!("expected mono audio file");
-    }
-    if spec.sample_format != SampleFormat::Int {
-        panic!("expected integer sample format");
+// Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
+/// Transcribes `path`, answering with its speaker turns or, given a `format`,
+/// the plain text, the segments as JSON, or SRT or VTT subtitles.
+#[tauri::command]
+async fn transcribe(
+    path: String,
+    format: Option<TranscribeFormat>,
+    app: tauri::AppHandle,
+) -> Result<TranscribeOutput, Error> {
+    let transcript = transcription::run(&app, PathBuf::from(path)).await?;
+    let transcript = merge_segments(
+        &transcript,
+        &app.state::<SettingsStore>().get().segment_merging,
+    );
+    // `format` is the argument here, not the module.
+    let rendered =
+        |f| TranscribeOutput::Rendered(app_core::transcribe::format::render(&transcript, f));
+    Ok(match format {
+        None => TranscribeOutput::Turns(transcript.turns()),
+        Some(TranscribeFormat::Text) => rendered(Format::Text),
+        Some(TranscribeFormat::Srt) => rendered(Format::Srt),
+        Some(TranscribeFormat::Vtt) => rendered(Format::Vtt),
+        Some(TranscribeFormat::Json) => TranscribeOutput::Transcript(transcript),
+    })
+}
+
+/// Word-level timings for a script the user already has, e.g. an audiobook
+/// chapter, lined up with its recording at `path`.
+#[tauri::command]
+async fn align_text(
+    path: String,
+    text: String,
+    app: tauri::AppHandle,
+) -> Result<Vec<WordTiming>, Error> {
+    Ok(run_blocking(move || transcription::align(&app, &PathBuf::from(path), &text)).await?)
+}
+
+/// Per-word timings of a recording's current transcript, grouped by segment,
+/// for karaoke-style highlighting in the player. With `precise`, the words
+/// are lined up with the audio by whisper instead of estimated from segment
+/// times, which takes about as long as transcribing.
+#[tauri::command]
+async fn karaoke_timings(
+    recording_id: RecordingId,
+    precise: Option<bool>,
+    app: tauri::AppHandle,
+) -> Result<Karaoke, Error> {
+    Ok(run_blocking(move || {
+        let library = app.state::<Library>();
+        let recording = library
+            .recording(recording_id)?
+            .ok_or_else(|| anyhow::anyhow!("no recording with id {}", recording_id))?;
+        let transcript = library
+            .transcript(recording_id)?
+            .ok_or_else(|| anyhow::anyhow!("{} has no transcript yet", recording.title))?;
+        if !precise.unwrap_or(false) {
+            return Ok(karaoke::karaoke(&transcript, None));
+        }
+        let words = transcription::align(&app, &recording.path, &karaoke::script(&transcript))?;
+        Ok(karaoke::karaoke(&transcript, Some(&words)))
+    })
+    .await?)
+}
+
+/// Downloads audio from `url`, e.g. a podcast episode, reporting progress
+/// with `download://progress`, and queues its transcription. Returns the
+/// transcription job.
+#[tauri::command]
+async fn transcribe_url(url: String, app: tauri::AppHandle) -> Result<JobId, Error> {
+    let path = remote::download(&app, &url).await?;
+    Ok(transcription::start(&app, path, Priority::Normal).0)
+}
+
+#[tauri::command]
+fn copy_transcript(
+    job_id: JobId,
+    format: Format,
+    app: tauri::AppHandle,
+    jobs: tauri::State<'_, Jobs>,
+    settings: tauri::State<'_, SettingsStore>,
+) -> Result<(), Error> {
+    let transcript = transcription::transcript(&jobs, job_id)?;
+    let transcript = merge_segments(&transcript, &settings.get().segment_merging);
+    app.clipboard_manager()
+        .write_text(format::render(&transcript, format))
+        .map_err(|e| anyhow::anyhow!(e))?;
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct Error {
+    message: String,
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(err: anyhow::Error) -> Self {
+        Error {
+            message: err.to_string(),
+        }
     }
-    if spec.bits_per_sample != 16 {
-        panic!("expected 16 bits per sample");
+}
+
+/// Where the most recent transcription spent its time and memory, for
+/// attaching to performance bug reports.
+#[tauri::command]
+fn profile_last_job() -> Option<Profile> {
+    profile::last()
+}
+
+/// Whether the transcription model is loaded, see the `warm_up_model` setting.
+#[tauri::command]
+fn is_model_ready(app: tauri::AppHandle) -> bool {
+    transcription::model_ready(&app)
+}
+
+/// Every model the app knows how to download, with whether it's on disk and
+/// which one the `transcription_model` setting picks.
+#[tauri::command]
+fn list_models(app: tauri::AppHandle) -> Result<Vec<ModelEntry>, Error> {
+    Ok(models::list(&app)?)
+}
+
+/// Times the selected model on the recording at `path`. Benchmark once
+/// before and once after downloading a model's Core ML encoder to see the
+/// speedup it gives.
+#[tauri::command]
+async fn benchmark_model(path: PathBuf, app: tauri::AppHandle) -> Result<Benchmark, Error> {
+    let model = models::path(&app);
+    let quality = app.state::<SettingsStore>().get().resample_quality;
+    Ok(run_blocking(move || benchmark::run(&path, &model, quality)).await?)
+}
+
+/// Which encoder backends this build and machine support, and which the
+/// selected model has encoders for.
+#[tauri::command]
+fn acceleration_capabilities(app: tauri::AppHandle) -> Capabilities {
+    model::capabilities(&models::path(&app))
+}
+
+/// Queues a download of the model with `id`, see `list_models`. Returns the
+/// download job. A copy that's already there is only downloaded again if it
+/// doesn't match its checksum, which is how the UI re-downloads a model
+/// reported on `model://corrupt`.
+#[tauri::command]
+fn download_model(id: String, app: tauri::AppHandle) -> Result<JobId, Error> {
+    Ok(models::download(&app, &id)?)
+}
+
+/// Checks the downloaded model with `id` against the checksum it was
+/// downloaded with.
+#[tauri::command]
+async fn verify_model(id: String, app: tauri::AppHandle) -> Result<Verification, Error> {
+    Ok(run_blocking(move || models::verify(&app, &id)).await?)
+}
+
+#[tauri::command]
+fn start_recording(app: tauri::AppHandle) -> Result<(), Error> {
+    Ok(recording::start(&app)?)
+}
+
+/// Starts a take that's kept in memory and transcribed from there when it
+/// stops, without a file or library entry. Stop it like any other take; its
+/// transcript is the result of the transcription job that follows.
+#[tauri::command]
+fn start_quick_note(app: tauri::AppHandle) -> Result<(), Error> {
+    Ok(recording::start_quick_note(&app)?)
+}
+
+#[tauri::command]
+fn stop_recording(app: tauri::AppHandle) -> Result<(), Error> {
+    Ok(recording::stop(&app)?)
+}
+
+/// The input device, sample rate and negotiated buffer size of the take in
+/// progress, or `None` when not recording.
+#[tauri::command]
+fn recording_stream_info(
+    recording: tauri::State<'_, recording::Recording>,
+) -> Result<Option<StreamInfo>, Error> {
+    Ok(recording.stream_info()?)
+}
+
+/// Plays an audio file from `start_ms`, replacing whatever was playing.
+#[tauri::command]
+fn play(path: PathBuf, start_ms: Option<u64>, app: tauri::AppHandle) -> Result<(), Error> {
+    Ok(playback::play(&app, path, start_ms.unwrap_or(0))?)
+}
+
+#[tauri::command]
+fn pause(app: tauri::AppHandle) -> Result<(), Error> {
+    Ok(playback::pause(&app)?)
+}
+
+#[tauri::command]
+fn resume(app: tauri::AppHandle) -> Result<(), Error> {
+    Ok(playback::resume(&app)?)
+}
+
+#[tauri::command]
+fn seek(position_ms: u64, app: tauri::AppHandle) -> Result<(), Error> {
+    Ok(playback::seek(&app, position_ms)?)
+}
+
+#[tauri::command]
+fn stop(app: tauri::AppHandle) -> Result<(), Error> {
+    Ok(playback::stop(&app)?)
+}
+
+/// Devices playback can be sent to, see the `playback_device` setting.
+#[tauri::command]
+fn list_output_devices() -> Result<Vec<AudioDevice>, Error> {
+    Ok(audio::devices::list_output_devices()?)
+}
+
+/// Microphones recordings and the microphone test can use.
+#[tauri::command]
+fn list_input_devices() -> Result<Vec<AudioDevice>, Error> {
+    Ok(audio::devices::list_input_devices()?)
+}
+
+/// Records about three seconds from `device_id` (the default input if
+/// `None`) and reports its levels, playing it back when `play_back` is set.
+#[tauri::command]
+async fn test_microphone(
+    device_id: Option<String>,
+    play_back: Option<bool>,
+    app: tauri::AppHandle,
+) -> Result<MicTest, Error> {
+    let test = run_blocking(move || {
+        audio::mic_test::test_microphone(device_id.as_deref(), Duration::from_secs(3))
+    })
+    .await?;
+    if play_back.unwrap_or(false) {
+        let dir = app
+            .path_resolver()
+            .app_cache_dir()
+            .ok_or_else(|| anyhow::anyhow!("no app cache directory"))?;
+        std::fs::create_dir_all(&dir).map_err(anyhow::Error::from)?;
+        let path = dir.join("microphone test.wav");
+        test.audio.write(&path)?;
+        playback::play(&app, path, 0)?;
     }
+    Ok(test)
+}
 
-    // Original sample rate
-    let original_sample_rate = spec.sample_rate as f64;
+#[tauri::command]
+fn playback_status(app: tauri::AppHandle) -> Result<playback::Position, Error> {
+    Ok(playback::position(&app)?)
+}
 
-    // Read all samples
-    let samples: Vec<i16> = reader
-        .samples::<i16>()
-        .map(|s| s.expect("failed to read sample"))
-        .collect();
+#[tauri::command]
+fn record(
+    recording: tauri::State<'_, Recording>,
+    library: tauri::State<'_, Library>,
+) -> Result<(), Error> {
+    println!("recording");
+    let path = recording.next_path()?;
+    Recorder::new().record_for(&path, Duration::from_secs(10))?;
+    library.add_recording(&NewRecording::from_file(&path)?)?;
+    Ok(())
+}
 
-    // Set up resampler if the sample rates are different
-    let resampled_samples = if (spec.sample_rate as f64 - target_sample_rate).abs() > f64::EPSILON {
-        resample_audio(samples, spec.sample_rate, target_sample_rate, spec.channels)
-    } else {
-        samples
-    };
+/// Runs blocking work (native dialogs, file I/O) off the async runtime.
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> anyhow::Result<T> + Send + 'static,
+) -> anyhow::Result<T> {
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| anyhow::anyhow!(e))?
+}
 
-    // Save the resampled audio to a new file
-    // save_to_wav(
-    //     &resampled_samples,
-    //     spec.channels,
-    //     target_sample_rate as u32,
-    //     "output_resampled.wav",
-    // );
-
-    resampled_samples
-}
-
-fn resample_audio(
-    samples: Vec<i16>,
-    original_rate: u32,
-    target_rate: f64,
-    channels: u16,
-) -> Vec<i16> {
-    let params = SincInterpolationParameters {
-        sinc_len: 256,
-        f_cutoff: 0.90,
-        interpolation: rubato::SincInterpolationType::Cubic,
-        oversampling_factor: 256,
-        window: rubato::WindowFunction::BlackmanHarris2,
-    };
-    let mut resampler = SincFixedIn::<f32>::new(
-        target_rate / original_rate as f64,
-        2.0,
-        params,
-        samples.len(),
-        1, // Channels
-    )
-    .unwrap();
-
-    // Convert i16 to f32 samples
-    let f32_samples: Vec<f32> = samples
-        .iter()
-        .map(|&s| s as f32 / i16::MAX as f32)
-        .collect();
-
-    let waves_in = &[f32_samples];
-    // Resample
-    let resampled_samples = resampler.process(waves_in, None).unwrap();
-
-    // Convert back to i16
-    return resampled_samples[0]
-        .iter()
-        .map(|&s| (s * i16::MAX as f32) as i16)
-        .collect();
+#[tauri::command]
+async fn repair_wav(path: PathBuf) -> Result<Repair, Error> {
+    Ok(run_blocking(move || audio::repair_wav(&path)).await?)
 }
 
-// Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
+/// Scales a WAV file in place so its EBU R128 integrated loudness is
+/// `target_lufs`.
 #[tauri::command]
-async fn transcribe(path: String) -> Result<Vec<String>, String> {
-    tokio::task::spawn_blocking(move || {
-        use std::path::Path;
+async fn normalize_loudness(path: PathBuf, target_lufs: f64) -> Result<Normalization, Error> {
+    Ok(run_blocking(move || audio::normalize_loudness(&path, target_lufs)).await?)
+}
 
-        println!("Path: {}", path);
-        let audio_path = Path::new("/Users/devingould/tauri-app/src-tauri/src/samples/a13.wav");
-        if !audio_path.exists() {
-            panic!("audio file doesn't exist");
-        }
-        let whisper_path =
-            Path::new("/Users/devingould/tauri-app/src-tauri/src/models/ggml-small.en-tdrz.bin");
-        if !whisper_path.exists() {
-            panic!("whisper file doesn't exist");
-        }
+/// Removes low-frequency rumble below `cutoff_hz` from a WAV file in place.
+#[tauri::command]
+async fn high_pass_filter(path: PathBuf, cutoff_hz: f32) -> Result<(), Error> {
+    Ok(run_blocking(move || audio::filters::apply_high_pass(&path, cutoff_hz)).await?)
+}
 
-        // Assuming parse_wav_file and other functions are correctly defined elsewhere
-        let original_samples = parse_and_resample_wav_file(audio_path, 16000.0);
-        let mut samples = vec![0.0f32; original_samples.len()];
-        whisper_rs::convert_integer_to_float_audio(&original_samples, &mut samples)
-            .expect("failed to convert samples");
-
-        let ctx = WhisperContext::new_with_params(
-            &whisper_path.to_string_lossy(),
-            WhisperContextParameters::default(),
-        )
-        .expect("failed to open model");
-        let mut state = ctx.create_state().expect("failed to create state");
-        let mut params = FullParams::new(SamplingStrategy::default());
-        params.set_initial_prompt("experience");
-        params.set_progress_callback_safe(|progress| println!("Progress callback: {}%", progress));
-        params.set_tdrz_enable(true);
-
-        let st = std::time::Instant::now();
-        state
-            .full(params, &samples)
-            .expect("failed to transcribe audio");
-
-        let et = std::time::Instant::now();
-
-        let num_segments = state
-            .full_n_segments()
-            .expect("failed to get number of segments");
-        let mut full_text: Vec<String> = vec![String::new()];
-        let mut full_text_index = 0;
-        for i in 0..num_segments {
-            let segment = state
-                .full_get_segment_text(i)
-                .expect("failed to get segment");
-            full_text[full_text_index].push_str(&segment);
-            if (state.full_get_segment_speaker_turn_next(i)) {
-                full_text.push(String::new());
-                full_text_index += 1
-            }
-            let start_timestamp = state
-                .full_get_segment_t0(i)
-                .expect("failed to get start timestamp");
-            let end_timestamp = state
-                .full_get_segment_t1(i)
-                .expect("failed to get end timestamp");
-            println!("[{} - {}]: {}", start_timestamp, end_timestamp, segment);
+/// Rebuilds clipped peaks in a WAV file in place.
+#[tauri::command]
+async fn declip_audio(path: PathBuf) -> Result<Declip, Error> {
+    Ok(run_blocking(move || audio::dynamics::declip_file(&path)).await?)
+}
+
+/// Frequency content of a WAV file, for spotting hum, hiss and rumble.
+#[tauri::command]
+async fn generate_spectrogram(
+    path: PathBuf,
+    params: Option<SpectrogramParams>,
+) -> Result<Spectrogram, Error> {
+    let params = params.unwrap_or_default();
+    Ok(run_blocking(move || audio::spectrogram::generate_spectrogram(&path, &params)).await?)
+}
+
+/// Min/max pairs for drawing a recording's waveform `columns` wide.
+#[tauri::command]
+async fn waveform_peaks(path: PathBuf, columns: usize) -> Result<Vec<Peak>, Error> {
+    Ok(run_blocking(move || audio::waveform::waveform_peaks(&path, columns)).await?)
+}
+
+/// Writes a copy of a WAV file with `ranges` kept or cut out, next to the
+/// original, and adds it to the library.
+#[tauri::command]
+async fn trim_audio(
+    path: PathBuf,
+    ranges: Vec<TimeRange>,
+    mode: TrimMode,
+    library: tauri::State<'_, Library>,
+) -> Result<app_core::library::Recording, Error> {
+    let library = library.inner().clone();
+    Ok(run_blocking(move || {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let output =
+            app_core::library::unique_path(&path.with_file_name(format!("{} (trimmed).wav", stem)));
+        audio::edit::trim_audio(&path, &ranges, mode, &output)?;
+        let id = library.add_recording(&NewRecording::from_file(&output)?)?;
+        Ok(library.recording(id)?.expect("just inserted"))
+    })
+    .await?)
+}
+
+/// Joins WAV files into one at `output`, converting them to a common format,
+/// and adds it to the library.
+#[tauri::command]
+async fn concat_audio(
+    paths: Vec<PathBuf>,
+    output: PathBuf,
+    library: tauri::State<'_, Library>,
+    settings: tauri::State<'_, SettingsStore>,
+) -> Result<app_core::library::Recording, Error> {
+    let library = library.inner().clone();
+    let quality = settings.get().resample_quality;
+    Ok(run_blocking(move || {
+        audio::edit::concat_audio(&paths, &output, quality)?;
+        let id = library.add_recording(&NewRecording::from_file(&output)?)?;
+        Ok(library.recording(id)?.expect("just inserted"))
+    })
+    .await?)
+}
+
+/// Writes a copy of `input` at `output` in another encoding, sample rate or
+/// channel count; unset options keep the input's.
+#[tauri::command]
+async fn convert_audio(
+    input: PathBuf,
+    output: PathBuf,
+    format: Option<Encoding>,
+    sample_rate: Option<u32>,
+    channels: Option<u16>,
+    settings: tauri::State<'_, SettingsStore>,
+) -> Result<(), Error> {
+    let options = ConvertOptions {
+        encoding: format.unwrap_or_default(),
+        sample_rate,
+        channels,
+    };
+    let quality = settings.get().resample_quality;
+    Ok(run_blocking(move || audio::edit::convert_audio(&input, &output, options, quality)).await?)
+}
+
+/// Splits a multichannel recording into one mono recording per channel, so
+/// each side of a call can be transcribed on its own.
+#[tauri::command]
+async fn split_channels(
+    path: PathBuf,
+    library: tauri::State<'_, Library>,
+) -> Result<Vec<app_core::library::Recording>, Error> {
+    let library = library.inner().clone();
+    Ok(run_blocking(move || {
+        audio::edit::split_channels_file(&path)?
+            .iter()
+            .map(|part| {
+                let id = library.add_recording(&NewRecording::from_file(part)?)?;
+                Ok(library.recording(id)?.expect("just inserted"))
+            })
+            .collect()
+    })
+    .await?)
+}
+
+/// Splits a long recording into numbered takes at its long pauses and adds
+/// them to the library.
+#[tauri::command]
+async fn split_on_silence(
+    path: PathBuf,
+    options: Option<SilenceOptions>,
+    library: tauri::State<'_, Library>,
+) -> Result<SilenceSplit, Error> {
+    let library = library.inner().clone();
+    let options = options.unwrap_or_default();
+    Ok(run_blocking(move || {
+        let split = audio::silence::split_on_silence(&path, &options)?;
+        for take in &split.takes {
+            library.add_recording(&NewRecording::from_file(take)?)?;
         }
-        println!("Transcription took {}ms", (et - st).as_millis());
-        Ok(full_text)
+        Ok(split)
     })
-    .await
-    .map_err(|e| e.to_string())?
+    .await?)
 }
 
-#[derive(Debug, Serialize)]
-struct Error {
-    message: String,
+#[tauri::command]
+fn check_mic_permission() -> MicPermission {
+    permissions::mic_permission()
+}
+
+#[tauri::command]
+async fn request_mic_permission() -> Result<MicPermission, Error> {
+    tauri::async_runtime::spawn_blocking(permissions::request_mic_permission)
+        .await
+        .map_err(|e| Error::from(anyhow::anyhow!(e)))
 }
 
-struct Recorder {
-    writer: Arc<Mutex<Option<WavWriter<BufWriter<File>>>>>,
-    stream: Option<Stream>,
+#[tauri::command]
+fn open_mic_settings() -> Result<(), Error> {
+    Ok(permissions::open_mic_settings()?)
 }
 
-impl Recorder {
-    fn new() -> Result<Self> {
-        let host = cpal::default_host();
-        let device = host
-            .default_input_device()
-            .expect("No input device available");
-        let config = device.default_input_config()?;
+#[tauri::command]
+async fn check_for_updates(app: tauri::AppHandle) -> Result<Option<updater::UpdateInfo>, Error> {
+    Ok(updater::check(&app).await?)
+}
 
-        let spec = WavSpec {
-            channels: config.channels(),
-            sample_rate: config.sample_rate().0,
-            bits_per_sample: 16,
-            sample_format: hound::SampleFormat::Int,
-        };
+#[tauri::command]
+async fn install_update(app: tauri::AppHandle) -> Result<(), Error> {
+    Ok(updater::install(&app).await?)
+}
 
-        let writer = Arc::new(Mutex::new(None));
+/// Exports a finished job's transcript, or a recording's current one when
+/// `recording_id` is given instead, as text, subtitles, PDF or DOCX.
+#[tauri::command]
+fn export_transcript(
+    job_id: Option<JobId>,
+    recording_id: Option<RecordingId>,
+    path: PathBuf,
+    format: ExportFormat,
+    jobs: tauri::State<'_, Jobs>,
+    library: tauri::State<'_, Library>,
+    settings: tauri::State<'_, SettingsStore>,
+) -> Result<(), Error> {
+    let source = match (job_id, recording_id) {
+        (_, Some(id)) => Source::Recording(id),
+        (Some(id), None) => Source::Job(id),
+        (None, None) => return Err(anyhow::anyhow!("nothing to export").into()),
+    };
+    Ok(export::export_transcript(
+        &jobs,
+        &library,
+        source,
+        &path,
+        format,
+        &settings.get().segment_merging,
+    )?)
+}
 
-        Ok(Self {
-            writer: writer,
-            stream: None,
-        })
-    }
+/// Shows the native share sheet for exported transcripts or audio files.
+#[tauri::command]
+fn share_files(paths: Vec<PathBuf>, window: tauri::Window) -> Result<(), Error> {
+    Ok(share::share(&window, paths)?)
+}
 
-    fn start(&mut self) -> Result<()> {
-        let device = cpal::default_host()
-            .default_input_device()
-            .expect("No input device available");
-        let config = device.default_input_config()?;
-
-        let spec = WavSpec {
-            channels: config.channels(),
-            sample_rate: config.sample_rate().0,
-            bits_per_sample: 16,
-            sample_format: hound::SampleFormat::Int,
-        };
-        self.writer = Arc::new(Mutex::new(Some(WavWriter::create("output.wav", spec)?)));
-
-        let writer_clone = self.writer.clone();
-        let stream = device.build_input_stream(
-            &config.into(),
-            move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                if let Ok(mut writer_lock) = writer_clone.lock() {
-                    if let Some(ref mut writer) = *writer_lock {
-                        for &sample in data {
-                            let amplitude = (sample * i16::MAX as f32) as i16;
-                            writer
-                                .write_sample(amplitude)
-                                .expect("Failed to write sample");
-                        }
-                    }
-                }
-            },
-            |err| eprintln!("Error: {:?}", err),
-            Some(std::time::Duration::from_secs(30)),
-        )?;
-
-        stream.play()?;
-        self.stream = Some(stream);
-        Ok(())
+/// Uploads a recording's audio and transcript to the bucket from settings.
+#[tauri::command]
+async fn upload_recording(id: RecordingId, app: tauri::AppHandle) -> Result<Vec<Upload>, Error> {
+    Ok(upload::upload(&app, id).await?)
+}
+
+#[tauri::command]
+fn list_uploads(id: RecordingId, library: tauri::State<'_, Library>) -> Result<Vec<Upload>, Error> {
+    Ok(library.uploads(id)?)
+}
+
+/// Saves a recording's transcript as a note in the notes folder from settings.
+#[tauri::command]
+fn export_to_notes(
+    id: RecordingId,
+    library: tauri::State<'_, Library>,
+    settings: tauri::State<'_, SettingsStore>,
+) -> Result<PathBuf, Error> {
+    let settings = settings.get();
+    let folder = PathBuf::from(&settings.notes_folder);
+    Ok(export::export_to_notes(
+        &library,
+        id,
+        &folder,
+        &settings.segment_merging,
+    )?)
+}
+
+#[tauri::command]
+async fn pick_audio_files() -> Result<Vec<PathBuf>, Error> {
+    Ok(run_blocking(dialogs::pick_audio_files).await?)
+}
+
+#[tauri::command]
+async fn pick_export_path(
+    default_name: String,
+    extension: String,
+) -> Result<Option<PathBuf>, Error> {
+    Ok(run_blocking(move || dialogs::pick_export_path(&default_name, &extension)).await?)
+}
+
+#[tauri::command]
+fn list_jobs(jobs: tauri::State<'_, Jobs>) -> Vec<Job> {
+    jobs.list()
+}
+
+#[tauri::command]
+fn get_job(id: JobId, jobs: tauri::State<'_, Jobs>) -> Option<Job> {
+    jobs.get(id)
+}
+
+/// Cancels every queued and running job except recordings, e.g. to free up
+/// the machine before a presentation. Returns how many were cancelled.
+#[tauri::command]
+fn cancel_all_jobs(
+    jobs: tauri::State<'_, Jobs>,
+    library: tauri::State<'_, Library>,
+) -> Result<usize, Error> {
+    let cancelled = jobs.cancel_all();
+    // Cancelled transcriptions shouldn't come back after a restart.
+    library.clear_transcription_queue()?;
+    Ok(cancelled)
+}
+
+/// Stops queued jobs from starting until `resume_queue`. Running jobs finish.
+#[tauri::command]
+fn pause_queue(jobs: tauri::State<'_, Jobs>) {
+    jobs.pause_queue();
+}
+
+#[tauri::command]
+fn resume_queue(jobs: tauri::State<'_, Jobs>) {
+    jobs.resume_queue();
+}
+
+#[tauri::command]
+fn list_recordings(query: ListQuery, library: tauri::State<'_, Library>) -> Result<Page, Error> {
+    Ok(library.list_recordings(&query)?)
+}
+
+#[tauri::command]
+fn set_favorite(
+    id: RecordingId,
+    favorite: bool,
+    library: tauri::State<'_, Library>,
+) -> Result<(), Error> {
+    Ok(library.set_favorite(id, favorite)?)
+}
+
+#[tauri::command]
+fn tag_recording(
+    id: RecordingId,
+    tag: String,
+    library: tauri::State<'_, Library>,
+) -> Result<(), Error> {
+    Ok(library.add_tag(id, &tag)?)
+}
+
+#[tauri::command]
+fn untag_recording(
+    id: RecordingId,
+    tag: String,
+    library: tauri::State<'_, Library>,
+) -> Result<(), Error> {
+    Ok(library.remove_tag(id, &tag)?)
+}
+
+fn trash_dir(app: &tauri::AppHandle) -> anyhow::Result<PathBuf> {
+    Ok(app
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| anyhow::anyhow!("no app data directory"))?
+        .join("trash"))
+}
+
+/// Moves a recording to the trash, or deletes it and its file right away
+/// when `permanent` is set.
+#[tauri::command]
+fn delete_recording(
+    id: RecordingId,
+    permanent: bool,
+    app: tauri::AppHandle,
+    library: tauri::State<'_, Library>,
+) -> Result<(), Error> {
+    if permanent {
+        library.delete_recording(id)?;
+    } else {
+        library.trash_recording(id, &trash_dir(&app)?)?;
     }
+    Ok(())
+}
 
-    fn stop(&mut self) -> Result<()> {
-        if let Some(stream) = self.stream.take() {
-            stream.pause()?;
-            drop(stream);
-        }
+#[tauri::command]
+fn restore_recording(id: RecordingId, library: tauri::State<'_, Library>) -> Result<(), Error> {
+    Ok(library.restore_recording(id)?)
+}
+
+/// Retitles a recording, renaming its file to match when `rename_file` is set.
+#[tauri::command]
+fn rename_recording(
+    id: RecordingId,
+    title: String,
+    rename_file: bool,
+    library: tauri::State<'_, Library>,
+) -> Result<(), Error> {
+    Ok(library.rename_recording(id, &title, rename_file)?)
+}
+
+/// Adds existing audio files to the library, see [`import::import_audio`].
+#[tauri::command]
+async fn import_audio(
+    paths: Vec<PathBuf>,
+    copy: bool,
+    transcribe: bool,
+    app: tauri::AppHandle,
+) -> Result<Vec<import::Imported>, Error> {
+    Ok(run_blocking(move || import::import_audio(&app, paths, copy, transcribe)).await?)
+}
+
+/// Writes the whole library to a portable archive at `path`.
+#[tauri::command]
+async fn export_library(path: PathBuf, library: tauri::State<'_, Library>) -> Result<usize, Error> {
+    let library = library.inner().clone();
+    Ok(run_blocking(move || library.export_backup(&path)).await?)
+}
+
+/// Merges a library archive into this library, unpacking its audio into the
+/// imports folder.
+#[tauri::command]
+async fn import_library(path: PathBuf, app: tauri::AppHandle) -> Result<usize, Error> {
+    Ok(run_blocking(move || {
+        app.state::<Library>()
+            .import_backup(&path, &import::imports_dir(&app)?)
+    })
+    .await?)
+}
+
+/// Shows what the current retention policy would delete, without deleting it.
+#[tauri::command]
+fn preview_retention(
+    settings: tauri::State<'_, SettingsStore>,
+    library: tauri::State<'_, Library>,
+) -> Result<RetentionPlan, Error> {
+    Ok(library.plan_retention(&settings.get().retention)?)
+}
+
+#[tauri::command]
+fn empty_trash(library: tauri::State<'_, Library>) -> Result<usize, Error> {
+    Ok(library.purge_trash(0)?)
+}
 
-        // Take out the WavWriter, finalize it, and replace with None
-        let maybe_writer = {
-            let mut writer_lock = self.writer.lock().unwrap();
-            writer_lock.take() // This takes the WavWriter out and leaves None in its place
-        };
+#[tauri::command]
+fn list_tags(library: tauri::State<'_, Library>) -> Result<Vec<TagCount>, Error> {
+    Ok(library.tags()?)
+}
+
+#[tauri::command]
+fn search_transcripts(
+    query: String,
+    session_id: Option<SessionId>,
+    library: tauri::State<'_, Library>,
+) -> Result<Vec<SearchHit>, Error> {
+    Ok(library.search_transcripts(&query, session_id)?)
+}
+
+#[tauri::command]
+fn list_transcript_versions(
+    recording_id: RecordingId,
+    library: tauri::State<'_, Library>,
+) -> Result<Vec<TranscriptVersion>, Error> {
+    Ok(library.transcript_versions(recording_id)?)
+}
+
+/// Compares two versions of a transcript segment by segment.
+#[tauri::command]
+fn diff_transcript_versions(
+    old_id: TranscriptId,
+    new_id: TranscriptId,
+    library: tauri::State<'_, Library>,
+) -> Result<Vec<Change>, Error> {
+    let old = library.require_transcript_version(old_id)?;
+    let new = library.require_transcript_version(new_id)?;
+    Ok(diff::diff(&old.segments, &new.segments))
+}
 
-        if let Some(mut writer) = maybe_writer {
-            writer.finalize()?; // Now you can finalize without moving out of the MutexGuard
+#[tauri::command]
+fn restore_transcript_version(
+    recording_id: RecordingId,
+    version_id: TranscriptId,
+    library: tauri::State<'_, Library>,
+) -> Result<TranscriptId, Error> {
+    Ok(library.restore_transcript_version(recording_id, version_id)?)
+}
+
+/// Saves the user's edits as a new version of the recording's transcript.
+#[tauri::command]
+fn edit_transcript(
+    recording_id: RecordingId,
+    transcript: Transcript,
+    library: tauri::State<'_, Library>,
+) -> Result<TranscriptId, Error> {
+    Ok(library.edit_transcript(recording_id, &transcript)?)
+}
+
+/// Summarizes a transcript version with the local LLM and stores the bullets
+/// and action items next to it.
+#[tauri::command]
+async fn summarize_transcript(
+    id: TranscriptId,
+    style: SummaryStyle,
+    library: tauri::State<'_, Library>,
+    settings: tauri::State<'_, SettingsStore>,
+) -> Result<TranscriptSummary, Error> {
+    let library = library.inner().clone();
+    let model = PathBuf::from(settings.get().summary_model_path);
+    Ok(run_blocking(move || {
+        if !model.is_file() {
+            anyhow::bail!("choose a summarization model in settings first");
         }
+        let transcript = library.require_transcript_version(id)?;
+        let summary = summarize::summarize(&transcript, style, &model)?;
+        let model_name = model.file_stem().unwrap_or_default().to_string_lossy();
+        library.save_summary(id, style, &model_name, &summary)
+    })
+    .await?)
+}
 
-        Ok(())
-    }
+/// The stored summary of a transcript version, if it has been summarized.
+#[tauri::command]
+fn get_transcript_summary(
+    id: TranscriptId,
+    style: SummaryStyle,
+    library: tauri::State<'_, Library>,
+) -> Result<Option<TranscriptSummary>, Error> {
+    Ok(library.summary(id, style)?)
 }
 
-pub struct RecorderState(Mutex<Recorder>);
+/// Key phrases kept per transcript version.
+const KEY_PHRASES: usize = 20;
 
-impl From<anyhow::Error> for Error {
-    fn from(err: anyhow::Error) -> Self {
-        Error {
-            message: err.to_string(),
+/// Ranks the key phrases of a transcript version and stores them next to it.
+/// With `auto_tag`, the recording is also tagged with that many of the top
+/// phrases.
+#[tauri::command]
+async fn extract_keywords(
+    transcript_id: TranscriptId,
+    auto_tag: Option<usize>,
+    library: tauri::State<'_, Library>,
+) -> Result<Vec<KeyPhrase>, Error> {
+    let library = library.inner().clone();
+    Ok(run_blocking(move || {
+        let transcript = library.require_transcript_version(transcript_id)?;
+        let phrases = key_phrases::extract_key_phrases(&transcript, KEY_PHRASES);
+        library.save_key_phrases(transcript_id, &phrases)?;
+        if let Some(top) = auto_tag {
+            library.tag_with_key_phrases(transcript_id, top)?;
         }
-    }
+        Ok(phrases)
+    })
+    .await?)
 }
 
-enum AudioCommand {
-    Start,
-    Stop,
+/// The stored key phrases of a transcript version, best first.
+#[tauri::command]
+fn get_keywords(
+    transcript_id: TranscriptId,
+    library: tauri::State<'_, Library>,
+) -> Result<Vec<KeyPhrase>, Error> {
+    Ok(library.key_phrases(transcript_id)?)
 }
 
-struct AudioController {
-    sender: Sender<AudioCommand>,
+/// Picks out the action items of a meeting transcript version, with owners
+/// and due dates where they were said, and stores them next to it.
+#[tauri::command]
+async fn extract_action_items(
+    transcript_id: TranscriptId,
+    library: tauri::State<'_, Library>,
+) -> Result<Vec<ActionItem>, Error> {
+    let library = library.inner().clone();
+    Ok(run_blocking(move || {
+        let transcript = library.require_transcript_version(transcript_id)?;
+        let items = action_items::find_action_items(&transcript);
+        library.save_action_items(transcript_id, &items)?;
+        Ok(items)
+    })
+    .await?)
 }
 
-impl AudioController {
-    fn new() -> Self {
-        let (sender, receiver) = mpsc::channel();
-        thread::spawn(move || {
-            let mut recorder = Recorder::new().expect("Failed to initialize the recorder");
-            for command in receiver {
-                match command {
-                    AudioCommand::Start => {
-                        recorder.start().expect("Failed to start recording");
-                    }
-                    AudioCommand::Stop => {
-                        recorder.stop().expect("Failed to stop recording");
-                    }
-                }
-            }
-        });
-        AudioController { sender }
-    }
+/// The stored action items of a transcript version.
+#[tauri::command]
+fn get_action_items(
+    transcript_id: TranscriptId,
+    library: tauri::State<'_, Library>,
+) -> Result<Vec<ActionItem>, Error> {
+    Ok(library.action_items(transcript_id)?)
+}
 
-    fn start(&self) {
-        self.sender
-            .send(AudioCommand::Start)
-            .expect("Failed to send start command");
-    }
+/// Splits a transcript version into titled chapters where the topic shifts
+/// and stores them next to it.
+#[tauri::command]
+async fn generate_chapters(
+    transcript_id: TranscriptId,
+    library: tauri::State<'_, Library>,
+) -> Result<Vec<Chapter>, Error> {
+    let library = library.inner().clone();
+    Ok(run_blocking(move || {
+        let transcript = library.require_transcript_version(transcript_id)?;
+        let chapters = chapters::generate_chapters(&transcript);
+        library.save_chapters(transcript_id, &chapters)?;
+        Ok(chapters)
+    })
+    .await?)
+}
 
-    fn stop(&self) {
-        self.sender
-            .send(AudioCommand::Stop)
-            .expect("Failed to send stop command");
-    }
+/// The stored chapters of a transcript version, in order.
+#[tauri::command]
+fn get_chapters(
+    transcript_id: TranscriptId,
+    library: tauri::State<'_, Library>,
+) -> Result<Vec<Chapter>, Error> {
+    Ok(library.chapters(transcript_id)?)
 }
 
+/// A transcript version's chapters as a list to paste into a YouTube
+/// description, also written to `path` when one is given. Chapters are
+/// generated first if there are none yet.
 #[tauri::command]
-fn start_recording(audio_controller: tauri::State<'_, Arc<AudioController>>) {
-    audio_controller.start();
+async fn export_youtube_chapters(
+    transcript_id: TranscriptId,
+    path: Option<PathBuf>,
+    library: tauri::State<'_, Library>,
+) -> Result<String, Error> {
+    let library = library.inner().clone();
+    Ok(run_blocking(move || {
+        export::export_youtube_chapters(&library, transcript_id, path.as_deref())
+    })
+    .await?)
 }
 
+/// Tags the people, organizations and dates mentioned in a transcript
+/// version and stores them next to it.
 #[tauri::command]
-fn stop_recording(audio_controller: tauri::State<'_, Arc<AudioController>>) {
-    audio_controller.stop();
+async fn tag_entities(
+    transcript_id: TranscriptId,
+    library: tauri::State<'_, Library>,
+) -> Result<Vec<Entity>, Error> {
+    let library = library.inner().clone();
+    Ok(run_blocking(move || {
+        let transcript = library.require_transcript_version(transcript_id)?;
+        let entities = entities::tag_entities(&transcript);
+        library.save_entities(transcript_id, &entities)?;
+        Ok(entities)
+    })
+    .await?)
 }
 
+/// The stored entities of a transcript version, for highlighting them.
 #[tauri::command]
-fn record() -> Result<(), Error> {
-    println!("recording");
-    let host = cpal::default_host();
-    let device = host
-        .default_input_device()
-        .expect("no output device available");
-    println!("{:#?}", device.name());
-    let supported_formats_range = device.supported_input_configs().map_err(|e| anyhow!(e))?;
-
-    for format in supported_formats_range {
-        println!("{:?}", format);
-    }
-    let config = device.default_input_config().map_err(|e| anyhow!(e))?;
-
-    // Define WAV file specifications
-    let spec = WavSpec {
-        channels: config.channels(),
-        sample_rate: config.sample_rate().0,
-        bits_per_sample: 16,
-        sample_format: hound::SampleFormat::Int,
-    };
+fn get_entities(
+    transcript_id: TranscriptId,
+    library: tauri::State<'_, Library>,
+) -> Result<Vec<Entity>, Error> {
+    Ok(library.entities(transcript_id)?)
+}
 
-    let writer: Arc<Mutex<WavWriter<std::io::BufWriter<std::fs::File>>>> = Arc::new(Mutex::new(
-        WavWriter::create("output.wav", spec).map_err(|e| anyhow!(e))?,
-    ));
-
-    let writer_clone = writer.clone();
-    let stream = device
-        .build_input_stream(
-            &config.into(),
-            move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                let mut writer = writer_clone.lock().unwrap();
-                for &sample in data {
-                    let amplitude = (sample * i16::MAX as f32) as i16; // convert f32 audio samples to i16
-                    writer
-                        .write_sample(amplitude)
-                        .expect("Failed to write sample");
-                }
-            },
-            |err| {
-                eprintln!("Error: {:?}", err);
-            },
-            Some(std::time::Duration::from_secs(30)), // Set a timeout of 30 seconds
-        )
-        .map_err(|e| anyhow!(e))?;
+/// Recordings mentioning an entity whose text contains `query`.
+#[tauri::command]
+fn search_entities(
+    query: String,
+    kind: Option<EntityKind>,
+    library: tauri::State<'_, Library>,
+) -> Result<Vec<EntityHit>, Error> {
+    Ok(library.search_entities(&query, kind)?)
+}
+
+/// Translates a transcript version into `language` with the translator from
+/// settings and stores it next to the original. Segment timing is kept.
+#[tauri::command]
+async fn translate_transcript(
+    id: TranscriptId,
+    language: String,
+    library: tauri::State<'_, Library>,
+    settings: tauri::State<'_, SettingsStore>,
+) -> Result<TranscriptTranslation, Error> {
+    let library = library.inner().clone();
+    let settings = settings.get();
+    Ok(run_blocking(move || {
+        let transcript = library.require_transcript_version(id)?;
+        let translator = translate::translator(
+            &settings.translation,
+            PathBuf::from(settings.summary_model_path),
+        );
+        let translated = translate::translate(&transcript, &language, translator.as_ref())?;
+        library.save_translation(id, &language, &translator.name(), &translated)
+    })
+    .await?)
+}
+
+#[tauri::command]
+fn get_transcript_translation(
+    id: TranscriptId,
+    language: String,
+    library: tauri::State<'_, Library>,
+) -> Result<Option<TranscriptTranslation>, Error> {
+    Ok(library.translation(id, &language)?)
+}
+
+#[tauri::command]
+fn list_transcript_translations(
+    id: TranscriptId,
+    library: tauri::State<'_, Library>,
+) -> Result<Vec<String>, Error> {
+    Ok(library.translation_languages(id)?)
+}
+
+#[tauri::command]
+fn list_sessions(library: tauri::State<'_, Library>) -> Result<Vec<Session>, Error> {
+    Ok(library.sessions()?)
+}
 
-    stream.play().map_err(|e| anyhow!(e))?;
+#[tauri::command]
+fn create_session(name: String, library: tauri::State<'_, Library>) -> Result<SessionId, Error> {
+    Ok(library.create_session(&name)?)
+}
 
-    // Record for a specific duration
-    std::thread::sleep(std::time::Duration::from_secs(10));
+#[tauri::command]
+fn rename_session(
+    id: SessionId,
+    name: String,
+    library: tauri::State<'_, Library>,
+) -> Result<(), Error> {
+    Ok(library.rename_session(id, &name)?)
+}
 
-    // Finalize the WAV file
-    drop(stream);
-    drop(writer);
+#[tauri::command]
+fn delete_session(id: SessionId, library: tauri::State<'_, Library>) -> Result<(), Error> {
+    Ok(library.delete_session(id)?)
+}
+
+/// Moves a recording into a session, or out of its session when `session_id` is null.
+#[tauri::command]
+fn set_recording_session(
+    recording_id: RecordingId,
+    session_id: Option<SessionId>,
+    library: tauri::State<'_, Library>,
+) -> Result<(), Error> {
+    Ok(library.set_session(recording_id, session_id)?)
+}
+
+#[tauri::command]
+fn export_session(
+    id: SessionId,
+    path: PathBuf,
+    format: Format,
+    library: tauri::State<'_, Library>,
+    settings: tauri::State<'_, SettingsStore>,
+) -> Result<(), Error> {
+    Ok(export::export_session(
+        &library,
+        id,
+        &path,
+        format,
+        &settings.get().segment_merging,
+    )?)
+}
+
+#[tauri::command]
+fn get_settings(settings: tauri::State<'_, SettingsStore>) -> Settings {
+    settings.get()
+}
+
+#[tauri::command]
+fn update_settings(
+    new_settings: Settings,
+    app: tauri::AppHandle,
+    settings: tauri::State<'_, SettingsStore>,
+    jobs: tauri::State<'_, Jobs>,
+) -> Result<(), Error> {
+    let old = settings.get();
+    // The shortcut and autostart live in the OS. They're changed first so a
+    // shortcut that can't be registered is never saved, and put back if a
+    // later step fails so they keep matching what's stored.
+    shortcut::replace(
+        &app,
+        &old.toggle_recording_shortcut,
+        &new_settings.toggle_recording_shortcut,
+    )?;
+    let autostart_changed = old.launch_at_login != new_settings.launch_at_login;
+    let roll_back = |autostart: bool| {
+        if autostart {
+            if let Err(err) = autostart::apply(&app, old.launch_at_login) {
+                eprintln!("Failed to restore launch at login: {:?}", err);
+            }
+        }
+        if let Err(err) = shortcut::replace(
+            &app,
+            &new_settings.toggle_recording_shortcut,
+            &old.toggle_recording_shortcut,
+        ) {
+            eprintln!("Failed to restore the recording shortcut: {:?}", err);
+        }
+    };
+    if autostart_changed {
+        if let Err(err) = autostart::apply(&app, new_settings.launch_at_login) {
+            roll_back(false);
+            return Err(err.into());
+        }
+    }
+    if let Err(err) = settings.set(new_settings.clone()) {
+        roll_back(autostart_changed);
+        return Err(err.into());
+    }
 
+    jobs.set_max_parallel(new_settings.max_parallel_jobs);
+    jobs.set_background_limits(new_settings.background_job_limits);
+    jobs.set_retry_policy(new_settings.retry);
+    model::set_openvino(new_settings.openvino);
+    model::set_pool_size(new_settings.whisper_states);
+    if old.language != new_settings.language {
+        i18n::set_language(new_settings.language);
+        tray::relabel(&app);
+        menu::relabel(&app);
+    }
+    let restart_api = old.http_api_enabled != new_settings.http_api_enabled
+        || old.http_api_port != new_settings.http_api_port
+        || old.http_api_token != new_settings.http_api_token
+        || old.http_api_live_captions != new_settings.http_api_live_captions;
+    let restart_wake_word = old.wake_word_enabled != new_settings.wake_word_enabled
+        || old.wake_phrase != new_settings.wake_phrase
+        || old.wake_word_model_path != new_settings.wake_word_model_path;
+    if restart_api {
+        app.state::<HttpApi>().apply(&app, &new_settings);
+    }
+    if restart_wake_word {
+        app.state::<WakeWord>().apply(&app, &new_settings);
+    }
     Ok(())
 }
 
 fn main() {
-    let audio_controller = Arc::new(AudioController::new());
+    let context = tauri::generate_context!();
+    if let Some(ids) = models::fetch_models_arg(std::env::args().skip(1)) {
+        let data_dir = tauri::api::path::app_data_dir(context.config())
+            .expect("failed to resolve app data dir");
+        if let Err(err) = models::fetch_from_cli(&data_dir, &ids) {
+            eprintln!("{:#}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+    deep_link::prepare(&context.config().tauri.bundle.identifier);
+
+    // Loaded before the builder because the menu and tray are built from
+    // translated labels before setup runs.
+    let config_dir = tauri::api::path::app_config_dir(context.config())
+        .expect("failed to resolve app config dir");
+    let settings =
+        SettingsStore::load(config_dir.join("settings.json")).expect("failed to load settings");
+    i18n::set_language(settings.get().language);
+    model::set_openvino(settings.get().openvino);
+    model::set_pool_size(settings.get().whisper_states);
+    http_api::ensure_token(&settings).expect("failed to save settings");
+    let data_dir =
+        tauri::api::path::app_data_dir(context.config()).expect("failed to resolve app data dir");
+    let library = Library::open(&data_dir.join("library.db")).expect("failed to open library");
 
     tauri::Builder::default()
-        .manage(audio_controller)
+        .plugin(instance::plugin())
+        .manage(Recording::new(data_dir.join("recordings")))
+        .manage(library)
+        .manage(settings)
+        .plugin(autostart::plugin())
+        .menu(menu::build("tauri-app"))
+        .on_menu_event(menu::handle_event)
+        .system_tray(tray::build())
+        .on_system_tray_event(tray::handle_event)
+        .manage(Notifier::default())
+        .manage(HttpApi::default())
+        .manage(WakeWord::default())
+        .manage(Captions::default())
+        .manage(Playback::default())
+        .on_window_event(|event| {
+            if let tauri::WindowEvent::FileDrop(drop) = event.event() {
+                file_drop::handle(&event.window().app_handle(), drop);
+            }
+            event
+                .window()
+                .state::<Notifier>()
+                .handle_window_event(&event)
+        })
+        .setup(|app| {
+            let settings = app.state::<SettingsStore>();
+            let jobs = Jobs::new(AppEvents(app.handle()), settings.get().max_parallel_jobs);
+            jobs.continue_after(app.state::<Library>().last_job_id()?);
+            jobs.set_background_limits(settings.get().background_job_limits);
+            jobs.set_retry_policy(settings.get().retry);
+            app.manage(jobs);
+            if let Err(err) = transcription::resume_queue(&app.handle()) {
+                eprintln!("Failed to resume queued transcriptions: {:?}", err);
+            }
+
+            let library = app.state::<Library>().inner().clone();
+            let retention_days = settings.get().trash_retention_days;
+            std::thread::spawn(move || {
+                if let Err(err) = library.purge_trash(retention_days) {
+                    eprintln!("Failed to purge trash: {:?}", err);
+                }
+            });
+            retention::spawn(&app.handle());
+            power::spawn(&app.handle());
+            if settings.get().warm_up_model {
+                transcription::warm_up(&app.handle());
+            }
+            app.state::<HttpApi>().apply(&app.handle(), &settings.get());
+            app.state::<WakeWord>()
+                .apply(&app.handle(), &settings.get());
+            if let Err(err) =
+                shortcut::register(&app.handle(), &settings.get().toggle_recording_shortcut)
+            {
+                eprintln!("Failed to register recording shortcut: {:?}", err);
+            }
+
+            autostart::hide_if_minimized(&app.handle());
+            updater::check_in_background(&app.handle());
+            if let Err(err) = deep_link::register(&app.handle()) {
+                eprintln!(
+                    "Failed to register {}:// links: {:?}",
+                    deep_link::SCHEME,
+                    err
+                );
+            }
+            instance::open_args(
+                &app.handle(),
+                std::env::args().skip(1),
+                &std::env::current_dir()?,
+            );
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             transcribe,
+            align_text,
+            copy_transcript,
+            transcribe_url,
+            karaoke_timings,
+            export_transcript,
+            export_to_notes,
+            share_files,
+            upload_recording,
+            list_uploads,
+            is_model_ready,
+            list_models,
+            download_model,
+            verify_model,
+            benchmark_model,
+            acceleration_capabilities,
+            profile_last_job,
             start_recording,
             stop_recording,
-            record
+            start_quick_note,
+            recording_stream_info,
+            play,
+            pause,
+            resume,
+            seek,
+            stop,
+            playback_status,
+            list_output_devices,
+            list_input_devices,
+            test_microphone,
+            record,
+            repair_wav,
+            normalize_loudness,
+            high_pass_filter,
+            declip_audio,
+            generate_spectrogram,
+            waveform_peaks,
+            trim_audio,
+            concat_audio,
+            convert_audio,
+            split_channels,
+            split_on_silence,
+            check_mic_permission,
+            request_mic_permission,
+            open_mic_settings,
+            pick_audio_files,
+            pick_export_path,
+            check_for_updates,
+            install_update,
+            list_jobs,
+            get_job,
+            cancel_all_jobs,
+            pause_queue,
+            resume_queue,
+            list_recordings,
+            set_favorite,
+            tag_recording,
+            untag_recording,
+            list_tags,
+            delete_recording,
+            restore_recording,
+            rename_recording,
+            import_audio,
+            export_library,
+            import_library,
+            empty_trash,
+            preview_retention,
+            search_transcripts,
+            list_transcript_versions,
+            diff_transcript_versions,
+            restore_transcript_version,
+            edit_transcript,
+            summarize_transcript,
+            get_transcript_summary,
+            extract_keywords,
+            get_keywords,
+            extract_action_items,
+            get_action_items,
+            generate_chapters,
+            get_chapters,
+            export_youtube_chapters,
+            tag_entities,
+            get_entities,
+            search_entities,
+            translate_transcript,
+            get_transcript_translation,
+            list_transcript_translations,
+            list_sessions,
+            create_session,
+            rename_session,
+            delete_session,
+            set_recording_session,
+            export_session,
+            get_settings,
+            update_settings
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(context)
+        .expect("error while building tauri application")
+        .run(shutdown::handle_run_event);
 }
@@ -0,0 +1,119 @@
+use anyhow::Result;
+use app_core::i18n::{t, Msg};
+use app_core::jobs::{JobKind, JobState, Jobs};
+use app_core::library::Library;
+use app_core::settings::SettingsStore;
+use app_core::transcribe::format::Format;
+use tauri::{AppHandle, CustomMenuItem, Manager, Menu, Submenu, WindowMenuEvent};
+
+use crate::export::{self, Source};
+use crate::{dialogs, instance, recording};
+
+const RECORD: &str = "record";
+const STOP: &str = "stop";
+const IMPORT_AUDIO: &str = "import_audio";
+const EXPORT_TRANSCRIPT: &str = "export_transcript";
+const PREFERENCES: &str = "preferences";
+
+/// Emitted when Preferences is picked so the frontend can show its settings view.
+pub const PREFERENCES_EVENT: &str = "menu://preferences";
+
+pub fn build(app_name: &str) -> Menu {
+    let file = Menu::new()
+        .add_item(CustomMenuItem::new(IMPORT_AUDIO, t(Msg::ImportAudio)).accelerator("CmdOrCtrl+O"))
+        .add_item(
+            CustomMenuItem::new(EXPORT_TRANSCRIPT, t(Msg::ExportTranscript))
+                .accelerator("CmdOrCtrl+E"),
+        )
+        .add_native_item(tauri::MenuItem::Separator)
+        .add_item(CustomMenuItem::new(PREFERENCES, t(Msg::Preferences)).accelerator("CmdOrCtrl+,"));
+    let recording = Menu::new()
+        .add_item(CustomMenuItem::new(RECORD, t(Msg::Record)).accelerator("CmdOrCtrl+R"))
+        .add_item(CustomMenuItem::new(STOP, t(Msg::Stop)).accelerator("CmdOrCtrl+."));
+    Menu::os_default(app_name)
+        .add_submenu(Submenu::new(t(Msg::File), file))
+        .add_submenu(Submenu::new(t(Msg::Recording), recording))
+}
+
+/// Re-applies item labels after the language setting changes. Submenu titles
+/// can't be changed at runtime in Tauri v1 and update on the next launch.
+pub fn relabel(app: &AppHandle) {
+    for window in app.windows().values() {
+        let menu = window.menu_handle();
+        for (id, msg) in [
+            (IMPORT_AUDIO, Msg::ImportAudio),
+            (EXPORT_TRANSCRIPT, Msg::ExportTranscript),
+            (PREFERENCES, Msg::Preferences),
+            (RECORD, Msg::Record),
+            (STOP, Msg::Stop),
+        ] {
+            let _ = menu.get_item(id).set_title(t(msg));
+        }
+    }
+}
+
+pub fn handle_event(event: WindowMenuEvent) {
+    let app = event.window().app_handle();
+    let id = event.menu_item_id().to_string();
+    let result = match event.menu_item_id().as_str() {
+        RECORD => recording::start(&app),
+        STOP => recording::stop(&app),
+        // Dialogs block, so they can't run on the menu (main) thread.
+        IMPORT_AUDIO => {
+            std::thread::spawn(move || report(&id, import_audio(&app)));
+            Ok(())
+        }
+        EXPORT_TRANSCRIPT => {
+            std::thread::spawn(move || report(&id, export_last_transcript(&app)));
+            Ok(())
+        }
+        PREFERENCES => {
+            let _ = event.window().show();
+            let _ = event.window().set_focus();
+            event
+                .window()
+                .emit(PREFERENCES_EVENT, ())
+                .map_err(Into::into)
+        }
+        _ => Ok(()),
+    };
+    if let Err(err) = result {
+        eprintln!("Menu action {} failed: {:?}", event.menu_item_id(), err);
+    }
+}
+
+fn report(id: &str, result: Result<()>) {
+    if let Err(err) = result {
+        eprintln!("Menu action {} failed: {:?}", id, err);
+    }
+}
+
+fn import_audio(app: &AppHandle) -> Result<()> {
+    for path in dialogs::pick_audio_files()? {
+        instance::open_file(app, path);
+    }
+    Ok(())
+}
+
+fn export_last_transcript(app: &AppHandle) -> Result<()> {
+    let jobs = app.state::<Jobs>();
+    let Some(job) = jobs
+        .list()
+        .into_iter()
+        .rev()
+        .find(|job| job.kind == JobKind::Transcription && job.state == JobState::Done)
+    else {
+        return Ok(());
+    };
+    if let Some(path) = dialogs::pick_export_path("transcript.md", "md")? {
+        export::export_transcript(
+            &jobs,
+            &app.state::<Library>(),
+            Source::Job(job.id),
+            &path,
+            Format::Markdown.into(),
+            &app.state::<SettingsStore>().get().segment_merging,
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,277 @@
+use anyhow::{bail, Context, Result};
+use app_core::jobs::{JobId, JobKind, Jobs, Priority};
+use app_core::settings::SettingsStore;
+use app_core::transcribe::model::{self, CorruptModel};
+use app_core::transcribe::registry::{self, ModelInfo, Verification, DEFAULT_MODEL};
+use serde::Serialize;
+use std::cell::Cell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// Where development builds find the default model without downloading it.
+const BUNDLED_MODEL_PATH: &str =
+    "/Users/devingould/tauri-app/src-tauri/src/models/ggml-small.en-tdrz.bin";
+
+/// Emitted when a model turns out to be damaged as it's loaded, so the UI
+/// can offer to download it again with `download_model`.
+pub const MODEL_CORRUPT_EVENT: &str = "model://corrupt";
+
+#[derive(Clone, Serialize)]
+pub struct ModelCorrupt {
+    pub path: PathBuf,
+    /// Registry id to download it again with, unless it isn't one of ours.
+    pub id: Option<&'static str>,
+}
+
+/// A registry entry as the model picker shows it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelEntry {
+    #[serde(flatten)]
+    pub info: ModelInfo,
+    pub downloaded: bool,
+    /// Its Core ML encoder is downloaded and this build can use it.
+    pub coreml_ready: bool,
+    pub selected: bool,
+}
+
+/// Where downloaded models are kept.
+pub fn models_dir(app: &AppHandle) -> Result<PathBuf> {
+    Ok(dir_in(
+        &app.path_resolver()
+            .app_data_dir()
+            .context("no app data directory")?,
+    ))
+}
+
+fn dir_in(data_dir: &Path) -> PathBuf {
+    data_dir.join("models")
+}
+
+fn selected_id(app: &AppHandle) -> String {
+    let id = app.state::<SettingsStore>().get().transcription_model;
+    match registry::find(&id) {
+        Some(_) => id,
+        None => DEFAULT_MODEL.to_string(),
+    }
+}
+
+/// The file of the model picked in settings. The default model falls back to
+/// the bundled copy until it's been downloaded.
+pub fn path(app: &AppHandle) -> PathBuf {
+    let id = selected_id(app);
+    let model = registry::find(&id).expect("selected_id only returns known models");
+    match models_dir(app) {
+        Ok(dir) if dir.join(model.file_name).exists() || id != DEFAULT_MODEL => {
+            dir.join(model.file_name)
+        }
+        _ => PathBuf::from(BUNDLED_MODEL_PATH),
+    }
+}
+
+/// Every known model, with whether it's downloaded and picked.
+pub fn list(app: &AppHandle) -> Result<Vec<ModelEntry>> {
+    let dir = models_dir(app)?;
+    let selected = selected_id(app);
+    Ok(registry::MODELS
+        .iter()
+        .map(|info| ModelEntry {
+            info: *info,
+            downloaded: dir.join(info.file_name).exists(),
+            coreml_ready: model::uses_coreml(&dir.join(info.file_name)),
+            selected: info.id == selected,
+        })
+        .collect())
+}
+
+/// Queues a download of the model with `id` as a job whose progress is the
+/// percentage received and whose result is the model's path. On Apple Silicon
+/// builds with Core ML, its encoder is fetched too, so a model that's already
+/// downloaded only gets the encoder.
+pub fn download(app: &AppHandle, id: &str) -> Result<JobId> {
+    let model = *registry::find(id).with_context(|| format!("no model named {}", id))?;
+    let dir = models_dir(app)?;
+    let job = app
+        .state::<Jobs>()
+        .enqueue(JobKind::Download, Priority::Normal, move |job| {
+            let result = fetch(&model, &dir, |percent| job.progress(percent));
+            let _ = job.finish(result);
+        });
+    Ok(job.id())
+}
+
+/// Tells the UI if `err` came from loading a damaged model.
+pub fn report_if_corrupt(app: &AppHandle, err: &anyhow::Error) {
+    if let Some(corrupt) = CorruptModel::find(err) {
+        let file_name = corrupt.path.file_name().unwrap_or_default();
+        let id = registry::MODELS
+            .iter()
+            .find(|model| file_name == model.file_name)
+            .map(|model| model.id);
+        let _ = app.emit_all(
+            MODEL_CORRUPT_EVENT,
+            ModelCorrupt {
+                path: corrupt.path.clone(),
+                id,
+            },
+        );
+    }
+}
+
+/// Checks the downloaded model with `id` against the checksum it was
+/// downloaded with. Blocks while the file is hashed.
+pub fn verify(app: &AppHandle, id: &str) -> Result<Verification> {
+    let model = registry::find(id).with_context(|| format!("no model named {}", id))?;
+    let path = models_dir(app)?.join(model.file_name);
+    if !path.exists() {
+        bail!("{} isn't downloaded", id);
+    }
+    registry::verify(&path)
+}
+
+/// Downloads whichever of the model and its Core ML encoder are missing,
+/// reporting one percentage across both. A model that's there but doesn't
+/// match the checksum it was downloaded with is downloaded again; one with
+/// no checksum, e.g. copied in by hand, is kept.
+fn fetch(model: &ModelInfo, dir: &Path, on_progress: impl Fn(f32)) -> Result<PathBuf> {
+    let path = dir.join(model.file_name);
+    if path.exists() && registry::verify(&path)? == Verification::Corrupt {
+        fs::remove_file(&path)?;
+        let _ = fs::remove_file(registry::checksum_path(&path));
+    }
+    let fetch_model = !path.exists();
+    let fetch_encoder =
+        model::coreml_supported() && model.coreml && !model::coreml_encoder_path(&path).is_dir();
+    // The encoder is about a third the size of the model.
+    let model_share = match (fetch_model, fetch_encoder) {
+        (true, true) => 75.0,
+        (true, false) => 100.0,
+        (false, _) => 0.0,
+    };
+    let report = |from: f32, share: f32| {
+        let on_progress = &on_progress;
+        move |received: u64, total: Option<u64>| {
+            if let Some(total) = total.filter(|&total| total > 0) {
+                on_progress(from + received as f32 * share / total as f32);
+            }
+        }
+    };
+    if fetch_model {
+        registry::download(model, dir, report(0.0, model_share))?;
+    }
+    if fetch_encoder {
+        registry::download_coreml(model, dir, report(model_share, 100.0 - model_share))?;
+        // A copy loaded before the encoder was there keeps running without it.
+        model::unload();
+    }
+    Ok(path)
+}
+
+/// Model ids given as `--fetch-models small.en,base` or
+/// `--fetch-models=small.en,base`, if the app was started that way.
+pub fn fetch_models_arg(args: impl IntoIterator<Item = String>) -> Option<Vec<String>> {
+    let mut args = args.into_iter();
+    let list = loop {
+        let arg = args.next()?;
+        if arg == "--fetch-models" {
+            break args.next().unwrap_or_default();
+        }
+        if let Some(list) = arg.strip_prefix("--fetch-models=") {
+            break list.to_string();
+        }
+    };
+    Some(
+        list.split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// Downloads the models with `ids` into the app's model folder under
+/// `data_dir` without starting the app, for seeding machines that will be
+/// offline. Models already there are kept if they match the checksum they
+/// were downloaded with; new downloads are checked against the one the
+/// server lists. Fails, so the process exits non-zero, unless every model
+/// ends up verified. Unknown ids fail before anything's downloaded.
+pub fn fetch_from_cli(data_dir: &Path, ids: &[String]) -> Result<()> {
+    if ids.is_empty() {
+        bail!("--fetch-models needs a comma-separated list of models, e.g. small.en,base");
+    }
+    let models = ids
+        .iter()
+        .map(|id| {
+            registry::find(id).with_context(|| {
+                let known: Vec<&str> = registry::MODELS.iter().map(|model| model.id).collect();
+                format!("no model named {}; known models: {}", id, known.join(", "))
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let dir = dir_in(data_dir);
+    for model in models {
+        let existing = dir.join(model.file_name);
+        if existing.exists() && registry::verify(&existing)? == Verification::Unknown {
+            // Copied in or downloaded before checksums were kept: it's only
+            // seeded if it matches what the server lists, or fetched anew.
+            if registry::adopt(model, &existing)? == Verification::Corrupt {
+                fs::remove_file(&existing)?;
+            }
+        }
+        let shown = Cell::new(-1);
+        let path = fetch(model, &dir, |percent| {
+            // Only print whole steps, the callback fires for every chunk.
+            let percent = percent as i32;
+            if shown.replace(percent) != percent {
+                eprint!("\r{}: {}%", model.id, percent);
+            }
+        })?;
+        if registry::verify(&path)? != Verification::Intact {
+            bail!("{} couldn't be verified against its checksum", model.id);
+        }
+        eprintln!("\r{}: {}", model.id, path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn reads_the_models_to_fetch() {
+        assert_eq!(fetch_models_arg(args(&[])), None);
+        assert_eq!(fetch_models_arg(args(&["--minimized"])), None);
+        assert_eq!(
+            fetch_models_arg(args(&["--fetch-models", "small.en, base"])),
+            Some(args(&["small.en", "base"]))
+        );
+        assert_eq!(
+            fetch_models_arg(args(&["--verbose", "--fetch-models=tiny,,large-v3"])),
+            Some(args(&["tiny", "large-v3"]))
+        );
+    }
+
+    #[test]
+    fn a_bare_flag_fetches_nothing() {
+        let ids = fetch_models_arg(args(&["--fetch-models"])).unwrap();
+        assert!(ids.is_empty());
+        let err = fetch_from_cli(&std::env::temp_dir(), &ids).unwrap_err();
+        assert!(err.to_string().contains("comma-separated list"));
+    }
+
+    #[test]
+    fn unknown_models_fail_before_downloading() {
+        let data_dir =
+            std::env::temp_dir().join(format!("tauri-app-models-{}", std::process::id()));
+        let err = fetch_from_cli(&data_dir, &args(&["tiny", "huge"])).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("no model named huge; known models: tiny,"));
+        assert!(!data_dir.exists());
+    }
+}
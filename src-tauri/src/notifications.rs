@@ -0,0 +1,90 @@
+use app_core::i18n::{t, tf, Msg};
+use app_core::jobs::{job, Job, JobKind, JobState, Jobs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tauri::api::notification::Notification;
+use tauri::{AppHandle, GlobalWindowEvent, Manager, WindowEvent};
+
+/// Emitted with the finished job when the user comes back to the app from a
+/// completion notification.
+pub const OPEN_TRANSCRIPT_EVENT: &str = "notification://open-transcript";
+
+/// Sends a native notification when a transcription finishes while the app
+/// isn't focused, and a summary once a whole batch has drained.
+///
+/// Tauri v1 notifications have no click callback, so "click-through" is done
+/// by remembering the last notified job and opening it the next time the main
+/// window gains focus, which is what clicking the notification does.
+#[derive(Default)]
+pub struct Notifier {
+    finished_in_batch: AtomicUsize,
+    pending_open: Mutex<Option<Job>>,
+}
+
+impl Notifier {
+    pub fn job_event(&self, app: &AppHandle, event: &str, job: &Job) {
+        // Cancelling is something the user just did; no need to tell them.
+        if job.kind != JobKind::Transcription
+            || !job.state.is_finished()
+            || job.state == JobState::Cancelled
+        {
+            return;
+        }
+        let finished = self.finished_in_batch.fetch_add(1, Ordering::SeqCst) + 1;
+        let batch_drained = app
+            .state::<Jobs>()
+            .list()
+            .iter()
+            .all(|job| job.kind != JobKind::Transcription || job.state.is_finished());
+        if batch_drained {
+            self.finished_in_batch.store(0, Ordering::SeqCst);
+        }
+        if main_window_focused(app) {
+            return;
+        }
+
+        let (title, body) = if batch_drained && finished > 1 {
+            (
+                t(Msg::TranscriptionsFinished),
+                tf(Msg::TranscriptionsDone, &[("count", &finished.to_string())]),
+            )
+        } else if event == job::DONE_EVENT {
+            (
+                t(Msg::TranscriptionFinished),
+                t(Msg::ClickToOpenTranscript).to_string(),
+            )
+        } else {
+            (
+                t(Msg::TranscriptionFailed),
+                job.error.clone().unwrap_or_default(),
+            )
+        };
+        *self.pending_open.lock().unwrap() = Some(job.clone());
+        let _ = Notification::new(&app.config().tauri.bundle.identifier)
+            .title(title)
+            .body(body)
+            .show();
+    }
+
+    pub fn handle_window_event(&self, event: &GlobalWindowEvent) {
+        if let WindowEvent::Focused(true) = event.event() {
+            if let Some(job) = self.pending_open.lock().unwrap().take() {
+                let _ = event.window().emit(OPEN_TRANSCRIPT_EVENT, job);
+            }
+        }
+    }
+}
+
+/// Shows a one-off native notification.
+pub fn show(app: &AppHandle, title: &str, body: &str) {
+    let _ = Notification::new(&app.config().tauri.bundle.identifier)
+        .title(title)
+        .body(body)
+        .show();
+}
+
+fn main_window_focused(app: &AppHandle) -> bool {
+    app.get_window("main")
+        .and_then(|window| window.is_focused().ok())
+        .unwrap_or(false)
+}
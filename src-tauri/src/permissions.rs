@@ -0,0 +1,162 @@
+use anyhow::Result;
+use app_core::i18n::{t, Msg};
+use serde::Serialize;
+
+/// Mirrors `AVAuthorizationStatus`. On Windows `Restricted` means the
+/// device-wide switch is off. Platforms without privacy controls always report
+/// `Authorized`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MicPermission {
+    NotDetermined,
+    Restricted,
+    Denied,
+    Authorized,
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use super::MicPermission;
+    use block::ConcreteBlock;
+    use objc::runtime::{Object, BOOL, YES};
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::sync::mpsc;
+
+    #[link(name = "AVFoundation", kind = "framework")]
+    extern "C" {
+        static AVMediaTypeAudio: *const Object;
+    }
+
+    pub fn status() -> MicPermission {
+        let status: isize = unsafe {
+            msg_send![class!(AVCaptureDevice), authorizationStatusForMediaType: AVMediaTypeAudio]
+        };
+        match status {
+            0 => MicPermission::NotDetermined,
+            1 => MicPermission::Restricted,
+            2 => MicPermission::Denied,
+            _ => MicPermission::Authorized,
+        }
+    }
+
+    /// Shows the system prompt (only the first time) and blocks until the user answers.
+    pub fn request() -> bool {
+        let (tx, rx) = mpsc::channel();
+        let handler = ConcreteBlock::new(move |granted: BOOL| {
+            let _ = tx.send(granted == YES);
+        })
+        .copy();
+        unsafe {
+            let _: () = msg_send![
+                class!(AVCaptureDevice),
+                requestAccessForMediaType: AVMediaTypeAudio
+                completionHandler: &*handler
+            ];
+        }
+        rx.recv().unwrap_or(false)
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use super::MicPermission;
+    use winreg::enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE};
+    use winreg::{RegKey, HKEY};
+
+    const CONSENT_STORE: &str = r"Software\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore\microphone";
+
+    fn denied(root: HKEY, subkey: &str) -> bool {
+        RegKey::predef(root)
+            .open_subkey(subkey)
+            .and_then(|key| key.get_value::<String, _>("Value"))
+            .map(|value| value.eq_ignore_ascii_case("Deny"))
+            .unwrap_or(false)
+    }
+
+    /// Reads the privacy switches cpal can't see: the device-wide toggle (set
+    /// by an admin), the per-user toggle, and "Let desktop apps access your
+    /// microphone", which is what applies to us.
+    pub fn status() -> MicPermission {
+        if denied(HKEY_LOCAL_MACHINE, CONSENT_STORE) {
+            MicPermission::Restricted
+        } else if denied(HKEY_CURRENT_USER, CONSENT_STORE)
+            || denied(
+                HKEY_CURRENT_USER,
+                &format!(r"{}\NonPackaged", CONSENT_STORE),
+            )
+        {
+            MicPermission::Denied
+        } else {
+            MicPermission::Authorized
+        }
+    }
+}
+
+pub fn mic_permission() -> MicPermission {
+    #[cfg(target_os = "macos")]
+    {
+        macos::status()
+    }
+    #[cfg(windows)]
+    {
+        windows::status()
+    }
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        MicPermission::Authorized
+    }
+}
+
+/// Prompts for microphone access if it hasn't been decided yet. Blocks while
+/// the system dialog is open.
+pub fn request_mic_permission() -> MicPermission {
+    #[cfg(target_os = "macos")]
+    if macos::status() == MicPermission::NotDetermined {
+        macos::request();
+    }
+    mic_permission()
+}
+
+/// Emitted when recording is refused because the OS blocks microphone access.
+pub const MIC_BLOCKED_EVENT: &str = "recording://mic-blocked";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MicBlocked {
+    pub permission: MicPermission,
+    pub remediation: &'static str,
+}
+
+fn remediation() -> &'static str {
+    if cfg!(target_os = "macos") {
+        t(Msg::MicRemediationMacos)
+    } else if cfg!(windows) {
+        t(Msg::MicRemediationWindows)
+    } else {
+        t(Msg::MicRemediationOther)
+    }
+}
+
+/// Returns what the user needs to do if the OS is blocking the microphone,
+/// so we fail clearly instead of letting cpal record silence.
+pub fn mic_blocked() -> Option<MicBlocked> {
+    match mic_permission() {
+        permission @ (MicPermission::Denied | MicPermission::Restricted) => Some(MicBlocked {
+            permission,
+            remediation: remediation(),
+        }),
+        _ => None,
+    }
+}
+
+/// Opens the OS microphone privacy pane.
+pub fn open_mic_settings() -> Result<()> {
+    #[cfg(target_os = "macos")]
+    std::process::Command::new("open")
+        .arg("x-apple.systempreferences:com.apple.preference.security?Privacy_Microphone")
+        .spawn()?;
+    #[cfg(windows)]
+    std::process::Command::new("cmd")
+        .args(["/C", "start", "ms-settings:privacy-microphone"])
+        .spawn()?;
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use anyhow::Result;
+use app_core::audio::{PlaybackStatus, Player};
+use app_core::library::Library;
+use app_core::settings::SettingsStore;
+use app_core::transcribe::Transcript;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Emitted with a [`Position`] after every playback command and regularly
+/// while playing.
+pub const POSITION_EVENT: &str = "playback://position";
+
+/// Faster than this and events just pile up in the webview.
+const MIN_INTERVAL_MS: u64 = 16;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Position {
+    #[serde(flatten)]
+    pub status: PlaybackStatus,
+    /// Index of the transcript segment being spoken, for highlighting it.
+    pub segment: Option<usize>,
+}
+
+/// The app's audio player, the transcript of what it's playing and whether a
+/// thread is reporting its position.
+#[derive(Default)]
+pub struct Playback {
+    player: Player,
+    transcript: Mutex<Option<Transcript>>,
+    ticking: AtomicBool,
+}
+
+impl Playback {
+    fn position(&self) -> Result<Position> {
+        let status = self.player.status()?;
+        let segment = self
+            .transcript
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|transcript| transcript.segment_at(status.position_ms));
+        Ok(Position { status, segment })
+    }
+}
+
+/// Plays on the output device from settings, independent of the one
+/// recordings come from.
+pub fn play(app: &AppHandle, path: PathBuf, start_ms: u64) -> Result<()> {
+    let device = app.state::<SettingsStore>().get().playback_device;
+    let device = (!device.is_empty()).then_some(device);
+    let library = app.state::<Library>();
+    let transcript = match library.recording_by_path(&path)? {
+        Some(recording) => library.transcript(recording.id)?,
+        None => None,
+    };
+    let playback = app.state::<Playback>();
+    playback.player.play(path, start_ms, device)?;
+    *playback.transcript.lock().unwrap() = transcript;
+    report(app)
+}
+
+pub fn pause(app: &AppHandle) -> Result<()> {
+    app.state::<Playback>().player.pause()?;
+    report(app)
+}
+
+pub fn resume(app: &AppHandle) -> Result<()> {
+    app.state::<Playback>().player.resume()?;
+    report(app)
+}
+
+pub fn seek(app: &AppHandle, position_ms: u64) -> Result<()> {
+    app.state::<Playback>().player.seek(position_ms)?;
+    report(app)
+}
+
+pub fn stop(app: &AppHandle) -> Result<()> {
+    let playback = app.state::<Playback>();
+    playback.player.stop()?;
+    *playback.transcript.lock().unwrap() = None;
+    report(app)
+}
+
+pub fn position(app: &AppHandle) -> Result<Position> {
+    app.state::<Playback>().position()
+}
+
+/// Emits the current position and, if something is playing, makes sure a
+/// thread keeps emitting it until playback pauses, stops or finishes.
+fn report(app: &AppHandle) -> Result<()> {
+    let position = position(app)?;
+    let _ = app.emit_all(POSITION_EVENT, &position);
+    if position.status.playing && !app.state::<Playback>().ticking.swap(true, Ordering::SeqCst) {
+        let app = app.clone();
+        std::thread::spawn(move || tick(&app));
+    }
+    Ok(())
+}
+
+fn tick(app: &AppHandle) {
+    let playback = app.state::<Playback>();
+    loop {
+        // Read each time so a settings change applies mid-playback.
+        let interval = app
+            .state::<SettingsStore>()
+            .get()
+            .playback_position_interval_ms
+            .max(MIN_INTERVAL_MS);
+        std::thread::sleep(Duration::from_millis(interval));
+        let Ok(position) = playback.position() else {
+            break;
+        };
+        let _ = app.emit_all(POSITION_EVENT, &position);
+        if !position.status.playing {
+            playback.ticking.store(false, Ordering::SeqCst);
+            // Playback may have resumed between the check and the store, in
+            // which case the resume saw us still running and left it to us.
+            let resumed = playback.player.status().is_ok_and(|status| status.playing);
+            if !resumed || playback.ticking.swap(true, Ordering::SeqCst) {
+                break;
+            }
+        }
+    }
+}
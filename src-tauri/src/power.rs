@@ -0,0 +1,82 @@
+use app_core::i18n::{t, Msg};
+use app_core::settings::SettingsStore;
+use serde::Serialize;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Manager};
+
+use crate::{notifications, recording};
+
+/// Emitted with an [`Interrupted`] when a take was cut short by the system
+/// sleeping.
+pub const INTERRUPTED_EVENT: &str = "recording://interrupted";
+
+#[derive(Clone, Serialize)]
+pub struct Interrupted {
+    /// Roughly how long the system was asleep.
+    pub slept_ms: u64,
+    /// Whether a new take was started, see `resume_recording_after_sleep`.
+    pub resumed: bool,
+}
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A gap this much longer than the check interval means the system slept
+/// rather than the thread being scheduled late.
+const SLEEP_THRESHOLD: Duration = Duration::from_secs(20);
+
+/// Watches for the system waking from sleep on a background thread.
+///
+/// There's no portable sleep notification, so it's detected afterwards: the
+/// wall clock keeps running while the system is suspended but the monotonic
+/// clock and timers don't, leaving a gap between them. Nothing can be done
+/// right before sleeping, but the take on disk is flushed every couple of
+/// seconds, so at most that much is lost.
+pub fn spawn(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut wall = SystemTime::now();
+        let mut monotonic = Instant::now();
+        loop {
+            std::thread::sleep(CHECK_INTERVAL);
+            let wall_elapsed = wall.elapsed().unwrap_or_default();
+            let gap = wall_elapsed.saturating_sub(monotonic.elapsed().min(CHECK_INTERVAL));
+            wall = SystemTime::now();
+            monotonic = Instant::now();
+            if gap > SLEEP_THRESHOLD {
+                woke(&app, gap);
+            }
+        }
+    });
+}
+
+/// The capture stream doesn't survive sleep, so a take in progress is
+/// finalized and either resumed into a new take or reported.
+fn woke(app: &AppHandle, slept: Duration) {
+    eprintln!("System woke after about {}s", slept.as_secs());
+    if !app.state::<recording::Recording>().is_recording() {
+        return;
+    }
+    if let Err(err) = recording::stop(app) {
+        eprintln!("Failed to finalize recording after sleep: {:?}", err);
+    }
+    let resume = app
+        .state::<SettingsStore>()
+        .get()
+        .resume_recording_after_sleep;
+    let resumed = resume
+        && recording::start(app)
+            .map_err(|err| eprintln!("Failed to resume recording after sleep: {:?}", err))
+            .is_ok();
+    if !resumed {
+        notifications::show(
+            app,
+            t(Msg::RecordingInterrupted),
+            t(Msg::RecordingInterruptedDetail),
+        );
+    }
+    let interrupted = Interrupted {
+        slept_ms: slept.as_millis() as u64,
+        resumed,
+    };
+    let _ = app.emit_all(INTERRUPTED_EVENT, interrupted);
+}
@@ -0,0 +1,353 @@
+use anyhow::{anyhow, bail, Context, Result};
+use app_core::audio::{loudness, AudioBuffer, AudioController, CaptureOptions, StreamInfo};
+use app_core::i18n::{t, tf, Msg};
+use app_core::jobs::{JobHandle, JobKind, Jobs, Priority};
+use app_core::library::{self, Library, NewRecording, RecordingId};
+use app_core::settings::{Settings, SettingsStore};
+use app_core::transcribe::WHISPER_SAMPLE_RATE;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::{calendar, notifications, permissions, transcription, tray};
+
+/// The app's single microphone recorder and the job tracking its current take.
+pub struct Recording {
+    controller: AudioController,
+    dir: PathBuf,
+    active: Mutex<Option<Take>>,
+    /// Whether a thread is watching the current take for a dead microphone.
+    watching: AtomicBool,
+}
+
+struct Take {
+    job: JobHandle,
+    path: PathBuf,
+    /// Overrides the file-name title, e.g. with the current meeting's name.
+    title: Option<String>,
+    /// Kept only in memory, see [`start_quick_note`].
+    quick: bool,
+}
+
+/// What stopping a take leaves behind.
+pub enum Stopped {
+    /// A take added to the library, with its in-memory copy if the capture
+    /// options asked for one.
+    Saved(library::Recording, Option<AudioBuffer>),
+    /// A quick note that never touched the disk.
+    Quick(AudioBuffer),
+}
+
+impl Recording {
+    /// Each take is written to a new timestamped file in `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Recording {
+            controller: AudioController::new(),
+            dir: dir.into(),
+            active: Mutex::new(None),
+            watching: AtomicBool::new(false),
+        }
+    }
+
+    /// A fresh file name for a new take.
+    pub fn next_path(&self) -> Result<PathBuf> {
+        std::fs::create_dir_all(&self.dir)?;
+        Ok(self
+            .dir
+            .join(format!("recording-{}.wav", library::now_ms())))
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.active.lock().unwrap().is_some()
+    }
+
+    /// The device and buffer size the current take is being captured with.
+    pub fn stream_info(&self) -> Result<Option<StreamInfo>> {
+        self.controller.info()
+    }
+
+    /// Starts a take; `title` names its library entry instead of the file name.
+    pub fn start(&self, jobs: &Jobs, title: Option<String>, options: CaptureOptions) -> Result<()> {
+        let mut active = self.active.lock().unwrap();
+        if active.is_some() {
+            return Ok(());
+        }
+        let path = self.next_path()?;
+        let quick = options.memory_only;
+        self.controller.start(path.clone(), options)?;
+        *active = Some(Take {
+            job: jobs.start(JobKind::Recording),
+            path,
+            title,
+            quick,
+        });
+        Ok(())
+    }
+
+    /// Finalizes the current take, applies the processing from `settings` and
+    /// adds it to the library. Quick notes are just handed back. `None` means
+    /// nothing was being recorded.
+    pub fn stop(&self, library: &Library, settings: &Settings) -> Result<Option<Stopped>> {
+        let Some(Take {
+            job,
+            path,
+            title,
+            quick,
+        }) = self.active.lock().unwrap().take()
+        else {
+            return Ok(None);
+        };
+        if quick {
+            let audio = self
+                .controller
+                .stop()
+                .and_then(|tap| tap.context("the quick note wasn't kept in memory"));
+            job.finish(
+                audio
+                    .as_ref()
+                    .map(|_| ())
+                    .map_err(|err| anyhow!("{:#}", err)),
+            )?;
+            return audio.map(|audio| Some(Stopped::Quick(audio)));
+        }
+        let info = self.controller.info().ok().flatten();
+        let mut audio = None;
+        let result = self.controller.stop().and_then(|tap| {
+            audio = tap;
+            post_process(&path, settings);
+            let mut recording = NewRecording::from_file(&path)?;
+            if let Some(title) = title {
+                recording.title = title;
+            }
+            recording.device = info.as_ref().map(|info| info.device.clone());
+            let id = library.add_recording(&recording)?;
+            if let Some(info) = &info {
+                store_speech_stats(library, id, info, recording.duration_ms)?;
+            }
+            Ok(library.recording(id)?.expect("just inserted"))
+        });
+        job.finish(result)
+            .map(|recording| Some(Stopped::Saved(recording, audio)))
+    }
+}
+
+/// Keeps how much of the take was speech as `speech_ms` and `speech_density`
+/// (a fraction of its length) metadata.
+fn store_speech_stats(
+    library: &Library,
+    id: RecordingId,
+    info: &StreamInfo,
+    duration_ms: i64,
+) -> Result<()> {
+    library.set_metadata(id, "speech_ms", &info.speech_ms.to_string())?;
+    let density = info.speech_ms as f64 / duration_ms.max(1) as f64;
+    library.set_metadata(id, "speech_density", &format!("{:.3}", density.min(1.0)))
+}
+
+/// Processing is best effort: a take that can't be processed (say, it's
+/// silent) is still kept as recorded.
+fn post_process(path: &Path, settings: &Settings) {
+    if let Some(target) = settings.recording_loudness_lufs {
+        if let Err(err) = loudness::normalize_loudness(path, target) {
+            eprintln!("Failed to normalize {}: {:?}", path.display(), err);
+        }
+    }
+}
+
+/// Lower cutoffs leave the rumble in; higher ones start thinning voices.
+const HIGH_PASS_MIN_HZ: f32 = 80.0;
+const HIGH_PASS_MAX_HZ: f32 = 120.0;
+
+pub const STATE_EVENT: &str = "recording://state";
+
+/// Emitted with a [`StreamInfo`] when a take starts, naming the device it's
+/// recorded from and whether that's a fallback.
+pub const DEVICE_EVENT: &str = "recording://device";
+
+/// Emitted with a [`NoSignal`] when the input has been near-silent for the
+/// `no_signal_warning_secs` setting, so a muted microphone is noticed before
+/// the meeting ends.
+pub const NO_SIGNAL_EVENT: &str = "recording://no-signal";
+
+#[derive(Clone, Serialize)]
+pub struct NoSignal {
+    pub device: String,
+    pub silent_ms: u64,
+}
+
+/// Emitted with a [`SpeechActivity`] when someone starts talking.
+pub const SPEECH_STARTED_EVENT: &str = "recording://speech-started";
+/// Emitted with a [`SpeechActivity`] when the talking stops.
+pub const SPEECH_ENDED_EVENT: &str = "recording://speech-ended";
+
+#[derive(Clone, Serialize)]
+pub struct SpeechActivity {
+    /// Speech in the take so far, in ms.
+    pub speech_ms: u64,
+}
+
+/// How often a take is checked for speech and a dead microphone.
+const WATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Serialize)]
+pub struct RecordingStatus {
+    pub recording: bool,
+}
+
+/// Starts recording from anywhere in the app (commands, shortcuts, menus) and
+/// tells the frontend.
+pub fn start(app: &AppHandle) -> Result<()> {
+    start_take(app, false)
+}
+
+/// Starts a quick note: a take kept in memory instead of on disk, and
+/// transcribed straight from there when it stops. Nothing is added to the
+/// library, and a crash loses it, so it's meant for short notes.
+pub fn start_quick_note(app: &AppHandle) -> Result<()> {
+    start_take(app, true)
+}
+
+fn start_take(app: &AppHandle, quick: bool) -> Result<()> {
+    if let Some(blocked) = permissions::mic_blocked() {
+        let _ = app.emit_all(permissions::MIC_BLOCKED_EVENT, &blocked);
+        bail!(tf(Msg::MicBlocked, &[("remediation", blocked.remediation)]));
+    }
+    let settings = app.state::<SettingsStore>().get();
+    let title = calendar::meeting_title(&settings);
+    let options = CaptureOptions {
+        preferred_devices: settings.preferred_input_devices.clone(),
+        high_pass_hz: settings
+            .recording_high_pass_hz
+            .map(|hz| hz.clamp(HIGH_PASS_MIN_HZ, HIGH_PASS_MAX_HZ)),
+        // The take is transcribed straight from memory when it stops.
+        tap_sample_rate: (settings.auto_transcribe || quick).then_some(WHISPER_SAMPLE_RATE),
+        memory_only: quick,
+        buffer_frames: settings.recording_buffer_frames,
+        profiles: settings.device_profiles.clone(),
+        exclusive: settings.recording_exclusive_mode,
+    };
+    app.state::<Recording>()
+        .start(&app.state::<Jobs>(), title, options)?;
+    emit_state(app);
+    match app.state::<Recording>().stream_info() {
+        Ok(Some(info)) => {
+            if info.narrowband {
+                let rate = format!("{}", info.sample_rate as f32 / 1000.0);
+                notifications::show(
+                    app,
+                    t(Msg::NarrowbandInput),
+                    &tf(
+                        Msg::NarrowbandInputDetail,
+                        &[("device", &info.device), ("rate", &rate)],
+                    ),
+                );
+            }
+            let _ = app.emit_all(DEVICE_EVENT, info);
+        }
+        Ok(None) => {}
+        Err(err) => eprintln!("Failed to get recording device: {:?}", err),
+    }
+    if !app
+        .state::<Recording>()
+        .watching
+        .swap(true, Ordering::SeqCst)
+    {
+        let app = app.clone();
+        std::thread::spawn(move || watch_signal(&app));
+    }
+    Ok(())
+}
+
+/// Stops recording and, if the setting is on, queues a transcription of the
+/// new take. Quick notes are always transcribed.
+pub fn stop(app: &AppHandle) -> Result<()> {
+    let settings = app.state::<SettingsStore>().get();
+    let result = app
+        .state::<Recording>()
+        .stop(&app.state::<Library>(), &settings);
+    emit_state(app);
+    match result? {
+        Some(Stopped::Saved(recording, audio)) if settings.auto_transcribe => {
+            match audio {
+                Some(audio) => transcription::start_from_samples(app, recording.path, audio),
+                None => transcription::start(app, recording.path, Priority::Live),
+            };
+        }
+        Some(Stopped::Quick(audio)) => {
+            transcription::start_quick_note(app, audio);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Stops recording without any follow-up work, for when the app is exiting.
+pub fn finalize(app: &AppHandle) -> Result<()> {
+    let result = app
+        .state::<Recording>()
+        .stop(&app.state::<Library>(), &app.state::<SettingsStore>().get());
+    emit_state(app);
+    result.map(|_| ())
+}
+
+pub fn toggle(app: &AppHandle) -> Result<()> {
+    if app.state::<Recording>().is_recording() {
+        stop(app)
+    } else {
+        start(app)
+    }
+}
+
+/// Reports speech starting and stopping while a take is being recorded,
+/// and warns once per silent stretch.
+fn watch_signal(app: &AppHandle) {
+    let recording = app.state::<Recording>();
+    let mut warned = false;
+    let mut speaking = false;
+    loop {
+        std::thread::sleep(WATCH_INTERVAL);
+        let info = match recording.stream_info() {
+            Ok(Some(info)) => info,
+            _ => {
+                recording.watching.store(false, Ordering::SeqCst);
+                // A new take may have started between the check and the
+                // store, in which case its start left watching to us.
+                if !recording.is_recording() || recording.watching.swap(true, Ordering::SeqCst) {
+                    break;
+                }
+                continue;
+            }
+        };
+        if info.speaking != speaking {
+            speaking = info.speaking;
+            let event = match speaking {
+                true => SPEECH_STARTED_EVENT,
+                false => SPEECH_ENDED_EVENT,
+            };
+            let activity = SpeechActivity {
+                speech_ms: info.speech_ms,
+            };
+            let _ = app.emit_all(event, activity);
+        }
+        // Read each time so a settings change applies mid-take.
+        let limit_ms = app.state::<SettingsStore>().get().no_signal_warning_secs * 1000;
+        let silent = limit_ms > 0 && info.silent_ms >= limit_ms;
+        if silent && !warned {
+            let warning = NoSignal {
+                device: info.device,
+                silent_ms: info.silent_ms,
+            };
+            let _ = app.emit_all(NO_SIGNAL_EVENT, warning);
+        }
+        warned = silent;
+    }
+}
+
+fn emit_state(app: &AppHandle) {
+    let recording = app.state::<Recording>().is_recording();
+    tray::set_recording(app, recording);
+    let _ = app.emit_all(STATE_EVENT, RecordingStatus { recording });
+}
@@ -0,0 +1,98 @@
+use anyhow::{bail, Context, Result};
+use app_core::audio::wav::{is_wav, WAV_HEADER_LEN};
+use app_core::library::unique_path;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use url::Url;
+
+use crate::import;
+
+/// Emitted with a [`DownloadProgress`] while remote audio downloads.
+pub const DOWNLOAD_PROGRESS_EVENT: &str = "download://progress";
+
+/// Progress events are spaced at least this far apart.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadProgress {
+    pub url: String,
+    pub received: u64,
+    /// Size from `Content-Length`, when the server sends one.
+    pub total: Option<u64>,
+    pub done: bool,
+}
+
+/// Downloads audio at `url` into the imports folder, where it's kept as the
+/// recording's file, reporting progress with [`DOWNLOAD_PROGRESS_EVENT`].
+/// The file only gets its real name once it's complete. Only WAV can be
+/// transcribed, so anything else is deleted again with an error.
+pub async fn download(app: &AppHandle, url: &str) -> Result<PathBuf> {
+    let parsed = Url::parse(url).context("not a valid URL")?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        bail!("only http and https URLs can be downloaded");
+    }
+    let name = parsed
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("download.wav")
+        .to_string();
+    let dir = import::imports_dir(app)?;
+    tokio::fs::create_dir_all(&dir).await?;
+    let path = unique_path(&dir.join(name));
+    let partial = path.with_extension("part");
+
+    let mut response = reqwest::get(parsed)
+        .await
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("failed to download {}", url))?;
+    let mut progress = DownloadProgress {
+        url: url.to_string(),
+        received: 0,
+        total: response.content_length(),
+        done: false,
+    };
+    let mut file = tokio::fs::File::create(&partial)
+        .await
+        .with_context(|| format!("failed to create {}", partial.display()))?;
+    let mut reported = Instant::now();
+    let _ = app.emit_all(DOWNLOAD_PROGRESS_EVENT, &progress);
+    let written: Result<()> = async {
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+            progress.received += chunk.len() as u64;
+            if reported.elapsed() >= PROGRESS_INTERVAL {
+                reported = Instant::now();
+                let _ = app.emit_all(DOWNLOAD_PROGRESS_EVENT, &progress);
+            }
+        }
+        file.flush().await?;
+        Ok(())
+    }
+    .await;
+    if let Err(err) = written {
+        let _ = tokio::fs::remove_file(&partial).await;
+        return Err(err.context(format!("failed to download {}", url)));
+    }
+    progress.done = true;
+    let _ = app.emit_all(DOWNLOAD_PROGRESS_EVENT, &progress);
+
+    let mut header = [0; WAV_HEADER_LEN];
+    let read = async {
+        let mut file = tokio::fs::File::open(&partial).await?;
+        file.read_exact(&mut header).await
+    }
+    .await;
+    if read.is_err() || !is_wav(&header) {
+        let _ = tokio::fs::remove_file(&partial).await;
+        bail!(
+            "{} isn't WAV audio, the only kind that can be transcribed",
+            url
+        );
+    }
+    tokio::fs::rename(&partial, &path).await?;
+    Ok(path)
+}
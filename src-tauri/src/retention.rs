@@ -0,0 +1,25 @@
+use app_core::library::Library;
+use app_core::settings::SettingsStore;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Applies the retention policy from settings now and then every hour, on a
+/// background thread.
+pub fn spawn(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        let policy = app.state::<SettingsStore>().get().retention;
+        match app.state::<Library>().apply_retention(&policy) {
+            Ok(plan) if !plan.removals.is_empty() => eprintln!(
+                "Retention removed audio of {} recordings ({} bytes)",
+                plan.removals.len(),
+                plan.freed_bytes
+            ),
+            Ok(_) => {}
+            Err(err) => eprintln!("Failed to apply retention policy: {:?}", err),
+        }
+        std::thread::sleep(INTERVAL);
+    });
+}
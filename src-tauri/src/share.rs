@@ -0,0 +1,78 @@
+use anyhow::{bail, Result};
+use std::path::PathBuf;
+use tauri::Window;
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::CString;
+    use std::path::PathBuf;
+
+    #[repr(C)]
+    struct Rect {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    }
+
+    /// `NSRectEdgeMinY`: the picker opens below the anchor.
+    const MIN_Y_EDGE: usize = 1;
+
+    unsafe fn file_url(path: &PathBuf) -> *mut Object {
+        let path = CString::new(path.to_string_lossy().as_bytes()).unwrap_or_default();
+        let string: *mut Object = msg_send![class!(NSString), stringWithUTF8String: path.as_ptr()];
+        msg_send![class!(NSURL), fileURLWithPath: string]
+    }
+
+    /// Opens `NSSharingServicePicker` anchored at the top of the window's
+    /// content. Must run on the main thread.
+    pub unsafe fn show_picker(ns_window: *mut Object, paths: &[PathBuf]) {
+        let items: *mut Object = msg_send![class!(NSMutableArray), array];
+        for path in paths {
+            let _: () = msg_send![items, addObject: file_url(path)];
+        }
+        let picker: *mut Object = msg_send![class!(NSSharingServicePicker), alloc];
+        let picker: *mut Object = msg_send![picker, initWithItems: items];
+        let view: *mut Object = msg_send![ns_window, contentView];
+        let bounds: Rect = msg_send![view, bounds];
+        let anchor = Rect {
+            x: bounds.width / 2.0,
+            y: bounds.height,
+            width: 1.0,
+            height: 1.0,
+        };
+        let _: () = msg_send![
+            picker,
+            showRelativeToRect: anchor
+            ofView: view
+            preferredEdge: MIN_Y_EDGE
+        ];
+    }
+}
+
+/// Opens the system share sheet for `paths` (Messages, Mail, AirDrop…)
+/// over `window`. Only macOS has one.
+pub fn share(window: &Window, paths: Vec<PathBuf>) -> Result<()> {
+    if paths.is_empty() {
+        bail!("nothing to share");
+    }
+    if let Some(missing) = paths.iter().find(|path| !path.is_file()) {
+        bail!("{} doesn't exist", missing.display());
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let target = window.clone();
+        window.run_on_main_thread(move || match target.ns_window() {
+            Ok(ns_window) => unsafe { macos::show_picker(ns_window.cast(), &paths) },
+            Err(err) => eprintln!("Failed to get the window to share from: {:?}", err),
+        })?;
+        Ok(())
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = window;
+        bail!("sharing is only available on macOS")
+    }
+}
@@ -0,0 +1,35 @@
+use anyhow::Result;
+use tauri::{AppHandle, GlobalShortcutManager};
+
+use crate::recording;
+
+/// Registers `accelerator` as the system-wide recording toggle. An empty
+/// accelerator leaves no shortcut registered.
+pub fn register(app: &AppHandle, accelerator: &str) -> Result<()> {
+    if accelerator.is_empty() {
+        return Ok(());
+    }
+    let handle = app.clone();
+    app.global_shortcut_manager()
+        .register(accelerator, move || {
+            if let Err(err) = recording::toggle(&handle) {
+                eprintln!("Failed to toggle recording: {:?}", err);
+            }
+        })?;
+    Ok(())
+}
+
+/// Swaps the registered shortcut, restoring the old one if the new one can't be registered.
+pub fn replace(app: &AppHandle, old: &str, new: &str) -> Result<()> {
+    if old == new {
+        return Ok(());
+    }
+    if !old.is_empty() {
+        app.global_shortcut_manager().unregister(old)?;
+    }
+    if let Err(err) = register(app, new) {
+        register(app, old)?;
+        return Err(err);
+    }
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use app_core::i18n::{t, Msg};
+use app_core::jobs::Jobs;
+use tauri::{AppHandle, Manager, RunEvent};
+
+use crate::recording;
+
+/// Finalizes any in-progress recording and marks jobs that were cut short as
+/// failed before the process exits. Their final state reaches the library
+/// through the job events.
+pub fn handle_run_event(app: &AppHandle, event: RunEvent) {
+    if let RunEvent::ExitRequested { .. } | RunEvent::Exit = event {
+        shutdown(app);
+    }
+}
+
+fn shutdown(app: &AppHandle) {
+    if let Err(err) = recording::finalize(app) {
+        eprintln!("Failed to finalize recording on exit: {:?}", err);
+    }
+
+    app.state::<Jobs>()
+        .interrupt_running(t(Msg::AppClosedBeforeJobFinished));
+}
@@ -0,0 +1,235 @@
+use anyhow::{anyhow, bail, Result};
+use app_core::audio::AudioBuffer;
+use app_core::jobs::{JobId, JobKind, JobState, Jobs, Priority};
+use app_core::library::{Library, RecordingId};
+use app_core::settings::SettingsStore;
+use app_core::transcribe::align::{self, WordTiming};
+use app_core::transcribe::{cjk, model, transcribe_file, transcribe_samples, Segment, Transcript};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tokio::sync::oneshot;
+
+use crate::captions::{Caption, Captions};
+use crate::models;
+use crate::upload;
+
+/// Emitted once a background warm-up has loaded the model.
+pub const MODEL_READY_EVENT: &str = "model://ready";
+
+#[derive(Clone, Serialize)]
+pub struct ModelReady {
+    pub path: PathBuf,
+}
+
+/// Whether the model is already in memory, for a UI that missed the
+/// ready event.
+pub fn model_ready(app: &AppHandle) -> bool {
+    model::is_loaded(&models::path(app))
+}
+
+/// Loads the model on a background thread so it's in memory by the time the
+/// first transcription needs it.
+pub fn warm_up(app: &AppHandle) {
+    let app = app.clone();
+    let path = models::path(&app);
+    std::thread::spawn(move || match model::load(&path) {
+        Ok(_) => {
+            let _ = app.emit_all(MODEL_READY_EVENT, ModelReady { path });
+        }
+        Err(err) => {
+            eprintln!("Failed to warm up the model: {:?}", err);
+            models::report_if_corrupt(&app, &err);
+        }
+    });
+}
+
+/// Word-level timings of `text` as read aloud in the audio at `path`. Blocks
+/// while whisper runs.
+pub fn align(app: &AppHandle, path: &Path, text: &str) -> Result<Vec<WordTiming>> {
+    let quality = app.state::<SettingsStore>().get().resample_quality;
+    align::align_text(path, &models::path(app), quality, text)
+}
+
+/// Queues a transcription of `path` on the job pool. Progress and the result
+/// are reported through job events; the receiver yields the transcript.
+/// Finished transcripts are also stored in the library under the file's
+/// recording, and uploaded with it when auto-upload is on. Batches should
+/// queue at [`Priority::Background`] so they don't hold up transcriptions
+/// someone is waiting for.
+pub fn start(
+    app: &AppHandle,
+    path: PathBuf,
+    priority: Priority,
+) -> (JobId, oneshot::Receiver<Result<Transcript>>) {
+    enqueue(app, Some(path), None, priority, false)
+}
+
+/// Like [`start`], but transcribes `audio`, mono at whisper's sample rate,
+/// instead of decoding `path` again. The transcript is still saved under
+/// `path`'s recording. It's the take that just stopped, so it runs live and
+/// its segments go out through [`Captions`] as they're decoded.
+pub fn start_from_samples(
+    app: &AppHandle,
+    path: PathBuf,
+    audio: AudioBuffer,
+) -> (JobId, oneshot::Receiver<Result<Transcript>>) {
+    enqueue(app, Some(path), Some(audio), Priority::Live, true)
+}
+
+/// Transcribes a quick note that only ever lived in memory. There's no file,
+/// so the transcript is left on the job rather than saved to the library.
+/// Captioned like [`start_from_samples`].
+pub fn start_quick_note(
+    app: &AppHandle,
+    audio: AudioBuffer,
+) -> (JobId, oneshot::Receiver<Result<Transcript>>) {
+    enqueue(app, None, Some(audio), Priority::Live, true)
+}
+
+fn enqueue(
+    app: &AppHandle,
+    path: Option<PathBuf>,
+    audio: Option<AudioBuffer>,
+    priority: Priority,
+    captions: bool,
+) -> (JobId, oneshot::Receiver<Result<Transcript>>) {
+    let (tx, rx) = oneshot::channel();
+    // End of the last caption sent, so a retry doesn't send them again.
+    let captioned_until = Arc::new(AtomicI64::new(i64::MIN));
+    let app = app.clone();
+    let quality = app.state::<SettingsStore>().get().resample_quality;
+    let model = models::path(&app);
+    // Files can be transcribed again after a restart; audio only in memory can't.
+    let queued = path.as_ref().and_then(|path| {
+        app.state::<Library>()
+            .queue_transcription(path, priority)
+            .map_err(|err| eprintln!("Failed to queue {}: {:?}", path.display(), err))
+            .ok()
+    });
+    let job = app
+        .state::<Jobs>()
+        .enqueue(JobKind::Transcription, priority, move |job| {
+            let limits = job.limits();
+            let result = job
+                .retrying(|attempt| {
+                    if attempt > 1 {
+                        // Start afresh, in case the cached model was what ran out.
+                        model::unload();
+                    }
+                    let progress = job.clone();
+                    let captions_app = app.clone();
+                    let captioned_until = captioned_until.clone();
+                    let job_id = job.id();
+                    let on_progress = move |p: i32| progress.progress(p as f32);
+                    let on_segment = move |segment: Segment| {
+                        if !captions || segment.end <= captioned_until.load(Ordering::Relaxed) {
+                            return;
+                        }
+                        captioned_until.store(segment.end, Ordering::Relaxed);
+                        captions_app.state::<Captions>().publish(
+                            &captions_app,
+                            Caption {
+                                job_id,
+                                start: segment.start,
+                                end: segment.end,
+                                text: segment.text,
+                                speaker_turn_next: segment.speaker_turn_next,
+                            },
+                        )
+                    };
+                    let watched = job.clone();
+                    let cancelled = move || watched.is_cancelled();
+                    match (&audio, &path) {
+                        (Some(audio), _) => transcribe_samples(
+                            &audio.samples,
+                            &model,
+                            limits,
+                            on_progress,
+                            on_segment,
+                            cancelled,
+                        ),
+                        (None, Some(path)) => transcribe_file(
+                            path,
+                            &model,
+                            quality,
+                            limits,
+                            on_progress,
+                            on_segment,
+                            cancelled,
+                        ),
+                        (None, None) => Err(anyhow!("nothing to transcribe")),
+                    }
+                })
+                .map(|transcription| {
+                    let transcript = Transcript {
+                        segments: transcription.segments,
+                    };
+                    cjk::postprocess(&transcript, transcription.language)
+                });
+            if let Err(err) = &result {
+                models::report_if_corrupt(&app, err);
+            }
+            if let (Ok(transcript), Some(path)) = (&result, &path) {
+                match save(&app.state::<Library>(), path, &model, transcript) {
+                    Ok(recording_id) => upload::upload_if_enabled(&app, recording_id),
+                    Err(err) => {
+                        eprintln!("Failed to save transcript of {}: {:?}", path.display(), err)
+                    }
+                }
+            }
+            if let Some(queued) = queued {
+                let _ = app.state::<Library>().unqueue_transcription(queued);
+            }
+            let _ = tx.send(job.finish(result));
+        });
+    (job.id(), rx)
+}
+
+/// Starts the transcriptions that were still waiting or running when the
+/// app last quit, skipping files that have gone since.
+pub fn resume_queue(app: &AppHandle) -> Result<()> {
+    let library = app.state::<Library>();
+    for queued in library.queued_transcriptions()? {
+        library.unqueue_transcription(queued.id)?;
+        if queued.path.exists() {
+            start(app, queued.path, queued.priority);
+        }
+    }
+    Ok(())
+}
+
+/// Returns the id of the recording the transcript was saved under.
+fn save(
+    library: &Library,
+    path: &Path,
+    model: &Path,
+    transcript: &Transcript,
+) -> Result<RecordingId> {
+    let recording = library.ensure_recording(path)?;
+    let model = model
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    library.save_transcript(recording.id, &model, transcript)?;
+    Ok(recording.id)
+}
+
+pub async fn run(app: &AppHandle, path: PathBuf) -> Result<Transcript> {
+    let (_, rx) = start(app, path, Priority::Normal);
+    rx.await
+        .map_err(|_| anyhow!("transcription job was dropped"))?
+}
+
+/// Reads back the transcript stored on a finished transcription job.
+pub fn transcript(jobs: &Jobs, id: JobId) -> Result<Transcript> {
+    let Some(job) = jobs.get(id) else {
+        bail!("no job with id {}", id);
+    };
+    if job.kind != JobKind::Transcription || job.state != JobState::Done {
+        bail!("job {} has no finished transcript", id);
+    }
+    Ok(serde_json::from_value(job.result.unwrap_or_default())?)
+}
@@ -0,0 +1,93 @@
+use app_core::i18n::{t, Msg};
+use app_core::jobs::{JobKind, JobState, Jobs};
+use tauri::{
+    AppHandle, CustomMenuItem, Icon, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu,
+    SystemTrayMenuItem,
+};
+
+use crate::recording;
+
+const START: &str = "start_recording";
+const STOP: &str = "stop_recording";
+const OPEN_LAST_TRANSCRIPT: &str = "open_last_transcript";
+const QUIT: &str = "quit";
+
+/// Emitted with the finished transcription job when "Open Last Transcript" is picked.
+pub const OPEN_TRANSCRIPT_EVENT: &str = "tray://open-transcript";
+
+const IDLE_ICON: &[u8] = include_bytes!("../icons/32x32.png");
+const RECORDING_ICON: &[u8] = include_bytes!("../icons/tray-recording.png");
+
+pub fn build() -> SystemTray {
+    let menu = SystemTrayMenu::new()
+        .add_item(CustomMenuItem::new(START, t(Msg::StartRecording)))
+        .add_item(CustomMenuItem::new(STOP, t(Msg::StopRecording)).disabled())
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new(
+            OPEN_LAST_TRANSCRIPT,
+            t(Msg::OpenLastTranscript),
+        ))
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new(QUIT, t(Msg::Quit)));
+    SystemTray::new().with_menu(menu)
+}
+
+pub fn handle_event(app: &AppHandle, event: SystemTrayEvent) {
+    let SystemTrayEvent::MenuItemClick { id, .. } = event else {
+        return;
+    };
+    let result = match id.as_str() {
+        START => recording::start(app),
+        STOP => recording::stop(app),
+        OPEN_LAST_TRANSCRIPT => {
+            open_last_transcript(app);
+            Ok(())
+        }
+        QUIT => {
+            app.exit(0);
+            Ok(())
+        }
+        _ => Ok(()),
+    };
+    if let Err(err) = result {
+        eprintln!("Tray action {} failed: {:?}", id, err);
+    }
+}
+
+/// Swaps the tray icon for a red dot and flips which of Start/Stop is enabled.
+pub fn set_recording(app: &AppHandle, recording: bool) {
+    let tray = app.tray_handle();
+    let icon = if recording { RECORDING_ICON } else { IDLE_ICON };
+    let _ = tray.set_icon(Icon::Raw(icon.to_vec()));
+    let _ = tray.get_item(START).set_enabled(!recording);
+    let _ = tray.get_item(STOP).set_enabled(recording);
+}
+
+/// Re-applies menu labels after the language setting changes.
+pub fn relabel(app: &AppHandle) {
+    let tray = app.tray_handle();
+    for (id, msg) in [
+        (START, Msg::StartRecording),
+        (STOP, Msg::StopRecording),
+        (OPEN_LAST_TRANSCRIPT, Msg::OpenLastTranscript),
+        (QUIT, Msg::Quit),
+    ] {
+        let _ = tray.get_item(id).set_title(t(msg));
+    }
+}
+
+fn open_last_transcript(app: &AppHandle) {
+    let last = app
+        .state::<Jobs>()
+        .list()
+        .into_iter()
+        .rev()
+        .find(|job| job.kind == JobKind::Transcription && job.state == JobState::Done);
+    if let Some(window) = app.get_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    if let Some(job) = last {
+        let _ = app.emit_all(OPEN_TRANSCRIPT_EVENT, job);
+    }
+}
@@ -0,0 +1,53 @@
+use anyhow::Result;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+pub const UPDATE_AVAILABLE_EVENT: &str = "updater://update-available";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub current_version: String,
+    pub version: String,
+    pub notes: Option<String>,
+}
+
+/// Asks the update endpoint for a newer build, emitting
+/// `updater://update-available` if there is one.
+pub async fn check(app: &AppHandle) -> Result<Option<UpdateInfo>> {
+    let update = app.updater().check().await?;
+    if !update.is_update_available() {
+        return Ok(None);
+    }
+    let info = UpdateInfo {
+        current_version: update.current_version().to_string(),
+        version: update.latest_version().to_string(),
+        notes: update.body().cloned(),
+    };
+    let _ = app.emit_all(UPDATE_AVAILABLE_EVENT, &info);
+    Ok(Some(info))
+}
+
+/// Downloads and installs the pending update. The app restarts on success.
+pub async fn install(app: &AppHandle) -> Result<()> {
+    let update = app.updater().check().await?;
+    if update.is_update_available() {
+        update.download_and_install().await?;
+        app.restart();
+    }
+    Ok(())
+}
+
+/// Checks once in the background after launch. Does nothing while the
+/// updater is turned off in `tauri.conf.json`, which it stays until builds
+/// are signed and `pubkey` is filled in.
+pub fn check_in_background(app: &AppHandle) {
+    if !app.config().tauri.updater.active {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = check(&app).await {
+            eprintln!("Update check failed: {:?}", err);
+        }
+    });
+}
@@ -0,0 +1,91 @@
+use anyhow::{bail, Context, Result};
+use app_core::library::{Library, RecordingId, Upload, UploadKind, UploadSettings};
+use app_core::settings::SettingsStore;
+use s3::creds::Credentials;
+use s3::{Bucket, Region};
+use tauri::{AppHandle, Manager};
+
+fn bucket(settings: &UploadSettings) -> Result<Bucket> {
+    if !settings.is_configured() {
+        bail!("set up an upload bucket in settings first");
+    }
+    let region = Region::Custom {
+        region: settings.region.clone(),
+        endpoint: settings.endpoint.clone(),
+    };
+    let credentials = Credentials::new(
+        Some(&settings.access_key),
+        Some(&settings.secret_key),
+        None,
+        None,
+        None,
+    )?;
+    let bucket = Bucket::new(&settings.bucket, region, credentials)?;
+    Ok(if settings.path_style {
+        bucket.with_path_style()
+    } else {
+        bucket
+    })
+}
+
+/// Uploads a recording's audio, if it still has it, and its current
+/// transcript, if it has one, recording their URLs in the library.
+pub async fn upload(app: &AppHandle, id: RecordingId) -> Result<Vec<Upload>> {
+    let settings = app.state::<SettingsStore>().get().upload;
+    let bucket = bucket(&settings)?;
+    let library = app.state::<Library>().inner().clone();
+    let Some(recording) = library.recording(id)? else {
+        bail!("no recording with id {}", id);
+    };
+    let stem = recording
+        .path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let url = |key: &str| format!("{}/{}", bucket.url(), key);
+
+    if recording.audio_deleted_at.is_none() && recording.path.is_file() {
+        let extension = recording
+            .path
+            .extension()
+            .map_or("wav".into(), |ext| ext.to_string_lossy());
+        let key = format!("{}{}-{}.{}", settings.prefix, id, stem, extension);
+        let mut file = tokio::fs::File::open(&recording.path)
+            .await
+            .with_context(|| format!("failed to open {}", recording.path.display()))?;
+        bucket
+            .put_object_stream(&mut file, &key)
+            .await
+            .with_context(|| format!("failed to upload {}", key))?;
+        library.save_upload(id, UploadKind::Audio, &url(&key))?;
+    }
+    if let Some(transcript) = library.transcript(id)? {
+        let key = format!("{}{}-{}.json", settings.prefix, id, stem);
+        bucket
+            .put_object_with_content_type(
+                &key,
+                &serde_json::to_vec(&transcript)?,
+                "application/json",
+            )
+            .await
+            .with_context(|| format!("failed to upload {}", key))?;
+        library.save_upload(id, UploadKind::Transcript, &url(&key))?;
+    }
+    library.uploads(id)
+}
+
+/// Uploads a freshly transcribed recording in the background if the user
+/// turned on auto-upload.
+pub fn upload_if_enabled(app: &AppHandle, id: RecordingId) {
+    let settings = app.state::<SettingsStore>().get().upload;
+    if !settings.auto_upload || !settings.is_configured() {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = upload(&app, id).await {
+            eprintln!("Failed to upload recording {}: {:?}", id, err);
+        }
+    });
+}
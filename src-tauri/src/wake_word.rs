@@ -0,0 +1,78 @@
+use app_core::audio::listener;
+use app_core::settings::Settings;
+use app_core::transcribe::keyword::KeywordSpotter;
+use app_core::transcribe::WHISPER_SAMPLE_RATE;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+
+use crate::recording;
+
+/// Emitted with a [`WakeWordHeard`] when the wake phrase starts a recording.
+pub const HEARD_EVENT: &str = "recording://wake-word";
+
+#[derive(Clone, Serialize)]
+pub struct WakeWordHeard {
+    pub phrase: String,
+}
+
+/// Utterances longer than this aren't checked for the phrase.
+const MAX_UTTERANCE_MS: usize = 3000;
+
+/// The background listener for the wake phrase. It only runs the model on
+/// stretches of speech, so it's idle while the room is quiet.
+#[derive(Default)]
+pub struct WakeWord {
+    running: Mutex<Option<Arc<AtomicBool>>>,
+}
+
+impl WakeWord {
+    /// Starts, stops or restarts the listener to match `settings`.
+    pub fn apply(&self, app: &AppHandle, settings: &Settings) {
+        let mut running = self.running.lock().unwrap();
+        if let Some(stop) = running.take() {
+            stop.store(true, Ordering::SeqCst);
+        }
+        if !settings.wake_word_enabled || settings.wake_word_model_path.is_empty() {
+            return;
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        *running = Some(stop.clone());
+        let app = app.clone();
+        let model_path = settings.wake_word_model_path.clone();
+        let phrase = settings.wake_phrase.clone();
+        std::thread::spawn(move || {
+            let spotter = match KeywordSpotter::new(Path::new(&model_path), &phrase) {
+                Ok(spotter) => spotter,
+                Err(err) => return eprintln!("Failed to load wake word model: {:?}", err),
+            };
+            let result = listener::listen(WHISPER_SAMPLE_RATE, MAX_UTTERANCE_MS, stop, |samples| {
+                if app.state::<recording::Recording>().is_recording() {
+                    return;
+                }
+                match spotter.heard(&samples) {
+                    Ok(true) => heard(&app, &phrase),
+                    Ok(false) => {}
+                    Err(err) => eprintln!("Failed to check for wake word: {:?}", err),
+                }
+            });
+            if let Err(err) = result {
+                eprintln!("Wake word listener stopped: {:?}", err);
+            }
+        });
+    }
+}
+
+fn heard(app: &AppHandle, phrase: &str) {
+    match recording::start(app) {
+        Ok(()) => {
+            let heard = WakeWordHeard {
+                phrase: phrase.to_string(),
+            };
+            let _ = app.emit_all(HEARD_EVENT, heard);
+        }
+        Err(err) => eprintln!("Failed to start recording on wake word: {:?}", err),
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::{bail, Result};
+use app_core::jobs::{job, Job, JobKind};
+use app_core::settings::SettingsStore;
+use hmac::{Hmac, Mac};
+use reqwest::StatusCode;
+use serde_json::json;
+use sha2::Sha256;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Header carrying `sha256=<hex HMAC of the body>` when a secret is set.
+pub const SIGNATURE_HEADER: &str = "X-Signature-256";
+
+const ATTEMPTS: u32 = 5;
+const FIRST_RETRY: Duration = Duration::from_secs(2);
+
+/// POSTs each finished transcript to the webhook URL from settings, if one is
+/// set. The body is `{"event": "transcription.done", "job_id": 1,
+/// "transcript": {...}}`, signed with the webhook secret so receivers can
+/// check it came from this app.
+///
+/// Delivery runs in the background and is retried with exponential backoff on
+/// network errors, `429` and `5xx` responses.
+pub fn job_event(app: &AppHandle, event: &str, job: &Job) {
+    if event != job::DONE_EVENT || job.kind != JobKind::Transcription {
+        return;
+    }
+    let settings = app.state::<SettingsStore>().get();
+    if settings.webhook_url.is_empty() {
+        return;
+    }
+    let body = json!({
+        "event": "transcription.done",
+        "job_id": job.id,
+        "transcript": job.result,
+    })
+    .to_string();
+    let job_id = job.id;
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = deliver(&settings.webhook_url, &settings.webhook_secret, body).await {
+            eprintln!("Failed to deliver webhook for job {}: {:?}", job_id, err);
+        }
+    });
+}
+
+async fn deliver(url: &str, secret: &str, body: String) -> Result<()> {
+    let client = reqwest::Client::new();
+    let mut request = client
+        .post(url)
+        .timeout(Duration::from_secs(30))
+        .header(reqwest::header::CONTENT_TYPE, "application/json");
+    if !secret.is_empty() {
+        request = request.header(SIGNATURE_HEADER, sign(secret, &body));
+    }
+    let request = request.body(body);
+
+    let mut delay = FIRST_RETRY;
+    let mut attempt = 1;
+    loop {
+        let retry = request.try_clone().expect("string bodies can be cloned");
+        let error = match retry.send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => {
+                let status = response.status();
+                if !retryable(status) {
+                    bail!("webhook rejected with {}", status);
+                }
+                anyhow::anyhow!("webhook answered {}", status)
+            }
+            Err(err) => err.into(),
+        };
+        if attempt == ATTEMPTS {
+            return Err(error.context(format!("gave up after {} attempts", ATTEMPTS)));
+        }
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+        attempt += 1;
+    }
+}
+
+/// Whether a failed delivery is worth trying again: the receiver is having
+/// trouble or asked us to slow down, rather than refusing the request.
+fn retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_with_hmac_sha256() {
+        // RFC 4231, test case 2.
+        assert_eq!(
+            sign("Jefe", "what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn retries_server_errors_and_rate_limits_only() {
+        assert!(retryable(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(retryable(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!retryable(StatusCode::BAD_REQUEST));
+        assert!(!retryable(StatusCode::UNAUTHORIZED));
+        assert!(!retryable(StatusCode::NOT_FOUND));
+    }
+}
@@ -21,7 +21,7 @@ function App() {
     // Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
     try {
       const value = (await invoke("transcribe", {
-        path: "./samples/a13.wav",
+        path: "src/samples/a13.wav",
       })) as string[];
       setGreetMsg(value);
     } finally {